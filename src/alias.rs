@@ -0,0 +1,225 @@
+use std::{collections::BTreeMap, ffi::OsStr, sync::RwLock};
+
+use crate::complete::NameKind;
+
+/// Short names for inputs and scenes from the `[aliases]` section of the config file.
+static ALIASES: RwLock<BTreeMap<String, String>> = RwLock::new(BTreeMap::new());
+
+/// Makes every command line parsed from now on resolve `aliases`, instead of any set before.
+pub(crate) fn set(aliases: BTreeMap<String, String>) {
+    *ALIASES.write().expect("alias lock is never poisoned") = aliases;
+}
+
+/// Replaces the arguments in `words` that are aliases with the names they stand for.
+///
+/// Only the values of arguments that take a scene, input, source or filter name are replaced,
+/// never command names, flags or other values, so `toggle-mute mic` toggles whatever `mic` is
+/// short for, while an alias named `input` changes nothing and `set-text clock mic` shows `mic`.
+pub(crate) fn resolve<W>(cmd: clap::Command, mut words: Vec<W>) -> Vec<W>
+where
+    W: AsRef<OsStr> + From<String>,
+{
+    let aliases = ALIASES.read().expect("alias lock is never poisoned");
+    if aliases.is_empty() {
+        return words;
+    }
+    substitute(&aliases, cmd, &mut words);
+    words
+}
+
+fn substitute<W>(aliases: &BTreeMap<String, String>, cmd: clap::Command, words: &mut [W])
+where
+    W: AsRef<OsStr> + From<String>,
+{
+    let names = values(cmd, words)
+        .into_iter()
+        .filter(|value| value.arg.as_ref().and_then(NameKind::of).is_some());
+    for value in names {
+        let word = &mut words[value.word];
+        let Some(text) = word.as_ref().to_str() else {
+            continue;
+        };
+        let (prefix, text) = text.split_at(value.offset);
+        if let Some(name) = aliases.get(text) {
+            *word = W::from(format!("{prefix}{name}"));
+        }
+    }
+}
+
+/// Where an argument value sits among the words of a command line.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct Value {
+    pub(crate) word: usize,
+    /// How far into the word the value starts, as in `--scene=main`.
+    pub(crate) offset: usize,
+    /// The argument the value is for, if it is for a known one.
+    pub(crate) arg: Option<clap::Id>,
+}
+
+/// Finds the argument values in `words`, following subcommands like clap does.
+pub(crate) fn values<W: AsRef<OsStr>>(mut cmd: clap::Command, words: &[W]) -> Vec<Value> {
+    // Building makes global arguments known to every subcommand.
+    cmd.build();
+    let mut cmd = &cmd;
+    let mut values = Vec::new();
+    let mut i = usize::from(!cmd.is_no_binary_name_set());
+    let mut positional = 0;
+    let mut escaped = false;
+    // The positional argument the `n`th positional value is for.
+    let positional_arg = |cmd: &clap::Command, n: usize| {
+        let args: Vec<_> = cmd.get_positionals().collect();
+        match args.get(n) {
+            Some(arg) => Some(arg.get_id().clone()),
+            // Like the inputs of `fade-input`, the last positional can take any number of values.
+            None => args
+                .last()
+                .filter(|arg| matches!(arg.get_action(), clap::ArgAction::Append))
+                .map(|arg| arg.get_id().clone()),
+        }
+    };
+    while i < words.len() {
+        let Some(word) = words[i].as_ref().to_str() else {
+            i += 1;
+            continue;
+        };
+        if escaped {
+            values.push(Value {
+                word: i,
+                offset: 0,
+                arg: positional_arg(cmd, positional),
+            });
+            positional += 1;
+        } else if word == "--" {
+            escaped = true;
+        } else if let Some(flag) = word.strip_prefix("--") {
+            let (name, inline) = match flag.split_once('=') {
+                Some((name, _)) => (name, true),
+                None => (flag, false),
+            };
+            let arg = cmd.get_arguments().find(|arg| {
+                arg.get_long() == Some(name)
+                    || arg
+                        .get_all_aliases()
+                        .is_some_and(|aliases| aliases.contains(&name))
+            });
+            if let Some(arg) = arg.filter(|arg| arg.get_action().takes_values()) {
+                if inline {
+                    values.push(Value {
+                        word: i,
+                        offset: name.len() + 3,
+                        arg: Some(arg.get_id().clone()),
+                    });
+                } else {
+                    values.push(Value {
+                        word: i + 1,
+                        offset: 0,
+                        arg: Some(arg.get_id().clone()),
+                    });
+                    i += 1;
+                }
+            }
+        } else if let Some(short) = word.strip_prefix('-').and_then(|s| s.chars().next()) {
+            let arg = cmd.get_arguments().find(|arg| {
+                arg.get_short_and_visible_aliases()
+                    .is_some_and(|s| s.contains(&short))
+            });
+            match arg {
+                // Values glued to short flags, like `-p1`, are never whole aliases.
+                Some(arg) if arg.get_action().takes_values() && word.len() == 2 => {
+                    values.push(Value {
+                        word: i + 1,
+                        offset: 0,
+                        arg: Some(arg.get_id().clone()),
+                    });
+                    i += 1;
+                }
+                Some(_) => {}
+                // Something like `-6dB`, which is a value rather than a flag.
+                None => {
+                    values.push(Value {
+                        word: i,
+                        offset: 0,
+                        arg: positional_arg(cmd, positional),
+                    });
+                    positional += 1;
+                }
+            }
+        } else if let Some(sub) = cmd.find_subcommand(word).filter(|_| positional == 0) {
+            cmd = sub;
+        } else if cmd.has_subcommands() && positional == 0 {
+            // An external subcommand, like a macro, whose arguments are its own business.
+            break;
+        } else {
+            values.push(Value {
+                word: i,
+                offset: 0,
+                arg: positional_arg(cmd, positional),
+            });
+            positional += 1;
+        }
+        i += 1;
+    }
+    values.retain(|value| value.word < words.len());
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+
+    use super::*;
+
+    fn resolved(words: &[&str]) -> Vec<String> {
+        let aliases = BTreeMap::from([
+            (String::from("mic"), String::from("Mic/Aux")),
+            (String::from("brb"), String::from("Be Right Back")),
+            (String::from("input"), String::from("Desktop Audio")),
+        ]);
+        let mut words: Vec<String> = words.iter().map(|&word| word.to_owned()).collect();
+        substitute(&aliases, crate::Chained::command(), &mut words);
+        words
+    }
+
+    #[test]
+    fn positional_values() {
+        assert_eq!(
+            resolved(&["toggle-mute", "mic"]),
+            ["toggle-mute", "Mic/Aux"]
+        );
+        assert_eq!(
+            resolved(&["set-scene", "brb"]),
+            ["set-scene", "Be Right Back"]
+        );
+        assert_eq!(resolved(&["set-scene", "Game"]), ["set-scene", "Game"]);
+    }
+
+    #[test]
+    fn option_values() {
+        assert_eq!(
+            resolved(&["enforce", "--scene", "brb", "--mute", "mic"]),
+            ["enforce", "--scene", "Be Right Back", "--mute", "Mic/Aux"]
+        );
+        assert_eq!(
+            resolved(&["enforce", "--scene=brb"]),
+            ["enforce", "--scene=Be Right Back"]
+        );
+    }
+
+    #[test]
+    fn leaves_other_values_alone() {
+        assert_eq!(
+            resolved(&["set-text", "mic", "brb"]),
+            ["set-text", "Mic/Aux", "brb"]
+        );
+        assert_eq!(
+            resolved(&["set-volume", "mic", "input"]),
+            ["set-volume", "Mic/Aux", "input"]
+        );
+    }
+
+    #[test]
+    fn leaves_commands_alone() {
+        assert_eq!(resolved(&["brb"]), ["brb"]);
+        assert_eq!(resolved(&["input", "list"]), ["input", "list"]);
+    }
+}
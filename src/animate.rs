@@ -0,0 +1,186 @@
+use anyhow::Context;
+use obws::{
+    requests::scene_items::{Position, Scale, SceneItemTransform, SetTransform},
+    Client,
+};
+use serde_json::json;
+use tokio::time::{Instant, MissedTickBehavior};
+
+use crate::{fade::STEP, output::Output, Curve};
+
+#[derive(Debug, Clone, clap::Args)]
+#[command(group(
+    clap::ArgGroup::new("target")
+        .required(true)
+        .multiple(true)
+        .args(["to_x", "to_y", "to_scale"])
+))]
+pub(crate) struct AnimateArgs {
+    scene: String,
+
+    source: String,
+
+    /// Where to move the item horizontally, in canvas pixels.
+    #[arg(long, value_name = "PIXELS", allow_hyphen_values = true)]
+    to_x: Option<f32>,
+
+    /// Where to move the item vertically, in canvas pixels.
+    #[arg(long, value_name = "PIXELS", allow_hyphen_values = true)]
+    to_y: Option<f32>,
+
+    /// What to scale the item to, where 1 is the size of the source.
+    #[arg(long, value_name = "FACTOR")]
+    to_scale: Option<f32>,
+
+    /// Jump to this horizontal position first, like off the edge of the canvas to slide in.
+    #[arg(long, value_name = "PIXELS", allow_hyphen_values = true)]
+    from_x: Option<f32>,
+
+    /// Jump to this vertical position first.
+    #[arg(long, value_name = "PIXELS", allow_hyphen_values = true)]
+    from_y: Option<f32>,
+
+    /// Jump to this scale first.
+    #[arg(long, value_name = "FACTOR")]
+    from_scale: Option<f32>,
+
+    /// How long the move should take, like `500ms` or `2s`.
+    #[arg(long, value_name = "DURATION", default_value = "1s")]
+    duration: String,
+
+    /// How the item progresses over the course of the move.
+    #[arg(long, value_enum, default_value_t = Curve::Smooth)]
+    curve: Curve,
+}
+
+/// Where an item is and how large it is, as far as animating it goes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Frame {
+    x: f32,
+    y: f32,
+    scale_x: f32,
+    scale_y: f32,
+}
+
+impl Frame {
+    /// The frame `progress` of the way from `self` to `to`.
+    fn towards(self, to: Frame, progress: f32) -> Frame {
+        let lerp = |from: f32, to: f32| from + (to - from) * progress;
+        Frame {
+            x: lerp(self.x, to.x),
+            y: lerp(self.y, to.y),
+            scale_x: lerp(self.scale_x, to.scale_x),
+            scale_y: lerp(self.scale_y, to.scale_y),
+        }
+    }
+}
+
+/// Moves and scales a scene item smoothly, one step per frame, like `fade-input` does for
+/// volume. Interrupting it leaves the item wherever it had got to.
+pub(crate) async fn run(client: &Client, args: &AnimateArgs, out: Output) -> anyhow::Result<()> {
+    let duration = crate::parse_duration(&args.duration)?;
+    let (scene, source) = (&args.scene, &args.source);
+    let item_id = crate::item::id(client, scene, source).await?;
+    let current = client
+        .scene_items()
+        .transform(scene, item_id)
+        .await
+        .with_context(|| format!("get transform of {source} in {scene}"))?;
+    let from = Frame {
+        x: args.from_x.unwrap_or(current.position_x),
+        y: args.from_y.unwrap_or(current.position_y),
+        scale_x: args.from_scale.unwrap_or(current.scale_x),
+        scale_y: args.from_scale.unwrap_or(current.scale_y),
+    };
+    let to = Frame {
+        x: args.to_x.unwrap_or(from.x),
+        y: args.to_y.unwrap_or(from.y),
+        scale_x: args.to_scale.unwrap_or(from.scale_x),
+        scale_y: args.to_scale.unwrap_or(from.scale_y),
+    };
+
+    // As with fades, progress follows the time that has actually passed.
+    let started = Instant::now();
+    let mut interval = tokio::time::interval_at(started + STEP, STEP);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    let mut at = from;
+    let finished = loop {
+        tokio::select! {
+            _ = interval.tick() => {}
+            _ = tokio::signal::ctrl_c() => break false,
+        }
+        let elapsed = started.elapsed();
+        let done = elapsed >= duration;
+        at = if done {
+            to
+        } else {
+            from.towards(
+                to,
+                args.curve
+                    .apply(elapsed.as_secs_f32() / duration.as_secs_f32()),
+            )
+        };
+        client
+            .scene_items()
+            .set_transform(SetTransform {
+                scene,
+                item_id,
+                transform: SceneItemTransform {
+                    position: Some(Position {
+                        x: Some(at.x),
+                        y: Some(at.y),
+                    }),
+                    scale: Some(Scale {
+                        x: Some(at.scale_x),
+                        y: Some(at.scale_y),
+                    }),
+                    ..Default::default()
+                },
+            })
+            .await
+            .with_context(|| format!("move {source} in {scene}"))?;
+        if done {
+            break true;
+        }
+    };
+
+    out.json(&json!({
+        "scene": scene,
+        "source": source,
+        "position": { "x": at.x, "y": at.y },
+        "scale": { "x": at.scale_x, "y": at.scale_y },
+        "cancelled": !finished,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interpolates_frames() {
+        let from = Frame {
+            x: -400.,
+            y: 900.,
+            scale_x: 1.,
+            scale_y: 1.,
+        };
+        let to = Frame {
+            x: 100.,
+            y: 900.,
+            scale_x: 0.5,
+            scale_y: 0.5,
+        };
+        assert_eq!(from.towards(to, 0.), from);
+        assert_eq!(from.towards(to, 1.), to);
+        assert_eq!(
+            from.towards(to, 0.5),
+            Frame {
+                x: -150.,
+                y: 900.,
+                scale_x: 0.75,
+                scale_y: 0.75,
+            }
+        );
+    }
+}
@@ -0,0 +1,121 @@
+use anyhow::Context;
+use clap::ValueEnum;
+use obws::{common::MonitorType, Client};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::output::Output;
+
+/// Where OBS sends an input's audio besides the stream and recording mix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum Monitoring {
+    /// Only to the mix.
+    None,
+    /// Only to the monitoring device, leaving it out of the mix.
+    MonitorOnly,
+    /// To both the monitoring device and the mix.
+    MonitorAndOutput,
+}
+
+impl Monitoring {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Monitoring::None => "none",
+            Monitoring::MonitorOnly => "monitor-only",
+            Monitoring::MonitorAndOutput => "monitor-and-output",
+        }
+    }
+}
+
+impl From<MonitorType> for Monitoring {
+    fn from(monitor_type: MonitorType) -> Self {
+        match monitor_type {
+            MonitorType::None => Monitoring::None,
+            MonitorType::MonitorOnly => Monitoring::MonitorOnly,
+            MonitorType::MonitorAndOutput => Monitoring::MonitorAndOutput,
+            // obws leaves room for types OBS may add; none of them exist yet.
+            _ => Monitoring::None,
+        }
+    }
+}
+
+impl From<Monitoring> for MonitorType {
+    fn from(monitoring: Monitoring) -> Self {
+        match monitoring {
+            Monitoring::None => MonitorType::None,
+            Monitoring::MonitorOnly => MonitorType::MonitorOnly,
+            Monitoring::MonitorAndOutput => MonitorType::MonitorAndOutput,
+        }
+    }
+}
+
+/// Delays (or, if negative, advances) the audio of `input` by `offset_ms` milliseconds.
+pub(crate) async fn set_sync_offset(
+    client: &Client,
+    input: &str,
+    offset_ms: i64,
+    out: Output,
+) -> anyhow::Result<()> {
+    client
+        .inputs()
+        .set_audio_sync_offset(input, time::Duration::milliseconds(offset_ms))
+        .await
+        .with_context(|| format!("set sync offset of {input}"))?;
+    out.json(&json!({ "input": input, "offset_ms": offset_ms }))
+}
+
+pub(crate) async fn sync_offset(client: &Client, input: &str, out: Output) -> anyhow::Result<()> {
+    let offset = client
+        .inputs()
+        .audio_sync_offset(input)
+        .await
+        .with_context(|| format!("get sync offset of {input}"))?;
+    let offset_ms = offset.whole_milliseconds();
+    out.result(
+        format!("{offset_ms} ms"),
+        &json!({ "input": input, "offset_ms": offset_ms }),
+    )
+}
+
+pub(crate) async fn set_monitoring(
+    client: &Client,
+    input: &str,
+    monitoring: Monitoring,
+    out: Output,
+) -> anyhow::Result<()> {
+    client
+        .inputs()
+        .set_audio_monitor_type(input, monitoring.into())
+        .await
+        .with_context(|| format!("set monitoring of {input}"))?;
+    out.json(&json!({ "input": input, "monitoring": monitoring.as_str() }))
+}
+
+/// Pans `input` between left (`0.0`) and right (`1.0`).
+pub(crate) async fn set_balance(
+    client: &Client,
+    input: &str,
+    balance: f32,
+    out: Output,
+) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        (0. ..=1.).contains(&balance),
+        "balance must be between 0.0 (left) and 1.0 (right)"
+    );
+    client
+        .inputs()
+        .set_audio_balance(input, balance)
+        .await
+        .with_context(|| format!("set balance of {input}"))?;
+    out.json(&json!({ "input": input, "balance": balance }))
+}
+
+pub(crate) async fn balance(client: &Client, input: &str, out: Output) -> anyhow::Result<()> {
+    let balance = client
+        .inputs()
+        .audio_balance(input)
+        .await
+        .with_context(|| format!("get balance of {input}"))?;
+    out.result(balance, &json!({ "input": input, "balance": balance }))
+}
@@ -0,0 +1,85 @@
+use anyhow::Context;
+use clap::Subcommand;
+use serde_json::json;
+use std::io::IsTerminal;
+
+use crate::output::Output;
+
+/// The keyring service that obs-do's credentials are filed under.
+const SERVICE: &str = "obs-do";
+
+/// The keyring account that holds the OBS WebSocket password.
+const ACCOUNT: &str = "websocket-password";
+
+#[derive(Debug, Clone, Subcommand)]
+pub(crate) enum AuthCommand {
+    /// Stores the OBS WebSocket password in the OS keyring.
+    ///
+    /// Prompts for the password, or reads it from standard input when that isn't a terminal.
+    Set,
+    /// Removes the OBS WebSocket password from the OS keyring.
+    Clear,
+}
+
+pub(crate) async fn run(cmd: &AuthCommand, out: Output) -> anyhow::Result<()> {
+    match cmd {
+        AuthCommand::Set => {
+            let password = tokio::task::spawn_blocking(|| -> std::io::Result<String> {
+                if std::io::stdin().is_terminal() {
+                    rpassword::prompt_password("OBS WebSocket password: ")
+                } else {
+                    let mut line = String::new();
+                    std::io::stdin().read_line(&mut line)?;
+                    Ok(line.trim_end_matches(['\r', '\n']).to_owned())
+                }
+            })
+            .await
+            .context("read password")?
+            .context("read password")?;
+            anyhow::ensure!(!password.is_empty(), "the password is empty");
+            keyring(move |entry| entry.set_password(&password))
+                .await
+                .context("store password in the keyring")?;
+            out.result(
+                "Stored the OBS WebSocket password in the keyring",
+                &json!({ "stored": true }),
+            )
+        }
+        AuthCommand::Clear => {
+            let removed = match keyring(|entry| entry.delete_password()).await {
+                Ok(()) => true,
+                Err(keyring::Error::NoEntry) => false,
+                Err(e) => return Err(e).context("remove password from the keyring"),
+            };
+            let human = if removed {
+                "Removed the OBS WebSocket password from the keyring"
+            } else {
+                "There was no OBS WebSocket password in the keyring"
+            };
+            out.result(human, &json!({ "removed": removed }))
+        }
+    }
+}
+
+/// The password stored with `auth set`, if there is one and the keyring can be reached.
+pub(crate) async fn password() -> Option<String> {
+    match keyring(|entry| entry.get_password()).await {
+        Ok(password) => Some(password),
+        Err(keyring::Error::NoEntry) => None,
+        Err(e) => {
+            tracing::debug!("could not read the keyring: {e}");
+            None
+        }
+    }
+}
+
+/// Runs `f` on obs-do's keyring entry, off the async runtime since keyrings block.
+async fn keyring<T, F>(f: F) -> keyring::Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce(&keyring::Entry) -> keyring::Result<T> + Send + 'static,
+{
+    tokio::task::spawn_blocking(move || f(&keyring::Entry::new(SERVICE, ACCOUNT)?))
+        .await
+        .map_err(|e| keyring::Error::PlatformFailure(e.into()))?
+}
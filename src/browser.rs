@@ -0,0 +1,94 @@
+use anyhow::Context;
+use clap::Subcommand;
+use obws::{requests::inputs::SetSettings, Client};
+use serde_json::json;
+
+use crate::output::Output;
+
+/// The kind OBS gives browser sources.
+const BROWSER_KIND: &str = "browser_source";
+
+#[derive(Debug, Clone, Subcommand)]
+pub(crate) enum BrowserCommand {
+    /// Reloads the page of a browser source, bypassing the cache, like after an overlay changed.
+    Refresh {
+        /// The browser source to reload.
+        input: String,
+    },
+    /// Points a browser source at another page.
+    SetUrl {
+        /// The browser source to change.
+        input: String,
+
+        /// The page to show.
+        url: String,
+
+        /// Also replace the custom CSS the page is shown with; an empty string removes it.
+        #[arg(long, value_name = "CSS")]
+        css: Option<String>,
+    },
+}
+
+impl BrowserCommand {
+    pub(crate) fn is_mutating(&self) -> bool {
+        match self {
+            BrowserCommand::Refresh { .. } | BrowserCommand::SetUrl { .. } => true,
+        }
+    }
+}
+
+pub(crate) async fn run(client: &Client, cmd: BrowserCommand, out: Output) -> anyhow::Result<()> {
+    match cmd {
+        BrowserCommand::Refresh { input } => {
+            crate::input::ensure_kind(client, &input, &[BROWSER_KIND], "a browser source").await?;
+            client
+                .inputs()
+                .press_properties_button(&input, "refreshnocache")
+                .await
+                .with_context(|| format!("refresh {input}"))?;
+            out.json(&json!({ "input": input, "refreshed": true }))?;
+        }
+        BrowserCommand::SetUrl { input, url, css } => {
+            crate::input::ensure_kind(client, &input, &[BROWSER_KIND], "a browser source").await?;
+            let settings = url_settings(&url, css.as_deref());
+            client
+                .inputs()
+                .set_settings(SetSettings {
+                    input: &input,
+                    settings: &settings,
+                    overlay: Some(true),
+                })
+                .await
+                .with_context(|| format!("set URL of {input}"))?;
+            out.json(&json!({ "input": input, "url": url, "css": css }))?;
+        }
+    }
+    Ok(())
+}
+
+/// The settings that point a browser source at `url`, with `css` if it is to be replaced too.
+fn url_settings(url: &str, css: Option<&str>) -> serde_json::Value {
+    // A browser source showing a local file ignores its URL.
+    let mut settings = json!({ "url": url, "is_local_file": false });
+    if let Some(css) = css {
+        settings["css"] = json!(css);
+    }
+    settings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn url_settings_keep_css_unless_given() {
+        assert_eq!(
+            url_settings("https://example.com/alerts", None),
+            json!({ "url": "https://example.com/alerts", "is_local_file": false })
+        );
+        assert_eq!(
+            url_settings("https://example.com/alerts", Some("")),
+            json!({ "url": "https://example.com/alerts", "is_local_file": false, "css": "" })
+        );
+    }
+}
@@ -0,0 +1,239 @@
+use crate::output::Output;
+use anyhow::Context;
+use clap::Subcommand;
+use futures_util::future::try_join_all;
+use obws::Client;
+use serde_json::json;
+
+#[derive(Debug, Clone, Subcommand)]
+pub(crate) enum CollectionCommand {
+    /// Removes every input and every scene but one from the current scene collection.
+    ///
+    /// Meant for resetting test and staging setups between runs. OBS needs at least one scene,
+    /// so the live scene is kept, emptied out.
+    Wipe {
+        /// Confirms that everything in the collection should really be removed.
+        #[arg(long)]
+        yes_i_mean_it: bool,
+
+        /// The name of the collection to wipe, which must be the current one.
+        ///
+        /// Without it, the name has to be typed in at a prompt.
+        #[arg(long, value_name = "NAME")]
+        collection: Option<String>,
+    },
+}
+
+impl CollectionCommand {
+    pub(crate) fn is_mutating(&self) -> bool {
+        match self {
+            CollectionCommand::Wipe { .. } => true,
+        }
+    }
+}
+
+pub(crate) async fn run(
+    client: &Client,
+    cmd: CollectionCommand,
+    out: Output,
+) -> anyhow::Result<()> {
+    match cmd {
+        CollectionCommand::Wipe {
+            yes_i_mean_it,
+            collection,
+        } => wipe(client, yes_i_mean_it, collection, out).await,
+    }
+}
+
+async fn wipe(
+    client: &Client,
+    yes_i_mean_it: bool,
+    collection: Option<String>,
+    out: Output,
+) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        yes_i_mean_it,
+        "wiping removes every scene and input in the collection; pass --yes-i-mean-it to do it anyway"
+    );
+    let (current, streaming, recording) = tokio::try_join!(
+        async {
+            client
+                .scene_collections()
+                .current()
+                .await
+                .context("get current scene collection")
+        },
+        async {
+            client
+                .streaming()
+                .status()
+                .await
+                .context("get streaming status")
+        },
+        async {
+            client
+                .recording()
+                .status()
+                .await
+                .context("get recording status")
+        },
+    )?;
+    anyhow::ensure!(
+        !streaming.active && !recording.active,
+        "refusing to wipe a scene collection while OBS is streaming or recording"
+    );
+
+    let confirmed = match collection {
+        Some(collection) => collection,
+        None => confirm(&current).await?,
+    };
+    anyhow::ensure!(
+        confirmed == current,
+        "the current scene collection is {current}, not {confirmed}; nothing was wiped"
+    );
+
+    let inputs = client.inputs().list(None).await.context("list inputs")?;
+    try_join_all(inputs.iter().map(|input| async move {
+        client
+            .inputs()
+            .remove(&input.name)
+            .await
+            .with_context(|| format!("remove input {}", input.name))
+    }))
+    .await?;
+
+    let (scenes, live) = crate::cycle::scenes(client).await?;
+    let keep = live.or_else(|| scenes.first().cloned());
+    let remove: Vec<&String> = scenes
+        .iter()
+        .filter(|scene| Some(*scene) != keep.as_ref())
+        .collect();
+    try_join_all(remove.iter().map(|scene| async move {
+        client
+            .scenes()
+            .remove(scene)
+            .await
+            .with_context(|| format!("remove scene {scene}"))
+    }))
+    .await?;
+    // Nested scenes are gone now, but the kept scene may still show them.
+    if let Some(scene) = &keep {
+        let items = client
+            .scene_items()
+            .list(scene)
+            .await
+            .with_context(|| format!("list items in {scene}"))?;
+        try_join_all(items.iter().map(|item| async move {
+            client
+                .scene_items()
+                .remove(scene, item.id)
+                .await
+                .with_context(|| format!("remove {} from {scene}", item.source_name))
+        }))
+        .await?;
+    }
+
+    out.result(
+        format!(
+            "Wiped {current}: removed {} inputs and {} scenes",
+            inputs.len(),
+            remove.len()
+        ),
+        &json!({
+            "collection": current,
+            "inputs": inputs.iter().map(|input| &input.name).collect::<Vec<_>>(),
+            "scenes": remove,
+            "kept": keep,
+        }),
+    )
+}
+
+/// Asks for the name of the collection to be typed in, as a last check.
+///
+/// Only the command line gets here: servers and bridges refuse a wipe without `--collection`
+/// (see `Command::is_servable`), since a prompt would block on their terminal or, under `rpc`,
+/// take the next request for the answer.
+async fn confirm(collection: &str) -> anyhow::Result<String> {
+    crate::prompt::ensure_interactive("pass --collection with the name of the collection to wipe")?;
+    crate::prompt::ask(format!(
+        "This removes everything in {collection}. Type its name to continue: "
+    ))
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fake OBS with the `Test` collection open, and the requests it was sent.
+    async fn obs(streaming: bool, recording: bool) -> (Client, crate::fake_obs::Requests) {
+        crate::fake_obs::connect(move |kind, _| {
+            Some(match kind {
+                "GetSceneCollectionList" => json!({
+                    "currentSceneCollectionName": "Test",
+                    "sceneCollections": ["Test", "Live"],
+                }),
+                "GetStreamStatus" => json!({
+                    "outputActive": streaming, "outputReconnecting": false,
+                    "outputTimecode": "00:00:00.000", "outputDuration": 0,
+                    "outputCongestion": 0.0, "outputBytes": 0,
+                    "outputSkippedFrames": 0, "outputTotalFrames": 0,
+                }),
+                "GetRecordStatus" => json!({
+                    "outputActive": recording, "outputPaused": false,
+                    "outputTimecode": "00:00:00.000", "outputDuration": 0,
+                    "outputBytes": 0,
+                }),
+                _ => return None,
+            })
+        })
+        .await
+    }
+
+    fn removed(requests: &crate::fake_obs::Requests) -> bool {
+        requests
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|(kind, _)| kind.starts_with("Remove"))
+    }
+
+    #[tokio::test]
+    async fn refuses_without_yes_i_mean_it() {
+        let (client, requests) = obs(false, false).await;
+        let err = crate::fake_obs::run_line(&client, "collection wipe --collection Test")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("--yes-i-mean-it"), "{err}");
+        assert!(requests.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn refuses_while_live() {
+        for (streaming, recording) in [(true, false), (false, true)] {
+            let (client, requests) = obs(streaming, recording).await;
+            let err = crate::fake_obs::run_line(
+                &client,
+                "collection wipe --yes-i-mean-it --collection Test",
+            )
+            .await
+            .unwrap_err();
+            assert!(err.to_string().contains("streaming or recording"), "{err}");
+            assert!(!removed(&requests));
+        }
+    }
+
+    #[tokio::test]
+    async fn refuses_another_collection() {
+        let (client, requests) = obs(false, false).await;
+        let err =
+            crate::fake_obs::run_line(&client, "collection wipe --yes-i-mean-it --collection Live")
+                .await
+                .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "the current scene collection is Test, not Live; nothing was wiped"
+        );
+        assert!(!removed(&requests));
+    }
+}
@@ -0,0 +1,295 @@
+use anyhow::Context;
+use clap::CommandFactory;
+use clap_complete::Shell;
+use obws::Client;
+use std::io::Write;
+
+/// The kinds of OBS names that can be completed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum NameKind {
+    Scenes,
+    Inputs,
+    /// Both, since scenes can be sources too, like when nested in other scenes.
+    Sources,
+    /// The filters of the source given in the `source` argument.
+    Filters,
+}
+
+impl NameKind {
+    /// The kind of names an argument takes, going by its id.
+    pub(crate) fn of(arg: &clap::Id) -> Option<NameKind> {
+        Some(match arg.as_str() {
+            "scene" | "scenes" | "to_scene" | "if_scene" => NameKind::Scenes,
+            "input" | "inputs" | "trigger" | "mute" | "unmute" | "text_source" => NameKind::Inputs,
+            "source" | "target" => NameKind::Sources,
+            "filter" => NameKind::Filters,
+            _ => return None,
+        })
+    }
+}
+
+/// Works out what names to complete next on a command line, and for which source if filters.
+///
+/// `words` are the words after `obs-do` up to the one being completed, as the shell has them.
+pub(crate) fn target(words: &[String]) -> Option<(NameKind, Option<String>)> {
+    // Only the last of several chained commands is being completed.
+    let chained = words
+        .iter()
+        .rposition(|word| word == crate::CHAIN_SEPARATOR || word == r"\;");
+    let (cmd, mut words) = match chained {
+        Some(i) => (crate::Chained::command(), words[i + 1..].to_vec()),
+        None => (
+            crate::Args::command(),
+            std::iter::once(String::from(env!("CARGO_PKG_NAME")))
+                .chain(words.iter().cloned())
+                .collect(),
+        ),
+    };
+    // The word being completed, which may be empty so far but is a value all the same.
+    words.push(String::new());
+    let values = crate::alias::values(cmd, &words);
+    let kind = values
+        .iter()
+        .find(|value| value.word == words.len() - 1)?
+        .arg
+        .as_ref()
+        .and_then(NameKind::of)?;
+    if kind != NameKind::Filters {
+        return Some((kind, None));
+    }
+    let source = values
+        .iter()
+        .find(|value| value.arg.as_ref().is_some_and(|arg| arg == "source"))
+        .map(|value| {
+            let source = &words[value.word][value.offset..];
+            // Shells hand over words as typed, quotes and all.
+            match shell_words::split(source).as_deref() {
+                Ok([source]) => source.clone(),
+                _ => source.to_owned(),
+            }
+        });
+    Some((kind, source))
+}
+
+/// Writes the completion script for `shell` to stdout.
+pub(crate) fn generate(shell: Shell) -> anyhow::Result<()> {
+    std::io::stdout()
+        .lock()
+        .write_all(script(shell)?.as_bytes())?;
+    Ok(())
+}
+
+/// The completion script for `shell`.
+///
+/// For shells that support it, the static clap-generated script is extended so that names are
+/// completed by asking the running OBS through `obs-do __complete` wherever an argument takes
+/// a scene, input, or filter name.
+fn script(shell: Shell) -> anyhow::Result<String> {
+    let bin = env!("CARGO_PKG_NAME");
+    // The bash generator splits subcommand paths on `__`, which `__complete` trips over. It is
+    // hidden, so what it's called in the script doesn't matter.
+    let mut cmd = crate::Args::command().mut_subcommand("__complete", |cmd| cmd.name("complete"));
+    let mut script = Vec::new();
+    clap_complete::generate(shell, &mut cmd, bin, &mut script);
+    let mut script = String::from_utf8(script).context("completion script is not UTF-8")?;
+
+    match shell {
+        Shell::Bash => {
+            let func = format!("_{bin}");
+            script.push_str(&format!(
+                r#"
+{func}_dynamic() {{
+    local cur="${{COMP_WORDS[COMP_CWORD]}}" name names=()
+    while IFS= read -r name; do
+        [[ ${{name}} == "${{cur}}"* ]] && names+=("$(printf '%q' "${{name}}")")
+    done < <({bin} __complete -- "${{COMP_WORDS[@]:1:COMP_CWORD-1}}" 2>/dev/null)
+    if [[ ${{#names[@]}} -eq 0 ]]; then
+        {func} "$@"
+        return
+    fi
+    COMPREPLY=("${{names[@]}}")
+}}
+complete -F {func}_dynamic -o bashdefault -o default {bin}
+"#
+            ));
+        }
+        Shell::Zsh => {
+            // The generated `_obs-do` becomes the fallback of one that tries names first, so
+            // that the script still works both autoloaded from `$fpath` and sourced.
+            let func = format!("_{bin}");
+            let dispatch = format!("if [ \"$funcstack[1]\" = \"{func}\" ]; then");
+            let at = script
+                .rfind(&dispatch)
+                .context("unexpected zsh completion script")?;
+            script.insert_str(
+                at,
+                &format!(
+                    r#"{func}() {{
+    local -a names
+    names=("${{(@f)$({bin} __complete -- "${{(@)words[2,CURRENT-1]}}" 2>/dev/null)}}")
+    if [[ -n ${{names[1]}} ]]; then
+        compadd -a names
+    else
+        {func}_static "$@"
+    fi
+}}
+
+"#
+                ),
+            );
+            script = script.replacen(
+                &format!("\n{func}() {{\n"),
+                &format!("\n{func}_static() {{\n"),
+                1,
+            );
+        }
+        Shell::Fish => {
+            let func = format!("__{}_names", bin.replace('-', "_"));
+            script.push_str(&format!(
+                r#"function {func}
+    set -g {func} ({bin} __complete -- (commandline -opc)[2..-1] 2>/dev/null)
+    test (count ${func}) -gt 0
+end
+complete -c {bin} -n {func} -f -a '${func}'
+"#
+            ));
+        }
+        _ => {}
+    }
+    Ok(script)
+}
+
+/// Prints the names to complete next on the command line `words`, one per line.
+pub(crate) async fn list(client: &Client, words: &[String]) -> anyhow::Result<()> {
+    let Some((kind, source)) = target(words) else {
+        return Ok(());
+    };
+    let mut stdout = std::io::stdout().lock();
+    for name in names(client, kind, source.as_deref()).await? {
+        writeln!(stdout, "{name}")?;
+    }
+    Ok(())
+}
+
+/// The names of the given kind known to OBS.
+pub(crate) async fn names(
+    client: &Client,
+    kind: NameKind,
+    source: Option<&str>,
+) -> anyhow::Result<Vec<String>> {
+    let scenes = || async {
+        anyhow::Ok(
+            client
+                .scenes()
+                .list()
+                .await
+                .context("list scenes")?
+                .scenes
+                .into_iter()
+                .map(|scene| scene.name),
+        )
+    };
+    let inputs = || async {
+        anyhow::Ok(
+            client
+                .inputs()
+                .list(None)
+                .await
+                .context("list inputs")?
+                .into_iter()
+                .map(|input| input.name),
+        )
+    };
+    Ok(match kind {
+        NameKind::Scenes => scenes().await?.collect(),
+        NameKind::Inputs => inputs().await?.collect(),
+        NameKind::Sources => scenes().await?.chain(inputs().await?).collect(),
+        NameKind::Filters => {
+            let source = source.context("completing filters requires a source name")?;
+            client
+                .filters()
+                .list(source)
+                .await
+                .with_context(|| format!("list filters of {source}"))?
+                .into_iter()
+                .map(|filter| filter.name)
+                .collect()
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target_of(line: &str) -> Option<(NameKind, Option<String>)> {
+        let words: Vec<String> = line.split(' ').map(String::from).collect();
+        target(&words[..words.len() - 1])
+    }
+
+    #[test]
+    fn targets() {
+        assert_eq!(target_of("set-scene "), Some((NameKind::Scenes, None)));
+        assert_eq!(target_of("set-scene Game "), None);
+        assert_eq!(target_of("set-volume "), Some((NameKind::Inputs, None)));
+        assert_eq!(target_of("fade-input a b "), None);
+        assert_eq!(
+            target_of("fade-cancel a b "),
+            Some((NameKind::Inputs, None))
+        );
+        assert_eq!(target_of("enforce --mute "), Some((NameKind::Inputs, None)));
+        assert_eq!(target_of("item add Main "), Some((NameKind::Sources, None)));
+        assert_eq!(
+            target_of("input set-settings "),
+            Some((NameKind::Inputs, None))
+        );
+        // Global flags don't throw off where the subcommand is, even those that take a value.
+        assert_eq!(
+            target_of("--json --profile studio set-scene "),
+            Some((NameKind::Scenes, None))
+        );
+        assert_eq!(
+            target_of("set-scene BRB ; toggle-mute "),
+            Some((NameKind::Inputs, None))
+        );
+        assert_eq!(target_of(""), None);
+        assert_eq!(target_of("brb "), None);
+    }
+
+    #[test]
+    fn scripts() {
+        for shell in [Shell::Bash, Shell::Zsh, Shell::Fish] {
+            let script = script(shell).unwrap();
+            assert!(
+                script.contains("__complete --"),
+                "{shell} doesn't complete names"
+            );
+        }
+        assert!(script(Shell::Zsh)
+            .unwrap()
+            .contains("\n_obs-do_static() {\n"));
+    }
+
+    #[test]
+    fn every_name_argument_is_completed() {
+        fn check(cmd: &clap::Command, path: &str) {
+            for arg in cmd.get_arguments() {
+                let id = arg.get_id().as_str();
+                let looks_like_a_name = ["scene", "input", "source"]
+                    .iter()
+                    .any(|name| id.contains(name));
+                // How long a scene is held is a duration, not a name.
+                if looks_like_a_name && arg.get_action().takes_values() && !id.ends_with("_hold") {
+                    assert!(
+                        NameKind::of(arg.get_id()).is_some(),
+                        "{path} {id} is not completed"
+                    );
+                }
+            }
+            for sub in cmd.get_subcommands() {
+                check(sub, &format!("{path} {}", sub.get_name()));
+            }
+        }
+        check(&crate::Args::command(), env!("CARGO_PKG_NAME"));
+    }
+}
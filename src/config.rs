@@ -0,0 +1,587 @@
+use anyhow::Context;
+use clap::{Subcommand, ValueEnum};
+use obws::requests::EventSubscription;
+use serde::Deserialize;
+use serde_json::json;
+use std::{
+    collections::BTreeMap,
+    fmt,
+    net::IpAddr,
+    path::Path,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+use tokio::{io::AsyncWriteExt, sync::watch};
+
+use crate::output::Output;
+
+/// Name of the configuration file within the obs-do configuration directory.
+pub(crate) const FILE_NAME: &str = "config.toml";
+
+/// How often long-running modes look for changes to the config file.
+const WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Settings read from `config.toml`; every setting is optional.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Config {
+    /// Refuse mutating commands while streaming, as if `--read-only` was always given.
+    pub(crate) read_only: bool,
+
+    /// The backup ingest that `failover` switches the stream to.
+    pub(crate) failover: Option<Failover>,
+
+    /// Where OBS runs and how to log in to it, unless a profile says otherwise.
+    #[serde(flatten)]
+    pub(crate) connection: Profile,
+
+    /// Other places OBS runs, picked with `--profile <name>`.
+    pub(crate) profiles: BTreeMap<String, Profile>,
+
+    /// Raise desktop notifications, as if `--notify` was always given.
+    pub(crate) notify: bool,
+
+    /// Match scene and input names loosely, as if `--fuzzy` was always given.
+    pub(crate) fuzzy: bool,
+
+    /// Short names for inputs and scenes, like `mic = "Mic/Aux"`, usable wherever a name is.
+    pub(crate) aliases: BTreeMap<String, String>,
+
+    /// Key chords, like `Ctrl+Shift+F1`, mapped to the command that `hotkeyd` runs for them.
+    pub(crate) hotkeys: BTreeMap<String, String>,
+
+    /// Commands that the daemon runs on a schedule, as `[[schedule]]` entries.
+    pub(crate) schedule: Vec<Scheduled>,
+
+    /// Named lists of commands, run with `obs-do macro <name>` or just `obs-do <name>`.
+    pub(crate) macros: BTreeMap<String, Vec<String>>,
+
+    /// The kinds of events to ask OBS for, as with `--events`.
+    pub(crate) events: Option<Vec<EventCategory>>,
+
+    /// Take the port and password from the configuration of the OBS on this machine, as if
+    /// `--auto-config` was always given.
+    pub(crate) auto_config: bool,
+
+    /// The password found in OBS' own configuration with `--auto-config`.
+    #[serde(skip)]
+    pub(crate) discovered_password: Option<String>,
+}
+
+/// A kind of event that OBS only sends to clients that ask for it.
+#[derive(Debug, Clone, Copy, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum EventCategory {
+    /// No events at all.
+    None,
+    /// All the low-volume kinds below, which is the default.
+    All,
+    General,
+    Config,
+    Scenes,
+    Inputs,
+    Transitions,
+    Filters,
+    Outputs,
+    SceneItems,
+    MediaInputs,
+    Vendors,
+    Ui,
+    /// Audio levels of every input, many times per second.
+    InputVolumeMeters,
+    InputActiveStateChanged,
+    InputShowStateChanged,
+    SceneItemTransformChanged,
+}
+
+impl EventCategory {
+    fn subscription(self) -> EventSubscription {
+        match self {
+            EventCategory::None => EventSubscription::NONE,
+            EventCategory::All => EventSubscription::ALL,
+            EventCategory::General => EventSubscription::GENERAL,
+            EventCategory::Config => EventSubscription::CONFIG,
+            EventCategory::Scenes => EventSubscription::SCENES,
+            EventCategory::Inputs => EventSubscription::INPUTS,
+            EventCategory::Transitions => EventSubscription::TRANSITIONS,
+            EventCategory::Filters => EventSubscription::FILTERS,
+            EventCategory::Outputs => EventSubscription::OUTPUTS,
+            EventCategory::SceneItems => EventSubscription::SCENE_ITEMS,
+            EventCategory::MediaInputs => EventSubscription::MEDIA_INPUTS,
+            EventCategory::Vendors => EventSubscription::VENDORS,
+            EventCategory::Ui => EventSubscription::UI,
+            EventCategory::InputVolumeMeters => EventSubscription::INPUT_VOLUME_METERS,
+            EventCategory::InputActiveStateChanged => EventSubscription::INPUT_ACTIVE_STATE_CHANGED,
+            EventCategory::InputShowStateChanged => EventSubscription::INPUT_SHOW_STATE_CHANGED,
+            EventCategory::SceneItemTransformChanged => {
+                EventSubscription::SCENE_ITEM_TRANSFORM_CHANGED
+            }
+        }
+    }
+
+    /// Combines the categories into what to subscribe to.
+    pub fn subscriptions(categories: &[EventCategory]) -> EventSubscription {
+        categories
+            .iter()
+            .fold(EventSubscription::NONE, |subs, category| {
+                subs | category.subscription()
+            })
+    }
+}
+
+/// Where to find OBS, as in the top level of the config file or a `[profiles.<name>]` section.
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) struct Profile {
+    /// A host name or address, or `ssh://[user@]box[:port]` to tunnel to the OBS on `box`.
+    pub(crate) host: Option<String>,
+    pub(crate) port: Option<u16>,
+    /// The OBS WebSocket password itself.
+    pub(crate) password: Option<String>,
+    /// A command that prints the OBS WebSocket password, like `pass show obs`.
+    #[serde(alias = "password_command")]
+    pub(crate) password_command: Option<String>,
+}
+
+impl fmt::Debug for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The config gets logged, and the password must not end up in the log with it.
+        f.debug_struct("Profile")
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("password", &self.password.as_ref().map(|_| "<redacted>"))
+            .field("password_command", &self.password_command)
+            .finish()
+    }
+}
+
+/// The OBS WebSocket server to connect to.
+#[derive(Debug, Clone)]
+pub struct Endpoint {
+    pub host: String,
+    pub port: u16,
+}
+
+impl Endpoint {
+    /// The SSH destination to tunnel through, for `ssh://` hosts.
+    pub fn ssh_destination(&self) -> Option<&str> {
+        self.host
+            .starts_with("ssh://")
+            .then_some(self.host.as_str())
+    }
+
+    /// Whether the host is this machine, going by its name or loopback address.
+    pub fn is_local(&self) -> bool {
+        self.host.eq_ignore_ascii_case("localhost")
+            || self
+                .host
+                .trim_start_matches('[')
+                .trim_end_matches(']')
+                .parse::<IpAddr>()
+                .is_ok_and(|ip| ip.is_loopback())
+    }
+}
+
+impl Default for Endpoint {
+    fn default() -> Self {
+        Endpoint {
+            host: String::from("localhost"),
+            port: 4455,
+        }
+    }
+}
+
+impl fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.host, self.port)
+    }
+}
+
+/// A custom RTMP server to stream to, as in the `[failover]` section.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) struct Failover {
+    pub(crate) server: String,
+    #[serde(default)]
+    pub(crate) key: String,
+}
+
+/// A command for the daemon to run whenever a cron expression matches, as in a `[[schedule]]`
+/// entry.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) struct Scheduled {
+    /// When to run, like `0 20 * * FRI` for 8 PM every Friday, in local time.
+    pub(crate) cron: String,
+    /// The command line to run, like `start-stream`.
+    pub(crate) run: String,
+}
+
+impl Config {
+    /// Loads the configuration from `dir`, falling back to defaults if there is no config file.
+    pub async fn load(dir: &Path) -> anyhow::Result<Self> {
+        let path = dir.join(FILE_NAME);
+        let contents = match tokio::fs::read_to_string(&path).await {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => {
+                return Err(e).with_context(|| format!("read config file {}", path.display()))
+            }
+        };
+        toml::from_str(&contents).with_context(|| format!("parse config file {}", path.display()))
+    }
+
+    /// Loads the configuration from where obs-do keeps it, like `~/.config/obs-do` on Linux.
+    pub async fn load_default() -> anyhow::Result<Self> {
+        Self::load(crate::project_dirs()?.config_dir()).await
+    }
+
+    /// Where to connect to OBS, from the given profile if any.
+    ///
+    /// Settings the profile leaves out are taken from the top level of the config file.
+    pub fn endpoint(&self, profile: Option<&str>) -> anyhow::Result<Endpoint> {
+        let profile = self.profile(profile)?;
+        let default = Endpoint::default();
+        let host = profile
+            .and_then(|p| p.host.as_ref())
+            .or(self.connection.host.as_ref());
+        let port = profile.and_then(|p| p.port).or(self.connection.port);
+        Ok(Endpoint {
+            host: host.cloned().unwrap_or(default.host),
+            port: port.unwrap_or(default.port),
+        })
+    }
+
+    /// The profile section called `name`, if a name is given.
+    pub(crate) fn profile(&self, name: Option<&str>) -> anyhow::Result<Option<&Profile>> {
+        name.map(|name| {
+            self.profiles
+                .get(name)
+                .with_context(|| format!("no profile named {name} in the config file"))
+        })
+        .transpose()
+    }
+}
+
+/// Watches the config file in `dir` for as long as obs-do runs, so that long-running modes pick
+/// up changes without reconnecting to OBS.
+///
+/// New aliases apply to every command parsed from then on; modes that use other settings learn
+/// about them from the returned receiver. Macros need neither, as they are read whenever they
+/// run. An edit that breaks the file is reported and otherwise ignored, so the last working
+/// configuration stays in effect.
+pub(crate) async fn watch(dir: &Path) -> anyhow::Result<watch::Receiver<Arc<Config>>> {
+    let (reloaded, reloads) = watch::channel(Arc::new(Config::load(dir).await?));
+    let dir = dir.to_owned();
+    tokio::spawn(async move {
+        let path = dir.join(FILE_NAME);
+        let modified = || async { tokio::fs::metadata(&path).await.ok()?.modified().ok() };
+        let mut last_modified: Option<SystemTime> = modified().await;
+        let mut ticks = tokio::time::interval(WATCH_INTERVAL);
+        loop {
+            ticks.tick().await;
+            let now_modified = modified().await;
+            if now_modified == last_modified {
+                continue;
+            }
+            last_modified = now_modified;
+            match Config::load(&dir).await {
+                Ok(config) => {
+                    tracing::info!("Reloaded {}.", path.display());
+                    crate::alias::set(config.aliases.clone());
+                    reloaded.send_replace(Arc::new(config));
+                }
+                Err(e) => tracing::warn!("Keeping the previous configuration: {e:#}"),
+            }
+        }
+    });
+    Ok(reloads)
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub(crate) enum ConfigCommand {
+    /// Asks where OBS runs and for its WebSocket password, checks that they work, and saves them.
+    ///
+    /// The host and port go into the config file, and the password into the `websocket-token`
+    /// file, which only the current user can read.
+    Init,
+}
+
+pub(crate) async fn run(cmd: &ConfigCommand, out: Output) -> anyhow::Result<()> {
+    match cmd {
+        ConfigCommand::Init => init(out).await,
+    }
+}
+
+async fn init(out: Output) -> anyhow::Result<()> {
+    crate::prompt::ensure_interactive("write config.toml and websocket-token by hand")?;
+    let dirs = crate::project_dirs()?;
+    let current = Config::load(dirs.config_dir()).await?.endpoint(None)?;
+
+    let endpoint = loop {
+        let host = crate::prompt::ask(format!("OBS host [{}]: ", current.host)).await?;
+        let host = match host.trim() {
+            "" => current.host.clone(),
+            host => host.to_owned(),
+        };
+        let port = crate::prompt::ask(format!("OBS WebSocket port [{}]: ", current.port)).await?;
+        let port = match port.trim() {
+            "" => current.port,
+            port => match port.parse() {
+                Ok(port) => port,
+                Err(_) => {
+                    eprintln!("{port} is not a port number.");
+                    continue;
+                }
+            },
+        };
+        break Endpoint { host, port };
+    };
+    anyhow::ensure!(
+        endpoint.ssh_destination().is_none(),
+        "SSH tunnels can't be tested here; put the host in the config file by hand"
+    );
+    let password = tokio::task::spawn_blocking(|| {
+        rpassword::prompt_password("OBS WebSocket password (enter for none): ")
+    })
+    .await
+    .context("read password")?
+    .context("read password")?;
+    let password = Some(password).filter(|password| !password.is_empty());
+
+    eprintln!("Connecting to OBS at {endpoint}...");
+    match crate::connect_with(&endpoint, password.clone(), EventSubscription::NONE).await {
+        Ok(_) => eprintln!("Connected."),
+        Err(e) => {
+            eprintln!("{e:#}");
+            let answer = crate::prompt::ask("Save these settings anyway? [y/N] ").await?;
+            if !answer.trim().eq_ignore_ascii_case("y") {
+                anyhow::bail!("nothing was written");
+            }
+        }
+    }
+
+    let dir = dirs.config_dir();
+    tokio::fs::create_dir_all(dir)
+        .await
+        .with_context(|| format!("create {}", dir.display()))?;
+    let path = dir.join(FILE_NAME);
+    let contents = match tokio::fs::read_to_string(&path).await {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e).with_context(|| format!("read config file {}", path.display())),
+    };
+    let contents = with_endpoint(&contents, &endpoint)
+        .with_context(|| format!("parse config file {}", path.display()))?;
+    tokio::fs::write(&path, contents)
+        .await
+        .with_context(|| format!("write config file {}", path.display()))?;
+
+    let token = dir.join(crate::password::TOKEN_FILE);
+    match &password {
+        Some(password) => write_private(&token, password).await?,
+        None => match tokio::fs::remove_file(&token).await {
+            Ok(()) => tracing::info!("Removed {}, as OBS needs no password.", token.display()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e).with_context(|| format!("remove {}", token.display())),
+        },
+    }
+
+    out.result(
+        format!("Saved the connection to {endpoint} in {}", path.display()),
+        &json!({
+            "config": path,
+            "host": endpoint.host,
+            "port": endpoint.port,
+            "password": password.is_some(),
+        }),
+    )
+}
+
+/// Sets the host and port in the config file `contents`, keeping the rest of it as it was,
+/// comments and all.
+fn with_endpoint(contents: &str, endpoint: &Endpoint) -> anyhow::Result<String> {
+    let mut config: toml_edit::DocumentMut = contents.parse()?;
+    config["host"] = toml_edit::value(endpoint.host.as_str());
+    config["port"] = toml_edit::value(i64::from(endpoint.port));
+    Ok(config.to_string())
+}
+
+/// Writes `contents` to `path` such that only the current user can read it.
+async fn write_private(path: &Path, contents: &str) -> anyhow::Result<()> {
+    let mut options = tokio::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    let mut file = options
+        .open(path)
+        .await
+        .with_context(|| format!("create {}", path.display()))?;
+    // The mode only applies to new files, so tighten up one that was already there.
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))
+            .await
+            .with_context(|| format!("restrict permissions of {}", path.display()))?;
+    }
+    file.write_all(contents.as_bytes())
+        .await
+        .with_context(|| format!("write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(toml: &str) -> Config {
+        toml::from_str(toml).unwrap()
+    }
+
+    #[test]
+    fn default_endpoint() {
+        let endpoint = Config::default().endpoint(None).unwrap();
+        assert_eq!(endpoint.to_string(), "localhost:4455");
+    }
+
+    #[test]
+    fn profiles_fall_back_to_the_top_level() {
+        let config = parse(
+            r#"
+            host = "obs.local"
+            port = 4456
+
+            [profiles.backup]
+            host = "backup.local"
+            "#,
+        );
+        assert_eq!(config.endpoint(None).unwrap().to_string(), "obs.local:4456");
+        assert_eq!(
+            config.endpoint(Some("backup")).unwrap().to_string(),
+            "backup.local:4456"
+        );
+        assert!(config.endpoint(Some("missing")).is_err());
+    }
+
+    #[test]
+    fn ssh_hosts() {
+        let config = parse(r#"host = "ssh://stream@studio""#);
+        let endpoint = config.endpoint(None).unwrap();
+        assert_eq!(endpoint.ssh_destination(), Some("ssh://stream@studio"));
+        assert_eq!(Endpoint::default().ssh_destination(), None);
+        assert!(!endpoint.is_local());
+    }
+
+    #[test]
+    fn local_hosts() {
+        for (host, local) in [
+            ("localhost", true),
+            ("127.0.0.1", true),
+            ("[::1]", true),
+            ("::1", true),
+            ("obs.local", false),
+            ("192.168.1.20", false),
+        ] {
+            let endpoint = Endpoint {
+                host: String::from(host),
+                port: 4455,
+            };
+            assert_eq!(endpoint.is_local(), local, "{host}");
+        }
+    }
+
+    #[test]
+    fn event_categories() {
+        let config = parse(r#"events = ["scenes", "input-volume-meters"]"#);
+        assert_eq!(
+            EventCategory::subscriptions(&config.events.unwrap()),
+            EventSubscription::SCENES | EventSubscription::INPUT_VOLUME_METERS
+        );
+        assert_eq!(EventCategory::subscriptions(&[]), EventSubscription::NONE);
+    }
+
+    #[tokio::test]
+    async fn watch_reloads_changes() {
+        let dir = std::env::temp_dir().join(format!("obs-do-watch-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(FILE_NAME), "[aliases]\nbrb = \"set-scene BRB\"\n").unwrap();
+        let mut reloads = watch(&dir).await.unwrap();
+        assert_eq!(reloads.borrow().aliases["brb"], "set-scene BRB");
+        // Rewrite the file until its mtime changes, as the watch goes by that, which filesystems
+        // with coarse timestamps leave the same for writes in quick succession.
+        let modified = || {
+            std::fs::metadata(dir.join(FILE_NAME))
+                .unwrap()
+                .modified()
+                .unwrap()
+        };
+        let before = modified();
+        while modified() == before {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            std::fs::write(dir.join(FILE_NAME), "read-only = true\n").unwrap();
+        }
+        tokio::time::timeout(WATCH_INTERVAL * 3, reloads.changed())
+            .await
+            .expect("the change was not noticed")
+            .unwrap();
+        assert!(reloads.borrow().read_only);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn init_keeps_comments() {
+        let endpoint = Endpoint {
+            host: String::from("obs.local"),
+            port: 4456,
+        };
+        let contents = with_endpoint(
+            "# Where OBS runs.\nhost = \"localhost\"\n\n[aliases]\n# Be right back.\nbrb = \"set-scene BRB\"\n",
+            &endpoint,
+        )
+        .unwrap();
+        assert_eq!(
+            contents,
+            "# Where OBS runs.\nhost = \"obs.local\"\nport = 4456\n\n[aliases]\n# Be right back.\nbrb = \"set-scene BRB\"\n"
+        );
+        assert_eq!(
+            parse(&contents).endpoint(None).unwrap().to_string(),
+            "obs.local:4456"
+        );
+        assert_eq!(
+            with_endpoint("", &endpoint).unwrap(),
+            "host = \"obs.local\"\nport = 4456\n"
+        );
+    }
+
+    #[test]
+    fn password_command_spellings() {
+        for toml in [
+            r#"password-command = "pass show obs""#,
+            r#"password_command = "pass show obs""#,
+        ] {
+            assert_eq!(
+                parse(toml).connection.password_command.as_deref(),
+                Some("pass show obs")
+            );
+        }
+    }
+
+    #[test]
+    fn profile_passwords() {
+        let config = parse(
+            r#"
+            password-command = "pass show obs"
+
+            [profiles.backup]
+            host = "backup.local"
+            password = "hunter2"
+            "#,
+        );
+        let backup = config.profile(Some("backup")).unwrap().unwrap();
+        assert_eq!(backup.password.as_deref(), Some("hunter2"));
+        assert_eq!(backup.password_command, None);
+        assert!(!format!("{config:?}").contains("hunter2"));
+        assert!(config.profile(None).unwrap().is_none());
+        assert!(config.profile(Some("missing")).is_err());
+    }
+}
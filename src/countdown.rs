@@ -0,0 +1,112 @@
+use anyhow::Context;
+use obws::Client;
+use serde_json::json;
+use std::time::Duration;
+use tokio::time::{Instant, MissedTickBehavior};
+
+use crate::output::Output;
+
+#[derive(Debug, Clone, clap::Args)]
+pub(crate) struct CountdownArgs {
+    /// How long to count down for, like `5m`.
+    duration: String,
+
+    /// The scene to show while counting down, like `Starting Soon`.
+    #[arg(long, value_name = "SCENE")]
+    scene: Option<String>,
+
+    /// The text source to show the time left in, like `Countdown`.
+    #[arg(long, value_name = "INPUT")]
+    text_source: Option<String>,
+
+    /// The scene to switch to once the countdown is over.
+    #[arg(long, value_name = "SCENE")]
+    live: Option<String>,
+
+    /// Start streaming once the countdown is over, unless OBS is streaming already.
+    #[arg(long)]
+    start_stream: bool,
+}
+
+/// Counts down, showing the time left in a text source every second, and goes live once the
+/// countdown is over. Interrupting it stops the countdown without going live.
+pub(crate) async fn run(client: &Client, args: &CountdownArgs, out: Output) -> anyhow::Result<()> {
+    let duration = crate::parse_duration(&args.duration)?;
+    if let Some(scene) = &args.scene {
+        client
+            .scenes()
+            .set_current_program_scene(scene)
+            .await
+            .with_context(|| format!("set-scene {scene}"))?;
+    }
+
+    let end = Instant::now() + duration;
+    let mut ticks = tokio::time::interval(Duration::from_secs(1));
+    ticks.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    loop {
+        let left = end.saturating_duration_since(Instant::now());
+        if let Some(input) = &args.text_source {
+            crate::text::set_text(client, input, &remaining(left)).await?;
+        }
+        if left.is_zero() {
+            break;
+        }
+        // Waking up at the end keeps the last second from lasting longer than the others.
+        tokio::select! {
+            _ = ticks.tick() => {}
+            () = tokio::time::sleep_until(end) => {}
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+        }
+    }
+
+    if let Some(scene) = &args.live {
+        client
+            .scenes()
+            .set_current_program_scene(scene)
+            .await
+            .with_context(|| format!("set-scene {scene}"))?;
+        tracing::info!("Switched to {scene}.");
+    }
+    let mut streaming = client
+        .streaming()
+        .status()
+        .await
+        .context("get stream status")?
+        .active;
+    if args.start_stream && !streaming {
+        client
+            .streaming()
+            .start()
+            .await
+            .context("start streaming")?;
+        crate::notify::outcome("Stream started", "").await;
+        streaming = true;
+    }
+    out.json(&json!({ "scene": args.live, "streaming": streaming }))
+}
+
+/// The time left as shown on screen, like `4:59` or `1:00:00`, rounded up so that `0:00` only
+/// shows once the time is up.
+fn remaining(left: Duration) -> String {
+    let secs = left.as_secs() + u64::from(left.subsec_nanos() > 0);
+    let (hours, mins, secs) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if hours > 0 {
+        format!("{hours}:{mins:02}:{secs:02}")
+    } else {
+        format!("{mins}:{secs:02}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_time_left() {
+        assert_eq!(remaining(Duration::from_secs(300)), "5:00");
+        assert_eq!(remaining(Duration::from_millis(299_001)), "5:00");
+        assert_eq!(remaining(Duration::from_secs(59)), "0:59");
+        assert_eq!(remaining(Duration::from_secs(3600)), "1:00:00");
+        assert_eq!(remaining(Duration::ZERO), "0:00");
+    }
+}
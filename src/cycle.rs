@@ -0,0 +1,155 @@
+use anyhow::Context;
+use obws::Client;
+use regex::Regex;
+use serde_json::json;
+
+use crate::output::Output;
+
+#[derive(Debug, Clone, clap::Args)]
+pub(crate) struct CycleArgs {
+    /// Only switch between scenes whose name matches this regular expression.
+    #[arg(long, value_name = "REGEX", value_parser = Regex::new)]
+    filter: Option<Regex>,
+}
+
+/// Which way to move through the scene list.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Direction {
+    Next,
+    Previous,
+}
+
+/// Switches to the scene below (or above) the live one, in the order of OBS' scene list,
+/// wrapping around at either end.
+///
+/// With a filter, scenes that don't match it are skipped. If the live scene doesn't match, the
+/// switch goes to the nearest matching scene in the given direction.
+pub(crate) async fn run(
+    client: &Client,
+    direction: Direction,
+    args: &CycleArgs,
+    out: Output,
+) -> anyhow::Result<()> {
+    let (names, current) = scenes(client).await?;
+    let current = current.context("OBS has no program scene")?;
+    let from = names
+        .iter()
+        .position(|name| *name == current)
+        .with_context(|| format!("live scene {current} is not in the scene list"))?;
+    let n = names.len();
+    let scene = (1..n)
+        .map(|step| match direction {
+            Direction::Next => (from + step) % n,
+            Direction::Previous => (from + n - step) % n,
+        })
+        .map(|i| &names[i])
+        .find(|name| match &args.filter {
+            Some(filter) => filter.is_match(name),
+            None => true,
+        })
+        .context("there is no other scene to switch to")?;
+
+    client
+        .scenes()
+        .set_current_program_scene(scene)
+        .await
+        .with_context(|| format!("set-scene {scene}"))?;
+    out.json(&json!({ "scene": scene }))
+}
+
+/// The names of all scenes, from the top of OBS' scene list down, and the live scene.
+pub(crate) async fn scenes(client: &Client) -> anyhow::Result<(Vec<String>, Option<String>)> {
+    let list = client.scenes().list().await.context("list scenes")?;
+    // OBS numbers its scenes from the bottom of the list up.
+    let mut scenes = list.scenes;
+    scenes.sort_by_key(|scene| std::cmp::Reverse(scene.index));
+    let names = scenes.into_iter().map(|scene| scene.name).collect();
+    Ok((names, list.current_program_scene_name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    /// Cycles from `live` through OBS' scene list, and returns the scene it switched to.
+    async fn cycle(
+        live: &str,
+        direction: Direction,
+        filter: Option<&str>,
+    ) -> anyhow::Result<Value> {
+        let live = live.to_owned();
+        let (client, _) = crate::fake_obs::connect(move |kind, _| {
+            Some(match kind {
+                // OBS lists its scenes from the bottom up.
+                "GetSceneList" => json!({
+                    "currentProgramSceneName": live,
+                    "currentPreviewSceneName": null,
+                    "scenes": [
+                        { "sceneName": "Outro", "sceneIndex": 0 },
+                        { "sceneName": "Game", "sceneIndex": 1 },
+                        { "sceneName": "Camera", "sceneIndex": 2 },
+                        { "sceneName": "Intro", "sceneIndex": 3 },
+                    ],
+                }),
+                "SetCurrentProgramScene" => Value::Null,
+                _ => return None,
+            })
+        })
+        .await;
+        let args = CycleArgs {
+            filter: filter.map(|filter| Regex::new(filter).unwrap()),
+        };
+        let (res, results) =
+            Output::capture(run(&client, direction, &args, Output::new(true))).await;
+        res.map(|()| results[0]["scene"].clone())
+    }
+
+    #[tokio::test]
+    async fn cycles_in_list_order() {
+        assert_eq!(
+            cycle("Intro", Direction::Next, None).await.unwrap(),
+            "Camera"
+        );
+        assert_eq!(
+            cycle("Outro", Direction::Next, None).await.unwrap(),
+            "Intro"
+        );
+        assert_eq!(
+            cycle("Intro", Direction::Previous, None).await.unwrap(),
+            "Outro"
+        );
+        assert_eq!(
+            cycle("Game", Direction::Previous, None).await.unwrap(),
+            "Camera"
+        );
+    }
+
+    #[tokio::test]
+    async fn cycles_through_matching_scenes() {
+        let filter = Some("^(Intro|Game)$");
+        assert_eq!(
+            cycle("Intro", Direction::Next, filter).await.unwrap(),
+            "Game"
+        );
+        assert_eq!(
+            cycle("Game", Direction::Next, filter).await.unwrap(),
+            "Intro"
+        );
+        // The live scene doesn't match, so the nearest one that does is next.
+        assert_eq!(
+            cycle("Camera", Direction::Next, filter).await.unwrap(),
+            "Game"
+        );
+        assert_eq!(
+            cycle("Camera", Direction::Previous, filter).await.unwrap(),
+            "Intro"
+        );
+        let e = cycle("Intro", Direction::Next, Some("^Intro$"))
+            .await
+            .unwrap_err();
+        assert_eq!(e.to_string(), "there is no other scene to switch to");
+        let e = cycle("Hidden", Direction::Next, None).await.unwrap_err();
+        assert_eq!(e.to_string(), "live scene Hidden is not in the scene list");
+    }
+}
@@ -0,0 +1,654 @@
+use obws::Client;
+use std::sync::Arc;
+use tokio::sync::watch;
+
+use crate::config::{Config, Scheduled};
+
+#[derive(Debug, Clone, clap::Args)]
+pub(crate) struct DaemonArgs {
+    /// The most commands to send to OBS per second.
+    ///
+    /// Commands that arrive faster wait in a queue, where a newer `set-scene`, `set-volume`,
+    /// `set-text` or `fade-input` replaces a waiting one for the same scene or input. That way a
+    /// flood of fader moves from a MIDI controller only sends OBS the latest value.
+    #[arg(long, value_name = "PER_SECOND", default_value_t = 20.)]
+    max_rate: f32,
+
+    /// How long a scene must stay live before `set-scene` may switch away from it.
+    ///
+    /// Scene changes sent sooner are refused, which absorbs accidental double presses of a
+    /// hotkey and keeps rapid switching from strobing on air.
+    #[arg(long, value_name = "DURATION", default_value = "0s")]
+    min_scene_hold: String,
+
+    /// Overrides `--min-scene-hold` for one scene (may be repeated).
+    #[arg(long, value_name = "SCENE=DURATION")]
+    scene_hold: Vec<String>,
+
+    /// Also answer health checks over HTTP at `http://<ADDR>/healthz`, like `127.0.0.1:9408`.
+    ///
+    /// The response is the same JSON as the `health` command, with status 503 while OBS can't be
+    /// reached.
+    #[arg(long, value_name = "ADDR")]
+    health_listen: Option<String>,
+}
+
+#[cfg(not(unix))]
+pub(crate) async fn run(
+    _client: Client,
+    _args: &DaemonArgs,
+    _schedule: &[Scheduled],
+    _reloads: watch::Receiver<Arc<Config>>,
+    _flags: crate::Flags,
+) -> anyhow::Result<()> {
+    anyhow::bail!("the daemon is only supported on Unix-like systems")
+}
+
+#[cfg(unix)]
+pub(crate) use unix::run;
+
+#[cfg(unix)]
+mod unix {
+    use super::*;
+    use anyhow::Context;
+    use futures_util::{
+        stream::{FuturesOrdered, FuturesUnordered},
+        StreamExt,
+    };
+    use obws::events::Event;
+    use serde_json::json;
+    use std::{
+        collections::{HashMap, VecDeque},
+        future::Future,
+        sync::{
+            atomic::{AtomicBool, AtomicUsize, Ordering},
+            Mutex, RwLock,
+        },
+        time::{Duration, Instant},
+    };
+    use tokio::{
+        io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+        net::{TcpListener, TcpStream, UnixListener, UnixStream},
+        sync::{oneshot, Notify},
+        time::MissedTickBehavior,
+    };
+
+    use crate::{output::Output, Command};
+
+    /// Name of the socket the daemon listens on, within the runtime directory.
+    const SOCKET_NAME: &str = "daemon.sock";
+
+    /// The line that asks the daemon about its health instead of running a command.
+    const HEALTH: &str = "health";
+
+    /// How long a health check waits for OBS to answer before calling it unreachable.
+    const HEALTH_PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+    /// How long each scene must stay live, as set by `--min-scene-hold` and `--scene-hold`.
+    #[derive(Debug)]
+    struct SceneHold {
+        default: Duration,
+        per_scene: HashMap<String, Duration>,
+    }
+
+    impl SceneHold {
+        fn parse(args: &DaemonArgs) -> anyhow::Result<Self> {
+            let default = crate::parse_duration(&args.min_scene_hold)?;
+            let per_scene = args
+                .scene_hold
+                .iter()
+                .map(|hold| {
+                    let (scene, duration) = hold.rsplit_once('=').ok_or_else(|| {
+                        anyhow::anyhow!("invalid --scene-hold `{hold}`, expected SCENE=DURATION")
+                    })?;
+                    Ok((scene.to_owned(), crate::parse_duration(duration)?))
+                })
+                .collect::<anyhow::Result<_>>()?;
+            Ok(SceneHold { default, per_scene })
+        }
+
+        fn of(&self, scene: &str) -> Duration {
+            self.per_scene.get(scene).copied().unwrap_or(self.default)
+        }
+    }
+
+    /// Listens for commands on the daemon socket and runs them, along with the `schedule` from
+    /// the config file as it comes due, until interrupted.
+    ///
+    /// The schedule and the settings that `flags` leave to the config file are replaced whenever
+    /// it changes, as announced on `reloads`, and the connection to OBS is made again whenever
+    /// it's lost. In read-only mode, mutating commands are refused while OBS is streaming, just
+    /// as they would be on the command line.
+    pub(crate) async fn run(
+        client: Client,
+        args: &DaemonArgs,
+        schedule: &[Scheduled],
+        mut reloads: watch::Receiver<Arc<Config>>,
+        flags: crate::Flags,
+    ) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            args.max_rate.is_finite() && args.max_rate > 0.,
+            "--max-rate must be a positive number"
+        );
+        let hold = SceneHold::parse(args)?;
+        let mut schedule = crate::schedule::Schedule::new(schedule)?;
+        // Commands aren't the only thing that starts streams and saves recordings here.
+        crate::notify::follow_events();
+        let dir = crate::runtime_dir()?;
+        create_private_dir(&dir).await?;
+        let path = dir.join(SOCKET_NAME);
+        if UnixStream::connect(&path).await.is_ok() {
+            anyhow::bail!("another daemon is already listening on {}", path.display());
+        }
+        // Whoever left this socket behind is gone.
+        let _ = tokio::fs::remove_file(&path).await;
+        let listener =
+            UnixListener::bind(&path).with_context(|| format!("listen on {}", path.display()))?;
+        tracing::info!("Listening for commands on {}.", path.display());
+        let health_listener = match &args.health_listen {
+            Some(addr) => {
+                let listener = TcpListener::bind(addr)
+                    .await
+                    .with_context(|| format!("listen on {addr}"))?;
+                tracing::info!("Answering health checks on http://{addr}/healthz.");
+                Some(listener)
+            }
+            None => None,
+        };
+
+        let mut config = reloads.borrow_and_update().clone();
+        let read_only = AtomicBool::new(flags.read_only(&config));
+        let queue = Queue::new(client);
+        let mut worker = std::pin::pin!(queue.work(args.max_rate, &hold, &read_only));
+        let mut connections = FuturesUnordered::new();
+        let mut health_checks = FuturesUnordered::new();
+        let mut scheduled = FuturesUnordered::new();
+        if !schedule.is_empty() {
+            tracing::info!("Running {} scheduled commands.", schedule.len());
+        }
+        let res = loop {
+            tokio::select! {
+                accepted = listener.accept() => match accepted {
+                    Ok((stream, _)) => connections.push(serve(stream, &queue)),
+                    Err(e) => tracing::warn!("Failed to accept connection: {e}"),
+                },
+                Some(accepted) = accept(health_listener.as_ref()) => match accepted {
+                    Ok((stream, _)) => health_checks.push(serve_health(stream, &queue)),
+                    Err(e) => tracing::warn!("Failed to accept health check: {e}"),
+                },
+                Some(res) = connections.next() => {
+                    if let Err(e) = res {
+                        tracing::warn!("Connection failed: {e:#}");
+                    }
+                }
+                Some(res) = health_checks.next() => {
+                    if let Err(e) = res {
+                        tracing::warn!("Health check failed: {e:#}");
+                    }
+                }
+                due = schedule.next() => {
+                    for line in due {
+                        scheduled.push(run_scheduled(&queue, line));
+                    }
+                }
+                Some(()) = scheduled.next() => {}
+                Ok(()) = reloads.changed() => {
+                    let reloaded = reloads.borrow_and_update().clone();
+                    read_only.store(flags.reload(&config, &reloaded), Ordering::Relaxed);
+                    config = reloaded;
+                    match crate::schedule::Schedule::new(&config.schedule) {
+                        Ok(reloaded) => {
+                            schedule = reloaded;
+                            tracing::info!("Now running {} scheduled commands.", schedule.len());
+                        }
+                        Err(e) => tracing::warn!("Keeping the previous schedule: {e:#}"),
+                    }
+                }
+                res = &mut worker => break res,
+                _ = tokio::signal::ctrl_c() => break Ok(()),
+            }
+        };
+
+        let _ = tokio::fs::remove_file(&path).await;
+        res
+    }
+
+    /// Runs a command from the schedule through the queue like any other; failures are only
+    /// logged, since there's no one to tell.
+    async fn run_scheduled(queue: &Queue, line: String) {
+        tracing::info!("Running scheduled `{line}`.");
+        let reply = queue.submit(&line).await;
+        if let Some(error) = reply.get("error") {
+            tracing::warn!("Scheduled `{line}` failed: {error}");
+        }
+    }
+
+    /// Creates `dir`, making sure that only the current user can reach the socket within.
+    async fn create_private_dir(dir: &std::path::Path) -> anyhow::Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        tokio::fs::create_dir_all(dir)
+            .await
+            .with_context(|| format!("create {}", dir.display()))?;
+        tokio::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o700))
+            .await
+            .with_context(|| format!("restrict access to {}", dir.display()))
+    }
+
+    /// Reads commands from a client and answers each with a line of JSON, in order.
+    ///
+    /// Commands are queued as soon as they are read rather than one at a time, so that a client
+    /// streaming updates benefits from coalescing.
+    async fn serve(stream: UnixStream, queue: &Queue) -> anyhow::Result<()> {
+        let (read, mut write) = stream.into_split();
+        let mut lines = BufReader::new(read).lines();
+        let mut replies = FuturesOrdered::new();
+        let mut reading = true;
+        while reading || !replies.is_empty() {
+            tokio::select! {
+                line = lines.next_line(), if reading => match line.context("read command")? {
+                    Some(line) if line.trim().is_empty() => {}
+                    Some(line) => replies.push_back(queue.submit(&line)),
+                    None => reading = false,
+                },
+                Some(reply) = replies.next() => {
+                    let mut reply = serde_json::to_vec(&reply).context("serialize reply")?;
+                    reply.push(b'\n');
+                    write.write_all(&reply).await.context("write reply")?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Waits for the next connection on `listener`, if there is one to listen on at all.
+    async fn accept(
+        listener: Option<&TcpListener>,
+    ) -> Option<std::io::Result<(TcpStream, std::net::SocketAddr)>> {
+        match listener {
+            Some(listener) => Some(listener.accept().await),
+            None => std::future::pending().await,
+        }
+    }
+
+    /// Answers a single HTTP request for `/healthz`.
+    async fn serve_health(stream: TcpStream, queue: &Queue) -> anyhow::Result<()> {
+        let mut stream = BufReader::new(stream);
+        let request = crate::http::read_request(&mut stream).await?;
+        let (status, body) = match request.path.as_str() {
+            "/healthz" => {
+                let health = queue.health().await;
+                let status = if health["connected"] == true {
+                    "200 OK"
+                } else {
+                    "503 Service Unavailable"
+                };
+                (status, health.to_string())
+            }
+            _ => ("404 Not Found", json!({ "error": "not found" }).to_string()),
+        };
+        crate::http::respond(stream.get_mut(), status, "application/json", &body).await
+    }
+
+    /// Parses one line sent to the daemon into a command, along with the words it was given as.
+    fn parse(line: &str) -> anyhow::Result<(Command, Vec<String>)> {
+        let words = shell_words::split(line).context("split command into arguments")?;
+        let cmd = crate::parse_chained(words.clone())?;
+        anyhow::ensure!(
+            cmd.is_servable(),
+            "this command cannot be sent to the daemon"
+        );
+        Ok((cmd, words))
+    }
+
+    /// A command waiting to be sent to OBS.
+    struct Queued {
+        cmd: Command,
+        /// The words the command was given as, to match names in with `--fuzzy` once it's run.
+        words: Vec<String>,
+        reply: oneshot::Sender<serde_json::Value>,
+    }
+
+    struct Queue {
+        /// The connection to OBS, which is replaced when it has to be made again.
+        client: RwLock<Arc<Client>>,
+        waiting: Mutex<VecDeque<Queued>>,
+        added: Notify,
+        /// How many commands have been sent to OBS and haven't finished yet.
+        running: AtomicUsize,
+        /// Whether OBS is still sending events, which stops when the connection is lost.
+        events_open: AtomicBool,
+        /// The type of the last event from OBS, and when it arrived.
+        last_event: Mutex<Option<(String, Instant)>>,
+    }
+
+    impl Queue {
+        fn new(client: Client) -> Self {
+            Queue {
+                client: RwLock::new(Arc::new(client)),
+                waiting: Mutex::default(),
+                added: Notify::new(),
+                running: AtomicUsize::new(0),
+                events_open: AtomicBool::new(false),
+                last_event: Mutex::default(),
+            }
+        }
+
+        /// The current connection to OBS.
+        fn client(&self) -> Arc<Client> {
+            self.client
+                .read()
+                .expect("client lock is never poisoned")
+                .clone()
+        }
+
+        /// Queues the command in `line`, and returns the reply to send once it has run.
+        ///
+        /// `health` is answered right away instead, without waiting its turn.
+        fn submit<'a>(&'a self, line: &str) -> impl Future<Output = serde_json::Value> + 'a {
+            let reply = (line.trim() != HEALTH)
+                .then(|| parse(line).map(|(cmd, words)| self.push(cmd, words)));
+            async move {
+                match reply {
+                    None => self.health().await,
+                    Some(Ok(reply)) => reply.await.unwrap_or_else(|_| {
+                        crate::output::error_json(&anyhow::anyhow!("the daemon shut down"))
+                    }),
+                    Some(Err(e)) => crate::output::error_json(&e),
+                }
+            }
+        }
+
+        /// Reports whether OBS can be reached, what it last sent, and how much work is waiting,
+        /// so that a daemon that's stuck can be told apart from an OBS that's down.
+        async fn health(&self) -> serde_json::Value {
+            let client = self.client();
+            let reachable = matches!(
+                tokio::time::timeout(HEALTH_PROBE_TIMEOUT, client.general().version()).await,
+                Ok(Ok(_))
+            );
+            let last_event = self
+                .last_event
+                .lock()
+                .expect("health lock is never poisoned")
+                .as_ref()
+                .map(|(kind, at)| json!({ "type": kind, "secs_ago": at.elapsed().as_secs_f64() }));
+            let queue_depth = self
+                .waiting
+                .lock()
+                .expect("queue lock is never poisoned")
+                .len();
+            json!({
+                "ok": true,
+                "connected": reachable && self.events_open.load(Ordering::Relaxed),
+                "last_event": last_event,
+                "queue_depth": queue_depth,
+                "running": self.running.load(Ordering::Relaxed),
+            })
+        }
+
+        fn push(&self, cmd: Command, words: Vec<String>) -> oneshot::Receiver<serde_json::Value> {
+            let (tx, rx) = oneshot::channel();
+            let queued = Queued {
+                cmd,
+                words,
+                reply: tx,
+            };
+            let mut waiting = self.waiting.lock().expect("queue lock is never poisoned");
+            let key = coalesce_key(&queued.cmd);
+            // The newer command takes its own place in line rather than the superseded one's, so
+            // that it still runs after whatever was queued in between.
+            let superseded = key.as_ref().and_then(|key| {
+                let at = waiting
+                    .iter()
+                    .position(|waiting| coalesce_key(&waiting.cmd).as_ref() == Some(key))?;
+                waiting.remove(at)
+            });
+            if let Some(superseded) = superseded {
+                tracing::debug!(cmd = ?queued.cmd, "coalescing with a queued command");
+                let _ = superseded
+                    .reply
+                    .send(json!({ "ok": true, "coalesced": true }));
+            }
+            waiting.push_back(queued);
+            drop(waiting);
+            self.added.notify_one();
+            rx
+        }
+
+        async fn next(&self) -> Queued {
+            loop {
+                let next = self
+                    .waiting
+                    .lock()
+                    .expect("queue lock is never poisoned")
+                    .pop_front();
+                if let Some(next) = next {
+                    return next;
+                }
+                self.added.notified().await;
+            }
+        }
+
+        /// Sends queued commands to OBS, no more than `max_rate` per second.
+        ///
+        /// Commands are started in order but run concurrently, so that a long fade doesn't hold
+        /// up everything queued behind it. When OBS goes away, this waits for it to come back,
+        /// and commands stay queued until it does.
+        async fn work(
+            &self,
+            max_rate: f32,
+            hold: &SceneHold,
+            read_only: &AtomicBool,
+        ) -> anyhow::Result<()> {
+            let events = self.client().events().context("subscribe to OBS events")?;
+            let mut events = Box::pin(events);
+            self.events_open.store(true, Ordering::Relaxed);
+            let mut slots = tokio::time::interval(Duration::from_secs_f32(1. / max_rate));
+            slots.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            let mut running = FuturesUnordered::new();
+            // The scene that went live last, and when; scenes switched to elsewhere count too.
+            let mut live: Option<(String, Instant)> = None;
+            loop {
+                let dispatch = async {
+                    slots.tick().await;
+                    self.next().await
+                };
+                tokio::select! {
+                    queued = dispatch => {
+                        let switch = match &queued.cmd {
+                            Command::SetScene { scene } => Some(scene.as_str()),
+                            // Where these land is only known once they run, so the scene change
+                            // event records it instead.
+                            Command::NextScene { .. } | Command::PrevScene { .. } => {
+                                Some("another scene")
+                            }
+                            _ => None,
+                        };
+                        if let Some(e) = switch.and_then(|scene| held(live.as_ref(), scene, hold)) {
+                            let _ = queued.reply.send(crate::output::error_json(&e));
+                            continue;
+                        }
+                        // The switch counts from when it is sent, so that those queued behind
+                        // it are held too, but is taken back if it fails.
+                        let undo = match &queued.cmd {
+                            Command::SetScene { scene } => {
+                                let switched = (scene.clone(), Instant::now());
+                                Some((live.replace(switched.clone()), switched))
+                            }
+                            _ => None,
+                        };
+                        let client = self.client();
+                        let read_only = read_only.load(Ordering::Relaxed);
+                        running.push(async move {
+                            let ok = execute(&client, queued, read_only).await;
+                            undo.filter(|_| !ok)
+                        });
+                        self.running.store(running.len(), Ordering::Relaxed);
+                    }
+                    Some(failed) = running.next() => {
+                        self.running.store(running.len(), Ordering::Relaxed);
+                        if let Some((previous, switched)) = failed {
+                            // Unless another scene has gone live since.
+                            if live.as_ref() == Some(&switched) {
+                                live = previous;
+                            }
+                        }
+                    }
+                    event = events.next() => {
+                        let Some(event) = event else {
+                            // The events also stop when they come faster than they are read, with
+                            // OBS still there.
+                            if let Ok(resubscribed) = self.client().events() {
+                                tracing::warn!("Missed some events from OBS.");
+                                events = Box::pin(resubscribed);
+                                continue;
+                            }
+                            tracing::warn!("Lost connection to OBS; reconnecting.");
+                            self.events_open.store(false, Ordering::Relaxed);
+                            crate::notify::connection_lost().await;
+                            let client = crate::reconnect().await;
+                            events = Box::pin(client.events().context("subscribe to OBS events")?);
+                            *self.client.write().expect("client lock is never poisoned") =
+                                Arc::new(client);
+                            self.events_open.store(true, Ordering::Relaxed);
+                            continue;
+                        };
+                        crate::notify::event(&event).await;
+                        crate::record::follow(&event).await;
+                        if let Ok(json) = serde_json::to_value(&event) {
+                            let kind = json["eventType"].as_str().unwrap_or_default().to_owned();
+                            *self.last_event.lock().expect("health lock is never poisoned") =
+                                Some((kind, Instant::now()));
+                        }
+                        if let Event::CurrentProgramSceneChanged { name } = event {
+                            // Our own switches were already counted when they were sent.
+                            if !matches!(&live, Some((scene, _)) if *scene == name) {
+                                live = Some((name, Instant::now()));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Explains why switching to `scene` has to wait, if the live scene hasn't been up for long
+    /// enough.
+    fn held(
+        live: Option<&(String, Instant)>,
+        scene: &str,
+        hold: &SceneHold,
+    ) -> Option<anyhow::Error> {
+        let (live, since) = live?;
+        let minimum = hold.of(live);
+        let elapsed = since.elapsed();
+        (elapsed < minimum).then(|| {
+            anyhow::anyhow!(
+                "not switching to {scene}: {live} has been live for {:.1}s of its minimum {:.1}s",
+                elapsed.as_secs_f32(),
+                minimum.as_secs_f32()
+            )
+        })
+    }
+
+    /// Runs a queued command and replies with what it would have printed with `--json`.
+    ///
+    /// Returns whether the command succeeded.
+    async fn execute(client: &Client, queued: Queued, read_only: bool) -> bool {
+        let Queued { cmd, words, reply } = queued;
+        let (res, results) = Output::capture(async {
+            if read_only && cmd.is_mutating() {
+                crate::ensure_not_streaming(client).await?;
+            }
+            // Names are matched with `--fuzzy` only now, against what OBS has at this point.
+            let cmd = crate::parse_fuzzy(client, words).await?;
+            crate::run(client, cmd, Output::new(true)).await
+        })
+        .await;
+        let ok = res.is_ok();
+        let reply_json = match res {
+            Ok(()) => json!({ "ok": true, "result": crate::output::collapse(results) }),
+            Err(e) => {
+                tracing::warn!("Command failed: {e:#}");
+                crate::output::error_json(&e)
+            }
+        };
+        // The client may have hung up without waiting for the result.
+        let _ = reply.send(reply_json);
+        ok
+    }
+
+    /// Identifies what a command sets, so that a newer command setting the same thing can take
+    /// the place of a queued one. Commands like toggles, whose effect depends on the ones before,
+    /// never coalesce.
+    fn coalesce_key(cmd: &Command) -> Option<String> {
+        match cmd {
+            Command::SetScene { .. } => Some(String::from("set-scene")),
+            Command::SetVolume { set } => Some(format!("set-volume {}", set.input)),
+            Command::Mute { input } | Command::Unmute { input } => Some(format!("mute {input}")),
+            Command::SetText { input, .. } => Some(format!("set-text {input}")),
+            Command::FadeInput { input, fade } => {
+                let mut inputs: Vec<_> = std::iter::once(input).chain(&fade.inputs).collect();
+                inputs.sort_unstable();
+                inputs.dedup();
+                Some(format!("fade-input {inputs:?}"))
+            }
+            _ => None,
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use clap::Parser;
+
+        fn key(line: &str) -> Option<String> {
+            let words = std::iter::once("obs-do").chain(line.split(' '));
+            coalesce_key(&crate::Args::parse_from(words).cmd)
+        }
+
+        #[test]
+        fn coalesce_keys() {
+            assert_eq!(key("set-scene A"), key("set-scene B"));
+            assert_eq!(key("set-volume Mic 10%"), key("set-volume Mic -6dB"));
+            assert_ne!(key("set-volume Mic 10%"), key("set-volume Music 10%"));
+            assert_eq!(key("toggle-mute Mic"), None);
+            assert_eq!(key("mute Mic"), key("unmute Mic"));
+            // Fades cover all of their inputs, in whatever order they're given.
+            assert_eq!(
+                key("fade-input A --input B 0% 2s"),
+                key("fade-input B --input A -6dB 1s")
+            );
+            assert_ne!(
+                key("fade-input A --input B 0% 2s"),
+                key("fade-input A 0% 2s")
+            );
+        }
+
+        #[tokio::test]
+        async fn coalesced_commands_keep_their_place() {
+            let (client, _) = crate::fake_obs::connect(|_, _| None).await;
+            let queue = Queue::new(client);
+            let push = |line: &str| {
+                let (cmd, words) = parse(line).unwrap();
+                queue.push(cmd, words)
+            };
+            let mut first = push("set-scene A");
+            let _volume = push("set-volume Mic 0dB");
+            let _second = push("set-scene B");
+            assert_eq!(
+                first.try_recv().unwrap(),
+                json!({ "ok": true, "coalesced": true })
+            );
+            let order: Vec<_> = queue
+                .waiting
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|queued| queued.words.join(" "))
+                .collect();
+            assert_eq!(order, ["set-volume Mic 0dB", "set-scene B"]);
+        }
+    }
+}
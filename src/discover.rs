@@ -0,0 +1,158 @@
+use anyhow::Context;
+use serde::Deserialize;
+use std::path::Path;
+
+use crate::config::Config;
+
+/// The WebSocket server settings in `plugin_config/obs-websocket/config.json`, where OBS 30 and
+/// newer keep them.
+#[derive(Debug, Deserialize)]
+struct PluginConfig {
+    #[serde(default = "enabled")]
+    server_enabled: bool,
+    server_port: u16,
+    #[serde(default)]
+    auth_required: bool,
+    #[serde(default)]
+    server_password: String,
+}
+
+fn enabled() -> bool {
+    true
+}
+
+/// Points `config` at the OBS on this machine, using the port and password from OBS' own
+/// configuration.
+pub(crate) async fn apply(config: &mut Config) -> anyhow::Result<()> {
+    let settings = settings(&crate::import::default_obs_dir()?).await?;
+    if !settings.server_enabled {
+        tracing::warn!(
+            "OBS' WebSocket server is turned off under Tools -> WebSocket Server Settings."
+        );
+    }
+    tracing::debug!(
+        port = settings.server_port,
+        auth = settings.auth_required,
+        "discovered OBS WebSocket settings"
+    );
+    config.connection.host = Some(String::from("localhost"));
+    config.connection.port = Some(settings.server_port);
+    config.discovered_password = settings
+        .auth_required
+        .then_some(settings.server_password)
+        .filter(|password| !password.is_empty());
+    Ok(())
+}
+
+/// The WebSocket server settings in the OBS configuration in `obs_dir`, wherever this version of
+/// OBS keeps them.
+async fn settings(obs_dir: &Path) -> anyhow::Result<PluginConfig> {
+    match read_plugin_config(obs_dir).await? {
+        Some(settings) => Ok(settings),
+        None => read_global_ini(obs_dir)
+            .await?
+            .with_context(|| format!("found no WebSocket settings in {}", obs_dir.display())),
+    }
+}
+
+async fn read_plugin_config(obs_dir: &Path) -> anyhow::Result<Option<PluginConfig>> {
+    let path = obs_dir
+        .join("plugin_config")
+        .join("obs-websocket")
+        .join("config.json");
+    let contents = match tokio::fs::read_to_string(&path).await {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e).with_context(|| format!("read {}", path.display())),
+    };
+    serde_json::from_str(&contents)
+        .map(Some)
+        .with_context(|| format!("parse {}", path.display()))
+}
+
+/// Reads the `[OBSWebSocket]` section of `global.ini`, where OBS 28 and 29 keep the settings.
+async fn read_global_ini(obs_dir: &Path) -> anyhow::Result<Option<PluginConfig>> {
+    let path = obs_dir.join("global.ini");
+    let contents = match tokio::fs::read_to_string(&path).await {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e).with_context(|| format!("read {}", path.display())),
+    };
+
+    let mut settings = PluginConfig {
+        server_enabled: enabled(),
+        server_port: crate::config::Endpoint::default().port,
+        auth_required: false,
+        server_password: String::new(),
+    };
+    let mut found = false;
+    let mut section = "";
+    for line in contents.lines().map(str::trim) {
+        if let Some(header) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            section = header;
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        if section != "OBSWebSocket" {
+            continue;
+        }
+        found = true;
+        match key {
+            "ServerEnabled" => settings.server_enabled = value == "true",
+            "ServerPort" => {
+                settings.server_port = value
+                    .parse()
+                    .with_context(|| format!("parse ServerPort in {}", path.display()))?;
+            }
+            "AuthRequired" => settings.auth_required = value == "true",
+            "ServerPassword" => settings.server_password = value.to_owned(),
+            _ => {}
+        }
+    }
+    Ok(found.then_some(settings))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn finds_settings_where_obs_keeps_them() {
+        let dir = std::env::temp_dir().join(format!("obs-do-discover-{}", std::process::id()));
+        let plugin_dir = dir.join("plugin_config").join("obs-websocket");
+        std::fs::create_dir_all(&plugin_dir).unwrap();
+        assert!(settings(&dir).await.is_err());
+
+        // OBS 28 and 29.
+        std::fs::write(
+            dir.join("global.ini"),
+            "[General]\nServerPort=1\n\n[OBSWebSocket]\nServerEnabled=false\nServerPort=4456\nAuthRequired=true\nServerPassword=hunter2\n",
+        )
+        .unwrap();
+        let found = settings(&dir).await.unwrap();
+        assert!(!found.server_enabled);
+        assert_eq!(found.server_port, 4456);
+        assert!(found.auth_required);
+        assert_eq!(found.server_password, "hunter2");
+
+        // OBS 30 and newer, which wins over what older versions left behind.
+        std::fs::write(
+            plugin_dir.join("config.json"),
+            r#"{ "server_port": 4457, "auth_required": false }"#,
+        )
+        .unwrap();
+        let found = settings(&dir).await.unwrap();
+        assert!(found.server_enabled);
+        assert_eq!(found.server_port, 4457);
+        assert!(!found.auth_required);
+        assert_eq!(found.server_password, "");
+
+        std::fs::write(dir.join("global.ini"), "[OBSWebSocket]\nServerPort=many\n").unwrap();
+        assert!(read_global_ini(&dir).await.is_err());
+        std::fs::write(dir.join("global.ini"), "[General]\nName=Untitled\n").unwrap();
+        assert!(read_global_ini(&dir).await.unwrap().is_none());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,349 @@
+use anyhow::Context;
+use base64::Engine;
+use directories::ProjectDirs;
+use obws::{client::ConnectConfig, requests::EventSubscription, Client};
+use serde::{Deserialize, Serialize};
+use std::{fmt::Write, time::Duration};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::TcpStream,
+};
+
+use crate::{
+    config::{self, Config, Endpoint},
+    output::Output,
+    password,
+};
+
+/// How long each network check may take before it counts as failed.
+const TIMEOUT: Duration = Duration::from_secs(3);
+
+/// The obs-websocket RPC version that obs-do speaks.
+const RPC_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Status {
+    Ok,
+    Warn,
+    Fail,
+}
+
+/// The outcome of one check, along with how to fix it if it didn't pass.
+#[derive(Debug, Serialize)]
+struct Check {
+    name: &'static str,
+    status: Status,
+    detail: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fix: Option<String>,
+}
+
+impl Check {
+    fn ok(name: &'static str, detail: impl Into<String>) -> Self {
+        Check {
+            name,
+            status: Status::Ok,
+            detail: detail.into(),
+            fix: None,
+        }
+    }
+
+    fn warn(name: &'static str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Check {
+            name,
+            status: Status::Warn,
+            detail: detail.into(),
+            fix: Some(fix.into()),
+        }
+    }
+
+    fn fail(name: &'static str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Check {
+            name,
+            status: Status::Fail,
+            detail: detail.into(),
+            fix: Some(fix.into()),
+        }
+    }
+}
+
+/// The `Hello` message that obs-websocket greets every client with.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Hello {
+    obs_web_socket_version: String,
+    rpc_version: u32,
+    authentication: Option<serde_json::Value>,
+}
+
+/// Works through everything that has to be right for obs-do to reach OBS, and prints how to fix
+/// whatever isn't.
+///
+/// Checks stop at the first failure that the later ones depend on, like the port being closed.
+pub(crate) async fn run(
+    proj_dirs: &ProjectDirs,
+    config: anyhow::Result<Config>,
+    endpoint: &Endpoint,
+    password: anyhow::Result<Option<String>>,
+    out: Output,
+) -> anyhow::Result<()> {
+    let mut checks = vec![config_file(proj_dirs, &config).await];
+    checks.extend(token_file(proj_dirs).await);
+    let password = match password {
+        Ok(password) => password,
+        Err(e) => {
+            checks.push(Check::fail(
+                "password",
+                format!("{e:#}"),
+                "fix the password source named above, or run `obs-do config init`",
+            ));
+            None
+        }
+    };
+
+    let port = port(endpoint).await;
+    let open = port.status == Status::Ok;
+    checks.push(port);
+    if open {
+        match greeting(endpoint).await {
+            Err(e) => checks.push(Check::fail(
+                "greeting",
+                format!("{e:#}"),
+                format!(
+                    "make sure {endpoint} is the OBS WebSocket server and not another program, \
+                     and that OBS is version 28 or newer"
+                ),
+            )),
+            Ok(hello) => {
+                checks.push(protocol(&hello));
+                checks.push(auth(&hello, password.is_some()));
+                checks.push(login(endpoint, password).await);
+            }
+        }
+    }
+
+    let mut human = String::new();
+    for check in &checks {
+        let status = match check.status {
+            Status::Ok => "ok",
+            Status::Warn => "warn",
+            Status::Fail => "FAIL",
+        };
+        writeln!(human, "[{status:>4}] {}: {}", check.name, check.detail)?;
+        if let Some(fix) = &check.fix {
+            writeln!(human, "       fix: {fix}")?;
+        }
+    }
+    out.result(human.trim_end(), &checks)?;
+
+    let failed = checks
+        .iter()
+        .filter(|check| check.status == Status::Fail)
+        .count();
+    anyhow::ensure!(failed == 0, "{failed} of the checks failed");
+    Ok(())
+}
+
+async fn config_file(proj_dirs: &ProjectDirs, config: &anyhow::Result<Config>) -> Check {
+    let path = proj_dirs.config_dir().join(config::FILE_NAME);
+    match config {
+        Err(e) => Check::fail(
+            "config file",
+            format!("{e:#}"),
+            format!("fix the problem above in {}", path.display()),
+        ),
+        Ok(_) if !tokio::fs::try_exists(&path).await.unwrap_or(false) => Check::ok(
+            "config file",
+            format!("{} doesn't exist, so the defaults apply", path.display()),
+        ),
+        Ok(_) => Check::ok("config file", format!("{} is valid", path.display())),
+    }
+}
+
+/// Checks that the password file, if there is one, can't be read by other users.
+async fn token_file(proj_dirs: &ProjectDirs) -> Option<Check> {
+    let path = proj_dirs.config_dir().join(password::TOKEN_FILE);
+    let metadata = match tokio::fs::metadata(&path).await {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return None,
+        Err(e) => {
+            return Some(Check::fail(
+                "password file",
+                format!("could not read {}: {e}", path.display()),
+                format!("make {} readable by your user", path.display()),
+            ))
+        }
+    };
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = metadata.permissions().mode() & 0o777;
+        if mode & 0o077 != 0 {
+            return Some(Check::warn(
+                "password file",
+                format!("{} can be read by other users ({mode:o})", path.display()),
+                format!("run `chmod 600 {}`", path.display()),
+            ));
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = metadata;
+    Some(Check::ok("password file", path.display().to_string()))
+}
+
+/// Checks that something listens on the port.
+async fn port(endpoint: &Endpoint) -> Check {
+    let fix = format!(
+        "start OBS, check 'Enable WebSocket server' under Tools -> WebSocket Server Settings, and \
+         make sure the server port there is {}",
+        endpoint.port
+    );
+    match tokio::time::timeout(
+        TIMEOUT,
+        TcpStream::connect((endpoint.host.as_str(), endpoint.port)),
+    )
+    .await
+    {
+        Ok(Ok(_)) => Check::ok("port", format!("{endpoint} is open")),
+        Ok(Err(e)) => Check::fail("port", format!("could not connect to {endpoint}: {e}"), fix),
+        Err(_) => Check::fail(
+            "port",
+            format!("connecting to {endpoint} timed out"),
+            format!("{fix}; if OBS runs on another machine, check its firewall"),
+        ),
+    }
+}
+
+/// Opens a bare WebSocket connection and reads the greeting OBS sends before any login.
+async fn greeting(endpoint: &Endpoint) -> anyhow::Result<Hello> {
+    tokio::time::timeout(TIMEOUT, async {
+        let stream = TcpStream::connect((endpoint.host.as_str(), endpoint.port))
+            .await
+            .with_context(|| format!("connect to {endpoint}"))?;
+        let mut stream = BufReader::new(stream);
+        let key = base64::engine::general_purpose::STANDARD.encode(*b"obs-do doctor!!!");
+        let request = format!(
+            "GET / HTTP/1.1\r\nHost: {endpoint}\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+             Sec-WebSocket-Key: {key}\r\nSec-WebSocket-Version: 13\r\n\
+             Sec-WebSocket-Protocol: obswebsocket.json\r\n\r\n"
+        );
+        stream
+            .get_mut()
+            .write_all(request.as_bytes())
+            .await
+            .context("send WebSocket handshake")?;
+
+        let mut status = String::new();
+        stream
+            .read_line(&mut status)
+            .await
+            .context("read handshake response")?;
+        anyhow::ensure!(
+            status.split_whitespace().nth(1) == Some("101"),
+            "the server did not accept a WebSocket connection (it said `{}`)",
+            status.trim()
+        );
+        loop {
+            let mut header = String::new();
+            stream
+                .read_line(&mut header)
+                .await
+                .context("read handshake response")?;
+            if header.trim().is_empty() {
+                break;
+            }
+        }
+
+        // The server sends the greeting as a single unmasked text frame.
+        let mut head = [0; 2];
+        stream
+            .read_exact(&mut head)
+            .await
+            .context("read greeting")?;
+        anyhow::ensure!(head[0] & 0x0f == 0x1, "the greeting is not a text message");
+        let len = match head[1] & 0x7f {
+            126 => u64::from(stream.read_u16().await.context("read greeting")?),
+            127 => stream.read_u64().await.context("read greeting")?,
+            len => u64::from(len),
+        };
+        anyhow::ensure!(len <= 64 * 1024, "the greeting is suspiciously large");
+        let mut payload = vec![0; len as usize];
+        stream
+            .read_exact(&mut payload)
+            .await
+            .context("read greeting")?;
+
+        #[derive(Deserialize)]
+        struct Message {
+            op: u8,
+            d: serde_json::Value,
+        }
+        let message: Message = serde_json::from_slice(&payload).context("parse greeting")?;
+        anyhow::ensure!(message.op == 0, "the first message is not a greeting");
+        serde_json::from_value(message.d).context("parse greeting")
+    })
+    .await
+    .map_err(|_| anyhow::anyhow!("OBS did not greet within {}s", TIMEOUT.as_secs()))?
+}
+
+fn protocol(hello: &Hello) -> Check {
+    let version = &hello.obs_web_socket_version;
+    let major = version
+        .split('.')
+        .next()
+        .and_then(|major| major.parse::<u32>().ok());
+    if !matches!(major, Some(major) if major >= 5) || hello.rpc_version != RPC_VERSION {
+        Check::fail(
+            "protocol",
+            format!(
+                "obs-websocket {version} speaks RPC version {}, but obs-do needs {RPC_VERSION}",
+                hello.rpc_version
+            ),
+            "update OBS to version 28 or newer, which comes with obs-websocket 5",
+        )
+    } else {
+        Check::ok(
+            "protocol",
+            format!("obs-websocket {version}, RPC version {}", hello.rpc_version),
+        )
+    }
+}
+
+fn auth(hello: &Hello, have_password: bool) -> Check {
+    match (hello.authentication.is_some(), have_password) {
+        (true, true) => Check::ok("authentication", "OBS wants a password, and there is one"),
+        (true, false) => Check::fail(
+            "authentication",
+            "OBS wants a password, but none is configured",
+            "copy the password from Tools -> WebSocket Server Settings -> Show Connect Info, and \
+             run `obs-do config init` or `obs-do auth set`",
+        ),
+        (false, true) => Check::warn(
+            "authentication",
+            "a password is configured, but OBS doesn't ask for one",
+            "turn on 'Enable Authentication' in OBS, or remove the password from obs-do",
+        ),
+        (false, false) => Check::ok("authentication", "OBS doesn't ask for a password"),
+    }
+}
+
+/// Checks that logging in works, which is where a wrong password shows up.
+async fn login(endpoint: &Endpoint, password: Option<String>) -> Check {
+    let client = Client::connect_with_config(ConnectConfig {
+        host: endpoint.host.as_str(),
+        port: endpoint.port,
+        password,
+        event_subscriptions: Some(EventSubscription::NONE),
+        broadcast_capacity: None,
+    })
+    .await;
+    match client {
+        Ok(_) => Check::ok("login", format!("logged in to OBS at {endpoint}")),
+        Err(e) => Check::fail(
+            "login",
+            e.to_string(),
+            "check that the password matches the one in OBS' WebSocket Server Settings",
+        ),
+    }
+}
@@ -0,0 +1,147 @@
+use anyhow::Context;
+use futures_util::StreamExt;
+use obws::{events::Event, requests::inputs::Volume, Client};
+use tokio::time::{Instant, MissedTickBehavior};
+
+use crate::fade::STEP;
+
+#[derive(Debug, Clone, clap::Args)]
+pub(crate) struct DuckArgs {
+    /// The input whose level triggers ducking, such as a microphone.
+    trigger: String,
+
+    /// The input to turn down while the trigger is active, such as music.
+    target: String,
+
+    /// The level above which the trigger counts as active, in dB.
+    #[arg(
+        long,
+        value_name = "DB",
+        default_value = "-30dB",
+        allow_hyphen_values = true
+    )]
+    threshold: String,
+
+    /// How far to turn the target down, in dB.
+    #[arg(long, value_name = "DB", default_value = "12dB")]
+    by: String,
+
+    /// How long turning the target down takes.
+    #[arg(long, value_name = "DURATION", default_value = "100ms")]
+    attack: String,
+
+    /// How long the trigger must stay below the threshold before the target comes back up.
+    ///
+    /// Keeps the target from pumping up and down in the pauses between words.
+    #[arg(long, value_name = "DURATION", default_value = "500ms")]
+    hold: String,
+
+    /// How long bringing the target back up takes.
+    #[arg(long, value_name = "DURATION", default_value = "1s")]
+    release: String,
+}
+
+/// Turns the target input down whenever the trigger input is loud, until interrupted.
+///
+/// The target is ducked relative to its volume when this starts, and is put back at that volume
+/// when this stops.
+pub(crate) async fn run(client: &Client, args: &DuckArgs) -> anyhow::Result<()> {
+    let threshold = parse_db(&args.threshold).context("invalid --threshold")?;
+    let depth = parse_db(&args.by).context("invalid --by")?.abs();
+    let attack = crate::parse_duration(&args.attack)?;
+    let hold = crate::parse_duration(&args.hold)?;
+    let release = crate::parse_duration(&args.release)?;
+    let DuckArgs {
+        trigger, target, ..
+    } = args;
+
+    let events = client.events().context("subscribe to OBS events")?;
+    let mut events = std::pin::pin!(events);
+    // OBS only meters inputs with audio, and refuses to report a volume for any other.
+    client
+        .inputs()
+        .volume(trigger)
+        .await
+        .with_context(|| format!("get volume of {trigger}"))?;
+    let normal = client
+        .inputs()
+        .volume(target)
+        .await
+        .with_context(|| format!("get volume of {target}"))?
+        .db;
+    let ducked = normal - depth;
+    tracing::info!("Ducking {target} by {depth} dB while {trigger} is above {threshold} dB.");
+
+    let mut level = normal;
+    let mut last_loud: Option<Instant> = None;
+    let mut ticks = tokio::time::interval(STEP);
+    ticks.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    let mut last_tick = Instant::now();
+    loop {
+        tokio::select! {
+            event = events.next() => match event {
+                Some(Event::InputVolumeMeters { inputs }) => {
+                    let Some(meter) = inputs.iter().find(|meter| meter.name == *trigger) else {
+                        continue;
+                    };
+                    // Each channel reports its magnitude, peak and pre-fader peak.
+                    let magnitude = meter
+                        .levels
+                        .iter()
+                        .map(|level| level[0])
+                        .fold(0., f32::max);
+                    if 20. * magnitude.log10() >= threshold {
+                        last_loud = Some(Instant::now());
+                    }
+                }
+                Some(_) => {}
+                None => anyhow::bail!("lost connection to OBS"),
+            },
+            now = ticks.tick() => {
+                // Move by however much time actually passed, so that late ticks don't slow the
+                // fade down.
+                let elapsed = now - last_tick;
+                last_tick = now;
+                let ducking = last_loud.is_some_and(|loud| loud.elapsed() < hold);
+                let (goal, over) = if ducking { (ducked, attack) } else { (normal, release) };
+                if level == goal {
+                    continue;
+                }
+                let step = if over.is_zero() {
+                    depth
+                } else {
+                    depth * elapsed.as_secs_f32() / over.as_secs_f32()
+                };
+                let next = if level < goal {
+                    (level + step).min(goal)
+                } else {
+                    (level - step).max(goal)
+                };
+                set_volume(client, target, next).await?;
+                if next == goal {
+                    tracing::debug!(input = target, ducked = ducking, "duck settled");
+                }
+                level = next;
+            }
+            _ = tokio::signal::ctrl_c() => break,
+        }
+    }
+
+    set_volume(client, target, normal).await
+}
+
+async fn set_volume(client: &Client, input: &str, db: f32) -> anyhow::Result<()> {
+    client
+        .inputs()
+        .set_volume(input, Volume::Db(db))
+        .await
+        .with_context(|| format!("set volume of {input}"))
+}
+
+/// Parses a level like `-30dB`; the unit may be left out.
+fn parse_db(level: &str) -> anyhow::Result<f32> {
+    let db = level.strip_suffix("dB").unwrap_or(level);
+    db.trim()
+        .parse()
+        .with_context(|| format!("invalid level `{level}`, expected dB like `-30dB`"))
+}
@@ -0,0 +1,91 @@
+use anyhow::Context;
+use futures_util::future::try_join_all;
+use obws::Client;
+use serde_json::json;
+
+use crate::output::Output;
+
+/// Drives OBS into the given baseline state, only sending requests for what differs.
+pub(crate) async fn run(
+    client: &Client,
+    scene: Option<String>,
+    mute: Vec<String>,
+    unmute: Vec<String>,
+    out: Output,
+) -> anyhow::Result<()> {
+    if let Some(input) = mute.iter().find(|input| unmute.contains(input)) {
+        anyhow::bail!("{input} cannot be both muted and unmuted");
+    }
+
+    // Look up the current state all at once rather than one request at a time.
+    let wanted: Vec<_> = mute
+        .iter()
+        .map(|input| (input, true))
+        .chain(unmute.iter().map(|input| (input, false)))
+        .collect();
+    let current_scene = async {
+        match scene {
+            Some(_) => client
+                .scenes()
+                .current_program_scene()
+                .await
+                .map(Some)
+                .context("get current program scene"),
+            None => Ok(None),
+        }
+    };
+    let current_mutes = try_join_all(wanted.iter().map(|&(input, _)| async move {
+        client
+            .inputs()
+            .muted(input)
+            .await
+            .with_context(|| format!("get mute state of {input}"))
+    }));
+    let (current_scene, current_mutes) = tokio::try_join!(current_scene, current_mutes)?;
+
+    let switch_scene = scene.filter(|scene| current_scene.as_ref() != Some(scene));
+    let set_scene = async {
+        if let Some(scene) = &switch_scene {
+            client
+                .scenes()
+                .set_current_program_scene(scene)
+                .await
+                .with_context(|| format!("set-scene {scene}"))?;
+        }
+        anyhow::Ok(())
+    };
+    let changed_mutes: Vec<_> = wanted
+        .into_iter()
+        .zip(current_mutes)
+        .filter(|&((_, muted), current)| muted != current)
+        .map(|(wanted, _)| wanted)
+        .collect();
+    let set_mutes = try_join_all(changed_mutes.iter().map(|&(input, muted)| async move {
+        client
+            .inputs()
+            .set_muted(input, muted)
+            .await
+            .with_context(|| format!("set mute state of {input}"))
+    }));
+    tokio::try_join!(set_scene, set_mutes)?;
+
+    let (muted, unmuted): (Vec<_>, Vec<_>) = changed_mutes.iter().partition(|(_, muted)| *muted);
+    let muted: Vec<_> = muted.into_iter().map(|(input, _)| input).collect();
+    let unmuted: Vec<_> = unmuted.into_iter().map(|(input, _)| input).collect();
+
+    let mut changes: Vec<_> = switch_scene
+        .iter()
+        .map(|scene| format!("switched to scene {scene}"))
+        .collect();
+    changes.extend(muted.iter().map(|input| format!("muted {input}")));
+    changes.extend(unmuted.iter().map(|input| format!("unmuted {input}")));
+    let human = if changes.is_empty() {
+        String::from("OBS was already in the requested state")
+    } else {
+        changes.join("\n")
+    };
+    out.result(
+        human,
+        &json!({ "scene": switch_scene, "muted": muted, "unmuted": unmuted }),
+    )
+}
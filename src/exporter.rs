@@ -0,0 +1,314 @@
+use anyhow::Context;
+use futures_util::{stream::FuturesUnordered, StreamExt};
+use obws::{events::Event, Client};
+use std::{collections::BTreeMap, fmt::Write, sync::Mutex};
+use tokio::{
+    io::BufReader,
+    net::{TcpListener, TcpStream},
+};
+
+use crate::meter::Meter;
+
+#[derive(Debug, Clone, clap::Args)]
+pub(crate) struct ExporterArgs {
+    /// The address to serve metrics on, at `http://<ADDR>/metrics`.
+    ///
+    /// Use `0.0.0.0:9407` to let Prometheus scrape from other machines.
+    #[arg(long, value_name = "ADDR", default_value = "127.0.0.1:9407")]
+    listen: String,
+}
+
+/// Stays connected to OBS and serves its stats, the state of its outputs and the audio levels of
+/// its inputs as Prometheus metrics, until interrupted.
+///
+/// Everything but the audio levels is asked of OBS on each scrape; the levels are the latest that
+/// OBS sent.
+pub(crate) async fn run(client: &Client, args: &ExporterArgs) -> anyhow::Result<()> {
+    let events = client.events().context("subscribe to OBS events")?;
+    let mut events = std::pin::pin!(events);
+    let listener = TcpListener::bind(&args.listen)
+        .await
+        .with_context(|| format!("listen on {}", args.listen))?;
+    tracing::info!("Serving metrics on http://{}/metrics.", args.listen);
+
+    let meters = Mutex::new(BTreeMap::<String, Meter>::new());
+    let mut scrapes = FuturesUnordered::new();
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => scrapes.push(serve(stream, client, &meters)),
+                Err(e) => tracing::warn!("Failed to accept connection: {e}"),
+            },
+            Some(res) = scrapes.next() => {
+                if let Err(e) = res {
+                    tracing::warn!("Scrape failed: {e:#}");
+                }
+            }
+            event = events.next() => match event {
+                Some(Event::InputVolumeMeters { inputs }) => {
+                    let mut meters = meters.lock().unwrap();
+                    for input in inputs {
+                        meters.entry(input.name).or_default().update(&input.levels);
+                    }
+                }
+                Some(Event::InputRemoved { name }) => {
+                    meters.lock().unwrap().remove(&name);
+                }
+                Some(_) => {}
+                None => anyhow::bail!("lost connection to OBS"),
+            },
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+        }
+    }
+}
+
+async fn serve(
+    stream: TcpStream,
+    client: &Client,
+    meters: &Mutex<BTreeMap<String, Meter>>,
+) -> anyhow::Result<()> {
+    let mut stream = BufReader::new(stream);
+    let request = crate::http::read_request(&mut stream).await?;
+    let (status, body) = match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/metrics") => match metrics(client, meters).await {
+            Ok(metrics) => ("200 OK", metrics),
+            Err(e) => ("503 Service Unavailable", format!("{e:#}\n")),
+        },
+        _ => ("404 Not Found", String::from("not found\n")),
+    };
+    crate::http::respond(stream.get_mut(), status, "text/plain; version=0.0.4", &body).await
+}
+
+/// Renders the current metrics in the Prometheus text format.
+async fn metrics(
+    client: &Client,
+    meters: &Mutex<BTreeMap<String, Meter>>,
+) -> anyhow::Result<String> {
+    let stats = client.general().stats().await.context("get OBS stats")?;
+    let stream = client
+        .streaming()
+        .status()
+        .await
+        .context("get stream status")?;
+    let record = client
+        .recording()
+        .status()
+        .await
+        .context("get recording status")?;
+
+    // OBS reports sizes in MiB.
+    const MIB: f64 = 1024. * 1024.;
+    let mut out = Metrics::default();
+    out.gauge(
+        "obs_cpu_usage_percent",
+        "CPU usage of OBS.",
+        stats.cpu_usage,
+    );
+    out.gauge(
+        "obs_memory_usage_bytes",
+        "Memory used by OBS.",
+        stats.memory_usage * MIB,
+    );
+    out.gauge(
+        "obs_available_disk_space_bytes",
+        "Free space on the disk OBS records to.",
+        stats.available_disk_space * MIB,
+    );
+    out.gauge(
+        "obs_active_fps",
+        "Frames per second OBS renders at.",
+        stats.active_fps,
+    );
+    out.gauge(
+        "obs_frame_render_time_seconds",
+        "Average time it takes OBS to render a frame.",
+        stats.average_frame_render_time / 1000.,
+    );
+    out.counter(
+        "obs_render_frames_total",
+        "Frames OBS has rendered.",
+        stats.render_total_frames,
+    );
+    out.counter(
+        "obs_render_skipped_frames_total",
+        "Frames skipped because rendering fell behind.",
+        stats.render_skipped_frames,
+    );
+    out.counter(
+        "obs_output_frames_total",
+        "Frames OBS has encoded.",
+        stats.output_total_frames,
+    );
+    out.counter(
+        "obs_output_skipped_frames_total",
+        "Frames skipped because encoding fell behind.",
+        stats.output_skipped_frames,
+    );
+
+    out.gauge(
+        "obs_stream_active",
+        "Whether OBS is streaming.",
+        flag(stream.active),
+    );
+    out.gauge(
+        "obs_stream_reconnecting",
+        "Whether the stream is reconnecting.",
+        flag(stream.reconnecting),
+    );
+    out.gauge(
+        "obs_stream_duration_seconds",
+        "How long the stream has been live.",
+        stream.duration.as_seconds_f64(),
+    );
+    out.counter(
+        "obs_stream_bytes_total",
+        "Bytes sent by the stream.",
+        stream.bytes,
+    );
+    out.gauge(
+        "obs_stream_congestion",
+        "How backed up the stream output is, from 0 to 1.",
+        f64::from(stream.congestion),
+    );
+    out.counter(
+        "obs_stream_frames_total",
+        "Frames sent by the stream.",
+        stream.total_frames,
+    );
+    out.counter(
+        "obs_stream_skipped_frames_total",
+        "Frames the stream dropped.",
+        stream.skipped_frames,
+    );
+
+    out.gauge(
+        "obs_record_active",
+        "Whether OBS is recording.",
+        flag(record.active),
+    );
+    out.gauge(
+        "obs_record_paused",
+        "Whether the recording is paused.",
+        flag(record.paused),
+    );
+    out.gauge(
+        "obs_record_duration_seconds",
+        "How long the recording has been going.",
+        record.duration.as_seconds_f64(),
+    );
+    out.counter(
+        "obs_record_bytes_total",
+        "Bytes written by the recording.",
+        record.bytes,
+    );
+
+    let meters = meters.lock().unwrap();
+    let levels = |level: fn(&Meter) -> Option<f32>| {
+        meters
+            .iter()
+            .filter_map(move |(input, meter)| Some((input.as_str(), f64::from(level(meter)?))))
+    };
+    out.gauges(
+        "obs_input_magnitude_db",
+        "Loudness (RMS) of an input's audio.",
+        levels(Meter::magnitude),
+    );
+    out.gauges(
+        "obs_input_peak_db",
+        "Recent peak of an input's audio.",
+        levels(Meter::peak),
+    );
+    Ok(out.0)
+}
+
+fn flag(on: bool) -> f64 {
+    if on {
+        1.
+    } else {
+        0.
+    }
+}
+
+/// Metrics in the Prometheus text exposition format.
+#[derive(Debug, Default)]
+struct Metrics(String);
+
+impl Metrics {
+    fn gauge(&mut self, name: &str, help: &str, value: f64) {
+        self.header(name, "gauge", help);
+        let _ = writeln!(self.0, "{name} {}", number(value));
+    }
+
+    fn counter(&mut self, name: &str, help: &str, value: impl Into<u64>) {
+        self.header(name, "counter", help);
+        let _ = writeln!(self.0, "{name} {}", value.into());
+    }
+
+    /// Adds a gauge with one sample per input.
+    fn gauges<'a>(
+        &mut self,
+        name: &str,
+        help: &str,
+        samples: impl Iterator<Item = (&'a str, f64)>,
+    ) {
+        self.header(name, "gauge", help);
+        for (input, value) in samples {
+            let _ = writeln!(
+                self.0,
+                "{name}{{input=\"{}\"}} {}",
+                escape(input),
+                number(value)
+            );
+        }
+    }
+
+    fn header(&mut self, name: &str, kind: &str, help: &str) {
+        let _ = writeln!(self.0, "# HELP {name} {help}\n# TYPE {name} {kind}");
+    }
+}
+
+/// Formats `value` the way Prometheus spells infinities.
+fn number(value: f64) -> String {
+    if value == f64::NEG_INFINITY {
+        String::from("-Inf")
+    } else if value == f64::INFINITY {
+        String::from("+Inf")
+    } else {
+        value.to_string()
+    }
+}
+
+/// Escapes a label value.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_format() {
+        let mut metrics = Metrics::default();
+        metrics.gauge("obs_active_fps", "Frames per second.", 60.);
+        metrics.counter("obs_stream_bytes_total", "Bytes.", 1_500_000_u32);
+        metrics.gauges(
+            "obs_input_peak_db",
+            "Peak.",
+            [("Mic \"Main\"", -6.5), ("Music", f64::NEG_INFINITY)].into_iter(),
+        );
+        assert_eq!(
+            metrics.0,
+            "# HELP obs_active_fps Frames per second.\n# TYPE obs_active_fps gauge\n\
+             obs_active_fps 60\n\
+             # HELP obs_stream_bytes_total Bytes.\n# TYPE obs_stream_bytes_total counter\n\
+             obs_stream_bytes_total 1500000\n\
+             # HELP obs_input_peak_db Peak.\n# TYPE obs_input_peak_db gauge\n\
+             obs_input_peak_db{input=\"Mic \\\"Main\\\"\"} -6.5\n\
+             obs_input_peak_db{input=\"Music\"} -Inf\n"
+        );
+    }
+}
@@ -0,0 +1,862 @@
+use anyhow::Context;
+use clap::ValueEnum;
+use futures_util::future::try_join_all;
+use obws::Client;
+use serde_json::json;
+use std::{
+    path::PathBuf,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+use tokio::time::{Instant, MissedTickBehavior};
+
+use crate::{output::Output, Volume};
+
+/// How often the volume is updated during a fade.
+pub(crate) const STEP: Duration = Duration::from_micros(1_000_000 / 60);
+
+/// The level at which fades in dB consider an input silent.
+///
+/// OBS' faders go down to -100 dB, but little below -60 dB can be heard, so a fade from or to
+/// silence that started at -100 dB would spend much of its time inaudible.
+const SILENCE_DB: f32 = -60.;
+
+#[derive(Debug, Clone, clap::Args)]
+pub(crate) struct FadeArgs {
+    /// More inputs (or glob patterns) to fade at the same time.
+    #[arg(long = "input", value_name = "INPUT")]
+    pub(crate) inputs: Vec<String>,
+
+    /// The volume to fade to, in dB or % as for `set-volume`.
+    ///
+    /// `mute` fades to silence and then mutes the input, leaving its fader where it was so that
+    /// unmuting restores the old level. `unmute` does the reverse: it unmutes the input and fades
+    /// up from silence to that level. Fading a muted input to a volume also unmutes it first.
+    #[arg(allow_hyphen_values = true)]
+    volume: String,
+
+    /// How long the fade should take, like `500ms`, `2s` or `1m30s` (plain numbers are seconds).
+    #[arg(required_unless_present = "rate")]
+    duration: Option<String>,
+
+    /// Fade at this speed, like `3dB/s`, for however long it takes to reach the volume.
+    ///
+    /// Each input then takes its own time, depending on how far its volume has to move.
+    #[arg(long, value_name = "DB_PER_SECOND", conflicts_with = "duration")]
+    rate: Option<String>,
+
+    /// Jump to this volume before fading, rather than starting from the current volume.
+    #[arg(long, allow_hyphen_values = true)]
+    from: Option<String>,
+
+    #[command(flatten)]
+    shape: Shape,
+}
+
+#[derive(Debug, Clone, clap::Args)]
+pub(crate) struct CrossfadeArgs {
+    /// The input to fade out.
+    #[arg(value_name = "OUT")]
+    fade_out: String,
+
+    /// The input to fade in.
+    #[arg(value_name = "IN")]
+    fade_in: String,
+
+    /// How long the crossfade should take, like `500ms` or `2s`.
+    #[arg(default_value = "2s")]
+    duration: String,
+
+    /// The volume to bring the faded-in input up to, in dB or % as for `set-volume`.
+    ///
+    /// Defaults to the volume the faded-out input started at.
+    #[arg(long, allow_hyphen_values = true)]
+    volume: Option<String>,
+
+    #[command(flatten)]
+    shape: Shape,
+}
+
+/// How the volume moves from where a fade starts to where it ends.
+#[derive(Debug, Clone, clap::Args)]
+pub(crate) struct Shape {
+    /// How the volume progresses over the course of the fade.
+    #[arg(long, value_enum, default_value_t = Curve::Linear)]
+    curve: Curve,
+
+    /// The scale along which the volume is interpolated.
+    ///
+    /// Fading in dB sounds even to the ear, whereas fading the raw multiplier changes the
+    /// perceived loudness quickly at first and slowly towards the end.
+    #[arg(long, value_enum, default_value_t = Scale::Db)]
+    scale: Scale,
+}
+
+impl Default for Shape {
+    fn default() -> Self {
+        Self {
+            curve: Curve::Linear,
+            scale: Scale::Db,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum Curve {
+    /// Constant rate of change.
+    Linear,
+    /// Starts slowly and speeds up towards the end.
+    #[value(alias = "ease-in")]
+    Exponential,
+    /// Starts quickly and slows down towards the end.
+    #[value(alias = "ease-out")]
+    Logarithmic,
+    /// Starts and ends slowly, moving fastest in the middle.
+    #[value(name = "s-curve", alias = "ease-in-out")]
+    Smooth,
+}
+
+impl Curve {
+    /// How steep the exponential and logarithmic curves are.
+    const STEEPNESS: f32 = 4.;
+
+    /// Maps linear progress through the fade (0 to 1) onto this curve.
+    pub fn apply(self, t: f32) -> f32 {
+        let k = Self::STEEPNESS;
+        match self {
+            Curve::Linear => t,
+            Curve::Exponential => (k * t).exp_m1() / k.exp_m1(),
+            Curve::Logarithmic => (k.exp_m1() * t).ln_1p() / k,
+            Curve::Smooth => t * t * (3. - 2. * t),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum Scale {
+    /// Interpolate in decibels.
+    Db,
+    /// Interpolate the linear volume multiplier.
+    Mul,
+}
+
+impl Scale {
+    /// Expresses a volume multiplier on this scale.
+    pub fn level(self, mul: f32) -> f32 {
+        match self {
+            Scale::Db if mul <= 0. => SILENCE_DB,
+            Scale::Db => (20. * mul.log10()).max(SILENCE_DB),
+            Scale::Mul => mul,
+        }
+    }
+
+    /// Turns a level on this scale back into a volume OBS understands.
+    pub fn volume(self, level: f32) -> Volume {
+        match self {
+            Scale::Db => Volume::Db(level),
+            Scale::Mul => Volume::Mul(level),
+        }
+    }
+}
+
+/// Where a fade ends up.
+enum Target {
+    Volume(Volume),
+    Mute,
+    Unmute,
+}
+
+impl Target {
+    fn parse(target: &str) -> anyhow::Result<Self> {
+        Ok(match target {
+            "mute" => Target::Mute,
+            "unmute" => Target::Unmute,
+            volume => Target::Volume(crate::parse_volume(volume)?),
+        })
+    }
+}
+
+/// Gradually changes the volume of `input` and any further `--input`s together, as described by
+/// `args`.
+///
+/// Inputs may be given as glob patterns like `Music*`, which are matched against the names of
+/// all inputs in OBS.
+///
+/// If `--from` is given, the inputs first jump to that volume, so that the fade sounds the same
+/// no matter where the faders were left.
+pub(crate) async fn run(
+    client: &Client,
+    input: &str,
+    args: &FadeArgs,
+    out: Output,
+) -> anyhow::Result<()> {
+    let target = Target::parse(&args.volume)?;
+    let duration = args
+        .duration
+        .as_deref()
+        .map(crate::parse_duration)
+        .transpose()?;
+    let rate = args.rate.as_deref().map(parse_rate).transpose()?;
+    let from = args.from.as_deref().map(crate::parse_volume).transpose()?;
+    let mute = matches!(target, Target::Mute);
+
+    let inputs: Vec<_> = std::iter::once(input.to_owned())
+        .chain(args.inputs.iter().cloned())
+        .collect();
+    let inputs = expand_inputs(client, &inputs).await?;
+    // Stop any fades that are already running on these inputs before touching them.
+    let claims = try_join_all(inputs.iter().map(|input| Claim::take(input))).await?;
+    let currents = try_join_all(inputs.iter().map(|input| async move {
+        let (muted, current) = mix_state(client, input).await?;
+
+        // A muted input is faded in from silence rather than jumping back to its old level.
+        let unmute = muted && !mute;
+        let start = match from.or(unmute.then_some(Volume::Mul(0.))) {
+            Some(from) => {
+                client
+                    .inputs()
+                    .set_volume(input, from.into())
+                    .await
+                    .with_context(|| format!("set starting volume of {input}"))?;
+                to_mul(from)
+            }
+            None => current,
+        };
+        if unmute {
+            client
+                .inputs()
+                .set_muted(input, false)
+                .await
+                .with_context(|| format!("unmute {input}"))?;
+        }
+        anyhow::Ok((current, start))
+    }))
+    .await?;
+
+    let ramps: Vec<_> = inputs
+        .iter()
+        .zip(&currents)
+        .zip(claims)
+        .map(|((input, &(current, start)), claim)| Ramp {
+            input,
+            claim,
+            start,
+            end: match target {
+                Target::Volume(volume) => volume,
+                Target::Mute => Volume::Mul(0.),
+                Target::Unmute => Volume::Mul(current),
+            },
+        })
+        .collect();
+    let finished =
+        match (duration, rate) {
+            (Some(duration), _) => ramp(client, &ramps, duration, &args.shape).await?,
+            // Inputs further from their target take longer, so they can't move in lockstep.
+            (None, Some(rate)) => {
+                let durations = ramps
+                    .iter()
+                    .map(|r| rate_duration(r.start, r.end, rate))
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                try_join_all(ramps.iter().zip(durations).map(|(r, duration)| {
+                    ramp(client, std::slice::from_ref(r), duration, &args.shape)
+                }))
+                .await?
+            }
+            .into_iter()
+            .flatten()
+            .collect(),
+            (None, None) => unreachable!("clap requires a duration or a rate"),
+        };
+
+    if mute {
+        try_join_all(
+            ramps
+                .iter()
+                .zip(&currents)
+                .zip(&finished)
+                .filter(|(_, &finished)| finished)
+                .map(|((ramp, &(current, _)), _)| async move {
+                    let input = ramp.input;
+                    client
+                        .inputs()
+                        .set_muted(input, true)
+                        .await
+                        .with_context(|| format!("mute {input}"))?;
+                    // Put the fader back so that unmuting (or `fade-input <input> unmute`) returns
+                    // to it.
+                    client
+                        .inputs()
+                        .set_volume(input, Volume::Mul(current).into())
+                        .await
+                        .with_context(|| format!("restore volume of {input}"))
+                }),
+        )
+        .await?;
+    }
+
+    for ((ramp, &(current, _)), finished) in ramps.iter().zip(&currents).zip(finished) {
+        if !finished {
+            out.json(&json!({ "input": ramp.input, "cancelled": true }))?;
+            continue;
+        }
+        let level = if mute { current } else { to_mul(ramp.end) };
+        out.json(&json!({ "input": ramp.input, "muted": mute, "volume": { "mul": level } }))?;
+    }
+    Ok(())
+}
+
+/// Fades `fade_out` down to silence while fading `fade_in` up, as described by `args`.
+pub(crate) async fn crossfade(
+    client: &Client,
+    args: &CrossfadeArgs,
+    out: Output,
+) -> anyhow::Result<()> {
+    let duration = crate::parse_duration(&args.duration)?;
+    let volume = args
+        .volume
+        .as_deref()
+        .map(crate::parse_volume)
+        .transpose()?;
+
+    let (out_claim, in_claim) =
+        tokio::try_join!(Claim::take(&args.fade_out), Claim::take(&args.fade_in))?;
+    let ((_, out_start), (in_muted, in_start)) = tokio::try_join!(
+        mix_state(client, &args.fade_out),
+        mix_state(client, &args.fade_in)
+    )?;
+    let target = volume.unwrap_or(Volume::Mul(out_start));
+
+    // As with `fade-input`, a muted input comes in from silence.
+    let in_start = if in_muted {
+        client
+            .inputs()
+            .set_volume(&args.fade_in, Volume::Mul(0.).into())
+            .await
+            .with_context(|| format!("set starting volume of {}", args.fade_in))?;
+        client
+            .inputs()
+            .set_muted(&args.fade_in, false)
+            .await
+            .with_context(|| format!("unmute {}", args.fade_in))?;
+        0.
+    } else {
+        in_start
+    };
+
+    let finished = ramp(
+        client,
+        &[
+            Ramp {
+                input: &args.fade_out,
+                claim: out_claim,
+                start: out_start,
+                end: Volume::Mul(0.),
+            },
+            Ramp {
+                input: &args.fade_in,
+                claim: in_claim,
+                start: in_start,
+                end: target,
+            },
+        ],
+        duration,
+        &args.shape,
+    )
+    .await?;
+
+    out.json(&json!({
+        "out": {
+            "input": args.fade_out,
+            "volume": { "mul": 0. },
+            "cancelled": !finished[0],
+        },
+        "in": {
+            "input": args.fade_in,
+            "volume": { "mul": to_mul(target) },
+            "cancelled": !finished[1],
+        },
+    }))
+}
+
+/// Stops the fades running on `inputs`, wherever they were started from, leaving the volume
+/// wherever it had got to.
+pub(crate) async fn cancel(inputs: &[String], out: Output) -> anyhow::Result<()> {
+    for input in inputs {
+        let path = claim_path(input)?;
+        let cancelled = match tokio::fs::remove_file(&path).await {
+            Ok(()) => true,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => false,
+            Err(e) => {
+                return Err(e).with_context(|| format!("cancel fade of {input}"));
+            }
+        };
+        let human = if cancelled {
+            format!("Cancelled fade of {input}")
+        } else {
+            format!("No fade is running on {input}")
+        };
+        out.result(human, &json!({ "input": input, "cancelled": cancelled }))?;
+    }
+    Ok(())
+}
+
+/// A running fade's hold on the fader of an input.
+///
+/// Fades may run in separate obs-do processes (say, one per hotkey press), so the claim is a
+/// marker file holding a token unique to the fade. A newer fade on the same input overwrites the
+/// token and `fade-cancel` removes the file; either way, the older fade notices and stops.
+///
+/// Dropping the claim removes the file unless someone else has taken the fader since, so that a
+/// fade that fails or is interrupted doesn't leave its claim behind.
+struct Claim {
+    path: PathBuf,
+    token: String,
+}
+
+impl Claim {
+    async fn take(input: &str) -> anyhow::Result<Self> {
+        static FADES: AtomicU64 = AtomicU64::new(0);
+
+        let path = claim_path(input)?;
+        if let Some(dir) = path.parent() {
+            tokio::fs::create_dir_all(dir)
+                .await
+                .with_context(|| format!("create {}", dir.display()))?;
+        }
+        let token = format!(
+            "{}.{}",
+            std::process::id(),
+            FADES.fetch_add(1, Ordering::Relaxed)
+        );
+        tokio::fs::write(&path, &token)
+            .await
+            .with_context(|| format!("claim fader of {input}"))?;
+        Ok(Self { path, token })
+    }
+
+    /// Whether the fade still owns the fader, i.e. nothing has cancelled it.
+    async fn held(&self) -> bool {
+        tokio::fs::read_to_string(&self.path)
+            .await
+            .is_ok_and(|token| token == self.token)
+    }
+}
+
+impl Drop for Claim {
+    fn drop(&mut self) {
+        // Synchronously, since drop can't wait; the file is tiny and local.
+        if std::fs::read_to_string(&self.path).is_ok_and(|token| token == self.token) {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// Where the claim on the fader of `input` is kept.
+fn claim_path(input: &str) -> anyhow::Result<PathBuf> {
+    // Input names can contain anything, including path separators.
+    let name: String = input.bytes().map(|b| format!("{b:02x}")).collect();
+    Ok(crate::runtime_dir()?.join("fades").join(name))
+}
+
+/// Fades each input from one volume multiplier to another, all together over `duration`, for
+/// commands that mix several inputs at once.
+///
+/// Returns whether all the fades ran to completion rather than being cancelled.
+pub(crate) async fn fade_levels(
+    client: &Client,
+    levels: &[(&str, f32, f32)],
+    duration: Duration,
+) -> anyhow::Result<bool> {
+    let claims = try_join_all(levels.iter().map(|&(input, _, _)| Claim::take(input))).await?;
+    let ramps: Vec<_> = levels
+        .iter()
+        .zip(claims)
+        .map(|(&(input, start, end), claim)| Ramp {
+            input,
+            claim,
+            start,
+            end: Volume::Mul(end),
+        })
+        .collect();
+    let finished = ramp(client, &ramps, duration, &Shape::default()).await?;
+    Ok(finished.into_iter().all(|finished| finished))
+}
+
+/// Fades `input` from where its fader is now to `to` over `duration`, like `fade-input` does, for
+/// programs that use obs-do as a library.
+///
+/// Returns whether the fade ran to completion rather than being cancelled, as happens when
+/// another fade of the same input starts.
+pub async fn fade_to(
+    client: &Client,
+    input: &str,
+    to: Volume,
+    duration: Duration,
+    curve: Curve,
+    scale: Scale,
+) -> anyhow::Result<bool> {
+    anyhow::ensure!(!to_mul(to).is_nan(), "cannot fade {input} to {to:?}");
+    let claim = Claim::take(input).await?;
+    let (_, start) = mix_state(client, input).await?;
+    let ramps = [Ramp {
+        input,
+        claim,
+        start,
+        end: to,
+    }];
+    let finished = ramp(client, &ramps, duration, &Shape { curve, scale }).await?;
+    Ok(finished[0])
+}
+
+/// One input's part in a fade: it goes from the `start` multiplier to the `end` volume.
+struct Ramp<'a> {
+    input: &'a str,
+    claim: Claim,
+    start: f32,
+    end: Volume,
+}
+
+/// Moves all the `ramps` along together, so that inputs fading at the same time stay in sync.
+///
+/// Returns, for each ramp, whether it reached its end rather than being cancelled.
+async fn ramp(
+    client: &Client,
+    ramps: &[Ramp<'_>],
+    duration: Duration,
+    shape: &Shape,
+) -> anyhow::Result<Vec<bool>> {
+    let levels: Vec<_> = ramps
+        .iter()
+        .map(|ramp| {
+            (
+                shape.scale.level(ramp.start),
+                shape.scale.level(to_mul(ramp.end)),
+            )
+        })
+        .collect();
+
+    // Progress is worked out from the time that has actually passed, so that slow responses or
+    // late ticks make the fade skip ahead rather than drag on past its duration.
+    let started = Instant::now();
+    let mut interval = tokio::time::interval_at(started + STEP, STEP);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    let mut running = vec![true; ramps.len()];
+    let mut done = false;
+    while !done {
+        interval.tick().await;
+        let elapsed = started.elapsed();
+        done = elapsed >= duration;
+        for (running, ramp) in running.iter_mut().zip(ramps) {
+            if *running && !ramp.claim.held().await {
+                tracing::info!("Fade of {} was cancelled.", ramp.input);
+                *running = false;
+            }
+        }
+
+        let progress = shape
+            .curve
+            .apply(elapsed.as_secs_f32() / duration.as_secs_f32());
+        try_join_all(
+            ramps
+                .iter()
+                .zip(&levels)
+                .zip(&running)
+                .filter(|(_, &running)| running)
+                .map(|((ramp, &(start, end)), _)| {
+                    // Land exactly on the target, whatever rounding the curve does.
+                    let volume = if done {
+                        ramp.end
+                    } else {
+                        shape.scale.volume(start + (end - start) * progress)
+                    };
+                    set_volume(client, ramp.input, volume)
+                }),
+        )
+        .await?;
+    }
+    Ok(running)
+}
+
+/// How long a fade from the `start` multiplier to `end` takes at `rate` dB per second.
+fn rate_duration(start: f32, end: Volume, rate: f32) -> anyhow::Result<Duration> {
+    let distance = Scale::Db.level(to_mul(end)) - Scale::Db.level(start);
+    Duration::try_from_secs_f32(distance.abs() / rate)
+        .map_err(|_| anyhow::anyhow!("a fade at {rate}dB/s would take too long"))
+}
+
+/// Parses a fade speed like `3dB/s` (or just `3`), in dB per second.
+fn parse_rate(rate: &str) -> anyhow::Result<f32> {
+    let db = rate.strip_suffix("dB/s").unwrap_or(rate);
+    let db: f32 = db
+        .trim()
+        .parse()
+        .with_context(|| format!("invalid rate `{rate}`, expected e.g. `3dB/s`"))?;
+    anyhow::ensure!(
+        db.is_finite() && db > 0.,
+        "the rate must be a positive number of dB per second"
+    );
+    Ok(db)
+}
+
+async fn set_volume(client: &Client, input: &str, volume: Volume) -> anyhow::Result<()> {
+    client
+        .inputs()
+        .set_volume(input, volume.into())
+        .await
+        .with_context(|| format!("fade volume of {input}"))
+}
+
+/// Resolves glob patterns among `inputs` to the names of the inputs they match.
+///
+/// Plain names are passed through as they are, so OBS only has to be asked for the list of
+/// inputs when a pattern is given.
+async fn expand_inputs(client: &Client, inputs: &[String]) -> anyhow::Result<Vec<String>> {
+    let is_pattern = |input: &str| input.contains(['*', '?']);
+    let all = if inputs.iter().any(|input| is_pattern(input)) {
+        client.inputs().list(None).await.context("list inputs")?
+    } else {
+        Vec::new()
+    };
+
+    let mut expanded: Vec<String> = Vec::new();
+    for input in inputs {
+        if !is_pattern(input) {
+            expanded.push(input.clone());
+            continue;
+        }
+        let matches: Vec<_> = all
+            .iter()
+            .filter(|candidate| glob_match(input, &candidate.name))
+            .map(|candidate| candidate.name.clone())
+            .collect();
+        anyhow::ensure!(!matches.is_empty(), "no input matches {input}");
+        expanded.extend(matches);
+    }
+    // Fading an input twice at once would have the two fades fight over its fader.
+    let mut seen = std::collections::HashSet::new();
+    expanded.retain(|input| seen.insert(input.clone()));
+    Ok(expanded)
+}
+
+/// Matches `name` against a pattern where `*` stands for any run of characters and `?` for
+/// any single character.
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    // Position to resume from after the last `*`, and how much of `name` it has swallowed.
+    let mut backtrack = None;
+    let (mut p, mut n) = (0, 0);
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star, swallowed)) => {
+                    p = star + 1;
+                    n = swallowed + 1;
+                    backtrack = Some((star, swallowed + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Looks up whether `input` is muted, and its volume multiplier.
+async fn mix_state(client: &Client, input: &str) -> anyhow::Result<(bool, f32)> {
+    tokio::try_join!(
+        async {
+            client
+                .inputs()
+                .muted(input)
+                .await
+                .with_context(|| format!("get mute state of {input}"))
+        },
+        async {
+            client
+                .inputs()
+                .volume(input)
+                .await
+                .map(|volume| volume.mul)
+                .with_context(|| format!("get volume of {input}"))
+        },
+    )
+}
+
+/// Converts a volume to OBS' linear multiplier scale.
+fn to_mul(volume: Volume) -> f32 {
+    match volume {
+        Volume::Mul(mul) => mul,
+        Volume::Db(db) => 10f32.powf(db / 20.),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CURVES: [Curve; 4] = [
+        Curve::Linear,
+        Curve::Exponential,
+        Curve::Logarithmic,
+        Curve::Smooth,
+    ];
+
+    #[test]
+    fn curves_start_and_end_in_place() {
+        for curve in CURVES {
+            assert!(curve.apply(0.).abs() < 1e-6, "{curve:?} doesn't start at 0");
+            assert!(
+                (curve.apply(1.) - 1.).abs() < 1e-6,
+                "{curve:?} doesn't end at 1"
+            );
+        }
+    }
+
+    #[test]
+    fn curves_only_move_forward() {
+        for curve in CURVES {
+            let mut last = curve.apply(0.);
+            for step in 1..=100 {
+                let next = curve.apply(step as f32 / 100.);
+                assert!(next >= last, "{curve:?} goes back at step {step}");
+                last = next;
+            }
+        }
+    }
+
+    #[test]
+    fn curves_bend_the_right_way() {
+        assert!(Curve::Exponential.apply(0.5) < 0.5);
+        assert!(Curve::Logarithmic.apply(0.5) > 0.5);
+        assert!((Curve::Smooth.apply(0.5) - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn db_scale() {
+        assert!(Scale::Db.level(1.).abs() < 1e-6);
+        assert!((Scale::Db.level(0.5) - -6.0206).abs() < 1e-3);
+        assert_eq!(Scale::Db.level(0.), SILENCE_DB);
+        assert_eq!(Scale::Db.level(1e-9), SILENCE_DB);
+        assert_eq!(Scale::Mul.level(0.25), 0.25);
+    }
+
+    #[test]
+    fn rates() {
+        assert_eq!(parse_rate("3dB/s").unwrap(), 3.);
+        assert_eq!(parse_rate("1.5").unwrap(), 1.5);
+        assert!(parse_rate("0").is_err());
+        assert!(parse_rate("-3dB/s").is_err());
+        assert!(parse_rate("fast").is_err());
+    }
+
+    #[test]
+    fn rate_durations() {
+        assert_eq!(
+            rate_duration(1., Volume::Db(-6.), 3.).unwrap(),
+            Duration::from_secs(2)
+        );
+        // Silence counts as SILENCE_DB, rather than infinitely far away.
+        assert_eq!(
+            rate_duration(0., Volume::Db(0.), 10.).unwrap(),
+            Duration::from_secs_f32(-SILENCE_DB / 10.)
+        );
+        assert_eq!(
+            rate_duration(1., Volume::Mul(1.), 3.).unwrap(),
+            Duration::ZERO
+        );
+        assert!(rate_duration(1., Volume::Db(-6.), 1e-37).is_err());
+        assert!(rate_duration(1., Volume::Db(1e30), 3.).is_err());
+    }
+
+    #[test]
+    fn globs() {
+        for (pattern, name, matches) in [
+            ("Mic/Aux", "Mic/Aux", true),
+            ("Mic/Aux", "mic/aux", false),
+            ("Mic*", "Mic/Aux", true),
+            ("Mic*", "Mic", true),
+            ("*Aux", "Mic/Aux", true),
+            ("M*c*x", "Mic/Aux", true),
+            ("*", "", true),
+            ("*", "Desktop Audio", true),
+            ("Mic ?", "Mic 1", true),
+            ("Mic ?", "Mic 12", false),
+            ("Mic ?", "Mic ", false),
+            ("??", "Mi", true),
+            // Patterns are anchored at both ends.
+            ("Mic", "Mic/Aux", false),
+            ("Aux", "Mic/Aux", false),
+            ("ic/Au", "Mic/Aux", false),
+            ("*ic", "Mic/Aux", false),
+            ("", "", true),
+            ("", "Mic/Aux", false),
+        ] {
+            assert_eq!(
+                glob_match(pattern, name),
+                matches,
+                "{pattern:?} against {name:?}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn fades_to_a_volume() {
+        let input = format!("Music {}", std::process::id());
+        let (client, requests) = crate::fake_obs::connect(|kind, _| {
+            Some(match kind {
+                "GetInputMute" => json!({ "inputMuted": false }),
+                "GetInputVolume" => json!({ "inputVolumeMul": 1.0, "inputVolumeDb": 0.0 }),
+                _ => serde_json::Value::Null,
+            })
+        })
+        .await;
+        let duration = Duration::from_millis(100);
+        let finished = fade_to(
+            &client,
+            &input,
+            Volume::Db(-6.),
+            duration,
+            Curve::Smooth,
+            Scale::Db,
+        )
+        .await
+        .unwrap();
+        assert!(finished);
+        let (kind, data) = requests.lock().unwrap().last().cloned().unwrap();
+        assert_eq!(kind, "SetInputVolume");
+        assert_eq!(data["inputName"], input.as_str());
+        assert!((data["inputVolumeDb"].as_f64().unwrap() + 6.).abs() < 1e-3);
+        assert!(!claim_path(&input).unwrap().exists());
+
+        let nan = Volume::Mul(f32::NAN);
+        assert!(
+            fade_to(&client, &input, nan, duration, Curve::Linear, Scale::Db)
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn failed_fades_release_their_claims() {
+        let input = format!("Claimed {}", std::process::id());
+        let (client, _) = crate::fake_obs::connect(|kind, _| match kind {
+            "SetInputVolume" => None,
+            _ => Some(serde_json::Value::Null),
+        })
+        .await;
+        let path = claim_path(&input).unwrap();
+        let levels = [(input.as_str(), 1., 0.)];
+        assert!(fade_levels(&client, &levels, Duration::from_secs(1))
+            .await
+            .is_err());
+        assert!(!path.exists());
+    }
+}
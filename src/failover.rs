@@ -0,0 +1,141 @@
+use anyhow::Context;
+use futures_util::{Stream, StreamExt};
+use obws::{
+    events::{Event, OutputState},
+    Client,
+};
+use serde_json::json;
+use std::time::Duration;
+use tokio::time::Instant;
+
+use crate::{config::Config, output::Output};
+
+/// How long to wait for the failed stream to stop before switching it over.
+const STOP_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, clap::Args)]
+pub(crate) struct FailoverArgs {
+    /// The backup ingest server, like `rtmp://backup.example.com/live`.
+    ///
+    /// Defaults to `server` in the `[failover]` section of the config file.
+    #[arg(long, value_name = "URL")]
+    server: Option<String>,
+
+    /// The stream key to use with the backup server.
+    ///
+    /// Defaults to `key` in the `[failover]` section of the config file.
+    #[arg(long)]
+    key: Option<String>,
+
+    /// How long OBS may keep trying to reconnect before the stream is switched over.
+    #[arg(long, value_name = "DURATION", default_value = "30s")]
+    after: String,
+}
+
+/// Watches the stream, and switches it to the backup ingest if it fails.
+///
+/// The stream counts as failed once OBS has been reconnecting for longer than `--after`, or when
+/// it stops while reconnecting because OBS ran out of retries. After switching over this
+/// returns, so that a backup that fails too isn't restarted over and over.
+pub(crate) async fn run(client: &Client, args: &FailoverArgs, out: Output) -> anyhow::Result<()> {
+    let after = crate::parse_duration(&args.after)?;
+    let config = Config::load(crate::project_dirs()?.config_dir()).await?;
+    let configured = config.failover.as_ref();
+    let server = args
+        .server
+        .as_deref()
+        .or(configured.map(|failover| failover.server.as_str()))
+        .context(
+            "no backup server given; pass --server or set it under [failover] in the config file",
+        )?;
+    let key = args
+        .key
+        .as_deref()
+        .or(configured.map(|failover| failover.key.as_str()))
+        .unwrap_or_default();
+
+    let events = client.events().context("subscribe to OBS events")?;
+    let mut events = std::pin::pin!(events);
+    tracing::info!("Watching the stream; will fail over to {server}.");
+
+    let mut reconnecting_since: Option<Instant> = None;
+    loop {
+        let exhausted = async {
+            match reconnecting_since {
+                Some(since) => tokio::time::sleep_until(since + after).await,
+                None => std::future::pending().await,
+            }
+        };
+        tokio::select! {
+            event = events.next() => match event {
+                Some(Event::StreamStateChanged { state, .. }) => match state {
+                    OutputState::Reconnecting => {
+                        tracing::warn!("Stream lost its connection; OBS is reconnecting.");
+                        reconnecting_since.get_or_insert_with(Instant::now);
+                    }
+                    OutputState::Reconnected | OutputState::Started
+                        if reconnecting_since.is_some() =>
+                    {
+                        tracing::info!("Stream reconnected.");
+                        reconnecting_since = None;
+                    }
+                    // OBS gave up; a stop the user asked for doesn't come while reconnecting.
+                    OutputState::Stopped if reconnecting_since.is_some() => break,
+                    _ => {}
+                },
+                Some(_) => {}
+                None => anyhow::bail!("lost connection to OBS"),
+            },
+            _ = exhausted => break,
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+        }
+    }
+
+    tracing::warn!("Stream failed; switching to {server}.");
+    switch(client, &mut events, server, key).await?;
+    out.result(
+        format!("Switched the stream to {server}"),
+        &json!({ "server": server }),
+    )
+}
+
+/// Stops the stream if it's still up, points it at `server`, and starts it again.
+async fn switch(
+    client: &Client,
+    events: &mut (impl Stream<Item = Event> + Unpin),
+    server: &str,
+    key: &str,
+) -> anyhow::Result<()> {
+    let active = client
+        .streaming()
+        .status()
+        .await
+        .context("get streaming status")?
+        .active;
+    if active {
+        client.streaming().stop().await.context("stop stream")?;
+        // OBS refuses to start the stream again until it has fully stopped.
+        let stopped = async {
+            while let Some(event) = events.next().await {
+                if let Event::StreamStateChanged {
+                    state: OutputState::Stopped,
+                    ..
+                } = event
+                {
+                    return Ok(());
+                }
+            }
+            anyhow::bail!("lost connection to OBS")
+        };
+        tokio::time::timeout(STOP_TIMEOUT, stopped)
+            .await
+            .context("timed out waiting for the stream to stop")??;
+    }
+
+    client
+        .config()
+        .set_stream_service_settings("rtmp_custom", &json!({ "server": server, "key": key }))
+        .await
+        .context("switch stream service to the backup server")?;
+    client.streaming().start().await.context("start stream")
+}
@@ -0,0 +1,86 @@
+//! Plays just enough of OBS for tests to run commands against it.
+
+use futures_util::{SinkExt, StreamExt};
+use obws::Client;
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::output::{self, Output};
+
+/// The requests OBS was sent, as their type and data, apart from asking for its version.
+pub(crate) type Requests = Arc<Mutex<Vec<(String, Value)>>>;
+
+/// Connects to a fake OBS that answers each request with what `respond` returns for its type and
+/// data, and fails the ones it returns `None` for.
+pub(crate) async fn connect(
+    respond: impl Fn(&str, &Value) -> Option<Value> + Send + 'static,
+) -> (Client, Requests) {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let requests = Requests::default();
+    tokio::spawn(serve(listener, respond, requests.clone()));
+    let client = Client::connect("127.0.0.1", port, None::<&str>)
+        .await
+        .unwrap();
+    (client, requests)
+}
+
+/// Runs a command line against `client` the way the REPL does, and returns what it printed with
+/// `--json`: `null` if nothing, the document if one, and an array if several.
+pub(crate) async fn run_line(client: &Client, line: &str) -> anyhow::Result<Value> {
+    let (res, results) = Output::capture(crate::run_line(client, line, Output::new(true))).await;
+    res.map(|()| output::collapse(results))
+}
+
+async fn serve(
+    listener: tokio::net::TcpListener,
+    respond: impl Fn(&str, &Value) -> Option<Value>,
+    requests: Requests,
+) {
+    let (stream, _) = listener.accept().await.unwrap();
+    let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+    let text = |message: Value| Message::Text(message.to_string());
+    let hello = json!({ "op": 0, "d": { "obsWebSocketVersion": "5.4.2", "rpcVersion": 1 } });
+    socket.send(text(hello)).await.unwrap();
+    // What the client identifies with doesn't matter here.
+    socket.next().await.unwrap().unwrap();
+    let identified = json!({ "op": 2, "d": { "negotiatedRpcVersion": 1 } });
+    socket.send(text(identified)).await.unwrap();
+    while let Some(Ok(Message::Text(request))) = socket.next().await {
+        let request: Value = serde_json::from_str(&request).unwrap();
+        let kind = request["d"]["requestType"].as_str().unwrap_or_default();
+        let data = &request["d"]["requestData"];
+        let response = if kind == "GetVersion" {
+            Some(json!({
+                "obsVersion": "30.1.2",
+                "obsWebSocketVersion": "5.4.2",
+                "rpcVersion": 1,
+                "availableRequests": [],
+                "supportedImageFormats": [],
+                "platform": "linux",
+                "platformDescription": "",
+            }))
+        } else {
+            requests
+                .lock()
+                .unwrap()
+                .push((kind.to_owned(), data.clone()));
+            respond(kind, data)
+        };
+        let status = match &response {
+            Some(_) => json!({ "result": true, "code": 100 }),
+            None => json!({ "result": false, "code": 600, "comment": "not found" }),
+        };
+        let response = json!({
+            "op": 7,
+            "d": {
+                "requestType": kind,
+                "requestId": request["d"]["requestId"],
+                "requestStatus": status,
+                "responseData": response,
+            },
+        });
+        socket.send(text(response)).await.unwrap();
+    }
+}
@@ -0,0 +1,188 @@
+use anyhow::Context;
+use obws::{
+    requests::{
+        inputs,
+        scene_items::{Id, SetEnabled, SetIndex, SetTransform},
+        sources::SaveScreenshot,
+    },
+    Client,
+};
+use serde_json::json;
+use std::path::PathBuf;
+
+use crate::output::Output;
+
+/// Covers `input` in `scene` with a still image of what it shows right now, and hides it.
+///
+/// The image is a new image source named after the input, placed right above it with the same
+/// position, size and crop, so that the switch can't be seen.
+pub(crate) async fn freeze(
+    client: &Client,
+    input: &str,
+    scene: Option<String>,
+    out: Output,
+) -> anyhow::Result<()> {
+    let scene = scene_or_current(client, scene).await?;
+    let item_id = item_id(client, &scene, input).await?;
+    let (transform, index) = tokio::try_join!(
+        async {
+            client
+                .scene_items()
+                .transform(&scene, item_id)
+                .await
+                .with_context(|| format!("get transform of {input} in {scene}"))
+        },
+        async {
+            client
+                .scene_items()
+                .index(&scene, item_id)
+                .await
+                .with_context(|| format!("get index of {input} in {scene}"))
+        },
+    )?;
+
+    let path = frozen_path(input)?;
+    if let Some(dir) = path.parent() {
+        tokio::fs::create_dir_all(dir)
+            .await
+            .with_context(|| format!("create {}", dir.display()))?;
+    }
+    client
+        .sources()
+        .save_screenshot(SaveScreenshot {
+            source: input,
+            format: "png",
+            file_path: &path,
+            width: None,
+            height: None,
+            compression_quality: None,
+        })
+        .await
+        .with_context(|| format!("take screenshot of {input}"))?;
+
+    let frozen = frozen_name(input);
+    let frozen_id = client
+        .inputs()
+        .create(inputs::Create {
+            scene: &scene,
+            input: &frozen,
+            kind: "image_source",
+            settings: Some(json!({ "file": path })),
+            enabled: Some(true),
+        })
+        .await
+        .with_context(|| format!("create input {frozen} in {scene}"))?;
+    client
+        .scene_items()
+        .set_transform(SetTransform {
+            scene: &scene,
+            item_id: frozen_id,
+            transform: transform.into(),
+        })
+        .await
+        .with_context(|| format!("set transform of {frozen} in {scene}"))?;
+    client
+        .scene_items()
+        .set_index(SetIndex {
+            scene: &scene,
+            item_id: frozen_id,
+            index: index + 1,
+        })
+        .await
+        .with_context(|| format!("move {frozen} above {input} in {scene}"))?;
+    set_enabled(client, &scene, input, item_id, false).await?;
+
+    out.result(
+        format!("Froze {input} in {scene}"),
+        &json!({ "input": input, "scene": scene, "frozen": true }),
+    )
+}
+
+/// Shows `input` in `scene` again, and removes the image that covered it.
+pub(crate) async fn unfreeze(
+    client: &Client,
+    input: &str,
+    scene: Option<String>,
+    out: Output,
+) -> anyhow::Result<()> {
+    let scene = scene_or_current(client, scene).await?;
+    let item_id = item_id(client, &scene, input).await?;
+    set_enabled(client, &scene, input, item_id, true).await?;
+
+    let frozen = frozen_name(input);
+    client
+        .inputs()
+        .remove(&frozen)
+        .await
+        .with_context(|| format!("remove input {frozen}"))?;
+    let path = frozen_path(input)?;
+    if let Err(e) = tokio::fs::remove_file(&path).await {
+        tracing::debug!("could not remove {}: {e}", path.display());
+    }
+
+    out.result(
+        format!("Unfroze {input} in {scene}"),
+        &json!({ "input": input, "scene": scene, "frozen": false }),
+    )
+}
+
+fn frozen_name(input: &str) -> String {
+    format!("{input} (frozen)")
+}
+
+/// Where the still image of a frozen input is kept.
+///
+/// OBS writes and reads the file itself, so this only works with an OBS on the same machine.
+fn frozen_path(input: &str) -> anyhow::Result<PathBuf> {
+    let file: String = input
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect();
+    Ok(crate::runtime_dir()?
+        .join("freeze")
+        .join(format!("{file}.png")))
+}
+
+async fn scene_or_current(client: &Client, scene: Option<String>) -> anyhow::Result<String> {
+    match scene {
+        Some(scene) => Ok(scene),
+        None => client
+            .scenes()
+            .current_program_scene()
+            .await
+            .context("get current program scene"),
+    }
+}
+
+async fn item_id(client: &Client, scene: &str, input: &str) -> anyhow::Result<i64> {
+    client
+        .scene_items()
+        .id(Id {
+            scene,
+            source: input,
+            search_offset: None,
+        })
+        .await
+        .with_context(|| format!("find {input} in scene {scene}"))
+}
+
+async fn set_enabled(
+    client: &Client,
+    scene: &str,
+    input: &str,
+    item_id: i64,
+    enabled: bool,
+) -> anyhow::Result<()> {
+    client
+        .scene_items()
+        .set_enabled(SetEnabled {
+            scene,
+            item_id,
+            enabled,
+        })
+        .await
+        .with_context(|| {
+            let action = if enabled { "show" } else { "hide" };
+            format!("{action} {input} in {scene}")
+        })
+}
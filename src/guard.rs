@@ -0,0 +1,169 @@
+use anyhow::Context;
+use obws::Client;
+
+/// The exit code when a condition isn't met and `--strict` is given.
+pub(crate) const UNMET_EXIT_CODE: i32 = 3;
+
+/// Conditions on the state of OBS that must hold for the commands to run at all.
+///
+/// When one doesn't hold, nothing is run and the invocation still succeeds, so that hotkeys can
+/// be bound to commands that only make sense some of the time.
+#[derive(Debug, Default, clap::Args)]
+pub(crate) struct Guards {
+    /// Only run if OBS is streaming.
+    #[arg(long, global = true, conflicts_with = "if_not_streaming")]
+    if_streaming: bool,
+
+    /// Only run if OBS is not streaming.
+    #[arg(long, global = true)]
+    if_not_streaming: bool,
+
+    /// Only run if OBS is recording.
+    #[arg(long, global = true, conflicts_with = "if_not_recording")]
+    if_recording: bool,
+
+    /// Only run if OBS is not recording.
+    #[arg(long, global = true)]
+    if_not_recording: bool,
+
+    /// Only run if this scene is live.
+    #[arg(long, global = true, value_name = "SCENE")]
+    if_scene: Option<String>,
+
+    /// Fail with exit code 3 when a condition isn't met, rather than doing nothing successfully.
+    #[arg(long, global = true)]
+    pub(crate) strict: bool,
+}
+
+/// A condition that didn't hold, which `--strict` turns into a failure.
+#[derive(Debug)]
+pub(crate) struct Unmet(pub(crate) String);
+
+impl std::fmt::Display for Unmet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "not running: {}", self.0)
+    }
+}
+
+impl std::error::Error for Unmet {}
+
+impl Guards {
+    /// Checks the conditions against OBS, and explains the first one that doesn't hold.
+    ///
+    /// Only the state that some condition depends on is queried.
+    pub(crate) async fn check(&self, client: &Client) -> anyhow::Result<Option<String>> {
+        if self.if_streaming || self.if_not_streaming {
+            let streaming = client
+                .streaming()
+                .status()
+                .await
+                .context("get streaming status")?
+                .active;
+            if self.if_streaming && !streaming {
+                return Ok(Some(String::from("OBS is not streaming")));
+            }
+            if self.if_not_streaming && streaming {
+                return Ok(Some(String::from("OBS is streaming")));
+            }
+        }
+        if self.if_recording || self.if_not_recording {
+            let recording = client
+                .recording()
+                .status()
+                .await
+                .context("get recording status")?
+                .active;
+            if self.if_recording && !recording {
+                return Ok(Some(String::from("OBS is not recording")));
+            }
+            if self.if_not_recording && recording {
+                return Ok(Some(String::from("OBS is recording")));
+            }
+        }
+        if let Some(scene) = &self.if_scene {
+            let live = client
+                .scenes()
+                .current_program_scene()
+                .await
+                .context("get current program scene")?;
+            if live != *scene {
+                return Ok(Some(format!("{live} is live, not {scene}")));
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn checks_only_what_is_asked() {
+        let (client, requests) = crate::fake_obs::connect(|kind, _| {
+            Some(match kind {
+                "GetStreamStatus" => json!({
+                    "outputActive": true, "outputReconnecting": false,
+                    "outputTimecode": "00:00:00.000", "outputDuration": 0,
+                    "outputCongestion": 0.0, "outputBytes": 0,
+                    "outputSkippedFrames": 0, "outputTotalFrames": 0,
+                }),
+                "GetRecordStatus" => json!({
+                    "outputActive": false, "outputPaused": false,
+                    "outputTimecode": "00:00:00.000", "outputDuration": 0,
+                    "outputBytes": 0,
+                }),
+                "GetCurrentProgramScene" => json!({ "currentProgramSceneName": "Live" }),
+                _ => return None,
+            })
+        })
+        .await;
+
+        assert_eq!(Guards::default().check(&client).await.unwrap(), None);
+        assert!(requests.lock().unwrap().is_empty());
+
+        for (guards, unmet) in [
+            (
+                Guards {
+                    if_streaming: true,
+                    ..Guards::default()
+                },
+                None,
+            ),
+            (
+                Guards {
+                    if_not_streaming: true,
+                    ..Guards::default()
+                },
+                Some("OBS is streaming"),
+            ),
+            (
+                Guards {
+                    if_recording: true,
+                    ..Guards::default()
+                },
+                Some("OBS is not recording"),
+            ),
+            (
+                Guards {
+                    if_not_recording: true,
+                    if_scene: Some(String::from("Live")),
+                    ..Guards::default()
+                },
+                None,
+            ),
+            (
+                Guards {
+                    if_streaming: true,
+                    if_scene: Some(String::from("BRB")),
+                    ..Guards::default()
+                },
+                Some("Live is live, not BRB"),
+            ),
+        ] {
+            let checked = guards.check(&client).await.unwrap();
+            assert_eq!(checked.as_deref(), unmet, "{guards:?}");
+        }
+    }
+}
@@ -0,0 +1,189 @@
+use anyhow::Context;
+use obws::{responses::streaming::StreamStatus, Client};
+use serde_json::json;
+use std::time::Duration;
+use tokio::time::{Instant, MissedTickBehavior};
+
+use crate::output::Output;
+
+#[derive(Debug, Clone, clap::Args)]
+pub(crate) struct HealthArgs {
+    /// Raise the alarm when more than this share of frames is dropped, like `2%`.
+    #[arg(long, value_name = "PERCENT", default_value = "2%")]
+    dropped_threshold: String,
+
+    /// Also raise the alarm when the stream output is more backed up than this, like `50%`.
+    #[arg(long, value_name = "PERCENT")]
+    congestion_threshold: Option<String>,
+
+    /// How long a threshold has to stay exceeded before the alarm goes off.
+    #[arg(long = "for", value_name = "DURATION", default_value = "10s")]
+    sustained: String,
+
+    /// How often to check the stream; dropped frames are counted over this interval.
+    #[arg(long, value_name = "DURATION", default_value = "2s")]
+    interval: String,
+
+    /// Run this shell command when the alarm goes off, with `OBS_DO_ALARM` set to what is wrong.
+    #[arg(long, value_name = "COMMAND")]
+    exec: Option<String>,
+}
+
+/// Tells when a problem has lasted long enough to raise the alarm, and when it's over.
+#[derive(Debug, Default)]
+struct Alarm {
+    /// When the current problem started.
+    since: Option<Instant>,
+    raised: bool,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Change {
+    Raised,
+    Cleared,
+}
+
+impl Alarm {
+    fn update(&mut self, now: Instant, problem: bool, sustained: Duration) -> Option<Change> {
+        if !problem {
+            self.since = None;
+            return std::mem::take(&mut self.raised).then_some(Change::Cleared);
+        }
+        let since = *self.since.get_or_insert(now);
+        if !self.raised && now - since >= sustained {
+            self.raised = true;
+            return Some(Change::Raised);
+        }
+        None
+    }
+}
+
+/// Watches the stream until interrupted, and raises an alarm when it drops too many frames or
+/// is too congested for too long.
+///
+/// Each alarm, and each time the stream recovers, is printed; with `--exec`, raising the alarm
+/// also runs a command.
+pub(crate) async fn run(client: &Client, args: &HealthArgs, out: Output) -> anyhow::Result<()> {
+    let dropped_threshold = parse_percent(&args.dropped_threshold)?;
+    let congestion_threshold = args
+        .congestion_threshold
+        .as_deref()
+        .map(parse_percent)
+        .transpose()?;
+    let sustained = crate::parse_duration(&args.sustained)?;
+    let interval = crate::parse_duration(&args.interval)?;
+    anyhow::ensure!(!interval.is_zero(), "the --interval must be longer than 0s");
+    tracing::info!("Watching the health of the stream.");
+
+    let mut ticks = tokio::time::interval(interval);
+    ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let mut previous: Option<StreamStatus> = None;
+    let mut alarm = Alarm::default();
+    let mut problems = Vec::new();
+    loop {
+        tokio::select! {
+            _ = ticks.tick() => {}
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+        }
+        let status = client
+            .streaming()
+            .status()
+            .await
+            .context("get stream status")?;
+        let dropped = previous
+            .as_ref()
+            .filter(|_| status.active)
+            .and_then(|previous| dropped_share(previous, &status));
+        let congestion = f64::from(status.congestion) * 100.;
+        previous = Some(status);
+
+        problems.clear();
+        if let Some(dropped) = dropped.filter(|dropped| *dropped > dropped_threshold) {
+            problems.push(format!("{dropped:.1}% of frames dropped"));
+        }
+        if congestion_threshold.is_some_and(|threshold| congestion > threshold) {
+            problems.push(format!("{congestion:.0}% congestion"));
+        }
+
+        match alarm.update(Instant::now(), !problems.is_empty(), sustained) {
+            Some(Change::Raised) => {
+                let problem = problems.join(", ");
+                out.result(
+                    format!("Stream unhealthy: {problem}"),
+                    &json!({
+                        "healthy": false,
+                        "problem": problem,
+                        "dropped_percent": dropped,
+                        "congestion_percent": congestion,
+                    }),
+                )?;
+                if let Some(exec) = &args.exec {
+                    let status = crate::watch::shell(exec)
+                        .env("OBS_DO_ALARM", &problem)
+                        .status()
+                        .await
+                        .with_context(|| format!("run `{exec}`"))?;
+                    if !status.success() {
+                        tracing::warn!("`{exec}` exited with {status}");
+                    }
+                }
+            }
+            Some(Change::Cleared) => {
+                out.result("Stream healthy again", &json!({ "healthy": true }))?;
+            }
+            None => {}
+        }
+    }
+}
+
+/// The share of frames dropped between two statuses, in percent, if any frames were sent.
+fn dropped_share(previous: &StreamStatus, status: &StreamStatus) -> Option<f64> {
+    // The counts start over when the stream restarts.
+    let total = status.total_frames.checked_sub(previous.total_frames)?;
+    let skipped = status.skipped_frames.checked_sub(previous.skipped_frames)?;
+    (total > 0).then(|| f64::from(skipped) / f64::from(total) * 100.)
+}
+
+/// Parses a percentage like `2%` or `2`.
+fn parse_percent(percent: &str) -> anyhow::Result<f64> {
+    let number = percent.strip_suffix('%').unwrap_or(percent).trim();
+    let value: f64 = number
+        .parse()
+        .with_context(|| format!("invalid percentage `{percent}`"))?;
+    anyhow::ensure!(
+        (0. ..=100.).contains(&value),
+        "percentage `{percent}` is not between 0% and 100%"
+    );
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentages() {
+        assert_eq!(parse_percent("2%").unwrap(), 2.);
+        assert_eq!(parse_percent("0.5").unwrap(), 0.5);
+        assert!(parse_percent("150%").is_err());
+        assert!(parse_percent("lots").is_err());
+    }
+
+    #[test]
+    fn alarm_needs_a_lasting_problem() {
+        let sustained = Duration::from_secs(10);
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+        let mut alarm = Alarm::default();
+        assert_eq!(alarm.update(at(0), true, sustained), None);
+        assert_eq!(alarm.update(at(5), false, sustained), None);
+        assert_eq!(alarm.update(at(6), true, sustained), None);
+        assert_eq!(alarm.update(at(16), true, sustained), Some(Change::Raised));
+        assert_eq!(alarm.update(at(18), true, sustained), None);
+        assert_eq!(
+            alarm.update(at(20), false, sustained),
+            Some(Change::Cleared)
+        );
+        assert_eq!(alarm.update(at(22), false, sustained), None);
+    }
+}
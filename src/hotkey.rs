@@ -0,0 +1,245 @@
+use obws::Client;
+use std::{collections::BTreeMap, sync::Arc};
+use tokio::sync::watch;
+
+use crate::{config::Config, output::Output};
+
+// Hotkeys are only grabbed through X11. On Windows and macOS, global-hotkey only delivers them to
+// an event loop on the thread that registered them (the main thread, on macOS), which obs-do's
+// async runtime doesn't run; the system's own shortcut settings can run obs-do commands there.
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "openbsd",
+    target_os = "netbsd"
+)))]
+pub(crate) async fn run(
+    _client: &Client,
+    _hotkeys: &BTreeMap<String, String>,
+    _reloads: watch::Receiver<Arc<Config>>,
+    _flags: crate::Flags,
+    _out: Output,
+) -> anyhow::Result<()> {
+    anyhow::bail!(
+        "hotkeyd only works with X11, on Linux and the BSDs; bind obs-do commands to keys in the system's keyboard settings instead"
+    )
+}
+
+#[cfg(any(
+    target_os = "linux",
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "openbsd",
+    target_os = "netbsd"
+))]
+pub(crate) use x11::run;
+
+#[cfg(any(
+    target_os = "linux",
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "openbsd",
+    target_os = "netbsd"
+))]
+mod x11 {
+    use super::*;
+    use anyhow::Context;
+    use futures_util::{stream::FuturesUnordered, StreamExt};
+    use global_hotkey::{hotkey::HotKey, GlobalHotKeyEvent, GlobalHotKeyManager, HotKeyState};
+    use std::collections::HashMap;
+    use tokio::sync::mpsc;
+
+    /// Registers the `[hotkeys]` from the config file with the system, and runs a hotkey's command
+    /// whenever its chord is pressed, wherever the focus is, until interrupted.
+    ///
+    /// Chords are written like `Ctrl+Shift+F1`, as `import-hotkeys` suggests them. The hotkeys are
+    /// grabbed through X11, so on Wayland they only fire while an X11 window has the focus; binding
+    /// obs-do commands in the compositor works better there.
+    ///
+    /// Whenever the config file changes, as announced on `reloads`, its hotkeys replace the ones
+    /// registered before, and the settings that `flags` leave to it are applied again.
+    pub(crate) async fn run(
+        client: &Client,
+        hotkeys: &BTreeMap<String, String>,
+        mut reloads: watch::Receiver<Arc<Config>>,
+        flags: crate::Flags,
+        out: Output,
+    ) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            !hotkeys.is_empty(),
+            "there are no [hotkeys] in the config file to register"
+        );
+        anyhow::ensure!(
+            std::env::var_os("DISPLAY").is_some(),
+            "hotkeyd needs an X11 display, and DISPLAY is not set"
+        );
+        if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+            tracing::warn!(
+                "Under Wayland, hotkeys only fire while an X11 window has the focus; consider binding obs-do commands in the compositor instead."
+            );
+        }
+
+        let manager = GlobalHotKeyManager::new().context("connect to the display")?;
+        let mut bindings = register(&manager, parse(hotkeys)?)?;
+        let (pressed, mut presses) = mpsc::unbounded_channel();
+        GlobalHotKeyEvent::set_event_handler(Some(move |event: GlobalHotKeyEvent| {
+            if event.state == HotKeyState::Pressed {
+                let _ = pressed.send(event.id);
+            }
+        }));
+        tracing::info!("Registered {} hotkeys.", bindings.len());
+
+        let mut config = reloads.borrow_and_update().clone();
+        let mut read_only = flags.read_only(&config);
+        let mut running = FuturesUnordered::new();
+        loop {
+            tokio::select! {
+                Some(id) = presses.recv() => {
+                    let Some(binding) = bindings.get(&id) else {
+                        continue;
+                    };
+                    tracing::debug!("{} pressed", binding.chord);
+                    running.push(execute(client, binding.chord.clone(), binding.words.clone(), read_only, out));
+                }
+                Some(()) = running.next() => {}
+                Ok(()) = reloads.changed() => {
+                    let reloaded = reloads.borrow_and_update().clone();
+                    read_only = flags.reload(&config, &reloaded);
+                    config = reloaded;
+                    let parsed = match parse(&config.hotkeys) {
+                        Ok(parsed) => parsed,
+                        Err(e) => {
+                            tracing::warn!("Keeping the previous hotkeys: {e:#}");
+                            continue;
+                        }
+                    };
+                    unregister(&manager, &bindings);
+                    match register(&manager, parsed) {
+                        Ok(reloaded) => {
+                            bindings = reloaded;
+                            tracing::info!("Registered {} hotkeys.", bindings.len());
+                        }
+                        Err(e) => {
+                            tracing::warn!("Keeping the previous hotkeys: {e:#}");
+                            for binding in bindings.values() {
+                                let _ = manager.register(binding.hotkey);
+                            }
+                        }
+                    }
+                }
+                _ = tokio::signal::ctrl_c() => return Ok(()),
+            }
+        }
+    }
+
+    /// A registered hotkey and the words of the command it runs.
+    struct Binding {
+        chord: String,
+        hotkey: HotKey,
+        words: Vec<String>,
+    }
+
+    /// Registers `bindings` with the system, all or none of them, by hotkey id.
+    fn register(
+        manager: &GlobalHotKeyManager,
+        bindings: Vec<(&str, HotKey, Vec<String>)>,
+    ) -> anyhow::Result<HashMap<u32, Binding>> {
+        let mut registered = HashMap::new();
+        for (chord, hotkey, words) in bindings {
+            if let Err(e) = manager.register(hotkey) {
+                unregister(manager, &registered);
+                return Err(e).with_context(|| format!("register {chord}"));
+            }
+            let chord = chord.to_owned();
+            registered.insert(
+                hotkey.id(),
+                Binding {
+                    chord,
+                    hotkey,
+                    words,
+                },
+            );
+        }
+        Ok(registered)
+    }
+
+    fn unregister(manager: &GlobalHotKeyManager, bindings: &HashMap<u32, Binding>) {
+        for binding in bindings.values() {
+            if let Err(e) = manager.unregister(binding.hotkey) {
+                tracing::debug!("unregistering {} failed: {e}", binding.chord);
+            }
+        }
+    }
+
+    /// Parses the hotkeys and their commands, so that mistakes show up before any key is pressed.
+    ///
+    /// The commands are returned as words, to be parsed again with `--fuzzy` names matched when run.
+    fn parse(
+        hotkeys: &BTreeMap<String, String>,
+    ) -> anyhow::Result<Vec<(&str, HotKey, Vec<String>)>> {
+        hotkeys
+            .iter()
+            .map(|(chord, line)| {
+                let hotkey: HotKey = chord
+                    .parse()
+                    .map_err(|e| anyhow::anyhow!("{e}"))
+                    .with_context(|| format!("invalid hotkey `{chord}`"))?;
+                let words = shell_words::split(line)
+                    .with_context(|| format!("split `{line}` into arguments"))?;
+                let cmd = crate::parse_chained(words.clone())
+                    .with_context(|| format!("invalid command for hotkey {chord}"))?;
+                anyhow::ensure!(
+                    cmd.is_servable(),
+                    "`{line}` cannot be bound to hotkey {chord}"
+                );
+                Ok((chord.as_str(), hotkey, words))
+            })
+            .collect()
+    }
+
+    /// Runs the command of a hotkey; failures are only logged, since there's no one to tell.
+    async fn execute(
+        client: &Client,
+        chord: String,
+        words: Vec<String>,
+        read_only: bool,
+        out: Output,
+    ) {
+        let res = async {
+            let cmd = crate::parse_fuzzy(client, words).await?;
+            if read_only && cmd.is_mutating() {
+                crate::ensure_not_streaming(client).await?;
+            }
+            crate::run(client, cmd, out).await
+        }
+        .await;
+        if let Err(e) = res {
+            tracing::warn!("Command for {chord} failed: {e:#}");
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn parses_bindings() {
+            let hotkeys = |entries: &[(&str, &str)]| {
+                entries
+                    .iter()
+                    .map(|(chord, line)| (chord.to_string(), line.to_string()))
+                    .collect::<BTreeMap<_, _>>()
+            };
+            let bound = hotkeys(&[
+                ("Ctrl+Shift+F1", "set-scene Gaming"),
+                ("Alt+M", "toggle-mute 'Mic/Aux'"),
+            ]);
+            let parsed = parse(&bound).unwrap();
+            assert_eq!(parsed.len(), 2);
+            assert_eq!(parsed[0].1, "Alt+M".parse::<HotKey>().unwrap());
+            assert!(parse(&hotkeys(&[("Ctrl+Nope", "toggle-stream")])).is_err());
+            assert!(parse(&hotkeys(&[("Ctrl+F2", "repl")])).is_err());
+        }
+    }
+}
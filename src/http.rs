@@ -0,0 +1,135 @@
+use anyhow::Context;
+use std::time::Duration;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt, Take};
+
+/// The largest request body that is read; nothing obs-do serves needs more.
+const MAX_BODY: usize = 64 * 1024;
+
+/// The largest request line and headers, together, that are read.
+const MAX_HEAD: u64 = 16 * 1024;
+
+/// How long a client gets to send its whole request before the connection is dropped.
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The parts of an HTTP request that obs-do's small servers look at.
+#[derive(Debug)]
+pub(crate) struct Request {
+    pub(crate) method: String,
+    pub(crate) path: String,
+    /// Header names are lowercased.
+    pub(crate) headers: Vec<(String, String)>,
+    pub(crate) body: Vec<u8>,
+}
+
+impl Request {
+    pub(crate) fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Reads an HTTP request, including its body if it has a `Content-Length`.
+///
+/// This happens before any token is checked, so the request is limited in size like its body
+/// is, and has to arrive within [`READ_TIMEOUT`].
+pub(crate) async fn read_request(
+    stream: &mut (impl AsyncBufRead + Unpin),
+) -> anyhow::Result<Request> {
+    tokio::time::timeout(READ_TIMEOUT, read_limited(stream))
+        .await
+        .context("timed out reading request")?
+}
+
+async fn read_limited(stream: &mut (impl AsyncBufRead + Unpin)) -> anyhow::Result<Request> {
+    let mut head = (&mut *stream).take(MAX_HEAD);
+    let mut line = String::new();
+    read_head_line(&mut head, &mut line).await?;
+    let mut parts = line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_owned();
+    let path = parts.next().unwrap_or_default().to_owned();
+    let mut headers = Vec::new();
+    loop {
+        let mut header = String::new();
+        let read = read_head_line(&mut head, &mut header).await?;
+        if read == 0 || header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            headers.push((name.trim().to_lowercase(), value.trim().to_owned()));
+        }
+    }
+    let mut request = Request {
+        method,
+        path,
+        headers,
+        body: Vec::new(),
+    };
+    if let Some(length) = request.header("content-length") {
+        let length: usize = length.parse().context("invalid Content-Length")?;
+        anyhow::ensure!(length <= MAX_BODY, "request body is too large");
+        request.body.resize(length, 0);
+        stream
+            .read_exact(&mut request.body)
+            .await
+            .context("read request body")?;
+    }
+    Ok(request)
+}
+
+/// Reads one line of the request line and headers from `head`, which ends at [`MAX_HEAD`].
+async fn read_head_line(
+    head: &mut Take<impl AsyncBufRead + Unpin>,
+    line: &mut String,
+) -> anyhow::Result<usize> {
+    let read = head.read_line(line).await.context("read request")?;
+    anyhow::ensure!(
+        head.limit() > 0 || line.ends_with('\n'),
+        "request headers are too large"
+    );
+    Ok(read)
+}
+
+/// Writes a complete response with `body`, after which the connection is closed.
+pub(crate) async fn respond(
+    stream: &mut (impl AsyncWrite + Unpin),
+    status: &str,
+    content_type: &str,
+    body: &str,
+) -> anyhow::Result<()> {
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    stream
+        .write_all(response.as_bytes())
+        .await
+        .context("write response")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn reads_requests() {
+        let mut stream: &[u8] =
+            b"POST /run HTTP/1.1\r\nAuthorization: Bearer x\r\nContent-Length: 4\r\n\r\nbody";
+        let request = read_request(&mut stream).await.unwrap();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/run");
+        assert_eq!(request.header("authorization"), Some("Bearer x"));
+        assert_eq!(request.body, b"body");
+    }
+
+    #[tokio::test]
+    async fn refuses_endless_headers() {
+        let endless = [&b"GET / HTTP/1.1\r\nX-Padding: "[..], &[b'a'; 1 << 20]].concat();
+        let err = read_request(&mut &endless[..]).await.unwrap_err();
+        assert_eq!(err.to_string(), "request headers are too large");
+        let many = "GET / HTTP/1.1\r\n".to_owned() + &"X-Padding: a\r\n".repeat(10_000);
+        let err = read_request(&mut many.as_bytes()).await.unwrap_err();
+        assert_eq!(err.to_string(), "request headers are too large");
+    }
+}
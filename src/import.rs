@@ -0,0 +1,350 @@
+use anyhow::Context;
+use directories::BaseDirs;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+
+use crate::output::Output;
+
+/// A key combination as OBS stores it in its hotkey bindings.
+#[derive(Debug, Deserialize)]
+struct Binding {
+    key: String,
+    #[serde(default)]
+    control: bool,
+    #[serde(default)]
+    alt: bool,
+    #[serde(default)]
+    shift: bool,
+    #[serde(default)]
+    command: bool,
+}
+
+impl Binding {
+    /// Renders the binding as a chord like `Ctrl+Shift+F1`.
+    fn chord(&self) -> String {
+        let mut chord = String::new();
+        for (held, modifier) in [
+            (self.control, "Ctrl+"),
+            (self.alt, "Alt+"),
+            (self.shift, "Shift+"),
+            (self.command, "Super+"),
+        ] {
+            if held {
+                chord.push_str(modifier);
+            }
+        }
+        chord.push_str(self.key.strip_prefix("OBS_KEY_").unwrap_or(&self.key));
+        chord
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct SceneCollection {
+    name: String,
+    #[serde(default)]
+    sources: Vec<Source>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Source {
+    name: String,
+    #[serde(default)]
+    hotkeys: BTreeMap<String, Vec<Binding>>,
+}
+
+/// How the bindings of frontend hotkeys are stored in a profile's `basic.ini`.
+#[derive(Debug, Deserialize)]
+struct ProfileHotkey {
+    #[serde(default)]
+    bindings: Vec<Binding>,
+}
+
+#[derive(Debug)]
+struct Profile {
+    name: String,
+    hotkeys: BTreeMap<String, Vec<Binding>>,
+}
+
+/// The configuration that is suggested to the user.
+#[derive(Debug, Default, Serialize)]
+struct Starter {
+    /// Short names for the scenes and inputs that hotkeys refer to.
+    aliases: BTreeMap<String, String>,
+    /// Key chords mapped to the obs-do command they should run.
+    hotkeys: BTreeMap<String, String>,
+}
+
+impl Starter {
+    /// Returns the alias for `name`, making one up if it doesn't have one yet.
+    fn alias(&mut self, name: &str) -> String {
+        if let Some((alias, _)) = self.aliases.iter().find(|(_, target)| *target == name) {
+            return alias.clone();
+        }
+        let alias = suggest_alias(name, &self.aliases);
+        self.aliases.insert(alias.clone(), name.to_owned());
+        alias
+    }
+
+    fn bind(&mut self, bindings: &[Binding], command: &str) {
+        for binding in bindings {
+            let chord = binding.chord();
+            if let Some(existing) = self.hotkeys.get(&chord) {
+                if existing != command {
+                    tracing::warn!(
+                        "{chord} is bound to both `{existing}` and `{command}`; keeping the former"
+                    );
+                }
+                continue;
+            }
+            self.hotkeys.insert(chord, command.to_owned());
+        }
+    }
+}
+
+/// Makes up a short, lowercase alias for `name` that isn't taken in `aliases` yet.
+pub(crate) fn suggest_alias(name: &str, aliases: &BTreeMap<String, String>) -> String {
+    let mut base = String::new();
+    for c in name.chars().flat_map(char::to_lowercase) {
+        if c.is_alphanumeric() {
+            base.push(c);
+        } else if !base.is_empty() && !base.ends_with('-') {
+            base.push('-');
+        }
+    }
+    let base = match base.trim_end_matches('-') {
+        "" => "source",
+        base => base,
+    };
+    let mut alias = base.to_owned();
+    let mut n = 1;
+    while aliases.contains_key(&alias) {
+        n += 1;
+        alias = format!("{base}-{n}");
+    }
+    alias
+}
+
+/// Prints a starter obs-do configuration built from OBS' own configuration in `obs_dir` (or the
+/// default location for this platform).
+pub(crate) async fn run(obs_dir: Option<&Path>, out: Output) -> anyhow::Result<()> {
+    let obs_dir = match obs_dir {
+        Some(dir) => dir.to_owned(),
+        None => default_obs_dir()?,
+    };
+    let collections = read_collections(&obs_dir.join("basic").join("scenes")).await?;
+    let profiles = read_profiles(&obs_dir.join("basic").join("profiles")).await?;
+
+    let mut starter = Starter::default();
+    for collection in &collections {
+        for source in &collection.sources {
+            for (action, bindings) in &source.hotkeys {
+                let command = match action.as_str() {
+                    "OBSBasic.SelectScene" => format!("set-scene {}", starter.alias(&source.name)),
+                    "libobs.mute" => format!("enforce --mute {}", starter.alias(&source.name)),
+                    "libobs.unmute" => format!("enforce --unmute {}", starter.alias(&source.name)),
+                    _ => continue,
+                };
+                starter.bind(bindings, &command);
+            }
+        }
+    }
+    for profile in &profiles {
+        for (action, bindings) in &profile.hotkeys {
+            // obs-do only toggles outputs, so starting and stopping share a command.
+            let command = match action.as_str() {
+                "OBSBasic.StartStreaming" | "OBSBasic.StopStreaming" => "toggle-stream",
+                "OBSBasic.StartRecording" | "OBSBasic.StopRecording" => "toggle-record",
+                _ => continue,
+            };
+            starter.bind(bindings, command);
+        }
+    }
+
+    let mut toml = format!(
+        "# Starter obs-do configuration imported from {}.\n",
+        obs_dir.display()
+    );
+    let names = |names: Vec<&str>| {
+        if names.is_empty() {
+            String::from("(none)")
+        } else {
+            names.join(", ")
+        }
+    };
+    writeln!(
+        toml,
+        "#\n# Scene collections: {}\n# Profiles: {}\n",
+        names(collections.iter().map(|c| c.name.as_str()).collect()),
+        names(profiles.iter().map(|p| p.name.as_str()).collect()),
+    )?;
+    toml.push_str(&toml::to_string(&starter).context("serialize starter configuration")?);
+
+    out.result(toml.trim_end(), &starter)
+}
+
+/// Where OBS keeps its configuration on this platform.
+pub(crate) fn default_obs_dir() -> anyhow::Result<PathBuf> {
+    let dirs = BaseDirs::new().context("could not determine the home directory")?;
+    Ok(dirs.config_dir().join("obs-studio"))
+}
+
+async fn read_collections(dir: &Path) -> anyhow::Result<Vec<SceneCollection>> {
+    let mut collections = Vec::new();
+    for path in read_dir(dir).await? {
+        if path.extension() != Some("json".as_ref()) {
+            continue;
+        }
+        let contents = tokio::fs::read_to_string(&path)
+            .await
+            .with_context(|| format!("read {}", path.display()))?;
+        match serde_json::from_str(&contents) {
+            Ok(collection) => collections.push(collection),
+            // OBS keeps backups and other bits next to the collections.
+            Err(e) => tracing::debug!("skipping {}: {e}", path.display()),
+        }
+    }
+    Ok(collections)
+}
+
+async fn read_profiles(dir: &Path) -> anyhow::Result<Vec<Profile>> {
+    let mut profiles = Vec::new();
+    for path in read_dir(dir).await? {
+        let ini = path.join("basic.ini");
+        let contents = match tokio::fs::read_to_string(&ini).await {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e).with_context(|| format!("read {}", ini.display())),
+        };
+        let (name, hotkeys) = parse_basic_ini(&contents, &ini);
+        let name = name.unwrap_or_else(|| path.file_name().unwrap().to_string_lossy().into());
+        profiles.push(Profile { name, hotkeys });
+    }
+    Ok(profiles)
+}
+
+/// Picks the profile's name and its hotkey bindings out of the `basic.ini` at `ini`.
+fn parse_basic_ini(contents: &str, ini: &Path) -> (Option<String>, BTreeMap<String, Vec<Binding>>) {
+    let mut name = None;
+    let mut hotkeys = BTreeMap::new();
+    let mut section = "";
+    for line in contents.lines().map(str::trim) {
+        if let Some(header) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            section = header;
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        match section {
+            "General" if key == "Name" => name = Some(value.to_owned()),
+            "Hotkeys" => match serde_json::from_str::<ProfileHotkey>(value) {
+                Ok(hotkey) => {
+                    hotkeys.insert(key.to_owned(), hotkey.bindings);
+                }
+                Err(e) => tracing::debug!("skipping hotkey {key} in {}: {e}", ini.display()),
+            },
+            _ => {}
+        }
+    }
+    (name, hotkeys)
+}
+
+/// Lists the entries of `dir` in a stable order, treating a missing directory as empty.
+async fn read_dir(dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut entries = match tokio::fs::read_dir(dir).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).with_context(|| format!("list {}", dir.display())),
+    };
+    let mut paths = Vec::new();
+    while let Some(entry) = entries
+        .next_entry()
+        .await
+        .with_context(|| format!("list {}", dir.display()))?
+    {
+        paths.push(entry.path());
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn binding(value: serde_json::Value) -> Binding {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn chords() {
+        assert_eq!(binding(json!({ "key": "OBS_KEY_F1" })).chord(), "F1");
+        assert_eq!(
+            binding(json!({ "key": "OBS_KEY_F1", "shift": true, "control": true })).chord(),
+            "Ctrl+Shift+F1"
+        );
+        assert_eq!(
+            binding(json!({
+                "key": "OBS_KEY_NUM5",
+                "control": true,
+                "alt": true,
+                "shift": true,
+                "command": true,
+            }))
+            .chord(),
+            "Ctrl+Alt+Shift+Super+NUM5"
+        );
+        assert_eq!(
+            binding(json!({ "key": "KEY_X", "alt": true })).chord(),
+            "Alt+KEY_X"
+        );
+    }
+
+    #[test]
+    fn suggested_aliases() {
+        let mut aliases = BTreeMap::new();
+        assert_eq!(suggest_alias("Mic/Aux", &aliases), "mic-aux");
+        assert_eq!(
+            suggest_alias("  Be Right Back!  ", &aliases),
+            "be-right-back"
+        );
+        assert_eq!(suggest_alias("Ümlaut Cam", &aliases), "ümlaut-cam");
+        assert_eq!(suggest_alias("***", &aliases), "source");
+        aliases.insert(String::from("webcam"), String::from("Webcam"));
+        aliases.insert(String::from("webcam-2"), String::from("WEBCAM"));
+        assert_eq!(suggest_alias("webcam", &aliases), "webcam-3");
+    }
+
+    #[test]
+    fn profile_hotkeys() {
+        let ini = r#"
+            [General]
+            Name=Streaming
+
+            [Hotkeys]
+            OBSBasic.StartStreaming={"bindings":[{"key":"OBS_KEY_F9","control":true}]}
+            OBSBasic.StopStreaming={}
+            OBSBasic.Broken=not json
+
+            [Output]
+            Name=not the profile
+        "#;
+        let (name, hotkeys) = parse_basic_ini(ini, Path::new("basic.ini"));
+        assert_eq!(name.as_deref(), Some("Streaming"));
+        assert_eq!(
+            hotkeys.keys().collect::<Vec<_>>(),
+            ["OBSBasic.StartStreaming", "OBSBasic.StopStreaming"]
+        );
+        assert_eq!(hotkeys["OBSBasic.StartStreaming"][0].chord(), "Ctrl+F9");
+        assert!(hotkeys["OBSBasic.StopStreaming"].is_empty());
+
+        let (name, hotkeys) = parse_basic_ini("", Path::new("basic.ini"));
+        assert_eq!(name, None);
+        assert!(hotkeys.is_empty());
+    }
+}
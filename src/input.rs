@@ -0,0 +1,582 @@
+use anyhow::Context;
+use clap::Subcommand;
+use obws::{
+    requests::{filters, inputs},
+    Client,
+};
+use serde_json::json;
+use std::path::Path;
+
+use crate::output::Output;
+
+#[derive(Debug, Clone, Subcommand)]
+pub(crate) enum InputCommand {
+    /// Creates an input and adds it to a scene.
+    Create {
+        /// The scene to add the input to.
+        scene: String,
+
+        /// Name of the new input.
+        name: String,
+
+        /// The kind of input, like `browser_source`, `image_source` or `color_source_v3`.
+        kind: String,
+
+        /// Its settings as a JSON object, `@<path>` to read them from a file, or `-` to read them
+        /// from stdin; any left out have their defaults.
+        #[arg(allow_hyphen_values = true)]
+        settings: Option<String>,
+    },
+    /// Removes an input from every scene it is in.
+    Remove { input: String },
+    /// Creates a new input of the same kind, with the settings and filters of the given input.
+    Duplicate {
+        input: String,
+
+        /// Name of the new input.
+        #[arg(long)]
+        name: String,
+
+        /// Scene to add the new input to.
+        ///
+        /// Defaults to the current program scene.
+        #[arg(long)]
+        scene: Option<String>,
+    },
+    /// Changes a single, possibly nested, setting of an input.
+    Set {
+        input: String,
+
+        /// Which setting to change, as a JSON Pointer like `/url` or `/font/size`.
+        ///
+        /// Objects along the way are created if need be.
+        #[arg(long, value_name = "POINTER")]
+        path: String,
+
+        /// The new value; taken as JSON if it parses as such, and as a string otherwise.
+        #[arg(long, allow_hyphen_values = true)]
+        value: String,
+    },
+    /// Prints all the settings of an input, as JSON.
+    GetSettings { input: String },
+    /// Replaces the settings of an input with the given JSON object.
+    SetSettings {
+        input: String,
+
+        /// The settings as a JSON object, `@<path>` to read them from a file, or `-` to read them
+        /// from stdin.
+        #[arg(allow_hyphen_values = true)]
+        settings: String,
+
+        /// Only change the settings that are given, and keep the others as they are.
+        ///
+        /// Otherwise, settings that aren't given go back to their defaults.
+        #[arg(long)]
+        overlay: bool,
+    },
+    /// Covers the input with a still image of what it shows right now, until `unfreeze`.
+    ///
+    /// Useful for hiding a brief sensitive moment without switching scenes. Only works with an
+    /// OBS on the same machine, which has to be able to read the image back.
+    Freeze {
+        input: String,
+
+        /// The scene to freeze the input in.
+        ///
+        /// Defaults to the current program scene.
+        #[arg(long)]
+        scene: Option<String>,
+    },
+    /// Shows the live input again after `freeze`.
+    Unfreeze {
+        input: String,
+
+        /// The scene the input was frozen in.
+        ///
+        /// Defaults to the current program scene.
+        #[arg(long)]
+        scene: Option<String>,
+    },
+}
+
+impl InputCommand {
+    pub(crate) fn is_mutating(&self) -> bool {
+        match self {
+            InputCommand::Create { .. }
+            | InputCommand::Remove { .. }
+            | InputCommand::Duplicate { .. }
+            | InputCommand::Set { .. }
+            | InputCommand::Freeze { .. }
+            | InputCommand::Unfreeze { .. }
+            | InputCommand::SetSettings { .. } => true,
+            InputCommand::GetSettings { .. } => false,
+        }
+    }
+}
+
+pub(crate) async fn run(client: &Client, cmd: InputCommand, out: Output) -> anyhow::Result<()> {
+    match cmd {
+        InputCommand::Create {
+            scene,
+            name,
+            kind,
+            settings,
+        } => {
+            let settings = match settings {
+                Some(settings) => Some(parse_settings(&settings).await?),
+                None => None,
+            };
+            let kinds = client
+                .inputs()
+                .list_kinds(false)
+                .await
+                .context("list input kinds")?;
+            if let Some(e) = crate::names::unknown("input kind", &kind, &kinds) {
+                return Err(e);
+            }
+            client
+                .inputs()
+                .create(inputs::Create {
+                    scene: &scene,
+                    input: &name,
+                    kind: &kind,
+                    settings,
+                    enabled: None,
+                })
+                .await
+                .with_context(|| format!("create input {name} in {scene}"))?;
+            out.json(&json!({ "input": name, "scene": scene, "kind": kind }))?;
+        }
+        InputCommand::Remove { input } => {
+            client
+                .inputs()
+                .remove(&input)
+                .await
+                .with_context(|| format!("remove input {input}"))?;
+            out.json(&json!({ "input": input, "removed": true }))?;
+        }
+        InputCommand::Duplicate { input, name, scene } => {
+            let source = client
+                .inputs()
+                .settings::<serde_json::Value>(&input)
+                .await
+                .with_context(|| format!("get settings of {input}"))?;
+            let scene = match scene {
+                Some(scene) => scene,
+                None => client
+                    .scenes()
+                    .current_program_scene()
+                    .await
+                    .context("get current program scene")?,
+            };
+
+            client
+                .inputs()
+                .create(inputs::Create {
+                    scene: &scene,
+                    input: &name,
+                    kind: &source.kind,
+                    settings: Some(source.settings),
+                    enabled: None,
+                })
+                .await
+                .with_context(|| format!("create input {name} in {scene}"))?;
+
+            let source_filters = client
+                .filters()
+                .list(&input)
+                .await
+                .with_context(|| format!("list filters of {input}"))?;
+            let filter_count = source_filters.len();
+            for filter in source_filters {
+                client
+                    .filters()
+                    .create(filters::Create {
+                        source: &name,
+                        filter: &filter.name,
+                        kind: &filter.kind,
+                        settings: Some(filter.settings),
+                    })
+                    .await
+                    .with_context(|| format!("copy filter {} to {name}", filter.name))?;
+                if !filter.enabled {
+                    client
+                        .filters()
+                        .set_enabled(filters::SetEnabled {
+                            source: &name,
+                            filter: &filter.name,
+                            enabled: false,
+                        })
+                        .await
+                        .with_context(|| format!("disable filter {} on {name}", filter.name))?;
+                }
+            }
+            out.json(&json!({ "input": name, "scene": scene, "filters": filter_count }))?;
+        }
+        InputCommand::Set { input, path, value } => {
+            let value = serde_json::from_str(&value).unwrap_or(serde_json::Value::String(value));
+            let current = client
+                .inputs()
+                .settings::<serde_json::Value>(&input)
+                .await
+                .with_context(|| format!("get settings of {input}"))?;
+            // OBS leaves out settings that are at their defaults, which would otherwise be lost
+            // from within the object that gets sent back, like the face next to a font's size.
+            let mut settings = client
+                .inputs()
+                .default_settings::<serde_json::Value>(&current.kind)
+                .await
+                .with_context(|| format!("get default settings of {}", current.kind))?;
+            overlay(&mut settings, current.settings);
+            let top = set_pointer(&mut settings, &path, value.clone())?;
+
+            // Overlaying only replaces top-level settings, so send just the one that changed.
+            let patch = json!({ &top: settings[&top] });
+            client
+                .inputs()
+                .set_settings(inputs::SetSettings {
+                    input: &input,
+                    settings: &patch,
+                    overlay: Some(true),
+                })
+                .await
+                .with_context(|| format!("set {path} of {input}"))?;
+            out.json(&json!({ "input": input, "path": path, "value": value }))?;
+        }
+        InputCommand::GetSettings { input } => {
+            let settings = client
+                .inputs()
+                .settings::<serde_json::Value>(&input)
+                .await
+                .with_context(|| format!("get settings of {input}"))?;
+            out.result(
+                serde_json::to_string_pretty(&settings.settings)?,
+                &json!({ "input": input, "kind": settings.kind, "settings": settings.settings }),
+            )?;
+        }
+        InputCommand::SetSettings {
+            input,
+            settings,
+            overlay,
+        } => {
+            let settings = parse_settings(&settings).await?;
+            client
+                .inputs()
+                .set_settings(inputs::SetSettings {
+                    input: &input,
+                    settings: &settings,
+                    overlay: Some(overlay),
+                })
+                .await
+                .with_context(|| format!("change settings of {input}"))?;
+            out.json(&json!({ "input": input, "settings": settings, "overlay": overlay }))?;
+        }
+        InputCommand::Freeze { input, scene } => {
+            crate::freeze::freeze(client, &input, scene, out).await?;
+        }
+        InputCommand::Unfreeze { input, scene } => {
+            crate::freeze::unfreeze(client, &input, scene, out).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads settings given as a JSON object, or as `@<path>` or `-` to read them from a file or stdin.
+async fn parse_settings(arg: &str) -> anyhow::Result<serde_json::Value> {
+    let settings = crate::text::read(arg).await?;
+    let settings: serde_json::Value =
+        serde_json::from_str(&settings).context("parse settings as JSON")?;
+    anyhow::ensure!(settings.is_object(), "the settings must be a JSON object");
+    Ok(settings)
+}
+
+/// Shows the image at `path` in an image source.
+pub(crate) async fn set_image(
+    client: &Client,
+    input: &str,
+    path: &Path,
+    out: Output,
+) -> anyhow::Result<()> {
+    ensure_kind(client, input, &["image_source"], "an image source").await?;
+    // OBS doesn't know the directory obs-do runs in.
+    let path = crate::absolute(path)?;
+    set_settings(client, input, &json!({ "file": path })).await?;
+    out.json(&json!({ "input": input, "file": path }))
+}
+
+/// Fills a color source with `color`, given as `rrggbb` or `rrggbbaa`.
+pub(crate) async fn set_color(
+    client: &Client,
+    input: &str,
+    color: &str,
+    out: Output,
+) -> anyhow::Result<()> {
+    let abgr = parse_color(color)?;
+    ensure_kind(
+        client,
+        input,
+        &["color_source", "color_source_v2", "color_source_v3"],
+        "a color source",
+    )
+    .await?;
+    set_settings(client, input, &json!({ "color": abgr })).await?;
+    out.json(&json!({ "input": input, "color": color.trim_start_matches('#') }))
+}
+
+/// Parses a color like `ff8800` or `#ff880080` into the `0xAABBGGRR` form OBS stores colors in.
+fn parse_color(color: &str) -> anyhow::Result<u32> {
+    let invalid =
+        || anyhow::anyhow!("invalid color `{color}`, expected e.g. `ff8800` or `ff880080`");
+    let hex = color.strip_prefix('#').unwrap_or(color);
+    anyhow::ensure!(
+        matches!(hex.len(), 6 | 8) && hex.bytes().all(|b| b.is_ascii_hexdigit()),
+        invalid()
+    );
+    let rgba = u32::from_str_radix(hex, 16).map_err(|_| invalid())?;
+    let rgba = if hex.len() == 6 {
+        rgba << 8 | 0xff
+    } else {
+        rgba
+    };
+    Ok(rgba.swap_bytes())
+}
+
+/// Fails unless `input` is of one of the `kinds`, since other inputs would quietly take the
+/// settings without doing anything with them.
+pub(crate) async fn ensure_kind(
+    client: &Client,
+    input: &str,
+    kinds: &[&str],
+    description: &str,
+) -> anyhow::Result<()> {
+    let kind = client
+        .inputs()
+        .settings::<serde_json::Value>(input)
+        .await
+        .with_context(|| format!("get settings of {input}"))?
+        .kind;
+    anyhow::ensure!(
+        kinds.contains(&kind.as_str()),
+        "{input} is not {description}, but a {kind}"
+    );
+    Ok(())
+}
+
+async fn set_settings(
+    client: &Client,
+    input: &str,
+    settings: &serde_json::Value,
+) -> anyhow::Result<()> {
+    client
+        .inputs()
+        .set_settings(inputs::SetSettings {
+            input,
+            settings,
+            overlay: Some(true),
+        })
+        .await
+        .with_context(|| format!("change settings of {input}"))
+}
+
+/// Lays `settings` over `base`, merging objects found in both rather than replacing them.
+fn overlay(base: &mut serde_json::Value, settings: serde_json::Value) {
+    match (base, settings) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(settings)) => {
+            for (key, value) in settings {
+                match base.get_mut(&key) {
+                    Some(slot) => overlay(slot, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, settings) => *base = settings,
+    }
+}
+
+/// Sets the value at the JSON Pointer `pointer` within `root`, creating objects along the way,
+/// and returns the top-level key that was changed.
+fn set_pointer(
+    root: &mut serde_json::Value,
+    pointer: &str,
+    value: serde_json::Value,
+) -> anyhow::Result<String> {
+    let invalid = || anyhow::anyhow!("invalid JSON Pointer `{pointer}`, expected e.g. `/url`");
+    let tokens: Vec<String> = pointer
+        .strip_prefix('/')
+        .ok_or_else(invalid)?
+        .split('/')
+        .map(|token| token.replace("~1", "/").replace("~0", "~"))
+        .collect();
+    let (last, parents) = tokens.split_last().ok_or_else(invalid)?;
+
+    let mut target = &mut *root;
+    for token in parents {
+        target = match target {
+            serde_json::Value::Object(object) => {
+                object.entry(token.as_str()).or_insert_with(|| json!({}))
+            }
+            serde_json::Value::Array(array) => token
+                .parse::<usize>()
+                .ok()
+                .and_then(|i| array.get_mut(i))
+                .with_context(|| format!("{pointer} indexes past the end of an array"))?,
+            _ => anyhow::bail!("{pointer} goes through a setting that is not an object"),
+        };
+    }
+    match target {
+        serde_json::Value::Object(object) => {
+            object.insert(last.clone(), value);
+        }
+        serde_json::Value::Array(array) if last == "-" => array.push(value),
+        serde_json::Value::Array(array) => {
+            let slot = last
+                .parse::<usize>()
+                .ok()
+                .and_then(|i| array.get_mut(i))
+                .with_context(|| format!("{pointer} indexes past the end of an array"))?;
+            *slot = value;
+        }
+        _ => anyhow::bail!("{pointer} goes through a setting that is not an object"),
+    }
+    Ok(tokens[0].clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colors() {
+        assert_eq!(parse_color("ff8800").unwrap(), 0xff0088ff);
+        assert_eq!(parse_color("#ff880080").unwrap(), 0x800088ff);
+        assert!(parse_color("f80").is_err());
+        assert!(parse_color("+f8800").is_err());
+    }
+
+    #[tokio::test]
+    async fn settings_arguments() {
+        assert_eq!(
+            parse_settings(r#"{"url": "https://example.com"}"#)
+                .await
+                .unwrap(),
+            json!({ "url": "https://example.com" })
+        );
+        let path = std::env::temp_dir().join(format!("obs-do-settings-{}", std::process::id()));
+        std::fs::write(&path, "{\"width\": 1920}\n").unwrap();
+        assert_eq!(
+            parse_settings(&format!("@{}", path.display()))
+                .await
+                .unwrap(),
+            json!({ "width": 1920 })
+        );
+        std::fs::remove_file(&path).unwrap();
+        assert!(parse_settings("[1, 2]").await.is_err());
+        assert!(parse_settings("url=x").await.is_err());
+    }
+
+    #[test]
+    fn creating() {
+        let create = |settings: &str| {
+            let words = [
+                "input",
+                "create",
+                "Main",
+                "Guest",
+                "browser_source",
+                settings,
+            ];
+            crate::parse_chained(words.map(String::from).to_vec()).unwrap()
+        };
+        // Settings from files on the client's machine can't be read by a server.
+        assert!(create(r#"{"url": "https://example.com"}"#).is_servable());
+        assert!(!create("@guest.json").is_servable());
+        assert!(!create("-").is_servable());
+
+        let kinds = ["browser_source", "image_source"].map(String::from);
+        assert!(crate::names::unknown("input kind", "image_source", &kinds).is_none());
+        assert_eq!(
+            crate::names::unknown("input kind", "browser_sorce", &kinds)
+                .unwrap()
+                .to_string(),
+            "there is no input kind named 'browser_sorce'; did you mean 'browser_source'?"
+        );
+    }
+
+    #[test]
+    fn pointers() {
+        let mut settings = json!({ "url": "a", "list": [1, 2] });
+        assert_eq!(
+            set_pointer(&mut settings, "/url", json!("b")).unwrap(),
+            "url"
+        );
+        assert_eq!(
+            set_pointer(&mut settings, "/font/face", json!("Sans")).unwrap(),
+            "font"
+        );
+        assert_eq!(
+            set_pointer(&mut settings, "/list/1", json!(3)).unwrap(),
+            "list"
+        );
+        set_pointer(&mut settings, "/list/-", json!(4)).unwrap();
+        set_pointer(&mut settings, "/a~1b", json!(true)).unwrap();
+        assert_eq!(
+            settings,
+            json!({
+                "url": "b",
+                "font": { "face": "Sans" },
+                "list": [1, 3, 4],
+                "a/b": true,
+            })
+        );
+        assert!(set_pointer(&mut settings, "url", json!(1)).is_err());
+        assert!(set_pointer(&mut settings, "/list/9", json!(1)).is_err());
+        assert!(set_pointer(&mut settings, "/url/x", json!(1)).is_err());
+    }
+
+    #[tokio::test]
+    async fn setting_keeps_defaulted_siblings() {
+        let (client, requests) = crate::fake_obs::connect(|kind, _| {
+            Some(match kind {
+                "GetInputSettings" => json!({
+                    "inputSettings": { "font": { "size": 72 }, "text": "Up next" },
+                    "inputKind": "text_ft2_source_v2",
+                }),
+                "GetInputDefaultSettings" => json!({
+                    "defaultInputSettings": {
+                        "font": { "face": "Sans Serif", "size": 256, "style": "Regular" },
+                        "text": "",
+                    },
+                }),
+                _ => json!(null),
+            })
+        })
+        .await;
+        let cmd = InputCommand::Set {
+            input: "Title".into(),
+            path: "/font/size".into(),
+            value: "48".into(),
+        };
+        let (res, results) = Output::capture(run(&client, cmd, Output::new(true))).await;
+        res.unwrap();
+        assert_eq!(
+            results,
+            [json!({ "input": "Title", "path": "/font/size", "value": 48 })]
+        );
+        assert_eq!(
+            requests.lock().unwrap().last().unwrap(),
+            &(
+                String::from("SetInputSettings"),
+                json!({
+                    "inputName": "Title",
+                    "inputSettings": {
+                        "font": { "face": "Sans Serif", "size": 48, "style": "Regular" },
+                    },
+                    "overlay": true,
+                })
+            )
+        );
+    }
+}
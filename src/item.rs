@@ -0,0 +1,591 @@
+use anyhow::Context;
+use clap::{Subcommand, ValueEnum};
+use obws::{
+    common::{Alignment, BoundsType},
+    requests::scene_items::{
+        Bounds, CreateSceneItem, Crop, Duplicate, Id, Position, Scale, SceneItemTransform,
+        SetIndex, SetTransform,
+    },
+    responses::scene_items::SceneItemTransform as CurrentTransform,
+    Client,
+};
+use serde_json::json;
+
+use crate::output::Output;
+
+#[derive(Debug, Clone, Subcommand)]
+pub(crate) enum ItemCommand {
+    /// Adds an existing source to a scene, on top of what's there.
+    Add {
+        scene: String,
+
+        source: String,
+
+        /// Add it hidden, to be shown later.
+        #[arg(long)]
+        hidden: bool,
+    },
+    /// Removes a source from a scene, without removing the source itself.
+    Remove { scene: String, source: String },
+    /// Adds another copy of a source to a scene, with the same transform.
+    Duplicate {
+        scene: String,
+
+        source: String,
+
+        /// Add the copy to this scene instead.
+        #[arg(long, value_name = "SCENE")]
+        to_scene: Option<String>,
+    },
+    /// Moves a source up or down in a scene's list of sources, which is what covers what.
+    SetIndex {
+        scene: String,
+
+        source: String,
+
+        /// The position to move it to, where 0 is the bottom; negative positions count down
+        /// from the top, so -1 is the top.
+        #[arg(allow_hyphen_values = true)]
+        index: i64,
+    },
+    /// Scales and positions a source in a scene so that it fits a region of the canvas.
+    Fit {
+        scene: String,
+
+        source: String,
+
+        /// How the source is fitted into the region.
+        #[arg(long, value_enum, default_value_t = FitMode::Contain)]
+        mode: FitMode,
+
+        /// The region to fit the source into, in canvas pixels, like `960x540+960+0`.
+        ///
+        /// Defaults to the whole canvas. The offset may be left out, or just its Y.
+        #[arg(long, value_name = "WxH+X+Y")]
+        bounds: Option<String>,
+    },
+}
+
+impl ItemCommand {
+    pub(crate) fn is_mutating(&self) -> bool {
+        match self {
+            ItemCommand::Add { .. }
+            | ItemCommand::Remove { .. }
+            | ItemCommand::Duplicate { .. }
+            | ItemCommand::SetIndex { .. }
+            | ItemCommand::Fit { .. } => true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub(crate) enum FitMode {
+    /// Show all of the source, leaving empty space on the sides if the aspect ratios differ.
+    Contain,
+    /// Fill the whole region, cropping the source if the aspect ratios differ.
+    Cover,
+    /// Fill the whole region, distorting the source if the aspect ratios differ.
+    Stretch,
+}
+
+/// A rectangle on the canvas.
+#[derive(Debug, Clone, Copy)]
+struct Region {
+    width: f32,
+    height: f32,
+    x: f32,
+    y: f32,
+}
+
+impl Region {
+    /// Parses `WxH+X+Y`, where the offset may be left out, or just its Y as in `WxH+X`.
+    fn parse(bounds: &str) -> anyhow::Result<Self> {
+        let parse = || {
+            let (size, offset) = bounds.split_once('+').unwrap_or((bounds, "0+0"));
+            let (width, height) = size.split_once('x')?;
+            let (x, y) = offset.split_once('+').unwrap_or((offset, "0"));
+            Some(Region {
+                width: width.parse().ok()?,
+                height: height.parse().ok()?,
+                x: x.parse().ok()?,
+                y: y.parse().ok()?,
+            })
+        };
+        let region =
+            parse().with_context(|| format!("invalid bounds {bounds}, expected WxH+X+Y"))?;
+        anyhow::ensure!(
+            region.width > 0. && region.height > 0.,
+            "bounds {bounds} must have a non-zero size"
+        );
+        Ok(region)
+    }
+}
+
+impl std::fmt::Display for Region {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}x{}+{}+{}", self.width, self.height, self.x, self.y)
+    }
+}
+
+pub(crate) async fn run(client: &Client, cmd: ItemCommand, out: Output) -> anyhow::Result<()> {
+    match cmd {
+        ItemCommand::Add {
+            scene,
+            source,
+            hidden,
+        } => {
+            let item_id = client
+                .scene_items()
+                .create(CreateSceneItem {
+                    scene: &scene,
+                    source: &source,
+                    enabled: Some(!hidden),
+                })
+                .await
+                .with_context(|| format!("add {source} to {scene}"))?;
+            out.json(&json!({ "scene": scene, "source": source, "item_id": item_id }))?;
+        }
+        ItemCommand::Remove { scene, source } => {
+            let item_id = id(client, &scene, &source).await?;
+            client
+                .scene_items()
+                .remove(&scene, item_id)
+                .await
+                .with_context(|| format!("remove {source} from {scene}"))?;
+            out.json(&json!({ "scene": scene, "source": source, "removed": true }))?;
+        }
+        ItemCommand::Duplicate {
+            scene,
+            source,
+            to_scene,
+        } => {
+            let item_id = id(client, &scene, &source).await?;
+            let new_id = client
+                .scene_items()
+                .duplicate(Duplicate {
+                    scene: &scene,
+                    item_id,
+                    destination: to_scene.as_deref(),
+                })
+                .await
+                .with_context(|| format!("duplicate {source} in {scene}"))?;
+            let scene = to_scene.unwrap_or(scene);
+            out.json(&json!({ "scene": scene, "source": source, "item_id": new_id }))?;
+        }
+        ItemCommand::SetIndex {
+            scene,
+            source,
+            index,
+        } => {
+            let item_id = id(client, &scene, &source).await?;
+            let count = client
+                .scene_items()
+                .list(&scene)
+                .await
+                .with_context(|| format!("list sources in {scene}"))?
+                .len();
+            let index = absolute_index(index, count)?;
+            client
+                .scene_items()
+                .set_index(SetIndex {
+                    scene: &scene,
+                    item_id,
+                    index,
+                })
+                .await
+                .with_context(|| format!("move {source} in {scene}"))?;
+            out.json(&json!({ "scene": scene, "source": source, "index": index }))?;
+        }
+        ItemCommand::Fit {
+            scene,
+            source,
+            mode,
+            bounds,
+        } => {
+            let region = match bounds {
+                Some(bounds) => Region::parse(&bounds)?,
+                None => {
+                    let video = client
+                        .config()
+                        .video_settings()
+                        .await
+                        .context("get video settings")?;
+                    Region {
+                        width: video.base_width as f32,
+                        height: video.base_height as f32,
+                        x: 0.,
+                        y: 0.,
+                    }
+                }
+            };
+
+            let item_id = id(client, &scene, &source).await?;
+            let current = client
+                .scene_items()
+                .transform(&scene, item_id)
+                .await
+                .with_context(|| format!("get transform of {source} in {scene}"))?;
+
+            let transform = fit(&current, mode, region)
+                .with_context(|| format!("fit {source} into {region}"))?;
+            let json = json!({
+                "scene": scene,
+                "source": source,
+                "position": { "x": transform.x, "y": transform.y },
+                "scale": { "x": transform.scale_x, "y": transform.scale_y },
+                "crop": {
+                    "left": transform.crop[0],
+                    "right": transform.crop[1],
+                    "top": transform.crop[2],
+                    "bottom": transform.crop[3],
+                },
+            });
+            client
+                .scene_items()
+                .set_transform(SetTransform {
+                    scene: &scene,
+                    item_id,
+                    transform: transform.into(),
+                })
+                .await
+                .with_context(|| format!("set transform of {source} in {scene}"))?;
+
+            out.result(format!("Fitted {source} into {region}"), &json)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Prints the items of a scene from the top down, as in OBS' list of sources: their IDs, what
+/// they show, whether they are visible, and where they are.
+pub(crate) async fn list(client: &Client, scene: &str, out: Output) -> anyhow::Result<()> {
+    let mut items = client
+        .scene_items()
+        .list(scene)
+        .await
+        .with_context(|| format!("list sources in {scene}"))?;
+    items.sort_by_key(|item| std::cmp::Reverse(item.index));
+    let mut human = Vec::new();
+    let mut listed = Vec::new();
+    for item in items {
+        let enabled = client
+            .scene_items()
+            .enabled(scene, item.id)
+            .await
+            .with_context(|| format!("check whether {} is shown", item.source_name))?;
+        let transform = client
+            .scene_items()
+            .transform(scene, item.id)
+            .await
+            .with_context(|| format!("get transform of {}", item.source_name))?;
+        let mut line = format!(
+            "{:>4}  {:<6}  {}  {}x{} at {},{}",
+            item.id,
+            if enabled { "shown" } else { "hidden" },
+            item.source_name,
+            transform.width.round(),
+            transform.height.round(),
+            transform.position_x.round(),
+            transform.position_y.round(),
+        );
+        if transform.rotation != 0. {
+            line.push_str(&format!(", rotated {}°", transform.rotation));
+        }
+        human.push(line);
+        listed.push(json!({
+            "id": item.id,
+            "index": item.index,
+            "source": item.source_name,
+            "enabled": enabled,
+            "position": { "x": transform.position_x, "y": transform.position_y },
+            "size": { "width": transform.width, "height": transform.height },
+            "scale": { "x": transform.scale_x, "y": transform.scale_y },
+            "rotation": transform.rotation,
+        }));
+    }
+    out.result(human.join("\n"), &listed)
+}
+
+/// The ID of the (first) item showing `source` in `scene`.
+pub(crate) async fn id(client: &Client, scene: &str, source: &str) -> anyhow::Result<i64> {
+    client
+        .scene_items()
+        .id(Id {
+            scene,
+            source,
+            search_offset: None,
+        })
+        .await
+        .with_context(|| format!("find {source} in scene {scene}"))
+}
+
+/// Turns an index that may count down from the top, like -1, into one that counts up from the
+/// bottom among `count` items.
+fn absolute_index(index: i64, count: usize) -> anyhow::Result<u32> {
+    let count = count as i64;
+    let absolute = if index < 0 { count + index } else { index };
+    anyhow::ensure!(
+        (0..count).contains(&absolute),
+        "index {index} is out of range for a scene with {count} sources"
+    );
+    Ok(absolute as u32)
+}
+
+/// Where a fitted source ends up, anchored at its top-left corner.
+struct Fitted {
+    x: f32,
+    y: f32,
+    scale_x: f32,
+    scale_y: f32,
+    /// Source pixels cropped off the left, right, top and bottom.
+    crop: [u32; 4],
+}
+
+impl From<Fitted> for SceneItemTransform {
+    fn from(fitted: Fitted) -> Self {
+        let [left, right, top, bottom] = fitted.crop;
+        SceneItemTransform {
+            position: Some(Position {
+                x: Some(fitted.x),
+                y: Some(fitted.y),
+            }),
+            rotation: Some(0.),
+            scale: Some(Scale {
+                x: Some(fitted.scale_x),
+                y: Some(fitted.scale_y),
+            }),
+            alignment: Some(Alignment::LEFT | Alignment::TOP),
+            bounds: Some(Bounds {
+                r#type: Some(BoundsType::None),
+                ..Default::default()
+            }),
+            crop: Some(Crop {
+                left: Some(left),
+                right: Some(right),
+                top: Some(top),
+                bottom: Some(bottom),
+            }),
+        }
+    }
+}
+
+/// Works out how to place the (already cropped) source so that it fits `region`.
+fn fit(current: &CurrentTransform, mode: FitMode, region: Region) -> anyhow::Result<Fitted> {
+    let mut crop = [
+        current.crop_left,
+        current.crop_right,
+        current.crop_top,
+        current.crop_bottom,
+    ];
+    let width = current.source_width - (crop[0] + crop[1]) as f32;
+    let height = current.source_height - (crop[2] + crop[3]) as f32;
+    anyhow::ensure!(
+        width > 0. && height > 0.,
+        "source has no size (is it showing anything?)"
+    );
+
+    let scale_x = region.width / width;
+    let scale_y = region.height / height;
+    let (scale_x, scale_y) = match mode {
+        FitMode::Stretch => (scale_x, scale_y),
+        FitMode::Contain => {
+            let scale = scale_x.min(scale_y);
+            (scale, scale)
+        }
+        FitMode::Cover => {
+            let scale = scale_x.max(scale_y);
+            // Crop whatever sticks out of the region evenly off both sides.
+            let excess_x = ((width - region.width / scale) / 2.).round() as u32;
+            let excess_y = ((height - region.height / scale) / 2.).round() as u32;
+            crop[0] += excess_x;
+            crop[1] += excess_x;
+            crop[2] += excess_y;
+            crop[3] += excess_y;
+            (scale, scale)
+        }
+    };
+
+    // Center whatever doesn't fill the region exactly.
+    let shown_width = (current.source_width - (crop[0] + crop[1]) as f32) * scale_x;
+    let shown_height = (current.source_height - (crop[2] + crop[3]) as f32) * scale_y;
+    Ok(Fitted {
+        x: region.x + (region.width - shown_width) / 2.,
+        y: region.y + (region.height - shown_height) / 2.,
+        scale_x,
+        scale_y,
+        crop,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn indexes() {
+        assert_eq!(absolute_index(0, 3).unwrap(), 0);
+        assert_eq!(absolute_index(2, 3).unwrap(), 2);
+        assert_eq!(absolute_index(-1, 3).unwrap(), 2);
+        assert_eq!(absolute_index(-3, 3).unwrap(), 0);
+        assert!(absolute_index(3, 3).is_err());
+        assert!(absolute_index(-4, 3).is_err());
+    }
+
+    #[test]
+    fn regions() {
+        for (bounds, expected) in [
+            ("1920x1080", (1920., 1080., 0., 0.)),
+            ("960x540+960", (960., 540., 960., 0.)),
+            ("960x540+960+540", (960., 540., 960., 540.)),
+            ("640.5x360+0.5+1", (640.5, 360., 0.5, 1.)),
+        ] {
+            let region = Region::parse(bounds).unwrap();
+            assert_eq!(
+                (region.width, region.height, region.x, region.y),
+                expected,
+                "{bounds}"
+            );
+        }
+        for bounds in [
+            "",
+            "1920",
+            "1920x",
+            "0x1080",
+            "960x540+",
+            "960x540+x+0",
+            "960x540+1+2+3",
+        ] {
+            assert!(Region::parse(bounds).is_err(), "{bounds}");
+        }
+    }
+
+    #[test]
+    fn fits() {
+        let source = |width, height, crop: [u32; 4]| CurrentTransform {
+            source_width: width,
+            source_height: height,
+            crop_left: crop[0],
+            crop_right: crop[1],
+            crop_top: crop[2],
+            crop_bottom: crop[3],
+            ..Default::default()
+        };
+        let region = |bounds| Region::parse(bounds).unwrap();
+        let landscape = source(1920., 1080., [0; 4]);
+        let portrait = source(1080., 1920., [0; 4]);
+        let cropped = source(1000., 500., [100, 0, 0, 0]);
+        for (current, mode, bounds, (x, y, scale_x, scale_y, crop)) in [
+            // Letterboxed, so centred vertically.
+            (
+                &landscape,
+                FitMode::Contain,
+                "960x960+100+50",
+                (100., 260., 0.5, 0.5, [0; 4]),
+            ),
+            // Pillarboxed, so centred horizontally.
+            (
+                &portrait,
+                FitMode::Contain,
+                "1920x1080",
+                (656.25, 0., 0.5625, 0.5625, [0; 4]),
+            ),
+            (
+                &landscape,
+                FitMode::Contain,
+                "480x270+10+20",
+                (10., 20., 0.25, 0.25, [0; 4]),
+            ),
+            // What sticks out is cropped off both sides evenly.
+            (
+                &landscape,
+                FitMode::Cover,
+                "960x960+100+50",
+                (100., 50., 960. / 1080., 960. / 1080., [420, 420, 0, 0]),
+            ),
+            // Crops are whole pixels, so what's left is centred a little off the region.
+            (
+                &portrait,
+                FitMode::Cover,
+                "1920x1080",
+                (0., -0.444, 1920. / 1080., 1920. / 1080., [0, 0, 656, 656]),
+            ),
+            // On top of the crop the source already had.
+            (
+                &cropped,
+                FitMode::Cover,
+                "300x300",
+                (0., 0., 0.6, 0.6, [300, 200, 0, 0]),
+            ),
+            (
+                &landscape,
+                FitMode::Stretch,
+                "960x960+100+50",
+                (100., 50., 0.5, 960. / 1080., [0; 4]),
+            ),
+            (
+                &cropped,
+                FitMode::Stretch,
+                "300x300",
+                (0., 0., 300. / 900., 0.6, [100, 0, 0, 0]),
+            ),
+        ] {
+            let fitted = fit(current, mode, region(bounds)).unwrap();
+            let case = format!("{mode:?} into {bounds}");
+            let close = |a: f32, b: f32| (a - b).abs() < 0.01;
+            assert!(close(fitted.x, x), "{case}: x is {}", fitted.x);
+            assert!(close(fitted.y, y), "{case}: y is {}", fitted.y);
+            assert!(
+                close(fitted.scale_x, scale_x),
+                "{case}: scale x is {}",
+                fitted.scale_x
+            );
+            assert!(
+                close(fitted.scale_y, scale_y),
+                "{case}: scale y is {}",
+                fitted.scale_y
+            );
+            assert_eq!(fitted.crop, crop, "{case}");
+        }
+
+        let blank = source(100., 100., [50, 50, 0, 0]);
+        assert!(fit(&blank, FitMode::Contain, region("10x10")).is_err());
+    }
+
+    #[tokio::test]
+    async fn lists_items_from_the_top() {
+        let (client, _) = crate::fake_obs::connect(|kind, data| {
+            let id = data["sceneItemId"].as_i64().unwrap_or_default();
+            Some(match kind {
+                "GetSceneItemList" => json!({ "sceneItems": [
+                    { "sceneItemId": 1, "sceneItemIndex": 0, "sourceName": "Background",
+                      "sourceType": "OBS_SOURCE_TYPE_INPUT" },
+                    { "sceneItemId": 4, "sceneItemIndex": 1, "sourceName": "Webcam",
+                      "sourceType": "OBS_SOURCE_TYPE_INPUT" },
+                ]}),
+                "GetSceneItemEnabled" => json!({ "sceneItemEnabled": id == 1 }),
+                "GetSceneItemTransform" => json!({ "sceneItemTransform": {
+                    "sourceWidth": 1920.0, "sourceHeight": 1080.0,
+                    "positionX": if id == 1 { 0.0 } else { 1500.0 }, "positionY": 0.0,
+                    "rotation": if id == 1 { 0.0 } else { 90.0 },
+                    "scaleX": 0.25, "scaleY": 0.25, "width": 480.0, "height": 270.0,
+                    "alignment": 5, "boundsType": "OBS_BOUNDS_NONE", "boundsAlignment": 0,
+                    "boundsWidth": 0.0, "boundsHeight": 0.0,
+                    "cropLeft": 0, "cropRight": 0, "cropTop": 0, "cropBottom": 0,
+                }}),
+                _ => return None,
+            })
+        })
+        .await;
+
+        let (res, results) = Output::capture(list(&client, "Main", Output::new(true))).await;
+        res.unwrap();
+        let listed = &results[0];
+        assert_eq!(listed[0]["source"], "Webcam");
+        assert_eq!(listed[0]["enabled"], false);
+        assert_eq!(listed[0]["position"], json!({ "x": 1500.0, "y": 0.0 }));
+        assert_eq!(listed[0]["rotation"], 90.0);
+        assert_eq!(listed[1]["id"], 1);
+        assert_eq!(listed[1]["enabled"], true);
+    }
+}
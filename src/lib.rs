@@ -0,0 +1,2027 @@
+//! Controls OBS Studio over its WebSocket interface.
+//!
+//! This is the library behind the `obs-do` command-line tool. Besides running the tool itself
+//! with [`cli`], it lets other programs find OBS through the same config file with
+//! [`Config::load_default`], [`Config::endpoint`] and [`password`], connect to it with
+//! [`connect`], run any `obs-do` command line against the connection with [`run_line`], parse
+//! volumes and durations with [`parse_volume`] and [`parse_duration`], and fade inputs with
+//! [`fade_to`].
+
+use anyhow::Context;
+use clap::{CommandFactory, Parser, Subcommand};
+use directories::ProjectDirs;
+use obws::{client::ConnectConfig, requests::EventSubscription, Client};
+use serde_json::json;
+use std::{
+    ffi::OsString,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+    time::{Duration, Instant},
+};
+
+mod alias;
+mod animate;
+mod audio;
+mod auth;
+mod browser;
+mod collection;
+mod complete;
+mod config;
+mod countdown;
+mod cycle;
+mod daemon;
+mod discover;
+mod doctor;
+mod duck;
+mod enforce;
+mod exporter;
+mod fade;
+mod failover;
+#[cfg(test)]
+mod fake_obs;
+mod freeze;
+mod guard;
+mod health;
+mod hotkey;
+mod http;
+mod import;
+mod input;
+mod item;
+mod logging;
+mod macros;
+mod meter;
+mod midi;
+mod mirror;
+mod mix;
+mod mixer;
+mod mqtt;
+mod names;
+mod notify;
+mod osc;
+mod output;
+mod outputs;
+mod password;
+mod projector;
+mod prompt;
+mod record;
+mod repl;
+mod replay;
+mod rest;
+mod rotate;
+mod rpc;
+mod scene;
+mod schedule;
+mod screenshot;
+mod session;
+mod setup;
+mod sink;
+mod snapshot;
+mod state;
+mod stats;
+mod stream;
+mod text;
+mod transition;
+mod tunnel;
+mod video;
+mod wait;
+mod watch;
+
+pub use config::{Config, Endpoint, EventCategory};
+pub use fade::{fade_to, Curve, Scale};
+pub use output::Output;
+
+/// Separator used to chain several commands into a single invocation.
+const CHAIN_SEPARATOR: &str = ";";
+
+/// The longest to wait between two attempts to connect with `--retry`.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// How long to wait between attempts to reconnect to OBS once the connection is lost.
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How OBS was reached for this invocation, so that commands that reconnect go back there in the
+/// same way.
+static REACHED: OnceLock<Reached> = OnceLock::new();
+
+tokio::task_local! {
+    /// Whether the OBS that commands run against is on this machine, so that the files it names
+    /// can be looked at; see [`obs_is_local`].
+    pub(crate) static OBS_IS_LOCAL: bool;
+}
+
+/// What [`reconnect`] needs to know about the first connection.
+struct Reached {
+    endpoint: Endpoint,
+    password: Option<String>,
+    event_subscriptions: EventSubscription,
+}
+
+#[derive(Debug, Parser)]
+#[command(
+    author,
+    version,
+    about,
+    long_about = None,
+    after_help = "Multiple commands can be chained with a literal `;` argument (escaped as `\\;` in most shells), \
+                  e.g. `obs-do set-scene BRB \\; toggle-mute \\; toggle-record`. \
+                  All chained commands share a single connection to OBS."
+)]
+struct Args {
+    #[command(flatten)]
+    globals: Globals,
+
+    #[command(subcommand)]
+    cmd: Command,
+}
+
+/// Options that apply to the invocation as a whole rather than to a single command.
+#[derive(Debug, clap::Args)]
+struct Globals {
+    /// Run chained commands concurrently instead of one after the other.
+    #[arg(long)]
+    parallel: bool,
+
+    /// Print results and errors as JSON on stdout.
+    #[arg(long, global = true)]
+    json: bool,
+
+    /// Don't print informational messages (such as the connection banner) to stderr.
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Log more details; repeat (`-vv`) to also log every request and response exchanged with OBS.
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    verbose: u8,
+
+    /// Write logs to this file instead of stderr.
+    #[arg(long, global = true, value_name = "PATH")]
+    log_file: Option<PathBuf>,
+
+    /// Print every request sent to OBS with its size and round-trip time, plus totals.
+    #[arg(long, global = true)]
+    trace: bool,
+
+    /// Refuse to run commands that change OBS while it is streaming.
+    ///
+    /// Can also be enabled permanently with `read-only = true` in the config file.
+    #[arg(long, global = true)]
+    read_only: bool,
+
+    /// Run mutating commands even in read-only mode.
+    #[arg(long, global = true)]
+    force: bool,
+
+    /// Raise desktop notifications when a stream starts or stops, a recording or replay is saved,
+    /// or the daemon loses its connection to OBS.
+    ///
+    /// Can also be enabled permanently with `notify = true` in the config file.
+    #[arg(long, global = true)]
+    notify: bool,
+
+    /// Match scene and input names ignoring case, and by a unique part like `game` for
+    /// "Gaming — Main".
+    ///
+    /// Can also be enabled permanently with `fuzzy = true` in the config file.
+    #[arg(long, global = true)]
+    fuzzy: bool,
+
+    /// The kinds of events to ask OBS for, separated by commas, like `scenes,outputs`.
+    ///
+    /// Defaults to `all`, which leaves out the high-volume kinds. Commands that need one of those,
+    /// like `meter`, ask for it on top. Can also be set with `events = [...]` in the config file.
+    #[arg(long, global = true, value_name = "CATEGORIES", value_delimiter = ',')]
+    events: Vec<EventCategory>,
+
+    /// If OBS can't be reached, try connecting this many more times before giving up.
+    #[arg(long, global = true, value_name = "N", default_value_t = 0)]
+    retry: u32,
+
+    /// How long to wait before the first retry; each retry after that waits twice as long.
+    #[arg(long, global = true, value_name = "DURATION", default_value = "500ms")]
+    retry_delay: String,
+
+    /// Connect to the OBS on this machine with the port and password from its own configuration.
+    ///
+    /// Can also be enabled permanently with `auto-config = true` in the config file.
+    #[arg(long, global = true)]
+    auto_config: bool,
+
+    /// Read the OBS WebSocket password from the first line of standard input.
+    ///
+    /// Otherwise it is taken from the profile's `password` or `password-command`, from
+    /// `OBS_DO_PASSWORD`, from the top-level `password` or `password-command` in the config file,
+    /// from the OS keyring, or from the `websocket-token` file, in that order.
+    #[arg(long, global = true)]
+    password_stdin: bool,
+
+    /// Connect to the OBS described by this `[profiles.<name>]` section of the config file.
+    #[arg(long, global = true, value_name = "NAME")]
+    profile: Option<String>,
+
+    /// Run the commands against every profile in the config file at the same time, such as a
+    /// main and a backup encoder.
+    #[arg(long, global = true, conflicts_with_all = ["profile", "instances"])]
+    all: bool,
+
+    /// Run the commands against these profiles at the same time, separated by commas.
+    #[arg(
+        long,
+        global = true,
+        value_name = "NAMES",
+        value_delimiter = ',',
+        conflicts_with = "profile"
+    )]
+    instances: Vec<String>,
+
+    #[command(flatten)]
+    guards: guard::Guards,
+}
+
+impl Globals {
+    fn flags(&self) -> Flags {
+        Flags {
+            read_only: self.read_only,
+            force: self.force,
+            fuzzy: self.fuzzy,
+            notify: self.notify,
+            events: !self.events.is_empty(),
+        }
+    }
+}
+
+/// The settings that flags turn on over the config file, kept so that long-running modes can
+/// work them out again whenever the config file changes.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Flags {
+    read_only: bool,
+    force: bool,
+    fuzzy: bool,
+    notify: bool,
+    /// Whether `--events` was given, which takes the place of `events` in the config file.
+    events: bool,
+}
+
+impl Flags {
+    /// Whether mutating commands are refused while OBS is streaming.
+    pub(crate) fn read_only(&self, config: &Config) -> bool {
+        (self.read_only || config.read_only) && !self.force
+    }
+
+    /// Puts the settings that hold for every command into effect.
+    fn apply(&self, config: &Config) {
+        names::set_fuzzy(self.fuzzy || config.fuzzy);
+        notify::enable(self.notify || config.notify);
+    }
+
+    /// Puts a reloaded config file into effect as far as it can be without reconnecting to OBS,
+    /// and returns whether mutating commands are now refused while streaming.
+    pub(crate) fn reload(&self, previous: &Config, config: &Config) -> bool {
+        self.apply(config);
+        let events = |config: &Config| config.events.as_deref().map(EventCategory::subscriptions);
+        if !self.events && events(previous) != events(config) {
+            tracing::warn!("Changes to `events` only take effect once obs-do is restarted.");
+        }
+        self.read_only(config)
+    }
+}
+
+/// A command that follows a `;` in a chained invocation.
+#[derive(Debug, Parser)]
+#[command(no_binary_name = true)]
+struct Chained {
+    #[command(subcommand)]
+    cmd: Command,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+enum Command {
+    ToggleStream,
+    ToggleRecord,
+    /// Starts streaming.
+    StartStream,
+    /// Stops streaming.
+    StopStream,
+    /// Watches the stream and raises an alarm when it drops too many frames or is too congested
+    /// for too long.
+    ///
+    /// Keeps running until interrupted.
+    WatchHealth {
+        #[command(flatten)]
+        health: health::HealthArgs,
+    },
+    /// Serves OBS' stats, the state of its outputs and audio levels as Prometheus metrics.
+    ///
+    /// Keeps running until interrupted.
+    Exporter {
+        #[command(flatten)]
+        exporter: exporter::ExporterArgs,
+    },
+    /// Prints OBS' CPU and memory usage, free disk space, frame rate and skipped frames.
+    Stats {
+        #[command(flatten)]
+        stats: stats::StatsArgs,
+    },
+    /// Prints whether OBS is recording, for how long, how much it wrote, and to which file.
+    RecordStatus,
+    /// Controls outputs other than the stream and recording, like those added by plugins.
+    Output {
+        #[command(subcommand)]
+        cmd: outputs::OutputCommand,
+    },
+    /// Changes the resolution and frame rate OBS works at.
+    Video {
+        #[command(subcommand)]
+        cmd: video::VideoCommand,
+    },
+    /// Configures where OBS streams to.
+    Stream {
+        #[command(subcommand)]
+        cmd: stream::StreamCommand,
+    },
+    /// Prints whether OBS is streaming, for how long, the bitrate, and any trouble it has.
+    StreamStatus {
+        #[command(flatten)]
+        status: stream::StatusArgs,
+    },
+    /// Mutes the given input.
+    ToggleMute {
+        #[clap(default_value = "Mic/Aux")]
+        input: String,
+    },
+    /// Mutes the given input, whether or not it already is.
+    Mute {
+        #[clap(default_value = "Mic/Aux")]
+        input: String,
+    },
+    /// Unmutes the given input, whether or not it is muted.
+    Unmute {
+        #[clap(default_value = "Mic/Aux")]
+        input: String,
+    },
+    SetScene {
+        scene: String,
+    },
+    /// Switches to the scene after the live one in OBS' scene list, wrapping around at the end.
+    NextScene {
+        #[command(flatten)]
+        cycle: cycle::CycleArgs,
+    },
+    /// Switches to the scene before the live one in OBS' scene list, wrapping around at the start.
+    PrevScene {
+        #[command(flatten)]
+        cycle: cycle::CycleArgs,
+    },
+    /// Counts down to the start of a show in a text source, then switches to the live scene.
+    ///
+    /// Like `obs-do countdown 5m --scene 'Starting Soon' --text-source Countdown --live Main`.
+    Countdown {
+        #[command(flatten)]
+        countdown: countdown::CountdownArgs,
+    },
+    /// Switches between scenes on a timer, like a slideshow for a lobby or pre-show.
+    ///
+    /// Keeps running until interrupted.
+    RotateScenes {
+        #[command(flatten)]
+        rotate: rotate::RotateArgs,
+    },
+    /// Sets the volume of the given input to specified volume.
+    SetVolume {
+        #[command(flatten)]
+        set: SetVolumeArgs,
+    },
+    /// Sets the text of a text source, like a lower third or a now-playing display.
+    SetText {
+        input: String,
+
+        /// The text to show, `@<path>` to show the contents of a file, or `-` to read the text
+        /// from stdin.
+        #[arg(allow_hyphen_values = true)]
+        text: String,
+    },
+    /// Shows another image in an image source, like a thumbnail or a sponsor's logo.
+    SetImage {
+        input: String,
+        path: PathBuf,
+    },
+    /// Fills a color source with another color.
+    SetColor {
+        input: String,
+
+        /// The color as hex digits, like `ff8800`, with another two for opacity, like
+        /// `ff880080`.
+        color: String,
+    },
+    /// Delays the audio of the given input to line it up with video, e.g. to fix mic delay.
+    SetSyncOffset {
+        input: String,
+
+        /// The delay in milliseconds; negative values play the audio earlier.
+        #[arg(value_name = "MS", allow_hyphen_values = true)]
+        offset: i64,
+    },
+    /// Prints the audio sync offset of the given input, in milliseconds.
+    GetSyncOffset {
+        input: String,
+    },
+    /// Sets whether the given input is heard on the monitoring device.
+    SetMonitoring {
+        input: String,
+
+        #[arg(value_enum)]
+        monitoring: audio::Monitoring,
+    },
+    /// Pans the given input between the left and right channels.
+    SetBalance {
+        input: String,
+
+        /// From 0.0 (all the way left) through 0.5 (centered) to 1.0 (all the way right).
+        balance: f32,
+    },
+    /// Prints the balance of the given input, from 0.0 (left) to 1.0 (right).
+    GetBalance {
+        input: String,
+    },
+    /// Gradually changes the volume of the given input.
+    FadeInput {
+        /// The input to fade, or a glob pattern like `Music*` to fade all matching inputs.
+        input: String,
+
+        #[command(flatten)]
+        fade: fade::FadeArgs,
+    },
+    /// Stops fades that are running on the given inputs, leaving their volume where it is.
+    FadeCancel {
+        #[arg(required = true)]
+        inputs: Vec<String>,
+    },
+    /// Fades one input out while fading another one in, in lockstep.
+    Crossfade {
+        #[command(flatten)]
+        crossfade: fade::CrossfadeArgs,
+    },
+    /// Transitions the preview scene to program in studio mode.
+    Transition {
+        /// Also fade out the audio inputs only in the outgoing scene, and fade in those only in
+        /// the incoming scene, over the length of the transition.
+        #[arg(long)]
+        audio_follow: bool,
+    },
+    /// Brings OBS into a known baseline state, changing only what differs.
+    ///
+    /// Meant to be run when OBS starts, regardless of how it was left.
+    Enforce {
+        /// The scene that should be live.
+        #[arg(long)]
+        scene: Option<String>,
+
+        /// An input that should be muted (may be repeated).
+        #[arg(long, value_name = "INPUT")]
+        mute: Vec<String>,
+
+        /// An input that should be unmuted (may be repeated).
+        #[arg(long, value_name = "INPUT")]
+        unmute: Vec<String>,
+    },
+    /// Saves an image of a scene or source, optionally cropped.
+    Screenshot {
+        #[command(flatten)]
+        screenshot: screenshot::ScreenshotArgs,
+    },
+    /// Manages inputs.
+    Input {
+        #[command(subcommand)]
+        cmd: input::InputCommand,
+    },
+    /// Manages scene collections.
+    Collection {
+        #[command(subcommand)]
+        cmd: collection::CollectionCommand,
+    },
+    /// Moves and scales a source in a scene smoothly, like to slide in a lower third.
+    AnimateItem {
+        #[command(flatten)]
+        animate: animate::AnimateArgs,
+    },
+    /// Lists the sources in a scene from the top down, with their scene item IDs, whether they
+    /// are shown, and where.
+    ListItems {
+        scene: String,
+    },
+    /// Reloads and repoints browser sources, like alerts and overlays.
+    Browser {
+        #[command(subcommand)]
+        cmd: browser::BrowserCommand,
+    },
+    /// Opens projectors, like the multiview on a second display.
+    Projector {
+        #[command(subcommand)]
+        cmd: projector::ProjectorCommand,
+    },
+    /// Saves the whole setup to a file and puts it back later.
+    State {
+        #[command(subcommand)]
+        cmd: state::StateCommand,
+    },
+    /// Controls and checks recordings.
+    Record {
+        #[command(subcommand)]
+        cmd: record::RecordCommand,
+    },
+    /// Works with the replay buffer.
+    ReplayBuffer {
+        #[command(subcommand)]
+        cmd: replay::ReplayBufferCommand,
+    },
+    /// Manages scenes.
+    Scene {
+        #[command(subcommand)]
+        cmd: scene::SceneCommand,
+    },
+    /// Updates text sources.
+    Text {
+        #[command(subcommand)]
+        cmd: text::TextCommand,
+    },
+    /// Arranges sources within scenes.
+    Item {
+        #[command(subcommand)]
+        cmd: item::ItemCommand,
+    },
+    /// Saves and restores the audio settings of every input, e.g. to compare two mixes.
+    AudioSnapshot {
+        #[command(subcommand)]
+        cmd: snapshot::AudioSnapshotCommand,
+    },
+    /// Manages how inputs are mixed into the stream and recording tracks.
+    Mix {
+        #[command(subcommand)]
+        cmd: mix::MixCommand,
+    },
+    /// Shows live level meters for the given inputs, with peaks and clipping.
+    ///
+    /// Keeps running until interrupted.
+    Meter {
+        #[arg(required = true)]
+        inputs: Vec<String>,
+    },
+    /// Opens a full-screen mixer for all audio inputs, with faders, mute buttons and meters.
+    ///
+    /// Select an input with the up and down arrows, move its fader with left and right, mute it
+    /// with `m`, and quit with `q`.
+    Mixer,
+    /// Follows streaming and recording sessions, and reports a summary of each when it ends.
+    ///
+    /// Keeps running until interrupted.
+    Session {
+        #[command(flatten)]
+        session: session::SessionArgs,
+    },
+    /// Reflects the mute state of an input to an external indicator, such as an on-air light.
+    ///
+    /// Keeps running, and reconnects if OBS goes away, until interrupted.
+    MuteMirror {
+        #[command(flatten)]
+        mirror: mirror::MirrorArgs,
+    },
+    /// Turns one input down while another is loud, such as music while you speak.
+    ///
+    /// Keeps running until interrupted, then puts the ducked input back at its old volume.
+    Duck {
+        #[command(flatten)]
+        duck: duck::DuckArgs,
+    },
+    /// Switches the stream to a backup ingest server if the current one fails.
+    ///
+    /// Keeps watching until the stream is switched over or this is interrupted.
+    Failover {
+        #[command(flatten)]
+        failover: failover::FailoverArgs,
+    },
+    /// Keeps a connection to OBS open and runs commands sent to it over a local socket.
+    ///
+    /// Each line written to the socket is one command, as it would be given on the command
+    /// line, and is answered with a line of JSON once it has run.
+    ///
+    /// The line `health` is answered right away with whether OBS can be reached, the last event
+    /// it sent, and how many commands are queued.
+    Daemon {
+        #[command(flatten)]
+        daemon: daemon::DaemonArgs,
+    },
+    /// Keeps a connection to OBS open and runs commands posted to it over HTTP.
+    ///
+    /// `POST /set-scene` with the body `["Gaming"]` runs `set-scene Gaming`, and subcommands go
+    /// in the path, like `POST /input/set-volume`. `GET /status` tells whether OBS is streaming
+    /// or recording and which scene is live.
+    ServeHttp {
+        #[command(flatten)]
+        serve: rest::ServeArgs,
+    },
+    /// Connects OBS to an MQTT broker, running the commands sent to it and publishing its state.
+    ///
+    /// A message on `obs/set-scene` with the payload `Gaming` runs `set-scene Gaming`, and the
+    /// outcome is published to `obs/result`. The live scene and whether OBS is streaming or
+    /// recording are kept under `obs/state/`, and OBS' events are published to `obs/events`.
+    Mqtt {
+        #[command(flatten)]
+        mqtt: mqtt::MqttArgs,
+    },
+    /// Runs commands for the OSC messages it receives, as from TouchOSC or a lighting console.
+    ///
+    /// `/obs/scene "Gaming"` switches scenes, `/obs/volume/Mic -6.0` sets a volume in dB,
+    /// `/obs/mute/Mic` toggles a mute, and any other `/obs/<command>` runs that command.
+    Osc {
+        #[command(flatten)]
+        osc: osc::OscArgs,
+    },
+    /// Turns a MIDI controller into a control surface for OBS, with the mapping in a TOML file.
+    ///
+    /// Faders and knobs can run `set-volume` with their position, buttons can run any command,
+    /// and LEDs can show whether an input is muted or a scene is live. Only works on Linux.
+    Midi {
+        #[command(flatten)]
+        midi: midi::MidiArgs,
+    },
+    /// Runs the commands bound to key chords under `[hotkeys]` in the config file whenever they
+    /// are pressed, even while OBS doesn't have the focus.
+    ///
+    /// The hotkeys are grabbed through X11, so this only works on Linux and the BSDs.
+    Hotkeyd,
+    /// Answers JSON-RPC 2.0 requests read from stdin, one per line, with responses on stdout.
+    ///
+    /// Lets other programs run obs-do as a subprocess over a single connection to OBS, without
+    /// quoting command lines: `{"jsonrpc":"2.0","id":1,"method":"set-scene","params":["Gaming"]}`.
+    /// The result is what the command prints with `--json`.
+    Rpc,
+    /// Reads commands interactively, one line at a time, over a single connection to OBS.
+    ///
+    /// Lines are kept in a history across sessions, which Ctrl-R searches and `history` lists.
+    /// `!!` repeats the previous line and `!n` the line numbered `n`. Tab completes command
+    /// names as well as scene and input names from OBS.
+    Repl,
+    /// Waits until a time of day, then runs a command, along with any chained after it.
+    ///
+    /// OBS doesn't have to be running while this waits. Like `obs-do at 20:00 start-stream`.
+    At {
+        /// When to run the command, as `HH:MM` or `HH:MM:SS` in local time; if that has already
+        /// passed today, it means tomorrow.
+        time: String,
+
+        /// The command to run, with its arguments.
+        #[arg(
+            value_name = "COMMAND",
+            required = true,
+            trailing_var_arg = true,
+            allow_hyphen_values = true
+        )]
+        command: Vec<String>,
+    },
+    /// Waits for a while, then runs a command, along with any chained after it.
+    ///
+    /// OBS doesn't have to be running while this waits. Like `obs-do in 90m record stop`.
+    In {
+        /// How long to wait, like `90m` or `1h30m`.
+        delay: String,
+
+        /// The command to run, with its arguments.
+        #[arg(
+            value_name = "COMMAND",
+            required = true,
+            trailing_var_arg = true,
+            allow_hyphen_values = true
+        )]
+        command: Vec<String>,
+    },
+    /// Waits until something happens in OBS, then prints what happened.
+    ///
+    /// For a stopped recording, that is the path of the recorded file.
+    WaitFor {
+        /// One of `stream-started`, `stream-stopped`, `record-started`, `record-stopped`,
+        /// `scene=<name>` or `event:<type>` (like `event:InputMuteStateChanged`).
+        condition: wait::Condition,
+
+        /// Give up and fail after this long, like `60s`.
+        #[arg(long, value_name = "DURATION")]
+        timeout: Option<String>,
+    },
+    /// Waits until OBS can be connected to, e.g. before starting other things that need it.
+    WaitForObs {
+        /// Give up and fail after this long, like `60s`.
+        #[arg(long, value_name = "DURATION")]
+        timeout: Option<String>,
+
+        /// Start OBS with this shell command if it isn't reachable yet.
+        #[arg(long, value_name = "COMMAND")]
+        launch: Option<String>,
+    },
+    /// Prints OBS events as JSON lines as they happen.
+    Watch {
+        /// Pipe the events into the stdin of this shell command instead of printing them.
+        ///
+        /// The command is started once and receives all events, one JSON object per line.
+        #[arg(long)]
+        exec: Option<String>,
+
+        /// Also receive the high-frequency input volume meter events.
+        #[arg(long)]
+        meters: bool,
+
+        /// Send the events to `file:<path>`, `udp:<host>:<port>` or `webhook:<url>` instead of
+        /// stdout (may be repeated).
+        #[arg(long, value_name = "SINK")]
+        sink: Vec<sink::Sink>,
+    },
+    /// Prints a starter obs-do configuration with aliases and hotkeys taken from OBS' own
+    /// configuration files.
+    ImportHotkeys {
+        /// Read OBS' configuration from this directory rather than where OBS keeps it on this
+        /// platform.
+        #[arg(long, value_name = "DIR")]
+        obs_config: Option<PathBuf>,
+    },
+    /// Asks for short names for the scenes and audio inputs in OBS, an audio preset for each
+    /// scene, and a panic button, and adds them to the config file.
+    Setup,
+    /// Runs the commands of a macro from the `[macros]` section of the config file, in order.
+    ///
+    /// A macro can also be run as `obs-do <name>`, as long as no command has the same name.
+    Macro {
+        name: String,
+    },
+    #[command(external_subcommand)]
+    External(Vec<String>),
+    /// Checks the configuration and the connection to OBS step by step, and suggests fixes for
+    /// whatever is wrong.
+    Doctor,
+    /// Manages obs-do's own configuration.
+    Config {
+        #[command(subcommand)]
+        cmd: config::ConfigCommand,
+    },
+    /// Manages the OBS WebSocket password kept in the OS keyring.
+    Auth {
+        #[command(subcommand)]
+        cmd: auth::AuthCommand,
+    },
+    /// Prints a shell completion script to stdout.
+    Completions {
+        shell: clap_complete::Shell,
+    },
+    /// Lists the scene, input, or filter names from OBS that can come next on a command line,
+    /// for dynamic shell completion.
+    #[command(name = "__complete", hide = true)]
+    Complete {
+        /// The words after `obs-do` up to the one being completed.
+        #[arg(allow_hyphen_values = true)]
+        words: Vec<String>,
+    },
+}
+
+impl Command {
+    /// Whether the command changes the state of OBS.
+    fn is_mutating(&self) -> bool {
+        match self {
+            Command::ToggleStream
+            | Command::ToggleRecord
+            | Command::StartStream
+            | Command::StopStream
+            | Command::ToggleMute { .. }
+            | Command::Mute { .. }
+            | Command::Unmute { .. }
+            | Command::SetScene { .. }
+            | Command::NextScene { .. }
+            | Command::PrevScene { .. }
+            | Command::RotateScenes { .. }
+            | Command::Countdown { .. }
+            | Command::AnimateItem { .. }
+            | Command::SetVolume { .. }
+            | Command::SetText { .. }
+            | Command::SetImage { .. }
+            | Command::SetColor { .. }
+            | Command::SetSyncOffset { .. }
+            | Command::SetMonitoring { .. }
+            | Command::SetBalance { .. }
+            | Command::FadeInput { .. }
+            | Command::Crossfade { .. }
+            | Command::Transition { .. }
+            | Command::Duck { .. }
+            | Command::Failover { .. }
+            | Command::Mixer
+            | Command::Enforce { .. } => true,
+            // What a macro does is only known once the config file is read.
+            Command::Macro { .. } | Command::External(_) => true,
+            // These are replaced by the command they wait to run before anything runs.
+            Command::At { .. } | Command::In { .. } => true,
+            Command::Input { cmd } => cmd.is_mutating(),
+            Command::Collection { cmd } => cmd.is_mutating(),
+            Command::Scene { cmd } => cmd.is_mutating(),
+            Command::Text { cmd } => cmd.is_mutating(),
+            Command::Record { cmd } => cmd.is_mutating(),
+            Command::Stream { cmd } => cmd.is_mutating(),
+            Command::Video { cmd } => cmd.is_mutating(),
+            Command::Output { cmd } => cmd.is_mutating(),
+            Command::ReplayBuffer { cmd } => cmd.is_mutating(),
+            Command::Browser { cmd } => cmd.is_mutating(),
+            Command::State { cmd } => cmd.is_mutating(),
+            Command::Projector { cmd } => cmd.is_mutating(),
+            Command::Item { cmd } => cmd.is_mutating(),
+            Command::Mix { cmd } => cmd.is_mutating(),
+            Command::AudioSnapshot { cmd } => cmd.is_mutating(),
+            Command::StreamStatus { .. }
+            | Command::RecordStatus
+            | Command::ListItems { .. }
+            | Command::Stats { .. }
+            | Command::Exporter { .. }
+            | Command::WatchHealth { .. }
+            | Command::GetSyncOffset { .. }
+            | Command::GetBalance { .. }
+            | Command::FadeCancel { .. }
+            | Command::Screenshot { .. }
+            | Command::Meter { .. }
+            | Command::Session { .. }
+            | Command::Daemon { .. }
+            | Command::ServeHttp { .. }
+            | Command::Mqtt { .. }
+            | Command::Osc { .. }
+            | Command::Midi { .. }
+            | Command::Rpc
+            | Command::Hotkeyd
+            | Command::Repl
+            | Command::MuteMirror { .. }
+            | Command::WaitFor { .. }
+            | Command::WaitForObs { .. }
+            | Command::Watch { .. }
+            | Command::ImportHotkeys { .. }
+            | Command::Auth { .. }
+            | Command::Setup
+            | Command::Config { .. }
+            | Command::Doctor
+            | Command::Completions { .. }
+            | Command::Complete { .. } => false,
+        }
+    }
+
+    /// Whether the command can be run on behalf of a client, as the daemon does: it has to finish
+    /// by itself, without a terminal or the files on the client's machine, and mustn't read or
+    /// write paths on this one that the client picks.
+    fn is_servable(&self) -> bool {
+        !matches!(
+            self,
+            Command::Daemon { .. }
+                | Command::ServeHttp { .. }
+                | Command::Mqtt { .. }
+                | Command::Osc { .. }
+                | Command::Midi { .. }
+                | Command::Rpc
+                | Command::Hotkeyd
+                | Command::Repl
+                | Command::Watch { .. }
+                | Command::WaitFor { .. }
+                | Command::WaitForObs { .. }
+                | Command::At { .. }
+                | Command::In { .. }
+                | Command::RotateScenes { .. }
+                | Command::Countdown { .. }
+                | Command::Text {
+                    cmd: crate::text::TextCommand::Rotate { .. },
+                }
+                | Command::MuteMirror { .. }
+                | Command::Meter { .. }
+                | Command::Exporter { .. }
+                | Command::WatchHealth { .. }
+                | Command::Stats {
+                    stats: crate::stats::StatsArgs { watch: Some(_), .. },
+                }
+                | Command::Stats {
+                    stats: crate::stats::StatsArgs { log: Some(_), .. },
+                }
+                | Command::Mixer
+                | Command::Session { .. }
+                | Command::Duck { .. }
+                | Command::Failover { .. }
+                | Command::ImportHotkeys { .. }
+                | Command::State { .. }
+                | Command::Setup
+                | Command::Auth { .. }
+                | Command::Config { .. }
+                | Command::Doctor
+                | Command::Completions { .. }
+                | Command::Complete { .. }
+                | Command::Screenshot { .. }
+                | Command::Record {
+                    cmd: record::RecordCommand::Stop { verify: true, .. }
+                        | record::RecordCommand::Verify { .. }
+                        | record::RecordCommand::SetDirectory { create: true, .. },
+                }
+                | Command::Collection {
+                    cmd: collection::CollectionCommand::Wipe {
+                        collection: None,
+                        ..
+                    },
+                }
+        ) && !matches!(
+            self,
+            Command::SetText { text: arg, .. }
+                | Command::Input { cmd: input::InputCommand::SetSettings { settings: arg, .. } }
+                | Command::Input { cmd: input::InputCommand::Create { settings: Some(arg), .. } }
+                | Command::Stream { cmd: stream::StreamCommand::SetService { key: Some(arg), .. } }
+                if text::is_local(arg)
+        ) && !matches!(
+            self,
+            Command::Stream {
+                cmd: stream::StreamCommand::SetService {
+                    key_env: Some(_),
+                    ..
+                }
+            }
+        )
+    }
+
+    /// Events the command needs on top of the default (low-volume) subscriptions.
+    fn event_subscriptions(&self) -> EventSubscription {
+        match self {
+            // Commands run from the REPL may need them too.
+            Command::Watch { meters: true, .. }
+            | Command::Meter { .. }
+            | Command::Exporter { .. }
+            | Command::Duck { .. }
+            | Command::Repl => EventSubscription::INPUT_VOLUME_METERS,
+            Command::MuteMirror { .. } => EventSubscription::INPUTS,
+            _ => EventSubscription::NONE,
+        }
+    }
+}
+
+/// Runs obs-do as the command-line tool, with the arguments the process was started with.
+pub async fn cli() -> anyhow::Result<()> {
+    // A broken config file is reported once there is logging, so aliases are just skipped here.
+    if let Ok(config) = match project_dirs() {
+        Ok(proj_dirs) => Config::load(proj_dirs.config_dir()).await,
+        Err(e) => Err(e),
+    } {
+        alias::set(config.aliases);
+    }
+
+    let argv: Vec<OsString> = std::env::args_os().collect();
+    // The words of each command, all following the program name like the first one, which
+    // `--fuzzy` matches the names in once OBS can say what names there are.
+    let mut lines: Vec<Vec<OsString>> = argv
+        .split(|arg| arg == CHAIN_SEPARATOR)
+        .enumerate()
+        .map(|(i, segment)| {
+            let program = (i > 0).then(|| argv[0].clone());
+            let line = program.into_iter().chain(segment.iter().cloned()).collect();
+            alias::resolve(Args::command(), line)
+        })
+        .collect();
+    let args = Args::parse_from(&lines[0]);
+    let cmds: Vec<_> = std::iter::once(args.cmd)
+        .chain(
+            lines[1..]
+                .iter()
+                .map(|line| Chained::parse_from(&line[1..]).cmd),
+        )
+        .collect();
+
+    match &cmds[..] {
+        [Command::Completions { shell }] => return complete::generate(*shell),
+        // Most words aren't names, and there's no point in connecting to OBS for those.
+        [Command::Complete { words }] if complete::target(words).is_none() => return Ok(()),
+        _ => {}
+    }
+
+    let globals = &args.globals;
+    let tracer = logging::init(
+        globals.quiet,
+        globals.verbose,
+        globals.log_file.as_deref(),
+        globals.trace,
+    )?;
+    let started = Instant::now();
+    let out = Output::new(globals.json);
+    let res = async {
+        // Waiting comes first, so that OBS only has to be running once it's time.
+        let Some(cmds) = schedule::wait(cmds, &mut lines[0]).await? else {
+            return Ok(());
+        };
+        match &cmds[..] {
+            // Importing works on files alone, so there's no need for OBS to be running.
+            [Command::ImportHotkeys { obs_config }] => {
+                import::run(obs_config.as_deref(), out).await
+            }
+            // The keyring is local, so OBS doesn't have to be running either.
+            [Command::Auth { cmd }] => auth::run(cmd, out).await,
+            // Setting up the connection is what makes it possible to connect in the first place.
+            [Command::Config { cmd }] => config::run(cmd, out).await,
+            // Comparing two exported files doesn't involve OBS either.
+            [Command::State {
+                cmd:
+                    state::StateCommand::Diff {
+                        a,
+                        b: Some(b),
+                        live: false,
+                    },
+            }] => state::diff_files(a, b, out).await,
+            // Connecting is one of the things being checked.
+            [Command::Doctor] => doctor(globals, out).await,
+            // Not being able to connect is the point, so this can't connect up front.
+            [Command::WaitForObs { timeout, launch }] => {
+                wait_for_obs(globals, timeout.as_deref(), launch.as_deref(), out).await
+            }
+            _ => execute(cmds, lines, globals, out).await,
+        }
+    }
+    .await;
+    if let Some(tracer) = tracer {
+        tracer.summarize(started.elapsed());
+    }
+    match res {
+        Err(e) if e.is::<guard::Unmet>() => {
+            if out.is_json() {
+                out.error(&e);
+            } else {
+                eprintln!("Error: {e}");
+            }
+            std::process::exit(guard::UNMET_EXIT_CODE);
+        }
+        Err(e) if out.is_json() => {
+            out.error(&e);
+            std::process::exit(1);
+        }
+        res => res,
+    }
+}
+
+async fn wait_for_obs(
+    globals: &Globals,
+    timeout: Option<&str>,
+    launch: Option<&str>,
+    out: Output,
+) -> anyhow::Result<()> {
+    let timeout = timeout.map(parse_duration).transpose()?;
+    let proj_dirs = project_dirs()?;
+    let config = load_config(&proj_dirs, globals).await?;
+    let profile = globals.profile.as_deref();
+    let (_tunnel, endpoint) = locate(&config, profile).await?;
+    let password = password::resolve(&proj_dirs, &config, profile, globals.password_stdin).await?;
+    wait::for_obs(&endpoint, password, timeout, launch, out).await
+}
+
+async fn doctor(globals: &Globals, out: Output) -> anyhow::Result<()> {
+    let proj_dirs = project_dirs()?;
+    let config = load_config(&proj_dirs, globals).await;
+    // A broken config file is one of the findings, so carry on with the defaults.
+    let fallback = Config::default();
+    let usable = config.as_ref().unwrap_or(&fallback);
+    let profile = globals.profile.as_deref();
+    let (_tunnel, endpoint) = locate(usable, profile).await?;
+    let password = password::resolve(&proj_dirs, usable, profile, globals.password_stdin).await;
+    doctor::run(&proj_dirs, config, &endpoint, password, out).await
+}
+
+async fn execute(
+    cmds: Vec<Command>,
+    lines: Vec<Vec<OsString>>,
+    globals: &Globals,
+    out: Output,
+) -> anyhow::Result<()> {
+    let proj_dirs = project_dirs()?;
+    let config = load_config(&proj_dirs, globals).await?;
+
+    let base_subscriptions = match (&globals.events[..], &config.events) {
+        ([], None) => EventSubscription::ALL,
+        ([], Some(events)) => EventCategory::subscriptions(events),
+        (events, _) => EventCategory::subscriptions(events),
+    };
+    let event_subscriptions = cmds.iter().fold(base_subscriptions, |subs, cmd| {
+        subs | cmd.event_subscriptions()
+    });
+    globals.flags().apply(&config);
+
+    let instances: Vec<&str> = if globals.all {
+        config.profiles.keys().map(String::as_str).collect()
+    } else {
+        globals.instances.iter().map(String::as_str).collect()
+    };
+    if globals.all || !instances.is_empty() {
+        anyhow::ensure!(
+            !cmds.iter().any(|cmd| matches!(
+                cmd,
+                Command::Daemon { .. }
+                    | Command::ServeHttp { .. }
+                    | Command::Mqtt { .. }
+                    | Command::Osc { .. }
+                    | Command::Midi { .. }
+                    | Command::Rpc
+                    | Command::Hotkeyd
+                    | Command::Repl
+            )),
+            "daemon, serve-http, mqtt, osc, midi, rpc, hotkeyd and repl cannot be run against several instances at once"
+        );
+        return broadcast(
+            &proj_dirs,
+            &config,
+            globals,
+            &instances,
+            lines,
+            event_subscriptions,
+            out,
+        )
+        .await;
+    }
+    let profile = globals.profile.as_deref();
+    on_instance(
+        &proj_dirs,
+        &config,
+        globals,
+        profile,
+        lines,
+        event_subscriptions,
+        out,
+    )
+    .await
+}
+
+/// Runs `cmds` against each of the given profiles at the same time, and reports how each fared.
+async fn broadcast(
+    proj_dirs: &ProjectDirs,
+    config: &Config,
+    globals: &Globals,
+    instances: &[&str],
+    lines: Vec<Vec<OsString>>,
+    event_subscriptions: EventSubscription,
+    out: Output,
+) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        !instances.is_empty(),
+        "there are no [profiles.<name>] sections in the config file to run against"
+    );
+    if let Some(name) = instances
+        .iter()
+        .find(|name| !config.profiles.contains_key(**name))
+    {
+        anyhow::bail!("no profile named {name} in the config file");
+    }
+
+    let results = futures_util::future::join_all(instances.iter().map(|&name| {
+        let lines = lines.clone();
+        async move {
+            let res = on_instance(
+                proj_dirs,
+                config,
+                globals,
+                Some(name),
+                lines,
+                event_subscriptions,
+                out,
+            )
+            .await;
+            (name, res)
+        }
+    }))
+    .await;
+
+    let mut failed = 0;
+    for (name, res) in results {
+        match res {
+            Ok(()) => {
+                out.result(
+                    format!("{name}: ok"),
+                    &json!({ "instance": name, "ok": true }),
+                )?;
+            }
+            Err(e) => {
+                failed += 1;
+                out.result(
+                    format!("{name}: {e:#}"),
+                    &json!({ "instance": name, "ok": false, "error": output::error_json(&e) }),
+                )?;
+            }
+        }
+    }
+    anyhow::ensure!(
+        failed == 0,
+        "{failed} of {} instances failed",
+        instances.len()
+    );
+    Ok(())
+}
+
+/// Connects to the OBS described by `profile`, and runs the commands in `lines` there.
+async fn on_instance(
+    proj_dirs: &ProjectDirs,
+    config: &Config,
+    globals: &Globals,
+    profile: Option<&str>,
+    lines: Vec<Vec<OsString>>,
+    event_subscriptions: EventSubscription,
+    out: Output,
+) -> anyhow::Result<()> {
+    let (tunnel, endpoint) = locate(config, profile).await?;
+    let local = tunnel.is_none() && endpoint.is_local();
+    let password = password::resolve(proj_dirs, config, profile, globals.password_stdin).await?;
+    let retry_delay = parse_duration(&globals.retry_delay)?;
+    let mut attempt = 0;
+    let client = loop {
+        match connect_with(&endpoint, password.clone(), event_subscriptions).await {
+            Ok(client) => break client,
+            Err(e) if attempt < globals.retry => {
+                let delay = retry_delay
+                    .saturating_mul(1 << attempt.min(16))
+                    .min(MAX_RETRY_DELAY);
+                attempt += 1;
+                tracing::warn!(
+                    "Could not connect to OBS; retrying in {:.1}s ({attempt} of {}).",
+                    delay.as_secs_f32(),
+                    globals.retry
+                );
+                tracing::debug!("connection failed: {e:#}");
+                tokio::time::sleep(delay).await;
+            }
+            Err(e) => return Err(e),
+        }
+    };
+    // With several instances, commands that reconnect only ever go back to the first one.
+    let _ = REACHED.set(Reached {
+        endpoint,
+        password,
+        event_subscriptions,
+    });
+    OBS_IS_LOCAL
+        .scope(
+            local,
+            on_client(proj_dirs, config, globals, client, lines, out),
+        )
+        .await
+}
+
+/// Runs the commands in `lines` against the OBS that `client` is connected to.
+async fn on_client(
+    proj_dirs: &ProjectDirs,
+    config: &Config,
+    globals: &Globals,
+    client: Client,
+    lines: Vec<Vec<OsString>>,
+    out: Output,
+) -> anyhow::Result<()> {
+    // Names can only be matched with `--fuzzy` now that OBS can say what names there are.
+    let cmds = parse_lines(&client, lines).await?;
+
+    if let Some(unmet) = globals.guards.check(&client).await? {
+        if globals.guards.strict {
+            return Err(guard::Unmet(unmet).into());
+        }
+        tracing::info!("Not running: {unmet}.");
+        out.json(&json!({ "skipped": unmet }))?;
+        return Ok(());
+    }
+
+    let read_only = globals.flags().read_only(config);
+    if read_only && cmds.iter().any(Command::is_mutating) {
+        ensure_not_streaming(&client).await?;
+    }
+
+    if let [Command::Daemon { daemon }] = &cmds[..] {
+        let reloads = config::watch(proj_dirs.config_dir()).await?;
+        return daemon::run(client, daemon, &config.schedule, reloads, globals.flags()).await;
+    }
+    if let [Command::ServeHttp { serve }] = &cmds[..] {
+        return rest::run(&client, serve, read_only).await;
+    }
+    if let [Command::Mqtt { mqtt }] = &cmds[..] {
+        return mqtt::run(&client, mqtt, read_only).await;
+    }
+    if let [Command::Osc { osc }] = &cmds[..] {
+        return osc::run(&client, osc, read_only).await;
+    }
+    if let [Command::Midi { midi }] = &cmds[..] {
+        return midi::run(&client, midi, read_only).await;
+    }
+    if let [Command::Rpc] = &cmds[..] {
+        return rpc::run(&client, read_only).await;
+    }
+    if let [Command::Hotkeyd] = &cmds[..] {
+        let reloads = config::watch(proj_dirs.config_dir()).await?;
+        return hotkey::run(&client, &config.hotkeys, reloads, globals.flags(), out).await;
+    }
+    if let [Command::Repl] = &cmds[..] {
+        return repl::run(&client, read_only, out).await;
+    }
+    if globals.parallel {
+        futures_util::future::try_join_all(cmds.into_iter().map(|cmd| run(&client, cmd, out)))
+            .await?;
+    } else {
+        for cmd in cmds {
+            run(&client, cmd, out).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Loads the config file, filling in the connection settings from OBS' own configuration with
+/// `--auto-config`.
+async fn load_config(proj_dirs: &ProjectDirs, globals: &Globals) -> anyhow::Result<Config> {
+    let mut config = Config::load(proj_dirs.config_dir()).await?;
+    // Logged before the discovered password is added to it.
+    tracing::debug!(?config, "loaded configuration");
+    if globals.auto_config || config.auto_config {
+        discover::apply(&mut config).await?;
+    }
+    Ok(config)
+}
+
+/// Works out where OBS is, setting up an SSH tunnel to it if the profile asks for one.
+///
+/// The tunnel has to be kept alive for as long as OBS is talked to.
+async fn locate(
+    config: &Config,
+    profile: Option<&str>,
+) -> anyhow::Result<(Option<tunnel::Tunnel>, Endpoint)> {
+    let endpoint = config.endpoint(profile)?;
+    let tunnel = match endpoint.ssh_destination() {
+        Some(destination) => Some(tunnel::Tunnel::open(destination, endpoint.port).await?),
+        None => None,
+    };
+    let endpoint = match &tunnel {
+        Some(tunnel) => tunnel.endpoint.clone(),
+        None => endpoint,
+    };
+    Ok((tunnel, endpoint))
+}
+
+/// Whether OBS runs on this machine, as far as the connection to it tells: false for remote hosts
+/// and SSH tunnels, and true outside of a command line run, like in tests.
+pub(crate) fn obs_is_local() -> bool {
+    OBS_IS_LOCAL.try_with(|local| *local).unwrap_or(true)
+}
+
+/// Fails if OBS is streaming, for commands that would change it in read-only mode.
+async fn ensure_not_streaming(client: &Client) -> anyhow::Result<()> {
+    let streaming = client
+        .streaming()
+        .status()
+        .await
+        .context("get streaming status")?
+        .active;
+    anyhow::ensure!(
+        !streaming,
+        "refusing to change OBS while it is streaming in read-only mode (pass --force to do it anyway)"
+    );
+    Ok(())
+}
+
+fn project_dirs() -> anyhow::Result<ProjectDirs> {
+    ProjectDirs::from("", "", "obs-do").context("could not determine configuration file location")
+}
+
+/// Where obs-do keeps files that only matter while it is running, like sockets and markers.
+fn runtime_dir() -> anyhow::Result<PathBuf> {
+    let dirs = project_dirs()?;
+    Ok(dirs
+        .runtime_dir()
+        .map_or_else(|| std::env::temp_dir().join("obs-do"), Path::to_owned))
+}
+
+/// Makes `path` absolute, relative to the directory obs-do runs in, for handing it to OBS, which
+/// runs elsewhere.
+fn absolute(path: &Path) -> anyhow::Result<PathBuf> {
+    if path.is_absolute() {
+        return Ok(path.to_owned());
+    }
+    let dir = std::env::current_dir().context("get the current directory")?;
+    Ok(dir.join(path))
+}
+
+/// Connects to OBS again the way it was first reached, asking for the same events, retrying
+/// until it's back.
+pub(crate) async fn reconnect() -> Client {
+    loop {
+        tokio::time::sleep(RECONNECT_INTERVAL).await;
+        let reached = REACHED.get_or_init(|| Reached {
+            endpoint: Endpoint::default(),
+            password: None,
+            event_subscriptions: EventSubscription::ALL,
+        });
+        let password = reached.password.clone();
+        match connect_with(&reached.endpoint, password, reached.event_subscriptions).await {
+            Ok(client) => return client,
+            Err(e) => tracing::debug!("reconnect failed: {e:#}"),
+        }
+    }
+}
+
+/// Connects to the OBS WebSocket server at `endpoint` the way the command-line tool does, asking
+/// for the given kinds of `events` (or the default ones if there are none).
+///
+/// The password can be looked up as the tool does it with [`password`].
+pub async fn connect(
+    endpoint: &Endpoint,
+    password: Option<String>,
+    events: &[EventCategory],
+) -> anyhow::Result<Client> {
+    let event_subscriptions = match events {
+        [] => EventSubscription::ALL,
+        events => EventCategory::subscriptions(events),
+    };
+    connect_with(endpoint, password, event_subscriptions).await
+}
+
+/// Looks up the OBS WebSocket password for `profile` the way the command-line tool does without
+/// `--password-stdin`: from the config file, `OBS_DO_PASSWORD`, the OS keyring or the
+/// `websocket-token` file.
+pub async fn password(config: &Config, profile: Option<&str>) -> anyhow::Result<Option<String>> {
+    password::resolve(&project_dirs()?, config, profile, false).await
+}
+
+/// Connects to the OBS WebSocket server at `endpoint`, asking for `event_subscriptions`.
+pub(crate) async fn connect_with(
+    endpoint: &Endpoint,
+    pw: Option<String>,
+    event_subscriptions: EventSubscription,
+) -> anyhow::Result<Client> {
+    // Only used to say where the password file goes if connecting fails.
+    let cfg = project_dirs().map_or_else(
+        |_| PathBuf::from(password::TOKEN_FILE),
+        |dirs| dirs.config_dir().join(password::TOKEN_FILE),
+    );
+
+    tracing::debug!(?event_subscriptions, "connecting to OBS at {endpoint}");
+    let client_res = Client::connect_with_config(ConnectConfig {
+        host: endpoint.host.as_str(),
+        port: endpoint.port,
+        password: pw,
+        event_subscriptions: Some(event_subscriptions),
+        broadcast_capacity: None,
+    })
+    .await;
+    match client_res {
+        Ok(client) => {
+            let version = client
+                .general()
+                .version()
+                .await
+                .context("get OBS version")?;
+            tracing::info!(
+                "Connected to OBS: {} / {}",
+                version.obs_version,
+                version.obs_web_socket_version
+            );
+            Ok(client)
+        }
+        Err(error) => {
+            anyhow::bail!(
+                "\
+Could not connect to OBS over WebSocket.
+
+- Make sure OBS is running, and that 'Enable WebSocket server' is checked under Tools -> WebSocket Server Settings.
+  If that menu item does not appear for you, your OBS has not been built with WebSocket support.\
+  On Arch Linux for example, you'll want one of the AUR obs-studio packages that build WebSocket, such as obs-studio-git.
+
+- If your server requires a password, make sure that you have it written in {}, or pass it in
+  {} or with --password-stdin. `obs-do config init` can write the file for you.
+
+ERROR message:
+    {:?}
+                    ",
+                cfg.display(),
+                password::ENV_VAR,
+                error
+            )
+        }
+    }
+}
+
+/// Runs a single obs-do command line, like `set-scene BRB` or `fade Mic -6dB --duration 2s`, over
+/// an existing connection.
+pub async fn run_line(client: &Client, line: &str, out: Output) -> anyhow::Result<()> {
+    let words = shell_words::split(line).context("split command into arguments")?;
+    run(client, parse_fuzzy(client, words).await?, out).await
+}
+
+/// Parses the words of one command in a chain, resolving aliases in them.
+fn parse_chained(words: Vec<String>) -> anyhow::Result<Command> {
+    parse_resolved(alias::resolve(Chained::command(), words))
+}
+
+/// Like [`parse_chained`], also matching the names in the command to those in OBS with
+/// `--fuzzy`.
+async fn parse_fuzzy(client: &Client, words: Vec<String>) -> anyhow::Result<Command> {
+    let words = alias::resolve(Chained::command(), words);
+    parse_resolved(names::fuzzy(client, Chained::command(), words).await?)
+}
+
+fn parse_resolved(words: Vec<String>) -> anyhow::Result<Command> {
+    Chained::try_parse_from(words)
+        .map(|chained| chained.cmd)
+        .map_err(|e| anyhow::anyhow!("{}", e.render()))
+}
+
+/// Parses the commands given on the command line again from their `lines`, whose aliases are
+/// already resolved, matching the names in them to those in OBS with `--fuzzy`.
+///
+/// Only the first line may have global flags, as when the command line was first parsed; a
+/// command after a `;` is parsed on its own, so that a flag there is refused rather than dropped.
+async fn parse_lines(client: &Client, lines: Vec<Vec<OsString>>) -> anyhow::Result<Vec<Command>> {
+    let mut cmds = Vec::with_capacity(lines.len());
+    for (i, line) in lines.into_iter().enumerate() {
+        let line = names::fuzzy(client, Args::command(), line).await?;
+        let cmd = if i == 0 {
+            Args::try_parse_from(line).map(|args| args.cmd)
+        } else {
+            Chained::try_parse_from(&line[1..]).map(|chained| chained.cmd)
+        };
+        cmds.push(cmd.map_err(|e| anyhow::anyhow!("{}", e.render()))?);
+    }
+    Ok(cmds)
+}
+
+async fn run(client: &Client, cmd: Command, out: Output) -> anyhow::Result<()> {
+    tracing::debug!(?cmd, "running command");
+    match cmd {
+        Command::RecordStatus => record::status(client, out).await?,
+        Command::Stats { stats } => stats::run(client, &stats, out).await?,
+        Command::Exporter { exporter } => exporter::run(client, &exporter).await?,
+        Command::WatchHealth { health } => health::run(client, &health, out).await?,
+        Command::StreamStatus { status } => stream::status(client, &status, out).await?,
+        Command::Stream { cmd } => stream::run(client, cmd, out).await?,
+        Command::Video { cmd } => video::run(client, cmd, out).await?,
+        Command::Output { cmd } => outputs::run(client, cmd, out).await?,
+        Command::ToggleStream => {
+            let active = client
+                .streaming()
+                .toggle()
+                .await
+                .context("toggle streaming")?;
+            let summary = if active {
+                "Stream started"
+            } else {
+                "Stream stopped"
+            };
+            notify::outcome(summary, "").await;
+            out.json(&json!({ "streaming": active }))?;
+        }
+        Command::StartStream => {
+            let status = client
+                .streaming()
+                .status()
+                .await
+                .context("get stream status")?;
+            anyhow::ensure!(!status.active, "OBS is already streaming");
+            client
+                .streaming()
+                .start()
+                .await
+                .context("start streaming")?;
+            notify::outcome("Stream started", "").await;
+            out.json(&json!({ "streaming": true }))?;
+        }
+        Command::StopStream => {
+            let status = client
+                .streaming()
+                .status()
+                .await
+                .context("get stream status")?;
+            anyhow::ensure!(status.active, "OBS is not streaming");
+            client.streaming().stop().await.context("stop streaming")?;
+            notify::outcome("Stream stopped", "").await;
+            out.json(&json!({ "streaming": false }))?;
+        }
+        Command::ToggleRecord => {
+            let active = client
+                .recording()
+                .toggle()
+                .await
+                .context("toggle recording")?;
+            let summary = if active {
+                "Recording started"
+            } else {
+                "Recording saved"
+            };
+            notify::outcome(summary, "").await;
+            out.json(&json!({ "recording": active }))?;
+        }
+        Command::ToggleMute { input } => {
+            let muted = match client.inputs().toggle_mute(&input).await {
+                Ok(muted) => muted,
+                Err(e) => {
+                    return Err(names::input_error(client, &input, e).await)
+                        .context(format!("toggle-mute {input}"))
+                }
+            };
+            out.json(&json!({ "input": input, "muted": muted }))?;
+        }
+        Command::Mute { input } => set_muted(client, &input, true, out).await?,
+        Command::Unmute { input } => set_muted(client, &input, false, out).await?,
+        Command::SetScene { scene } => {
+            if let Err(e) = client.scenes().set_current_program_scene(&scene).await {
+                return Err(names::scene_error(client, &scene, e).await)
+                    .with_context(|| format!("set-scene {scene}"));
+            }
+            out.json(&json!({ "scene": scene }))?;
+        }
+        Command::NextScene { cycle } => {
+            cycle::run(client, cycle::Direction::Next, &cycle, out).await?;
+        }
+        Command::PrevScene { cycle } => {
+            cycle::run(client, cycle::Direction::Previous, &cycle, out).await?;
+        }
+        Command::RotateScenes { rotate } => rotate::run(client, &rotate).await?,
+        Command::Countdown { countdown } => countdown::run(client, &countdown, out).await?,
+        Command::SetImage { input, path } => input::set_image(client, &input, &path, out).await?,
+        Command::SetColor { input, color } => input::set_color(client, &input, &color, out).await?,
+        Command::SetText { input, text } => text::set(client, &input, &text, out).await?,
+        Command::SetVolume {
+            set: SetVolumeArgs { input, volume },
+        } => {
+            let new_volume = parse_volume(&volume)?;
+
+            if let Err(e) = client.inputs().set_volume(&input, new_volume.into()).await {
+                return Err(names::input_error(client, &input, e).await)
+                    .context(format!("set-volume {input} {volume}"));
+            }
+            let volume = match new_volume {
+                Volume::Db(db) => json!({ "db": db }),
+                Volume::Mul(mul) => json!({ "mul": mul }),
+            };
+            out.json(&json!({ "input": input, "volume": volume }))?;
+        }
+        Command::SetSyncOffset { input, offset } => {
+            audio::set_sync_offset(client, &input, offset, out).await?;
+        }
+        Command::GetSyncOffset { input } => audio::sync_offset(client, &input, out).await?,
+        Command::SetMonitoring { input, monitoring } => {
+            audio::set_monitoring(client, &input, monitoring, out).await?;
+        }
+        Command::SetBalance { input, balance } => {
+            audio::set_balance(client, &input, balance, out).await?;
+        }
+        Command::GetBalance { input } => audio::balance(client, &input, out).await?,
+        Command::FadeInput { input, fade } => fade::run(client, &input, &fade, out).await?,
+        Command::FadeCancel { inputs } => fade::cancel(&inputs, out).await?,
+        Command::Crossfade { crossfade } => fade::crossfade(client, &crossfade, out).await?,
+        Command::Transition { audio_follow } => transition::run(client, audio_follow, out).await?,
+        Command::Enforce {
+            scene,
+            mute,
+            unmute,
+        } => enforce::run(client, scene, mute, unmute, out).await?,
+        Command::Screenshot { screenshot } => screenshot::run(client, &screenshot, out).await?,
+        Command::Input { cmd } => input::run(client, cmd, out).await?,
+        Command::Collection { cmd } => collection::run(client, cmd, out).await?,
+        Command::Scene { cmd } => scene::run(client, cmd, out).await?,
+        Command::Text { cmd } => text::run(client, cmd, out).await?,
+        Command::Record { cmd } => record::run(client, cmd, out).await?,
+        Command::ReplayBuffer { cmd } => replay::run(client, cmd, out).await?,
+        Command::AnimateItem { animate } => animate::run(client, &animate, out).await?,
+        Command::ListItems { scene } => item::list(client, &scene, out).await?,
+        Command::Browser { cmd } => browser::run(client, cmd, out).await?,
+        Command::State { cmd } => state::run(client, cmd, out).await?,
+        Command::Projector { cmd } => projector::run(client, cmd, out).await?,
+        Command::Item { cmd } => item::run(client, cmd, out).await?,
+        Command::Mix { cmd } => mix::run(client, cmd, out).await?,
+        Command::AudioSnapshot { cmd } => snapshot::run(client, cmd, out).await?,
+        Command::Meter { inputs } => meter::run(client, &inputs, out).await?,
+        Command::Mixer => mixer::run(client).await?,
+        Command::Session { session } => session::run(client, &session, out).await?,
+        Command::MuteMirror { mirror } => mirror::run(client, &mirror).await?,
+        Command::Duck { duck } => duck::run(client, &duck).await?,
+        Command::Failover { failover } => failover::run(client, &failover, out).await?,
+        Command::WaitFor { condition, timeout } => {
+            wait::run(client, &condition, timeout.as_deref(), out).await?;
+        }
+        Command::Watch {
+            exec,
+            meters: _,
+            sink,
+        } => watch::run(client, exec.as_deref(), &sink).await?,
+        Command::Macro { name } => macros::run(client, &name, out).await?,
+        Command::External(args) => macros::run(client, macros::invoked(&args)?, out).await?,
+        Command::Daemon { .. } => {
+            anyhow::bail!("daemon cannot be chained with other commands");
+        }
+        Command::ServeHttp { .. } => {
+            anyhow::bail!("serve-http cannot be chained with other commands");
+        }
+        Command::Mqtt { .. } => {
+            anyhow::bail!("mqtt cannot be chained with other commands");
+        }
+        Command::Osc { .. } => {
+            anyhow::bail!("osc cannot be chained with other commands");
+        }
+        Command::Midi { .. } => {
+            anyhow::bail!("midi cannot be chained with other commands");
+        }
+        Command::Rpc => {
+            anyhow::bail!("rpc cannot be chained with other commands");
+        }
+        Command::Hotkeyd => {
+            anyhow::bail!("hotkeyd cannot be chained with other commands");
+        }
+        Command::Repl => {
+            anyhow::bail!("repl cannot be chained with other commands");
+        }
+        Command::ImportHotkeys { .. } => {
+            anyhow::bail!("import-hotkeys cannot be chained with other commands");
+        }
+        Command::WaitForObs { .. } => {
+            anyhow::bail!("wait-for-obs cannot be chained with other commands");
+        }
+        Command::At { .. } => {
+            anyhow::bail!("at can only come first in a chain");
+        }
+        Command::In { .. } => {
+            anyhow::bail!("in can only come first in a chain");
+        }
+        Command::Auth { .. } => {
+            anyhow::bail!("auth cannot be chained with other commands");
+        }
+        Command::Config { .. } => {
+            anyhow::bail!("config cannot be chained with other commands");
+        }
+        Command::Doctor => {
+            anyhow::bail!("doctor cannot be chained with other commands");
+        }
+        Command::Setup => setup::run(client, out).await?,
+        Command::Completions { .. } => {
+            anyhow::bail!("completions cannot be chained with other commands");
+        }
+        Command::Complete { words } => {
+            complete::list(client, &words).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Parses a duration like `250ms`, `2s` or `1m30s`; a plain number is taken as seconds.
+pub fn parse_duration(duration: &str) -> anyhow::Result<Duration> {
+    let invalid =
+        || anyhow::anyhow!("invalid duration `{duration}`, expected e.g. `250ms`, `2s` or `1m30s`");
+    if let Ok(secs) = duration.parse::<f64>() {
+        return Duration::try_from_secs_f64(secs).map_err(|_| invalid());
+    }
+    anyhow::ensure!(!duration.is_empty(), "the duration must not be empty");
+
+    let is_number = |c: char| c.is_ascii_digit() || c == '.';
+    let mut total = Duration::ZERO;
+    let mut rest = duration;
+    while !rest.is_empty() {
+        // Every number needs a unit after it, and every unit a number before it.
+        let unit_start = rest.find(|c| !is_number(c)).ok_or_else(invalid)?;
+        let (number, unit) = rest.split_at(unit_start);
+        let (unit, next) = unit.split_at(unit.find(is_number).unwrap_or(unit.len()));
+        let number: f64 = number.parse().map_err(|_| invalid())?;
+        let unit_secs = match unit {
+            "ms" => 0.001,
+            "s" => 1.,
+            "m" => 60.,
+            "h" => 3600.,
+            _ => return Err(invalid()),
+        };
+        let part = Duration::try_from_secs_f64(number * unit_secs).map_err(|_| invalid())?;
+        total = total.checked_add(part).ok_or_else(invalid)?;
+        rest = next;
+    }
+    Ok(total)
+}
+
+/// Mutes or unmutes `input`, for `mute` and `unmute`.
+async fn set_muted(client: &Client, input: &str, muted: bool, out: Output) -> anyhow::Result<()> {
+    if let Err(e) = client.inputs().set_muted(input, muted).await {
+        let cmd = if muted { "mute" } else { "unmute" };
+        return Err(names::input_error(client, input, e).await).context(format!("{cmd} {input}"));
+    }
+    out.json(&json!({ "input": input, "muted": muted }))
+}
+
+/// What `set-volume` sets, with the input left out for `Mic/Aux`.
+#[derive(Debug, Clone)]
+struct SetVolumeArgs {
+    input: String,
+    volume: String,
+}
+
+/// The words `set-volume` is given, before telling whether the first is the input or the volume.
+///
+/// Clap's `allow_missing_positional` only skips the input if what follows the volume doesn't
+/// look like a flag, so `set-volume -6dB --quiet` would take `-6dB` for the input.
+#[derive(clap::Args)]
+#[command(override_usage = "obs-do set-volume [OPTIONS] [INPUT] <VOLUME>")]
+struct SetVolumeWords {
+    /// The input to set the volume of; `Mic/Aux` if only a volume is given.
+    #[arg(value_name = "INPUT", allow_hyphen_values = true)]
+    input: String,
+
+    /// Volume should be provided in dB for absolute volume or % for relative adjustments.
+    ///
+    /// If no unit is provided, it is interpreted as %.
+    #[arg(value_name = "VOLUME", allow_hyphen_values = true)]
+    volume: Option<String>,
+}
+
+impl clap::FromArgMatches for SetVolumeArgs {
+    fn from_arg_matches(matches: &clap::ArgMatches) -> Result<Self, clap::Error> {
+        let SetVolumeWords { input, volume } = SetVolumeWords::from_arg_matches(matches)?;
+        Ok(match volume {
+            Some(volume) => Self { input, volume },
+            None => Self {
+                input: String::from("Mic/Aux"),
+                volume: input,
+            },
+        })
+    }
+
+    fn update_from_arg_matches(&mut self, matches: &clap::ArgMatches) -> Result<(), clap::Error> {
+        *self = Self::from_arg_matches(matches)?;
+        Ok(())
+    }
+}
+
+impl clap::Args for SetVolumeArgs {
+    fn augment_args(cmd: clap::Command) -> clap::Command {
+        SetVolumeWords::augment_args(cmd)
+    }
+
+    fn augment_args_for_update(cmd: clap::Command) -> clap::Command {
+        SetVolumeWords::augment_args_for_update(cmd)
+    }
+}
+
+/// A volume to set an input to.
+///
+/// Unlike obws' own, this one can be copied and matched on exhaustively.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Volume {
+    /// A multiplier, where 1 leaves the audio as loud as it is.
+    Mul(f32),
+    /// Decibels, where 0 leaves the audio as loud as it is.
+    Db(f32),
+}
+
+impl From<Volume> for obws::requests::inputs::Volume {
+    fn from(volume: Volume) -> Self {
+        match volume {
+            Volume::Mul(mul) => Self::Mul(mul),
+            Volume::Db(db) => Self::Db(db),
+        }
+    }
+}
+
+/// Parses a volume given in dB (`-6dB`) or in % (`50%` or just `50`).
+pub fn parse_volume(volume: &str) -> anyhow::Result<Volume> {
+    let parsed = if let Some(db) = volume.strip_suffix("dB") {
+        Volume::Db(db.parse().context("invalid dB quantity")?)
+    } else {
+        let volume = volume.strip_suffix('%').unwrap_or(volume);
+        Volume::Mul(volume.parse::<f32>().context("invalid % volume change")? / 100.)
+    };
+    let (Volume::Db(n) | Volume::Mul(n)) = parsed;
+    anyhow::ensure!(n.is_finite(), "invalid volume `{volume}`");
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn absolute_paths() {
+        let dir = std::env::current_dir().unwrap();
+        assert_eq!(
+            absolute(Path::new("logo.png")).unwrap(),
+            dir.join("logo.png")
+        );
+        let logo = dir.join("logo.png");
+        assert_eq!(absolute(&logo).unwrap(), logo);
+    }
+
+    #[test]
+    fn durations() {
+        assert_eq!(parse_duration("2").unwrap(), Duration::from_secs(2));
+        assert_eq!(parse_duration("0.5").unwrap(), Duration::from_millis(500));
+        assert_eq!(parse_duration("250ms").unwrap(), Duration::from_millis(250));
+        assert_eq!(parse_duration("1m30s").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_duration("1h").unwrap(), Duration::from_secs(3600));
+        assert_eq!(parse_duration("1.5s").unwrap(), Duration::from_millis(1500));
+    }
+
+    #[test]
+    fn invalid_durations() {
+        for duration in ["", "s", "5x", "m5", "-1", "1s-", "ms"] {
+            assert!(parse_duration(duration).is_err(), "{duration} parsed");
+        }
+    }
+
+    #[test]
+    fn volumes() {
+        assert!(matches!(parse_volume("-6dB").unwrap(), Volume::Db(db) if db == -6.));
+        assert!(matches!(parse_volume("50%").unwrap(), Volume::Mul(mul) if mul == 0.5));
+        assert!(matches!(parse_volume("100").unwrap(), Volume::Mul(mul) if mul == 1.));
+        assert!(parse_volume("loud").is_err());
+        assert!(parse_volume("-6 dB").is_err());
+        for volume in ["infdB", "-infdB", "NaNdB", "inf", "NaN%"] {
+            assert!(parse_volume(volume).is_err(), "{volume} parsed");
+        }
+    }
+
+    #[test]
+    fn servable_commands() {
+        let servable = |line: &str| {
+            let words = shell_words::split(line).unwrap();
+            parse_chained(words).unwrap().is_servable()
+        };
+        assert!(servable("set-scene BRB"));
+        assert!(servable("record stop"));
+        assert!(servable("record set-directory /srv/recordings"));
+        assert!(servable(
+            "collection wipe --yes-i-mean-it --collection Test"
+        ));
+        assert!(!servable("screenshot /tmp/shot.png"));
+        assert!(!servable("record stop --verify"));
+        assert!(!servable("record verify /tmp/recording.mkv"));
+        assert!(!servable("record set-directory --create /srv/recordings"));
+        assert!(!servable("collection wipe --yes-i-mean-it"));
+        assert!(!servable("set-text Clock @/etc/passwd"));
+    }
+
+    #[test]
+    fn flags_win_over_the_config_file() {
+        let flags = |read_only, force| Flags {
+            read_only,
+            force,
+            fuzzy: false,
+            notify: false,
+            events: false,
+        };
+        let config = |read_only| Config {
+            read_only,
+            ..Config::default()
+        };
+        assert!(!flags(false, false).read_only(&config(false)));
+        assert!(flags(false, false).read_only(&config(true)));
+        assert!(flags(true, false).read_only(&config(false)));
+        assert!(!flags(false, true).read_only(&config(true)));
+        assert!(!flags(true, true).read_only(&config(false)));
+    }
+
+    #[test]
+    fn chained_commands_parse() {
+        let cmd = Chained::try_parse_from(["set-scene", "BRB"]).unwrap().cmd;
+        assert!(matches!(cmd, Command::SetScene { scene } if scene == "BRB"));
+        // Anything else may be a macro, which is only known once the config file is read.
+        let cmd = Chained::try_parse_from(["brb"]).unwrap().cmd;
+        assert!(matches!(cmd, Command::External(args) if args == ["brb"]));
+        assert!(Chained::try_parse_from(["set-scene"]).is_err());
+    }
+
+    #[tokio::test]
+    async fn chained_commands_have_no_global_flags() {
+        let (client, _) = crate::fake_obs::connect(|_, _| None).await;
+        let line = |words: &[&str]| words.iter().map(OsString::from).collect::<Vec<_>>();
+        let first = line(&["obs-do", "--json", "set-scene", "A"]);
+        let cmds = parse_lines(
+            &client,
+            vec![first.clone(), line(&["obs-do", "set-scene", "B"])],
+        )
+        .await
+        .unwrap();
+        assert!(
+            matches!(&cmds[..], [Command::SetScene { .. }, Command::SetScene { scene }] if scene == "B")
+        );
+        for flags in [&["--json"][..], &["--profile", "studio"], &["--read-only"]] {
+            let chained = line(&[&["obs-do", "set-scene", "B"][..], flags].concat());
+            let e = parse_lines(&client, vec![first.clone(), chained])
+                .await
+                .unwrap_err();
+            assert!(e.to_string().contains("unexpected argument"), "{e}");
+        }
+    }
+
+    #[test]
+    fn set_volume_defaults_to_mic() {
+        let cmd = Chained::try_parse_from(["set-volume", "-5dB"]).unwrap().cmd;
+        assert!(
+            matches!(cmd, Command::SetVolume { set } if set.input == "Mic/Aux" && set.volume == "-5dB")
+        );
+        let cmd = Chained::try_parse_from(["set-volume", "Music", "-5dB"])
+            .unwrap()
+            .cmd;
+        assert!(
+            matches!(cmd, Command::SetVolume { set } if set.input == "Music" && set.volume == "-5dB")
+        );
+        let args = Args::try_parse_from(["obs-do", "set-volume", "-6dB", "--quiet"]).unwrap();
+        assert!(args.globals.quiet);
+        assert!(
+            matches!(args.cmd, Command::SetVolume { set } if set.input == "Mic/Aux" && set.volume == "-6dB")
+        );
+        let args =
+            Args::try_parse_from(["obs-do", "set-volume", "Music", "-6dB", "--quiet"]).unwrap();
+        assert!(args.globals.quiet);
+        assert!(
+            matches!(args.cmd, Command::SetVolume { set } if set.input == "Music" && set.volume == "-6dB")
+        );
+        assert!(Chained::try_parse_from(["set-volume"]).is_err());
+    }
+}
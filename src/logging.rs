@@ -0,0 +1,208 @@
+use anyhow::Context;
+use std::{
+    collections::HashMap,
+    fmt,
+    path::Path,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tracing::{field::Field, level_filters::LevelFilter, Event, Subscriber};
+use tracing_subscriber::{
+    filter::Targets,
+    layer::{self, SubscriberExt},
+    util::SubscriberInitExt,
+    EnvFilter, Layer,
+};
+
+/// Sets up `tracing` according to `--quiet`, `--verbose`, and `--log-file`.
+///
+/// `RUST_LOG` takes precedence over the verbosity flags if set. With `trace`, every request sent
+/// to OBS is additionally timed, and the returned [`RequestTrace`] can summarize them at the end.
+pub(crate) fn init(
+    quiet: bool,
+    verbose: u8,
+    log_file: Option<&Path>,
+    trace: bool,
+) -> anyhow::Result<Option<RequestTrace>> {
+    let default = match (quiet, verbose) {
+        (true, _) => "error",
+        (false, 0) => "warn,obs_do=info",
+        (false, 1) => "info,obs_do=debug,obws=debug",
+        // obws logs the messages it exchanges with OBS at trace level.
+        (false, _) => "debug,obs_do=trace,obws=trace",
+    };
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(default));
+    let logger = tracing_subscriber::fmt::layer();
+
+    let logger = if let Some(path) = log_file {
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("open log file {}", path.display()))?;
+        logger
+            .with_writer(Mutex::new(file))
+            .with_ansi(false)
+            .boxed()
+    } else if verbose == 0 {
+        // Without -v, the log output is just the occasional message for humans.
+        logger
+            .with_writer(std::io::stderr)
+            .without_time()
+            .with_target(false)
+            .with_level(false)
+            .boxed()
+    } else {
+        logger.with_writer(std::io::stderr).boxed()
+    };
+
+    let tracer = trace.then(RequestTrace::default);
+    let tracer_layer = tracer.clone().map(|tracer| {
+        // The tracer needs to see obws' trace-level messages regardless of the log verbosity.
+        tracer.with_filter(Targets::new().with_target("obws", LevelFilter::TRACE))
+    });
+
+    tracing_subscriber::registry()
+        .with(logger.with_filter(filter))
+        .with(tracer_layer)
+        .init();
+    Ok(tracer)
+}
+
+/// Times every request obws sends to OBS, printing each to stderr as its response arrives.
+///
+/// obws has no hooks for observing requests, so this picks apart the trace-level messages it
+/// logs whenever it sends a request or receives a response.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct RequestTrace {
+    state: Arc<Mutex<TraceState>>,
+}
+
+#[derive(Debug, Default)]
+struct TraceState {
+    /// Requests awaiting a response, by request ID.
+    pending: HashMap<String, PendingRequest>,
+    requests: usize,
+    total: Duration,
+}
+
+#[derive(Debug)]
+struct PendingRequest {
+    name: String,
+    size: usize,
+    sent: Instant,
+}
+
+impl RequestTrace {
+    /// Prints the totals across all requests, given how long the invocation took overall.
+    pub(crate) fn summarize(&self, elapsed: Duration) {
+        let state = self.state.lock().expect("trace state poisoned");
+        eprintln!(
+            "{} requests, {:.1?} waiting for OBS, {:.1?} in total",
+            state.requests, state.total, elapsed
+        );
+    }
+
+    fn sent(&self, json: &str) {
+        let Ok(message) = serde_json::from_str::<serde_json::Value>(json) else {
+            return;
+        };
+        let request = &message["d"];
+        let (Some(id), Some(name)) = (
+            request["requestId"].as_str(),
+            request["requestType"].as_str(),
+        ) else {
+            return;
+        };
+        let pending = PendingRequest {
+            name: name.to_owned(),
+            size: json.len(),
+            sent: Instant::now(),
+        };
+        let mut state = self.state.lock().expect("trace state poisoned");
+        state.pending.insert(id.to_owned(), pending);
+    }
+
+    fn received(&self, id: &str, data: Option<&str>) {
+        let mut state = self.state.lock().expect("trace state poisoned");
+        let Some(request) = state.pending.remove(id) else {
+            return;
+        };
+        let elapsed = request.sent.elapsed();
+        state.requests += 1;
+        state.total += elapsed;
+        eprintln!(
+            "{} ({} B sent, {} B received) in {elapsed:.1?}",
+            request.name,
+            request.size,
+            data.map_or(0, str::len),
+        );
+    }
+}
+
+impl<S: Subscriber> Layer<S> for RequestTrace {
+    fn on_event(&self, event: &Event<'_>, _ctx: layer::Context<'_, S>) {
+        let mut fields = MessageFields::default();
+        event.record(&mut fields);
+        match fields.message.as_deref() {
+            Some("sending message") => {
+                if let Some(json) = &fields.json {
+                    self.sent(json);
+                }
+            }
+            Some("got request-response message") => {
+                if let Some(id) = &fields.id {
+                    self.received(id, fields.data.as_deref());
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The fields of obws' trace messages that [`RequestTrace`] cares about.
+#[derive(Default)]
+struct MessageFields {
+    message: Option<String>,
+    json: Option<String>,
+    id: Option<String>,
+    data: Option<String>,
+}
+
+impl tracing::field::Visit for MessageFields {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        let slot = match field.name() {
+            "message" => &mut self.message,
+            "json" => &mut self.json,
+            "id" => &mut self.id,
+            "data" => &mut self.data,
+            _ => return,
+        };
+        *slot = Some(format!("{value:?}"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The trace relies on what obws logs, which it is free to change in any release.
+    #[tokio::test]
+    async fn sees_obws_requests() {
+        let tracer = RequestTrace::default();
+        let _subscriber = tracing_subscriber::registry()
+            .with(
+                tracer
+                    .clone()
+                    .with_filter(Targets::new().with_target("obws", LevelFilter::TRACE)),
+            )
+            .set_default();
+        let (client, _) = crate::fake_obs::connect(|_, _| None).await;
+        client.general().version().await.unwrap();
+
+        let state = tracer.state.lock().unwrap();
+        // Connecting asks for the version too.
+        assert_eq!(state.requests, 2);
+        assert!(state.pending.is_empty());
+    }
+}
@@ -0,0 +1,144 @@
+use anyhow::Context;
+use clap::CommandFactory;
+use futures_util::future::{FutureExt, LocalBoxFuture};
+use obws::Client;
+use std::collections::BTreeMap;
+
+use crate::{config::Config, output::Output, Command};
+
+/// Runs the commands that make up the macro `name`, one after the other.
+///
+/// This returns a boxed future rather than being an `async fn`, since a macro's commands are run
+/// with [`crate::run`], which is what runs macros in the first place.
+pub(crate) fn run<'a>(
+    client: &'a Client,
+    name: &'a str,
+    out: Output,
+) -> LocalBoxFuture<'a, anyhow::Result<()>> {
+    async move {
+        let config = Config::load(crate::project_dirs()?.config_dir()).await?;
+        for words in expand(&config.macros, name)? {
+            let cmd = crate::parse_fuzzy(client, words).await?;
+            crate::run(client, cmd, out).await?;
+        }
+        Ok(())
+    }
+    .boxed_local()
+}
+
+/// The commands that the macro `name` stands for, with the macros it uses expanded in turn.
+///
+/// The commands are checked, but returned as their words, to be parsed again with `--fuzzy`
+/// names matched when they run.
+fn expand(macros: &BTreeMap<String, Vec<String>>, name: &str) -> anyhow::Result<Vec<Vec<String>>> {
+    let mut cmds = Vec::new();
+    expand_into(macros, name, &mut Vec::new(), &mut cmds)?;
+    Ok(cmds)
+}
+
+fn expand_into(
+    macros: &BTreeMap<String, Vec<String>>,
+    name: &str,
+    stack: &mut Vec<String>,
+    cmds: &mut Vec<Vec<String>>,
+) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        !stack.iter().any(|used| used == name),
+        "macro {name} uses itself ({} -> {name})",
+        stack.join(" -> ")
+    );
+    let Some(lines) = macros.get(name) else {
+        let commands = crate::Chained::command();
+        let known = commands
+            .get_subcommands()
+            .map(clap::Command::get_name)
+            .chain(macros.keys().map(String::as_str));
+        match crate::names::closest(name, known) {
+            Some(closest) => anyhow::bail!("no command or macro named {name}; did you mean '{closest}'?"),
+            None => anyhow::bail!(
+                "no command or macro named {name}; macros are defined under [macros] in the config file"
+            ),
+        }
+    };
+    stack.push(name.to_owned());
+    for line in lines {
+        let words = shell_words::split(line)
+            .with_context(|| format!("split `{line}` in macro {name} into arguments"))?;
+        let cmd = crate::parse_chained(words.clone())
+            .with_context(|| format!("parse `{line}` in macro {name}"))?;
+        match cmd {
+            Command::Macro { name } => expand_into(macros, &name, stack, cmds)?,
+            Command::External(args) => expand_into(macros, invoked(&args)?, stack, cmds)?,
+            _ => cmds.push(words),
+        }
+    }
+    stack.pop();
+    Ok(())
+}
+
+/// The macro that `obs-do <name>` invokes.
+pub(crate) fn invoked(args: &[String]) -> anyhow::Result<&str> {
+    match args {
+        [name] => Ok(name),
+        [name, ..] => anyhow::bail!("no command named {name} (and macros take no arguments)"),
+        [] => unreachable!("clap always passes the name of an external subcommand"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(macros: &BTreeMap<String, Vec<String>>, name: &str) -> anyhow::Result<Vec<Command>> {
+        super::expand(macros, name)?
+            .into_iter()
+            .map(crate::parse_chained)
+            .collect()
+    }
+
+    fn macros(entries: &[(&str, &[&str])]) -> BTreeMap<String, Vec<String>> {
+        entries
+            .iter()
+            .map(|(name, lines)| {
+                let lines = lines.iter().map(|line| line.to_string()).collect();
+                (name.to_string(), lines)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn expands_in_order() {
+        let macros = macros(&[("brb", &["set-scene BRB", "toggle-mute 'Mic/Aux'"])]);
+        let cmds = expand(&macros, "brb").unwrap();
+        assert!(matches!(&cmds[..], [
+            Command::SetScene { scene },
+            Command::ToggleMute { input },
+        ] if scene == "BRB" && input == "Mic/Aux"));
+    }
+
+    #[test]
+    fn expands_nested_macros() {
+        let macros = macros(&[
+            ("brb", &["mute-all", "set-scene BRB"]),
+            ("mute-all", &["toggle-mute Mic", "macro music-off"]),
+            ("music-off", &["toggle-mute Music"]),
+        ]);
+        let cmds = expand(&macros, "brb").unwrap();
+        assert_eq!(cmds.len(), 3);
+        assert!(matches!(&cmds[2], Command::SetScene { scene } if scene == "BRB"));
+    }
+
+    #[test]
+    fn rejects_cycles() {
+        let macros = macros(&[("a", &["b"]), ("b", &["macro a"])]);
+        let error = expand(&macros, "a").unwrap_err();
+        assert!(error.to_string().contains("a -> b"), "{error}");
+    }
+
+    #[test]
+    fn rejects_unknown_and_broken_macros() {
+        let macros = macros(&[("typo", &["set-scen BRB"])]);
+        assert!(expand(&macros, "missing").is_err());
+        assert!(expand(&macros, "typo").is_err());
+    }
+}
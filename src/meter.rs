@@ -0,0 +1,178 @@
+use anyhow::Context;
+use futures_util::StreamExt;
+use obws::{events::Event, Client};
+use serde_json::json;
+use std::{
+    io::Write,
+    time::{Duration, Instant},
+};
+
+use crate::output::Output;
+
+/// The quietest level shown on the bars; anything below is drawn as silence.
+pub(crate) const FLOOR_DB: f32 = -60.;
+
+/// How many characters wide the bars are.
+const WIDTH: usize = 50;
+
+/// How long the peak marker stays at the highest recent peak before following the level down.
+const PEAK_HOLD: Duration = Duration::from_millis(1500);
+
+/// How long the clip indicator stays lit after the signal clipped.
+const CLIP_HOLD: Duration = Duration::from_secs(2);
+
+/// What is drawn for one input.
+#[derive(Debug, Default)]
+pub(crate) struct Meter {
+    /// The loudness (RMS) across channels, in dB.
+    magnitude: Option<f32>,
+    /// The highest recent peak, in dB, and when it was reached.
+    peak: Option<(f32, Instant)>,
+    clipped: Option<Instant>,
+}
+
+impl Meter {
+    pub(crate) fn update(&mut self, levels: &[[f32; 3]]) {
+        let now = Instant::now();
+        // Each channel reports its magnitude, peak and pre-fader peak as multipliers.
+        let loudest = |i: usize| to_db(levels.iter().map(|level| level[i]).fold(0., f32::max));
+        let magnitude = loudest(0);
+        let peak = loudest(1);
+        self.magnitude = Some(magnitude);
+        match self.peak {
+            Some((held, since)) if held > peak && now - since < PEAK_HOLD => {}
+            _ => self.peak = Some((peak, now)),
+        }
+        if peak >= 0. {
+            self.clipped = Some(now);
+        }
+    }
+
+    /// The loudness, in dB, if any levels have come in yet.
+    pub(crate) fn magnitude(&self) -> Option<f32> {
+        self.magnitude
+    }
+
+    /// The highest recent peak, in dB.
+    pub(crate) fn peak(&self) -> Option<f32> {
+        self.peak.map(|(peak, _)| peak)
+    }
+
+    /// Whether the signal clipped recently.
+    pub(crate) fn clipping(&self) -> bool {
+        self.clipped.is_some_and(|at| at.elapsed() < CLIP_HOLD)
+    }
+
+    fn render(&self, name: &str, name_width: usize) -> String {
+        let Some(magnitude) = self.magnitude else {
+            return format!("{name:<name_width$} (no signal yet)");
+        };
+        let position = |db: f32| {
+            let fraction = ((db - FLOOR_DB) / -FLOOR_DB).clamp(0., 1.);
+            (fraction * WIDTH as f32).round() as usize
+        };
+        let peak = self.peak().unwrap_or(FLOOR_DB);
+        let filled = position(magnitude);
+        let marker = position(peak).min(WIDTH - 1);
+        let bar: String = (0..WIDTH)
+            .map(|i| {
+                if i < filled {
+                    '#'
+                } else if i == marker && peak > FLOOR_DB {
+                    '|'
+                } else {
+                    '-'
+                }
+            })
+            .collect();
+        let clip = if self.clipping() { " CLIP" } else { "" };
+        format!(
+            "{name:<name_width$} [{bar}] {} / {} dB{clip}",
+            show_db(magnitude),
+            show_db(peak)
+        )
+    }
+}
+
+/// Draws live level meters for `inputs` in the terminal until interrupted.
+///
+/// With `--json`, prints the levels as JSON lines instead, one per input and update.
+pub(crate) async fn run(client: &Client, inputs: &[String], out: Output) -> anyhow::Result<()> {
+    let events = client.events().context("subscribe to OBS events")?;
+    let mut events = std::pin::pin!(events);
+    for input in inputs {
+        // OBS only meters inputs with audio, and refuses to report a volume for any other.
+        client
+            .inputs()
+            .volume(input)
+            .await
+            .with_context(|| format!("get volume of {input}"))?;
+    }
+
+    let mut meters: Vec<Meter> = inputs.iter().map(|_| Meter::default()).collect();
+    let name_width = inputs.iter().map(|input| input.chars().count()).max();
+    let name_width = name_width.unwrap_or_default();
+    let mut stdout = std::io::stdout();
+    let mut drawn = false;
+    if !out.is_json() {
+        // Hide the cursor while drawing.
+        write!(stdout, "\x1b[?25l")?;
+    }
+    let res = loop {
+        let event = tokio::select! {
+            event = events.next() => event,
+            _ = tokio::signal::ctrl_c() => break Ok(()),
+        };
+        let Some(event) = event else {
+            break Err(anyhow::anyhow!("lost connection to OBS"));
+        };
+        let Event::InputVolumeMeters { inputs: levels } = event else {
+            continue;
+        };
+        for level in levels {
+            let Some(i) = inputs.iter().position(|input| *input == level.name) else {
+                continue;
+            };
+            meters[i].update(&level.levels);
+            if out.is_json() {
+                let meter = &meters[i];
+                out.json(&json!({
+                    "input": level.name,
+                    "magnitude_db": meter.magnitude(),
+                    "peak_db": meter.peak(),
+                    "clipping": meter.clipping(),
+                }))?;
+            }
+        }
+        if out.is_json() {
+            continue;
+        }
+
+        if drawn {
+            // Go back up to redraw the meters in place.
+            write!(stdout, "\x1b[{}A", inputs.len())?;
+        }
+        for (input, meter) in inputs.iter().zip(&meters) {
+            writeln!(stdout, "\x1b[2K{}", meter.render(input, name_width))?;
+        }
+        stdout.flush()?;
+        drawn = true;
+    };
+    if !out.is_json() {
+        write!(stdout, "\x1b[?25h")?;
+        stdout.flush()?;
+    }
+    res
+}
+
+fn to_db(mul: f32) -> f32 {
+    20. * mul.log10()
+}
+
+fn show_db(db: f32) -> String {
+    if db <= FLOOR_DB {
+        String::from("  -inf")
+    } else {
+        format!("{db:>6.1}")
+    }
+}
@@ -0,0 +1,567 @@
+use anyhow::Context;
+use futures_util::{stream::FuturesUnordered, StreamExt};
+use obws::{events::Event, Client};
+use serde::Deserialize;
+use std::{
+    io::Read,
+    path::{Path, PathBuf},
+};
+use tokio::{io::AsyncWriteExt, sync::mpsc};
+
+use crate::output::Output;
+
+/// Where ALSA puts the raw MIDI ports of sound cards.
+const DEVICE_DIR: &str = "/dev/snd";
+
+#[derive(Debug, Clone, clap::Args)]
+pub(crate) struct MidiArgs {
+    /// The controller to use: part of its name, like `nanoKONTROL`, or a raw MIDI device path,
+    /// like `/dev/snd/midiC1D0`.
+    ///
+    /// Can be left out if only one MIDI device is plugged in.
+    #[arg(long, value_name = "NAME")]
+    device: Option<String>,
+
+    /// The TOML file that maps the controller's notes and CCs to commands.
+    #[arg(long, value_name = "PATH")]
+    map: PathBuf,
+}
+
+/// The contents of a `--map` file.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MapFile {
+    #[serde(default, rename = "control")]
+    controls: Vec<Control>,
+}
+
+/// A note or CC of the controller, and what it does.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct Control {
+    note: Option<u8>,
+    cc: Option<u8>,
+    /// The MIDI channel, from 1 to 16; any channel if left out.
+    channel: Option<u8>,
+    /// The command to run, in which `{value}`, `{percent}` and `{db}` are replaced by the
+    /// position of a fader or knob.
+    run: String,
+    /// What the control's LED shows: `muted:<input>`, `scene:<scene>`, `streaming` or
+    /// `recording`.
+    light: Option<String>,
+}
+
+/// A control from the map file, checked and ready to use.
+#[derive(Debug)]
+struct Binding {
+    source: Source,
+    channel: Option<u8>,
+    words: Vec<String>,
+    /// Whether `words` take the control's value, which makes every move run the command; others
+    /// only run when pressed.
+    continuous: bool,
+    light: Option<Light>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Source {
+    Note(u8),
+    Cc(u8),
+}
+
+/// Something about OBS that a controller LED can show.
+#[derive(Debug, PartialEq, Eq)]
+enum Light {
+    Muted(String),
+    Scene(String),
+    Streaming,
+    Recording,
+}
+
+impl Light {
+    fn parse(light: &str) -> anyhow::Result<Self> {
+        Ok(match light.split_once(':') {
+            Some(("muted", input)) => Light::Muted(input.to_owned()),
+            Some(("scene", scene)) => Light::Scene(scene.to_owned()),
+            None if light == "streaming" => Light::Streaming,
+            None if light == "recording" => Light::Recording,
+            _ => anyhow::bail!(
+                "unknown light `{light}`; expected muted:<input>, scene:<scene>, streaming or recording"
+            ),
+        })
+    }
+
+    /// Whether the light is on after `event`, if the event is about what it shows.
+    fn after(&self, event: &Event) -> Option<bool> {
+        match (self, event) {
+            (Light::Muted(input), Event::InputMuteStateChanged { name, muted })
+                if input == name =>
+            {
+                Some(*muted)
+            }
+            (Light::Scene(scene), Event::CurrentProgramSceneChanged { name }) => {
+                Some(scene == name)
+            }
+            (Light::Streaming, Event::StreamStateChanged { active, .. })
+            | (Light::Recording, Event::RecordStateChanged { active, .. }) => Some(*active),
+            _ => None,
+        }
+    }
+
+    async fn current(&self, client: &Client) -> anyhow::Result<bool> {
+        Ok(match self {
+            Light::Muted(input) => client
+                .inputs()
+                .muted(input)
+                .await
+                .with_context(|| format!("get mute state of {input}"))?,
+            Light::Scene(scene) => {
+                let current = client
+                    .scenes()
+                    .current_program_scene()
+                    .await
+                    .context("get current scene")?;
+                current == *scene
+            }
+            Light::Streaming => {
+                client
+                    .streaming()
+                    .status()
+                    .await
+                    .context("get stream status")?
+                    .active
+            }
+            Light::Recording => {
+                client
+                    .recording()
+                    .status()
+                    .await
+                    .context("get recording status")?
+                    .active
+            }
+        })
+    }
+}
+
+impl Binding {
+    fn new(control: Control) -> anyhow::Result<Self> {
+        let source = match (control.note, control.cc) {
+            (Some(note), None) => Source::Note(note),
+            (None, Some(cc)) => Source::Cc(cc),
+            _ => anyhow::bail!("each control needs either a `note` or a `cc`"),
+        };
+        let (Source::Note(n) | Source::Cc(n)) = source;
+        anyhow::ensure!(n < 128, "note or CC {n} is out of range (0-127)");
+        if let Some(channel) = control.channel {
+            anyhow::ensure!(
+                (1..=16).contains(&channel),
+                "channel {channel} is out of range (1-16)"
+            );
+        }
+        let words = shell_words::split(&control.run)
+            .with_context(|| format!("split `{}` into arguments", control.run))?;
+        anyhow::ensure!(!words.is_empty(), "a control has an empty `run`");
+        let continuous = ["{value}", "{percent}", "{db}"]
+            .iter()
+            .any(|placeholder| control.run.contains(placeholder));
+        Ok(Binding {
+            source,
+            // Channels are numbered from 1 in map files, as on controllers, but from 0 on the wire.
+            channel: control.channel.map(|channel| channel - 1),
+            words,
+            continuous,
+            light: control.light.as_deref().map(Light::parse).transpose()?,
+        })
+    }
+
+    /// The command to run for a MIDI message, if it's for this control and should run one.
+    fn command(&self, message: Message) -> Option<Vec<String>> {
+        let (channel, source, value) = match message {
+            Message::NoteOn {
+                channel,
+                note,
+                velocity,
+            } => (channel, Source::Note(note), velocity),
+            Message::ControlChange { channel, cc, value } => (channel, Source::Cc(cc), value),
+            Message::NoteOff { .. } => return None,
+        };
+        if source != self.source || self.channel.is_some_and(|c| c != channel) {
+            return None;
+        }
+        // A button sends 0 when released, which shouldn't count as another press.
+        if !self.continuous && value == 0 {
+            return None;
+        }
+        Some(self.words.iter().map(|word| expand(word, value)).collect())
+    }
+
+    /// The MIDI message that turns the control's LED on or off.
+    fn light_message(&self, on: bool) -> [u8; 3] {
+        let channel = self.channel.unwrap_or(0);
+        let value = if on { 127 } else { 0 };
+        match self.source {
+            Source::Note(note) => [0x90 | channel, note, value],
+            Source::Cc(cc) => [0xB0 | channel, cc, value],
+        }
+    }
+}
+
+/// Fills in the position of a fader or knob, from 0 to 127, in one word of a command.
+fn expand(word: &str, value: u8) -> String {
+    let fraction = f64::from(value) / 127.;
+    // The bottom of a fader is silence; the rest of its travel covers the useful 60 dB.
+    let db = if value == 0 {
+        -100.
+    } else {
+        60. * fraction - 60.
+    };
+    word.replace("{value}", &value.to_string())
+        .replace("{percent}", &format!("{:.1}", fraction * 100.))
+        .replace("{db}", &format!("{db:.1}"))
+}
+
+/// Turns a controller's notes and CCs into commands, until interrupted.
+///
+/// Each control in the map runs its command when pressed, or on every move if the command takes
+/// its value. While a control's command is running, only its latest move is kept to run next,
+/// so that a fader doesn't queue up a backlog. Controls with a `light` have their LED kept in
+/// step with OBS, if the controller lets them be driven from outside.
+///
+/// Controllers are opened through ALSA's raw MIDI devices, so this only works on Linux.
+pub(crate) async fn run(client: &Client, args: &MidiArgs, read_only: bool) -> anyhow::Result<()> {
+    let contents = tokio::fs::read_to_string(&args.map)
+        .await
+        .with_context(|| format!("read {}", args.map.display()))?;
+    let map: MapFile =
+        toml::from_str(&contents).with_context(|| format!("parse {}", args.map.display()))?;
+    let bindings = map
+        .controls
+        .into_iter()
+        .map(Binding::new)
+        .collect::<anyhow::Result<Vec<_>>>()
+        .with_context(|| format!("invalid control in {}", args.map.display()))?;
+
+    let path = find_device(args.device.as_deref())?;
+    let file = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(&path)
+        .with_context(|| format!("open {}", path.display()))?;
+    let mut reader = file
+        .try_clone()
+        .with_context(|| format!("open {}", path.display()))?;
+    let mut leds = tokio::fs::File::from_std(file);
+    // Reads block until the controller sends something, which may be never, so they get a
+    // thread of their own that doesn't hold up exiting.
+    let (bytes, mut received) = mpsc::unbounded_channel();
+    std::thread::spawn(move || {
+        let mut buf = [0; 256];
+        loop {
+            match reader.read(&mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => {
+                    if bytes.send(buf[..n].to_vec()).is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+    let events = client.events().context("subscribe to OBS events")?;
+    let mut events = std::pin::pin!(events);
+    tracing::info!("Listening to MIDI from {}.", path.display());
+
+    for binding in &bindings {
+        if let Some(light) = &binding.light {
+            let on = light.current(client).await?;
+            set_light(&mut leds, binding, on).await;
+        }
+    }
+
+    let mut parser = Parser::default();
+    // The command waiting to run for each binding while its previous one finishes.
+    let mut pending: Vec<Option<Vec<String>>> = bindings.iter().map(|_| None).collect();
+    let mut busy = vec![false; bindings.len()];
+    let mut running = FuturesUnordered::new();
+    loop {
+        tokio::select! {
+            bytes = received.recv() => {
+                let Some(bytes) = bytes else {
+                    anyhow::bail!("lost connection to {}", path.display());
+                };
+                for message in bytes.into_iter().filter_map(|byte| parser.push(byte)) {
+                    tracing::trace!(?message, "MIDI message");
+                    for (i, binding) in bindings.iter().enumerate() {
+                        let Some(words) = binding.command(message) else {
+                            continue;
+                        };
+                        if busy[i] {
+                            pending[i] = Some(words);
+                        } else {
+                            busy[i] = true;
+                            running.push(execute(client, i, words, read_only));
+                        }
+                    }
+                }
+            }
+            Some(i) = running.next() => match pending[i].take() {
+                Some(words) => running.push(execute(client, i, words, read_only)),
+                None => busy[i] = false,
+            },
+            event = events.next() => {
+                let Some(event) = event else {
+                    anyhow::bail!("lost connection to OBS");
+                };
+                for binding in &bindings {
+                    let on = binding.light.as_ref().and_then(|light| light.after(&event));
+                    if let Some(on) = on {
+                        set_light(&mut leds, binding, on).await;
+                    }
+                }
+            }
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+        }
+    }
+}
+
+/// Runs the command of the `i`th binding; failures are only logged, since there's no one to
+/// tell.
+async fn execute(client: &Client, i: usize, words: Vec<String>, read_only: bool) -> usize {
+    let line = words.join(" ");
+    let res = async {
+        let cmd = crate::parse_fuzzy(client, words).await?;
+        anyhow::ensure!(cmd.is_servable(), "this command cannot be run from MIDI");
+        if read_only && cmd.is_mutating() {
+            crate::ensure_not_streaming(client).await?;
+        }
+        // There's no one to answer, so what the command prints is dropped rather than
+        // written to the bridge's own output.
+        Output::capture(crate::run(client, cmd, Output::new(true)))
+            .await
+            .0
+    }
+    .await;
+    if let Err(e) = res {
+        tracing::warn!("`{line}` failed: {e:#}");
+    }
+    i
+}
+
+async fn set_light(leds: &mut tokio::fs::File, binding: &Binding, on: bool) {
+    let message = binding.light_message(on);
+    if let Err(e) = leds.write_all(&message).await {
+        tracing::warn!("Failed to set controller LED: {e}");
+    }
+}
+
+/// Finds the raw MIDI device to open, by path or by (part of) the name of its sound card.
+fn find_device(device: Option<&str>) -> anyhow::Result<PathBuf> {
+    if let Some(path) = device.filter(|device| device.starts_with('/')) {
+        return Ok(PathBuf::from(path));
+    }
+    let devices = devices().context("list MIDI devices")?;
+    let available = || {
+        devices
+            .iter()
+            .map(|(name, path)| format!("{name} ({})", path.display()))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    anyhow::ensure!(!devices.is_empty(), "no MIDI devices are plugged in");
+    let matching: Vec<_> = match device {
+        Some(device) => devices
+            .iter()
+            .filter(|(name, _)| name.to_lowercase().contains(&device.to_lowercase()))
+            .collect(),
+        None => devices.iter().collect(),
+    };
+    match (&matching[..], device) {
+        ([(_, path)], _) => Ok(path.clone()),
+        ([], Some(device)) => {
+            anyhow::bail!(
+                "no MIDI device matches '{device}'; there are: {}",
+                available()
+            )
+        }
+        (_, Some(device)) => {
+            anyhow::bail!("several MIDI devices match '{device}': {}", available())
+        }
+        (_, None) => anyhow::bail!(
+            "several MIDI devices are plugged in, so pick one with --device: {}",
+            available()
+        ),
+    }
+}
+
+/// The raw MIDI devices there are, with the names of their sound cards.
+fn devices() -> std::io::Result<Vec<(String, PathBuf)>> {
+    let entries = match std::fs::read_dir(DEVICE_DIR) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut devices = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        let file_name = entry.file_name();
+        let Some(card) = file_name
+            .to_str()
+            .and_then(|name| name.strip_prefix("midiC"))
+            .and_then(|rest| rest.split_once('D'))
+            .map(|(card, _)| card.to_owned())
+        else {
+            continue;
+        };
+        let id = Path::new("/proc/asound")
+            .join(format!("card{card}"))
+            .join("id");
+        let name = std::fs::read_to_string(id)
+            .map(|id| id.trim().to_owned())
+            .unwrap_or_else(|_| file_name.to_string_lossy().into_owned());
+        devices.push((name, entry.path()));
+    }
+    devices.sort();
+    Ok(devices)
+}
+
+/// The MIDI messages that controls are mapped from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Message {
+    NoteOn { channel: u8, note: u8, velocity: u8 },
+    NoteOff { channel: u8, note: u8 },
+    ControlChange { channel: u8, cc: u8, value: u8 },
+}
+
+/// Splits a stream of MIDI bytes into messages.
+#[derive(Debug, Default)]
+struct Parser {
+    /// The status byte of the message being read, which later messages may leave out
+    /// ("running status").
+    status: Option<u8>,
+    data: Vec<u8>,
+}
+
+impl Parser {
+    fn push(&mut self, byte: u8) -> Option<Message> {
+        match byte {
+            // Real-time messages, like clock ticks, can come in the middle of anything.
+            0xF8..=0xFF => return None,
+            // Other system messages, like SysEx, aren't mapped and end running status.
+            0xF0..=0xF7 => {
+                self.status = None;
+                return None;
+            }
+            0x80..=0xEF => {
+                self.status = Some(byte);
+                self.data.clear();
+                return None;
+            }
+            _ => {}
+        }
+        let status = self.status?;
+        self.data.push(byte);
+        let len = match status & 0xF0 {
+            0xC0 | 0xD0 => 1,
+            _ => 2,
+        };
+        if self.data.len() < len {
+            return None;
+        }
+        let data = std::mem::take(&mut self.data);
+        let channel = status & 0x0F;
+        match (status & 0xF0, data.as_slice()) {
+            (0x90, &[note, 0]) | (0x80, &[note, _]) => Some(Message::NoteOff { channel, note }),
+            (0x90, &[note, velocity]) => Some(Message::NoteOn {
+                channel,
+                note,
+                velocity,
+            }),
+            (0xB0, &[cc, value]) => Some(Message::ControlChange { channel, cc, value }),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_running_status() {
+        let mut parser = Parser::default();
+        let bytes = [0xB0, 0, 64, 0xF8, 1, 127, 0x91, 36, 100, 36, 0];
+        let messages: Vec<_> = bytes.into_iter().filter_map(|b| parser.push(b)).collect();
+        assert_eq!(
+            messages,
+            [
+                Message::ControlChange {
+                    channel: 0,
+                    cc: 0,
+                    value: 64
+                },
+                Message::ControlChange {
+                    channel: 0,
+                    cc: 1,
+                    value: 127
+                },
+                Message::NoteOn {
+                    channel: 1,
+                    note: 36,
+                    velocity: 100
+                },
+                Message::NoteOff {
+                    channel: 1,
+                    note: 36
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn bindings() {
+        let map: MapFile = toml::from_str(
+            r#"
+            [[control]]
+            cc = 0
+            run = "set-volume 'Mic/Aux' {db}dB"
+
+            [[control]]
+            cc = 32
+            channel = 1
+            run = "toggle-mute 'Mic/Aux'"
+            light = "muted:Mic/Aux"
+            "#,
+        )
+        .unwrap();
+        let bindings: Vec<_> = map
+            .controls
+            .into_iter()
+            .map(|control| Binding::new(control).unwrap())
+            .collect();
+        let cc = |channel, cc, value| Message::ControlChange { channel, cc, value };
+        assert_eq!(
+            bindings[0].command(cc(3, 0, 127)).unwrap(),
+            ["set-volume", "Mic/Aux", "0.0dB"]
+        );
+        assert_eq!(
+            bindings[0].command(cc(3, 0, 0)).unwrap(),
+            ["set-volume", "Mic/Aux", "-100.0dB"]
+        );
+        assert_eq!(
+            bindings[1].command(cc(0, 32, 127)).unwrap(),
+            ["toggle-mute", "Mic/Aux"]
+        );
+        assert_eq!(bindings[1].command(cc(0, 32, 0)), None);
+        assert_eq!(bindings[1].command(cc(1, 32, 127)), None);
+        assert_eq!(
+            bindings[1].light,
+            Some(Light::Muted(String::from("Mic/Aux")))
+        );
+        assert_eq!(bindings[1].light_message(true), [0xB0, 32, 127]);
+
+        let control = |toml| Binding::new(toml::from_str(toml).unwrap());
+        assert!(control("note = 1\ncc = 2\nrun = \"toggle-stream\"").is_err());
+        assert!(control("note = 1\nchannel = 17\nrun = \"toggle-stream\"").is_err());
+        assert!(control("note = 1\nrun = \"toggle-stream\"\nlight = \"blinking\"").is_err());
+    }
+}
@@ -0,0 +1,163 @@
+use anyhow::Context;
+use futures_util::StreamExt;
+use obws::{events::Event, Client};
+use serde_json::json;
+use std::time::Duration;
+use tokio::time::Instant;
+
+#[derive(Debug, Clone, clap::Args)]
+pub(crate) struct MirrorArgs {
+    /// The input whose mute state to mirror.
+    input: String,
+
+    /// Run this shell command on every change, with `OBS_DO_MUTED` set to `1` or `0`.
+    #[arg(long, required_unless_present_any = ["url", "gpio"])]
+    exec: Option<String>,
+
+    /// POST `{"input": ..., "muted": ...}` to this URL on every change.
+    #[arg(long)]
+    url: Option<String>,
+
+    /// Drive this GPIO pin (through /sys/class/gpio) high while the input is live, e.g. for an
+    /// on-air light.
+    #[arg(long, value_name = "PIN")]
+    gpio: Option<u32>,
+
+    /// How long the mute state must hold before it is mirrored.
+    ///
+    /// Keeps the indicator from flickering when the input is toggled several times in a row.
+    #[arg(long, value_name = "DURATION", default_value = "100ms")]
+    debounce: String,
+}
+
+/// Keeps reflecting the mute state of an input to the indicators in `args` until interrupted.
+///
+/// If the connection to OBS is lost, this keeps trying to reconnect, and re-reads the state once
+/// it's back since changes made in the meantime produce no events.
+pub(crate) async fn run(client: &Client, args: &MirrorArgs) -> anyhow::Result<()> {
+    let mut mirror = Mirror {
+        args,
+        debounce: crate::parse_duration(&args.debounce)?,
+        http: reqwest::Client::new(),
+        shown: None,
+    };
+
+    let mirror = async {
+        let mut reconnected = None;
+        loop {
+            mirror
+                .follow(reconnected.as_ref().unwrap_or(client))
+                .await?;
+            tracing::warn!("Lost connection to OBS; reconnecting.");
+            reconnected = Some(crate::reconnect().await);
+        }
+    };
+    tokio::select! {
+        res = mirror => res,
+        _ = tokio::signal::ctrl_c() => Ok(()),
+    }
+}
+
+struct Mirror<'a> {
+    args: &'a MirrorArgs,
+    debounce: Duration,
+    http: reqwest::Client,
+    /// The mute state the indicators were last successfully set to.
+    shown: Option<bool>,
+}
+
+impl Mirror<'_> {
+    /// Mirrors the mute state for as long as the connection to OBS lasts.
+    async fn follow(&mut self, client: &Client) -> anyhow::Result<()> {
+        let input = &self.args.input;
+        let events = client.events().context("subscribe to OBS events")?;
+        let mut events = std::pin::pin!(events);
+        let muted = client
+            .inputs()
+            .muted(input)
+            .await
+            .with_context(|| format!("get mute state of {input}"))?;
+        self.show(muted).await;
+
+        let mut pending: Option<(bool, Instant)> = None;
+        loop {
+            let settled = async {
+                match pending {
+                    Some((_, deadline)) => tokio::time::sleep_until(deadline).await,
+                    None => std::future::pending().await,
+                }
+            };
+            tokio::select! {
+                event = events.next() => match event {
+                    Some(Event::InputMuteStateChanged { name, muted }) if name == *input => {
+                        pending = Some((muted, Instant::now() + self.debounce));
+                    }
+                    Some(_) => {}
+                    None => return Ok(()),
+                },
+                _ = settled => {
+                    if let Some((muted, _)) = pending.take() {
+                        self.show(muted).await;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Updates the indicators, unless they already show `muted`.
+    ///
+    /// Failures are logged rather than returned, so that a flaky light doesn't stop the mirror.
+    async fn show(&mut self, muted: bool) {
+        if self.shown == Some(muted) {
+            return;
+        }
+        match self.update(muted).await {
+            Ok(()) => self.shown = Some(muted),
+            Err(e) => tracing::warn!("Failed to mirror mute state: {e:#}"),
+        }
+    }
+
+    async fn update(&self, muted: bool) -> anyhow::Result<()> {
+        let input = &self.args.input;
+        tracing::debug!(input, muted, "mirroring mute state");
+        if let Some(exec) = &self.args.exec {
+            let status = crate::watch::shell(exec)
+                .env("OBS_DO_MUTED", if muted { "1" } else { "0" })
+                .status()
+                .await
+                .with_context(|| format!("run `{exec}`"))?;
+            anyhow::ensure!(status.success(), "`{exec}` exited with {status}");
+        }
+        if let Some(url) = &self.args.url {
+            self.http
+                .post(url)
+                .json(&json!({ "input": input, "muted": muted }))
+                .send()
+                .await
+                .and_then(|response| response.error_for_status())
+                .with_context(|| format!("POST to {url}"))?;
+        }
+        if let Some(pin) = self.args.gpio {
+            set_gpio(pin, !muted)
+                .await
+                .with_context(|| format!("set GPIO pin {pin}"))?;
+        }
+        Ok(())
+    }
+}
+
+/// Drives a GPIO pin through the sysfs interface, exporting it first if need be.
+async fn set_gpio(pin: u32, high: bool) -> anyhow::Result<()> {
+    let dir = std::path::PathBuf::from(format!("/sys/class/gpio/gpio{pin}"));
+    if !tokio::fs::try_exists(&dir).await.unwrap_or(false) {
+        tokio::fs::write("/sys/class/gpio/export", pin.to_string())
+            .await
+            .context("export pin")?;
+        tokio::fs::write(dir.join("direction"), "out")
+            .await
+            .context("make pin an output")?;
+    }
+    tokio::fs::write(dir.join("value"), if high { "1" } else { "0" })
+        .await
+        .context("write pin value")
+}
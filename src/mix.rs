@@ -0,0 +1,182 @@
+use anyhow::Context;
+use clap::Subcommand;
+use obws::{requests::profiles::SetParameter, Client};
+use serde_json::json;
+use std::fmt::Write;
+
+use crate::output::Output;
+
+/// Number of audio tracks OBS mixes inputs into.
+const TRACKS: usize = 6;
+
+#[derive(Debug, Clone, Subcommand)]
+pub(crate) enum MixCommand {
+    /// Shows which tracks the stream and recording use, and which tracks each input feeds.
+    Show,
+    /// Routes an input to exactly the given tracks, e.g. `mix route Music 2`.
+    Route {
+        input: String,
+
+        /// Comma-separated track numbers (1-6); omit to remove the input from all tracks.
+        #[arg(value_delimiter = ',', value_parser = clap::value_parser!(u8).range(1..=6))]
+        tracks: Vec<u8>,
+    },
+    /// Selects the track that is sent to the stream.
+    StreamTrack {
+        #[arg(value_parser = clap::value_parser!(u8).range(1..=6))]
+        track: u8,
+    },
+    /// Selects the tracks that are written to recordings, e.g. `mix record-tracks 1,3`.
+    RecordTracks {
+        #[arg(required = true, value_delimiter = ',', value_parser = clap::value_parser!(u8).range(1..=6))]
+        tracks: Vec<u8>,
+    },
+}
+
+impl MixCommand {
+    pub(crate) fn is_mutating(&self) -> bool {
+        !matches!(self, MixCommand::Show)
+    }
+}
+
+pub(crate) async fn run(client: &Client, cmd: MixCommand, out: Output) -> anyhow::Result<()> {
+    match cmd {
+        MixCommand::Show => {
+            let advanced = is_advanced_output(client).await?;
+            // Simple output mode always streams and records track 1.
+            let (stream, record) = if advanced {
+                let stream = parameter(client, "AdvOut", "TrackIndex").await?;
+                let stream = stream
+                    .as_deref()
+                    .unwrap_or("1")
+                    .parse::<usize>()
+                    .context("parse stream track")?;
+                let record = parameter(client, "AdvOut", "RecTracks").await?;
+                let record = record
+                    .as_deref()
+                    .unwrap_or("1")
+                    .parse::<u8>()
+                    .context("parse recording track mask")?;
+                (stream, std::array::from_fn(|i| record & (1 << i) != 0))
+            } else {
+                (1, std::array::from_fn(|i| i == 0))
+            };
+
+            let mut human = format!(
+                "stream: track {stream}\nrecord: tracks {}",
+                format_tracks(record)
+            );
+            if !advanced {
+                human.push_str(" (simple output mode)");
+            }
+            let mut routing = Vec::new();
+            let inputs = client.inputs().list(None).await.context("list inputs")?;
+            for input in inputs {
+                // Inputs without audio (e.g. image sources) have no tracks; skip them.
+                if let Ok(tracks) = client.inputs().audio_tracks(&input.name).await {
+                    write!(human, "\n{}: {}", input.name, format_tracks(tracks))?;
+                    routing.push(json!({ "input": input.name, "tracks": track_numbers(tracks) }));
+                }
+            }
+
+            out.result(
+                human,
+                &json!({
+                    "stream_track": stream,
+                    "record_tracks": track_numbers(record),
+                    "inputs": routing,
+                }),
+            )?;
+        }
+        MixCommand::Route { input, tracks } => {
+            let enabled: [bool; TRACKS] = std::array::from_fn(|i| tracks.contains(&(i as u8 + 1)));
+            client
+                .inputs()
+                .set_audio_tracks(&input, enabled.map(Some))
+                .await
+                .with_context(|| format!("route {input} to tracks"))?;
+            out.json(&json!({ "input": input, "tracks": track_numbers(enabled) }))?;
+        }
+        MixCommand::StreamTrack { track } => {
+            ensure_advanced_output(client).await?;
+            set_parameter(client, "AdvOut", "TrackIndex", &track.to_string()).await?;
+            out.json(&json!({ "stream_track": track }))?;
+        }
+        MixCommand::RecordTracks { tracks } => {
+            ensure_advanced_output(client).await?;
+            let mask = tracks
+                .iter()
+                .fold(0u8, |mask, track| mask | 1 << (track - 1));
+            set_parameter(client, "AdvOut", "RecTracks", &mask.to_string()).await?;
+            let record = std::array::from_fn(|i| mask & (1 << i) != 0);
+            out.json(&json!({ "record_tracks": track_numbers(record) }))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// The 1-based numbers of the enabled tracks.
+fn track_numbers(tracks: [bool; TRACKS]) -> Vec<usize> {
+    (1..=TRACKS)
+        .zip(tracks)
+        .filter(|&(_, on)| on)
+        .map(|(track, _)| track)
+        .collect()
+}
+
+fn format_tracks(tracks: [bool; TRACKS]) -> String {
+    let enabled: Vec<_> = track_numbers(tracks)
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    if enabled.is_empty() {
+        String::from("none")
+    } else {
+        enabled.join(",")
+    }
+}
+
+pub(crate) async fn parameter(
+    client: &Client,
+    category: &str,
+    name: &str,
+) -> anyhow::Result<Option<String>> {
+    let parameter = client
+        .profiles()
+        .parameter(category, name)
+        .await
+        .with_context(|| format!("get profile parameter {category}.{name}"))?;
+    Ok(parameter.value.or(parameter.default_value))
+}
+
+pub(crate) async fn set_parameter(
+    client: &Client,
+    category: &str,
+    name: &str,
+    value: &str,
+) -> anyhow::Result<()> {
+    client
+        .profiles()
+        .set_parameter(SetParameter {
+            category,
+            name,
+            value: Some(value),
+        })
+        .await
+        .with_context(|| format!("set profile parameter {category}.{name}"))
+}
+
+/// Per-track stream and recording selection is only available in advanced output mode.
+pub(crate) async fn is_advanced_output(client: &Client) -> anyhow::Result<bool> {
+    Ok(parameter(client, "Output", "Mode").await?.as_deref() == Some("Advanced"))
+}
+
+async fn ensure_advanced_output(client: &Client) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        is_advanced_output(client).await?,
+        "the current OBS profile uses simple output mode, which does not support selecting tracks; \
+         switch Settings -> Output -> Output Mode to Advanced"
+    );
+    Ok(())
+}
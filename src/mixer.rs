@@ -0,0 +1,299 @@
+use anyhow::Context;
+use crossterm::{
+    event::{Event as TermEvent, EventStream, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    terminal::{self, EnterAlternateScreen, LeaveAlternateScreen},
+    ExecutableCommand,
+};
+use futures_util::{future::join_all, StreamExt};
+use obws::{events::Event, requests::inputs::Volume, Client};
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::{Constraint, Layout, Rect},
+    style::{Style, Stylize},
+    text::Line,
+    widgets::{Block, LineGauge, Paragraph},
+    Frame, Terminal,
+};
+use std::time::Duration;
+use tokio::time::MissedTickBehavior;
+
+use crate::meter::{Meter, FLOOR_DB};
+
+/// How often the screen is redrawn.
+const FRAME: Duration = Duration::from_micros(1_000_000 / 30);
+
+/// How far the fader moves per key press, in dB, and with Page Up/Down.
+const FADER_STEP: f32 = 1.;
+const FADER_PAGE: f32 = 6.;
+
+/// The range of OBS' faders, in dB.
+const FADER_MIN: f32 = -100.;
+const FADER_MAX: f32 = 26.;
+
+/// How many lines each input takes up on screen.
+const CHANNEL_HEIGHT: u16 = 3;
+
+/// One audio input in the mixer.
+struct Channel {
+    name: String,
+    /// The fader position, in dB.
+    db: f32,
+    muted: bool,
+    meter: Meter,
+}
+
+struct Mixer {
+    channels: Vec<Channel>,
+    selected: usize,
+    /// The last thing that went wrong, shown at the bottom until the next key press.
+    status: Option<String>,
+}
+
+/// Restores the terminal when the mixer exits, including when it fails.
+struct Screen;
+
+impl Screen {
+    fn enter() -> anyhow::Result<Self> {
+        terminal::enable_raw_mode().context("enable raw terminal mode")?;
+        std::io::stdout()
+            .execute(EnterAlternateScreen)
+            .context("switch to alternate screen")?;
+        Ok(Screen)
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        let _ = std::io::stdout().execute(LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// Shows every audio input with its fader, mute state and level meter, and lets the arrow keys
+/// control them, until `q` is pressed.
+pub(crate) async fn run(client: &Client) -> anyhow::Result<()> {
+    let events = client.events().context("subscribe to OBS events")?;
+    let mut events = std::pin::pin!(events);
+    let mut mixer = Mixer {
+        channels: channels(client).await?,
+        selected: 0,
+        status: None,
+    };
+
+    let _screen = Screen::enter()?;
+    let mut terminal =
+        Terminal::new(CrosstermBackend::new(std::io::stdout())).context("set up terminal")?;
+    let mut keys = EventStream::new();
+    let mut frames = tokio::time::interval(FRAME);
+    frames.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    loop {
+        tokio::select! {
+            key = keys.next() => match key {
+                Some(Ok(TermEvent::Key(key))) if key.kind == KeyEventKind::Press => {
+                    if !mixer.press(client, key).await {
+                        break;
+                    }
+                }
+                Some(Ok(_)) => {}
+                Some(Err(e)) => return Err(e).context("read terminal input"),
+                None => break,
+            },
+            event = events.next() => match event {
+                Some(event) => mixer.apply(client, event).await,
+                None => anyhow::bail!("lost connection to OBS"),
+            },
+            _ = frames.tick() => {
+                terminal
+                    .draw(|frame| mixer.render(frame))
+                    .context("draw mixer")?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The inputs that have audio, along with their fader and mute state.
+async fn channels(client: &Client) -> anyhow::Result<Vec<Channel>> {
+    let inputs = client.inputs().list(None).await.context("list inputs")?;
+    let channels = join_all(inputs.into_iter().map(|input| async move {
+        // OBS refuses to report a volume for inputs without audio, which is how we tell them apart.
+        let volume = client.inputs().volume(&input.name).await.ok()?;
+        let muted = client.inputs().muted(&input.name).await.ok()?;
+        Some(Channel {
+            name: input.name,
+            db: volume.db,
+            muted,
+            meter: Meter::default(),
+        })
+    }))
+    .await;
+    Ok(channels.into_iter().flatten().collect())
+}
+
+impl Mixer {
+    /// Handles a key press, and returns whether to keep going.
+    async fn press(&mut self, client: &Client, key: KeyEvent) -> bool {
+        self.status = None;
+        let res = match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return false,
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.selected = self.selected.saturating_sub(1);
+                Ok(())
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.selected = (self.selected + 1).min(self.channels.len().saturating_sub(1));
+                Ok(())
+            }
+            KeyCode::Right | KeyCode::Char('l') => self.nudge(client, FADER_STEP).await,
+            KeyCode::Left | KeyCode::Char('h') => self.nudge(client, -FADER_STEP).await,
+            KeyCode::PageUp => self.nudge(client, FADER_PAGE).await,
+            KeyCode::PageDown => self.nudge(client, -FADER_PAGE).await,
+            KeyCode::Char('m') | KeyCode::Char(' ') => self.toggle_mute(client).await,
+            _ => Ok(()),
+        };
+        if let Err(e) = res {
+            self.status = Some(format!("{e:#}"));
+        }
+        true
+    }
+
+    /// Moves the fader of the selected input by `by` dB.
+    async fn nudge(&mut self, client: &Client, by: f32) -> anyhow::Result<()> {
+        let Some(channel) = self.channels.get_mut(self.selected) else {
+            return Ok(());
+        };
+        let db = (channel.db.max(FADER_MIN) + by).clamp(FADER_MIN, FADER_MAX);
+        client
+            .inputs()
+            .set_volume(&channel.name, Volume::Db(db))
+            .await
+            .with_context(|| format!("set volume of {}", channel.name))?;
+        channel.db = db;
+        Ok(())
+    }
+
+    async fn toggle_mute(&mut self, client: &Client) -> anyhow::Result<()> {
+        let Some(channel) = self.channels.get_mut(self.selected) else {
+            return Ok(());
+        };
+        channel.muted = client
+            .inputs()
+            .toggle_mute(&channel.name)
+            .await
+            .with_context(|| format!("toggle mute of {}", channel.name))?;
+        Ok(())
+    }
+
+    /// Keeps the mixer in sync with changes made in OBS or by others.
+    async fn apply(&mut self, client: &Client, event: Event) {
+        match event {
+            Event::InputVolumeMeters { inputs } => {
+                for levels in inputs {
+                    if let Some(channel) = self.channel(&levels.name) {
+                        channel.meter.update(&levels.levels);
+                    }
+                }
+            }
+            Event::InputVolumeChanged { name, db, .. } => {
+                if let Some(channel) = self.channel(&name) {
+                    channel.db = db as f32;
+                }
+            }
+            Event::InputMuteStateChanged { name, muted } => {
+                if let Some(channel) = self.channel(&name) {
+                    channel.muted = muted;
+                }
+            }
+            Event::InputCreated { .. }
+            | Event::InputRemoved { .. }
+            | Event::InputNameChanged { .. }
+            | Event::CurrentSceneCollectionChanged { .. } => {
+                let selected = self
+                    .channels
+                    .get(self.selected)
+                    .map(|channel| channel.name.clone());
+                match channels(client).await {
+                    Ok(channels) => self.channels = channels,
+                    Err(e) => self.status = Some(format!("{e:#}")),
+                }
+                self.selected = selected
+                    .and_then(|name| self.channels.iter().position(|c| c.name == name))
+                    .unwrap_or(0);
+            }
+            _ => {}
+        }
+    }
+
+    fn channel(&mut self, name: &str) -> Option<&mut Channel> {
+        self.channels
+            .iter_mut()
+            .find(|channel| channel.name == name)
+    }
+
+    fn render(&self, frame: &mut Frame) {
+        let [list, footer] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.size());
+
+        // Scroll just far enough to keep the selected input on screen.
+        let visible = usize::from(list.height / CHANNEL_HEIGHT).max(1);
+        let first = self.selected.saturating_sub(visible - 1);
+        let rows = Layout::vertical(vec![Constraint::Length(CHANNEL_HEIGHT); visible]).split(list);
+        for (i, row) in rows.iter().enumerate() {
+            let Some(channel) = self.channels.get(first + i) else {
+                break;
+            };
+            render_channel(frame, channel, *row, first + i == self.selected);
+        }
+        if self.channels.is_empty() {
+            frame.render_widget(Paragraph::new("No inputs with audio."), list);
+        }
+
+        let footer_text = match &self.status {
+            Some(status) => Line::from(status.as_str()).red(),
+            None => {
+                Line::from("↑/↓ select  ←/→ fader ±1 dB  PgUp/PgDn ±6 dB  m mute  q quit").dim()
+            }
+        };
+        frame.render_widget(Paragraph::new(footer_text), footer);
+    }
+}
+
+fn render_channel(frame: &mut Frame, channel: &Channel, area: Rect, selected: bool) {
+    let mut block = Block::bordered().title(channel.name.as_str());
+    if selected {
+        block = block.border_style(Style::new().yellow());
+    }
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let [meter, fader] =
+        Layout::horizontal([Constraint::Min(10), Constraint::Length(24)]).areas(inner);
+    let level = channel.meter.magnitude().unwrap_or(f32::NEG_INFINITY);
+    let ratio = ((level - FLOOR_DB) / -FLOOR_DB).clamp(0., 1.);
+    let mut gauge = LineGauge::default()
+        .ratio(f64::from(ratio))
+        .label(format!("{:>6} dB", show_db(level)));
+    gauge = if channel.meter.clipping() {
+        gauge.gauge_style(Style::new().red())
+    } else if channel.muted {
+        gauge.gauge_style(Style::new().dark_gray())
+    } else {
+        gauge.gauge_style(Style::new().green())
+    };
+    frame.render_widget(gauge, meter);
+
+    let mut state = vec![format!(" fader {:>6} dB", show_db(channel.db)).into()];
+    if channel.muted {
+        state.push(" MUTED".red().bold());
+    }
+    frame.render_widget(Paragraph::new(Line::from(state)), fader);
+}
+
+fn show_db(db: f32) -> String {
+    if db <= FADER_MIN {
+        String::from("-inf")
+    } else {
+        format!("{db:.1}")
+    }
+}
@@ -0,0 +1,316 @@
+use anyhow::Context;
+use futures_util::{stream::FuturesUnordered, StreamExt};
+use obws::{events::Event, Client};
+use rumqttc::{AsyncClient, LastWill, MqttOptions, Packet, QoS};
+use serde_json::json;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+use crate::output::Output;
+
+/// The port MQTT brokers listen on unless told otherwise.
+const DEFAULT_PORT: u16 = 1883;
+
+#[derive(Debug, Clone, clap::Args)]
+pub(crate) struct MqttArgs {
+    /// The broker to connect to, like `tcp://homeassistant.local:1883`.
+    #[arg(long, value_name = "URL")]
+    broker: String,
+
+    /// The topic that command and status topics go under.
+    #[arg(long, value_name = "TOPIC", default_value = "obs")]
+    prefix: String,
+
+    /// The client ID to connect to the broker with.
+    #[arg(long, value_name = "ID", default_value = "obs-do")]
+    client_id: String,
+
+    /// The user to log in to the broker as.
+    #[arg(long, value_name = "USER", requires = "password")]
+    username: Option<String>,
+
+    /// The password to log in to the broker with.
+    #[arg(long, value_name = "PASSWORD", requires = "username")]
+    password: Option<String>,
+}
+
+/// Connects OBS to an MQTT broker until interrupted.
+///
+/// A message on `<prefix>/<command>` runs that command with the arguments in the payload, given
+/// as a JSON array of strings or as words like on the command line; subcommands are further
+/// levels, as in `obs/input/set-volume`, and `obs/mute` with `Mic/Aux` mutes the microphone. The
+/// outcome, with whatever the command answers as under `--json`, is published to
+/// `<prefix>/result`. The live
+/// scene and whether OBS is streaming or recording are kept up to date as retained messages under
+/// `<prefix>/state/`, with `<prefix>/state/online` telling whether obs-do is connected, and every
+/// OBS event is published as JSON to `<prefix>/events`.
+pub(crate) async fn run(client: &Client, args: &MqttArgs, read_only: bool) -> anyhow::Result<()> {
+    let (host, port) = parse_broker(&args.broker)?;
+    let prefix = args.prefix.trim_end_matches('/');
+    let online = format!("{prefix}/state/online");
+    let mut options = MqttOptions::new(&args.client_id, host, port);
+    options
+        .set_keep_alive(Duration::from_secs(30))
+        .set_last_will(LastWill::new(&online, "false", QoS::AtLeastOnce, true));
+    if let (Some(username), Some(password)) = (&args.username, &args.password) {
+        options.set_credentials(username, password);
+    }
+
+    let events = client.events().context("subscribe to OBS events")?;
+    let mut events = std::pin::pin!(events);
+    let (mqtt, mut connection) = AsyncClient::new(options, 64);
+    // The connection has to be polled for anything to be sent, including while this waits to
+    // hand it a message, so it gets a task of its own.
+    let (incoming, mut messages) = mpsc::unbounded_channel();
+    let mut poller = tokio::spawn(async move {
+        loop {
+            match connection.poll().await {
+                Ok(rumqttc::Event::Incoming(packet)) => {
+                    if incoming.send(packet).is_err() {
+                        break;
+                    }
+                }
+                Ok(rumqttc::Event::Outgoing(_)) => {}
+                Err(e) => {
+                    // Polling again reconnects.
+                    tracing::warn!("Lost connection to the MQTT broker: {e}");
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                }
+            }
+        }
+    });
+    tracing::info!("Bridging OBS to the MQTT broker at {host}:{port}.");
+
+    let mut running = FuturesUnordered::new();
+    let res = loop {
+        tokio::select! {
+            packet = messages.recv() => match packet {
+                Some(Packet::ConnAck(_)) => {
+                    // Subscriptions don't survive a reconnect, and the state may have changed
+                    // while disconnected.
+                    mqtt.subscribe(format!("{prefix}/#"), QoS::AtLeastOnce)
+                        .await
+                        .context("subscribe to command topics")?;
+                    publish(&mqtt, &online, "true", true).await?;
+                    publish_state(client, &mqtt, prefix).await?;
+                }
+                Some(Packet::Publish(message)) => {
+                    let Some(words) = command(prefix, &message.topic) else {
+                        continue;
+                    };
+                    running.push(execute(
+                        client,
+                        message.topic,
+                        words,
+                        message.payload,
+                        read_only,
+                    ));
+                }
+                Some(_) => {}
+                None => break Err(anyhow::anyhow!("lost connection to the MQTT broker")),
+            },
+            Some((topic, result)) = running.next() => {
+                let mut result = result;
+                result["topic"] = json!(topic);
+                publish(&mqtt, &format!("{prefix}/result"), result.to_string(), false).await?;
+            }
+            event = events.next() => {
+                let Some(event) = event else {
+                    break Err(anyhow::anyhow!("lost connection to OBS"));
+                };
+                publish_event(&mqtt, prefix, &event).await?;
+            }
+            _ = tokio::signal::ctrl_c() => break Ok(()),
+        }
+    };
+
+    // Leaving on purpose doesn't trigger the last will, so say so before going.
+    let _ = publish(&mqtt, &online, "false", true).await;
+    let _ = mqtt.disconnect().await;
+    let _ = tokio::time::timeout(Duration::from_secs(1), &mut poller).await;
+    poller.abort();
+    res
+}
+
+/// The words of the command that a message on `topic` asks for, unless the topic is one that
+/// obs-do publishes to itself.
+fn command(prefix: &str, topic: &str) -> Option<Vec<String>> {
+    let rest = topic.strip_prefix(prefix)?.strip_prefix('/')?;
+    let words: Vec<_> = rest
+        .split('/')
+        .filter(|level| !level.is_empty())
+        .map(String::from)
+        .collect();
+    match words.first().map(String::as_str) {
+        None | Some("state" | "result" | "events") => None,
+        Some(_) => Some(words),
+    }
+}
+
+/// Runs the command sent on `topic`, and describes how that went.
+async fn execute(
+    client: &Client,
+    topic: String,
+    mut words: Vec<String>,
+    payload: impl AsRef<[u8]>,
+    read_only: bool,
+) -> (String, serde_json::Value) {
+    let res = async {
+        words.extend(crate::rest::arguments(payload.as_ref())?);
+        let cmd = crate::parse_fuzzy(client, words).await?;
+        anyhow::ensure!(cmd.is_servable(), "this command cannot be run over MQTT");
+        if read_only && cmd.is_mutating() {
+            crate::ensure_not_streaming(client).await?;
+        }
+        let (res, results) = Output::capture(crate::run(client, cmd, Output::new(true))).await;
+        res.map(|()| crate::output::collapse(results))
+    }
+    .await;
+    let result = match res {
+        Ok(result) => json!({ "ok": true, "result": result }),
+        Err(e) => {
+            tracing::warn!("Command on {topic} failed: {e:#}");
+            crate::output::error_json(&e)
+        }
+    };
+    (topic, result)
+}
+
+/// Publishes what OBS is doing right now, for whoever subscribes later to find.
+async fn publish_state(client: &Client, mqtt: &AsyncClient, prefix: &str) -> anyhow::Result<()> {
+    let scene = client
+        .scenes()
+        .current_program_scene()
+        .await
+        .context("get current scene")?;
+    let streaming = client
+        .streaming()
+        .status()
+        .await
+        .context("get stream status")?
+        .active;
+    let recording = client
+        .recording()
+        .status()
+        .await
+        .context("get recording status")?
+        .active;
+    publish(mqtt, &format!("{prefix}/state/scene"), scene, true).await?;
+    publish(
+        mqtt,
+        &format!("{prefix}/state/streaming"),
+        streaming.to_string(),
+        true,
+    )
+    .await?;
+    publish(
+        mqtt,
+        &format!("{prefix}/state/recording"),
+        recording.to_string(),
+        true,
+    )
+    .await
+}
+
+/// Publishes an OBS event, and the state it changed if that is kept under `<prefix>/state/`.
+async fn publish_event(mqtt: &AsyncClient, prefix: &str, event: &Event) -> anyhow::Result<()> {
+    let event_json = serde_json::to_string(event).context("serialize event")?;
+    publish(mqtt, &format!("{prefix}/events"), event_json, false).await?;
+    let (state, value) = match event {
+        Event::CurrentProgramSceneChanged { name } => ("scene", name.clone()),
+        Event::StreamStateChanged { active, .. } => ("streaming", active.to_string()),
+        Event::RecordStateChanged { active, .. } => ("recording", active.to_string()),
+        _ => return Ok(()),
+    };
+    publish(mqtt, &format!("{prefix}/state/{state}"), value, true).await
+}
+
+async fn publish(
+    mqtt: &AsyncClient,
+    topic: &str,
+    payload: impl Into<Vec<u8>>,
+    retain: bool,
+) -> anyhow::Result<()> {
+    mqtt.publish(topic, QoS::AtLeastOnce, retain, payload)
+        .await
+        .with_context(|| format!("publish to {topic}"))
+}
+
+/// Parses a broker address like `tcp://host:1883`, `mqtt://host` or `host:1883`.
+fn parse_broker(broker: &str) -> anyhow::Result<(&str, u16)> {
+    let address = broker
+        .strip_prefix("tcp://")
+        .or_else(|| broker.strip_prefix("mqtt://"))
+        .unwrap_or(broker)
+        .trim_end_matches('/');
+    anyhow::ensure!(
+        !address.contains("://"),
+        "unsupported broker `{broker}`; only tcp:// and mqtt:// are supported"
+    );
+    let (host, port) = match address.rsplit_once(':') {
+        Some((host, port)) => {
+            let port = port
+                .parse()
+                .with_context(|| format!("invalid port in broker `{broker}`"))?;
+            (host, port)
+        }
+        None => (address, DEFAULT_PORT),
+    };
+    anyhow::ensure!(!host.is_empty(), "no host in broker `{broker}`");
+    Ok((host, port))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn brokers() {
+        assert_eq!(
+            parse_broker("tcp://broker.local:1884").unwrap(),
+            ("broker.local", 1884)
+        );
+        assert_eq!(
+            parse_broker("mqtt://broker.local").unwrap(),
+            ("broker.local", DEFAULT_PORT)
+        );
+        assert_eq!(parse_broker("10.0.0.2:1883").unwrap(), ("10.0.0.2", 1883));
+        assert!(parse_broker("mqtts://broker.local").is_err());
+        assert!(parse_broker("tcp://broker.local:http").is_err());
+    }
+
+    #[test]
+    fn command_topics() {
+        assert_eq!(
+            command("obs", "obs/set-scene"),
+            Some(vec![String::from("set-scene")])
+        );
+        assert_eq!(
+            command("obs", "obs/input/set-volume"),
+            Some(vec![String::from("input"), String::from("set-volume")])
+        );
+        assert_eq!(command("obs", "obs/state/scene"), None);
+        assert_eq!(command("obs", "obs/result"), None);
+        assert_eq!(command("obs", "obsolete/set-scene"), None);
+    }
+
+    #[tokio::test]
+    async fn publishes_what_commands_answer() {
+        let (client, requests) = crate::fake_obs::connect(|_, _| Some(json!(null))).await;
+        let words = command("obs", "obs/mute").unwrap();
+        let (topic, result) =
+            execute(&client, String::from("obs/mute"), words, "Mic/Aux", false).await;
+        assert_eq!(topic, "obs/mute");
+        assert_eq!(
+            result,
+            json!({ "ok": true, "result": { "input": "Mic/Aux", "muted": true } })
+        );
+        assert_eq!(
+            *requests.lock().unwrap(),
+            [(
+                String::from("SetInputMute"),
+                json!({ "inputName": "Mic/Aux", "inputMuted": true })
+            )]
+        );
+    }
+}
@@ -0,0 +1,309 @@
+use anyhow::Context;
+use obws::Client;
+use std::{
+    ffi::OsStr,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use crate::complete::NameKind;
+
+/// Whether names given on the command line may be shortened or differ in case, as with `--fuzzy`.
+static FUZZY: AtomicBool = AtomicBool::new(false);
+
+/// How alike a name has to be to the one asked for to be suggested, from 0 to 1.
+const SIMILARITY: f64 = 0.7;
+
+/// The name among `names` that `name` is most likely a misspelling of, if any is close enough.
+pub(crate) fn closest<'a>(name: &str, names: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    let name = name.to_lowercase();
+    names
+        .into_iter()
+        .map(|candidate| {
+            let similarity = strsim::jaro_winkler(&name, &candidate.to_lowercase());
+            (similarity, candidate)
+        })
+        .filter(|(similarity, _)| *similarity > SIMILARITY)
+        .max_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, candidate)| candidate)
+}
+
+/// Makes [`fuzzy`] resolve names in the commands it is given.
+pub(crate) fn set_fuzzy(fuzzy: bool) {
+    FUZZY.store(fuzzy, Ordering::Relaxed);
+}
+
+/// With `--fuzzy`, replaces the names in the words of a command line with the names in OBS they
+/// match, going by `cmd` for which arguments take a scene, input or source name.
+///
+/// Names that match nothing are left for OBS to reject, so that the error suggests a name. Glob
+/// patterns, like those `fade-input` takes, already match loosely in their own way.
+pub(crate) async fn fuzzy<W>(
+    client: &Client,
+    cmd: clap::Command,
+    words: Vec<W>,
+) -> anyhow::Result<Vec<W>>
+where
+    W: AsRef<OsStr> + From<String>,
+{
+    if !FUZZY.load(Ordering::Relaxed) {
+        return Ok(words);
+    }
+    match_names(client, cmd, words).await
+}
+
+async fn match_names<W>(
+    client: &Client,
+    cmd: clap::Command,
+    mut words: Vec<W>,
+) -> anyhow::Result<Vec<W>>
+where
+    W: AsRef<OsStr> + From<String>,
+{
+    let mut lookup = Lookup {
+        client,
+        scenes: None,
+        inputs: None,
+    };
+    for value in crate::alias::values(cmd, &words) {
+        let Some(kind) = value.arg.as_ref().and_then(NameKind::of) else {
+            continue;
+        };
+        let Some(text) = words[value.word].as_ref().to_str() else {
+            continue;
+        };
+        let (prefix, name) = text.split_at(value.offset);
+        if name.contains(['*', '?']) {
+            continue;
+        }
+        let mut name = name.to_owned();
+        match kind {
+            NameKind::Scenes => lookup.scene(&mut name).await?,
+            NameKind::Inputs => lookup.input(&mut name).await?,
+            NameKind::Sources => lookup.source(&mut name).await?,
+            // Which filters there are depends on the source, which may not be matched yet.
+            NameKind::Filters => continue,
+        }
+        words[value.word] = W::from(format!("{prefix}{name}"));
+    }
+    Ok(words)
+}
+
+/// The scenes and inputs in OBS, listed the first time they are needed.
+struct Lookup<'a> {
+    client: &'a Client,
+    scenes: Option<Vec<String>>,
+    inputs: Option<Vec<String>>,
+}
+
+impl Lookup<'_> {
+    async fn scene(&mut self, name: &mut String) -> anyhow::Result<()> {
+        if self.scenes.is_none() {
+            self.scenes = Some(crate::cycle::scenes(self.client).await?.0);
+        }
+        resolve("scene", name, self.scenes.as_deref().unwrap_or_default())
+    }
+
+    async fn input(&mut self, name: &mut String) -> anyhow::Result<()> {
+        if self.inputs.is_none() {
+            self.inputs = Some(inputs(self.client).await?);
+        }
+        resolve("input", name, self.inputs.as_deref().unwrap_or_default())
+    }
+
+    /// Like [`Lookup::scene`], for a name that may be a scene or an input.
+    async fn source(&mut self, name: &mut String) -> anyhow::Result<()> {
+        if self.scenes.is_none() {
+            self.scenes = Some(crate::cycle::scenes(self.client).await?.0);
+        }
+        if self.inputs.is_none() {
+            self.inputs = Some(inputs(self.client).await?);
+        }
+        let sources: Vec<String> = [&self.scenes, &self.inputs]
+            .into_iter()
+            .flatten()
+            .flatten()
+            .cloned()
+            .collect();
+        resolve("source", name, &sources)
+    }
+}
+
+/// Replaces `name` with the one among `names` it matches exactly, ignoring case, as the start of
+/// the name, or anywhere in it, in that order of preference.
+///
+/// Fails if the first way that matches anything matches more than one name.
+fn resolve(kind: &str, name: &mut String, names: &[String]) -> anyhow::Result<()> {
+    if names.contains(name) {
+        return Ok(());
+    }
+    let needle = name.to_lowercase();
+    let ways: [&dyn Fn(&str) -> bool; 3] = [
+        &|candidate| candidate == needle,
+        &|candidate| candidate.starts_with(&needle),
+        &|candidate| candidate.contains(&needle),
+    ];
+    for matches in ways {
+        let found: Vec<&String> = names
+            .iter()
+            .filter(|candidate| matches(&candidate.to_lowercase()))
+            .collect();
+        match found[..] {
+            [] => continue,
+            [found] => {
+                tracing::debug!("{kind} '{name}' matched '{found}'");
+                *name = found.clone();
+                return Ok(());
+            }
+            _ => {
+                let found: Vec<&str> = found.iter().map(|found| found.as_str()).collect();
+                anyhow::bail!("'{name}' matches several {kind}s: {}", found.join(", "));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Explains a failed request about the scene `name`, by suggesting the scene that was probably
+/// meant if there is no scene by that name.
+pub(crate) async fn scene_error(client: &Client, name: &str, error: obws::Error) -> anyhow::Error {
+    match crate::cycle::scenes(client).await {
+        Ok((scenes, _)) => unknown("scene", name, &scenes).unwrap_or_else(|| error.into()),
+        Err(_) => error.into(),
+    }
+}
+
+/// Like [`scene_error`], for the input `name`.
+pub(crate) async fn input_error(client: &Client, name: &str, error: obws::Error) -> anyhow::Error {
+    match inputs(client).await {
+        Ok(inputs) => unknown("input", name, &inputs).unwrap_or_else(|| error.into()),
+        Err(_) => error.into(),
+    }
+}
+
+async fn inputs(client: &Client) -> anyhow::Result<Vec<String>> {
+    let inputs = client.inputs().list(None).await.context("list inputs")?;
+    Ok(inputs.into_iter().map(|input| input.name).collect())
+}
+
+/// The error for a `kind` named `name` that isn't among `names`, or `None` if it is.
+pub(crate) fn unknown(kind: &str, name: &str, names: &[String]) -> Option<anyhow::Error> {
+    if names.iter().any(|known| known == name) {
+        return None;
+    }
+    Some(match closest(name, names.iter().map(String::as_str)) {
+        Some(closest) => {
+            anyhow::anyhow!("there is no {kind} named '{name}'; did you mean '{closest}'?")
+        }
+        None => anyhow::anyhow!("there is no {kind} named '{name}'"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn suggests_close_names() {
+        let names = ["Mic/Aux", "Desktop Audio", "Webcam"];
+        assert_eq!(closest("mic/aux", names), Some("Mic/Aux"));
+        assert_eq!(closest("Mic/Aix", names), Some("Mic/Aux"));
+        assert_eq!(closest("desktop", names), Some("Desktop Audio"));
+        assert_eq!(closest("Game Capture", names), None);
+    }
+
+    fn fuzzy(name: &str) -> anyhow::Result<String> {
+        let names = ["Gaming — Main", "Gaming — Backup", "BRB", "Just Chatting"].map(String::from);
+        let mut name = name.to_owned();
+        resolve("scene", &mut name, &names)?;
+        Ok(name)
+    }
+
+    #[test]
+    fn fuzzy_matches() {
+        assert_eq!(fuzzy("BRB").unwrap(), "BRB");
+        assert_eq!(fuzzy("brb").unwrap(), "BRB");
+        assert_eq!(fuzzy("just").unwrap(), "Just Chatting");
+        assert_eq!(fuzzy("main").unwrap(), "Gaming — Main");
+        assert_eq!(fuzzy("Starting").unwrap(), "Starting");
+        let e = fuzzy("gaming").unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "'gaming' matches several scenes: Gaming — Main, Gaming — Backup"
+        );
+    }
+
+    #[test]
+    fn known_names_are_not_errors() {
+        let names = [String::from("Gaming"), String::from("BRB")];
+        assert!(unknown("scene", "BRB", &names).is_none());
+        let e = unknown("scene", "brb", &names).unwrap();
+        assert_eq!(
+            e.to_string(),
+            "there is no scene named 'brb'; did you mean 'BRB'?"
+        );
+    }
+
+    #[tokio::test]
+    async fn fuzzy_names_wherever_the_command_takes_one() {
+        let (client, _) = crate::fake_obs::connect(|kind, _| {
+            Some(match kind {
+                "GetSceneList" => serde_json::json!({
+                    "currentProgramSceneName": "BRB",
+                    "currentPreviewSceneName": null,
+                    "scenes": [
+                        { "sceneName": "Gaming — Main", "sceneIndex": 1 },
+                        { "sceneName": "BRB", "sceneIndex": 0 },
+                    ],
+                }),
+                "GetInputList" => serde_json::json!({
+                    "inputs": [
+                        {
+                            "inputName": "Mic/Aux",
+                            "inputKind": "pulse_input_capture",
+                            "unversionedInputKind": "pulse_input_capture",
+                        },
+                        {
+                            "inputName": "Desktop Audio",
+                            "inputKind": "pulse_output_capture",
+                            "unversionedInputKind": "pulse_output_capture",
+                        },
+                    ],
+                }),
+                _ => return None,
+            })
+        })
+        .await;
+        let matched = |line: &'static str| {
+            let client = &client;
+            async move {
+                let words = line.split(' ').map(String::from).collect();
+                match_names(client, crate::Chained::command(), words)
+                    .await
+                    .map(|words| words.join(" "))
+            }
+        };
+        assert_eq!(
+            matched("set-scene main").await.unwrap(),
+            "set-scene Gaming — Main"
+        );
+        assert_eq!(
+            matched("enforce --scene=brb --mute mic").await.unwrap(),
+            "enforce --scene=BRB --mute Mic/Aux"
+        );
+        // Glob patterns and values that aren't names are left alone.
+        assert_eq!(
+            matched("fade-input desktop Mic* -6dB").await.unwrap(),
+            "fade-input Desktop Audio Mic* -6dB"
+        );
+        assert_eq!(
+            matched("set-text mic main").await.unwrap(),
+            "set-text Mic/Aux main"
+        );
+        assert_eq!(
+            matched("set-scene Starting").await.unwrap(),
+            "set-scene Starting"
+        );
+        assert!(matched("toggle-mute a").await.is_err());
+    }
+}
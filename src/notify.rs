@@ -0,0 +1,72 @@
+use obws::events::{Event, OutputState};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Whether to raise desktop notifications, as with `--notify`.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Whether notifications come from OBS' events instead of from the commands that were run, as in
+/// the daemon, where both would otherwise notify about the same thing.
+static FROM_EVENTS: AtomicBool = AtomicBool::new(false);
+
+pub(crate) fn enable(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Makes [`event`] raise the notifications from now on, rather than [`outcome`].
+pub(crate) fn follow_events() {
+    FROM_EVENTS.store(true, Ordering::Relaxed);
+}
+
+/// Tells about the outcome of a command, like the stream starting.
+pub(crate) async fn outcome(summary: &str, body: &str) {
+    if !FROM_EVENTS.load(Ordering::Relaxed) {
+        show(summary, body).await;
+    }
+}
+
+/// Tells about what OBS reports if it matters, like a recording having been saved.
+pub(crate) async fn event(event: &Event) {
+    if !FROM_EVENTS.load(Ordering::Relaxed) {
+        return;
+    }
+    match event {
+        Event::StreamStateChanged {
+            state: OutputState::Started,
+            ..
+        } => show("Stream started", "").await,
+        Event::StreamStateChanged {
+            state: OutputState::Stopped,
+            ..
+        } => show("Stream stopped", "").await,
+        Event::RecordStateChanged {
+            state: OutputState::Stopped,
+            path,
+            ..
+        } => show("Recording saved", path.as_deref().unwrap_or_default()).await,
+        Event::ReplayBufferSaved { path } => {
+            show("Replay saved", &path.display().to_string()).await;
+        }
+        _ => {}
+    }
+}
+
+/// Tells that the connection to OBS was lost, which matters most when nobody is watching.
+pub(crate) async fn connection_lost() {
+    show("Lost connection to OBS", "").await;
+}
+
+/// Raises a desktop notification if they're enabled; failing to is only logged, since the
+/// notification is never the point.
+async fn show(summary: &str, body: &str) {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    let mut notification = notify_rust::Notification::new();
+    notification.appname("obs-do").summary(summary).body(body);
+    let shown = tokio::task::spawn_blocking(move || notification.show().map(drop)).await;
+    match shown {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => tracing::warn!("Could not show a notification: {e}"),
+        Err(e) => tracing::warn!("Could not show a notification: {e}"),
+    }
+}
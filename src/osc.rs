@@ -0,0 +1,472 @@
+use anyhow::Context;
+use futures_util::{stream::FuturesUnordered, StreamExt};
+use obws::{events::Event, Client};
+use std::net::SocketAddr;
+use tokio::net::UdpSocket;
+
+use crate::output::Output;
+
+/// The address that every message to and from obs-do starts with.
+const PREFIX: &str = "/obs";
+
+#[derive(Debug, Clone, clap::Args)]
+pub(crate) struct OscArgs {
+    /// The UDP address to receive OSC messages on.
+    ///
+    /// Use `0.0.0.0:9000` to accept messages from other devices, like a tablet running TouchOSC.
+    #[arg(long, value_name = "ADDR", default_value = "127.0.0.1:9000")]
+    listen: String,
+
+    /// Send the live scene, volumes, mutes and output states to this address as they change.
+    #[arg(long, value_name = "ADDR")]
+    feedback: Option<String>,
+}
+
+/// Runs commands for the OSC messages it receives, until interrupted.
+///
+/// `/obs/scene "Gaming"` switches scenes, `/obs/volume/<input> -6.0` sets a volume in dB (or as
+/// given, for a string like `"50%"`), and `/obs/mute/<input>` toggles a mute, or mutes for a
+/// true or non-zero argument and unmutes otherwise. Any other `/obs/<command>` runs that command,
+/// with the arguments of the message as its arguments.
+///
+/// With `--feedback`, changes to the scene, volumes and mutes are sent back at the same addresses,
+/// and whether OBS is streaming or recording at `/obs/streaming` and `/obs/recording`.
+pub(crate) async fn run(client: &Client, args: &OscArgs, read_only: bool) -> anyhow::Result<()> {
+    let socket = UdpSocket::bind(&args.listen)
+        .await
+        .with_context(|| format!("listen on {}", args.listen))?;
+    let feedback = match &args.feedback {
+        Some(feedback) => Some(
+            tokio::net::lookup_host(feedback)
+                .await
+                .with_context(|| format!("resolve {feedback}"))?
+                .next()
+                .with_context(|| format!("{feedback} has no address"))?,
+        ),
+        None => None,
+    };
+    let events = client.events().context("subscribe to OBS events")?;
+    let mut events = std::pin::pin!(events);
+    tracing::info!("Receiving OSC messages on {}.", args.listen);
+
+    if let Some(feedback) = feedback {
+        send_state(client, &socket, feedback).await?;
+    }
+    let mut buf = vec![0; 64 * 1024];
+    let mut running = FuturesUnordered::new();
+    loop {
+        tokio::select! {
+            received = socket.recv_from(&mut buf) => {
+                let (len, peer) = match received {
+                    Ok(received) => received,
+                    Err(e) => {
+                        tracing::warn!("Failed to receive OSC message: {e}");
+                        continue;
+                    }
+                };
+                let messages = match decode(&buf[..len]) {
+                    Ok(messages) => messages,
+                    Err(e) => {
+                        tracing::warn!("Ignoring malformed OSC packet from {peer}: {e:#}");
+                        continue;
+                    }
+                };
+                for message in messages {
+                    tracing::debug!("{peer}: {message:?}");
+                    running.push(execute(client, message, read_only));
+                }
+            }
+            Some(()) = running.next() => {}
+            event = events.next() => {
+                let Some(event) = event else {
+                    anyhow::bail!("lost connection to OBS");
+                };
+                if let (Some(feedback), Some(message)) = (feedback, self::feedback(&event)) {
+                    send(&socket, feedback, &message).await;
+                }
+            }
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+        }
+    }
+}
+
+/// What an OSC message asks of OBS.
+#[derive(Debug, PartialEq)]
+enum Action {
+    /// Runs a command, given as it would be on the command line.
+    Run(Vec<String>),
+    /// Mutes or unmutes an input; there's no command for that, only for toggling.
+    SetMute { input: String, muted: bool },
+}
+
+impl Action {
+    fn of(message: &Message) -> anyhow::Result<Self> {
+        let path = message
+            .address
+            .strip_prefix(PREFIX)
+            .and_then(|path| path.strip_prefix('/'))
+            .with_context(|| format!("{} is not under {PREFIX}/", message.address))?;
+        let words = |command: &str| {
+            std::iter::once(String::from(command))
+                .chain(message.args.iter().map(Arg::to_string))
+                .collect()
+        };
+        if path == "scene" {
+            return Ok(Action::Run(words("set-scene")));
+        }
+        if let Some(input) = path.strip_prefix("volume/") {
+            let volume = match message.args.as_slice() {
+                [Arg::Str(volume)] => volume.clone(),
+                [volume] => format!("{volume}dB"),
+                _ => anyhow::bail!("{} takes a single volume", message.address),
+            };
+            let words = vec![String::from("set-volume"), input.to_owned(), volume];
+            return Ok(Action::Run(words));
+        }
+        if let Some(input) = path.strip_prefix("mute/") {
+            return Ok(match message.args.first() {
+                None => Action::Run(vec![String::from("toggle-mute"), input.to_owned()]),
+                Some(muted) => Action::SetMute {
+                    input: input.to_owned(),
+                    muted: muted.is_truthy(),
+                },
+            });
+        }
+        let mut levels = path.split('/').filter(|level| !level.is_empty());
+        let command = levels.next().context("no command in the address")?;
+        let mut words: Vec<String> = words(command);
+        words.splice(1..1, levels.map(String::from));
+        Ok(Action::Run(words))
+    }
+}
+
+/// Does what an OSC message asks; failures are only logged, since OSC has no replies.
+async fn execute(client: &Client, message: Message, read_only: bool) {
+    let res = async {
+        match Action::of(&message)? {
+            Action::Run(words) => {
+                let cmd = crate::parse_fuzzy(client, words).await?;
+                anyhow::ensure!(cmd.is_servable(), "this command cannot be run over OSC");
+                if read_only && cmd.is_mutating() {
+                    crate::ensure_not_streaming(client).await?;
+                }
+                // There's no one to answer, so what the command prints is dropped rather than
+                // written to the bridge's own output.
+                Output::capture(crate::run(client, cmd, Output::new(true)))
+                    .await
+                    .0
+            }
+            Action::SetMute { input, muted } => {
+                if read_only {
+                    crate::ensure_not_streaming(client).await?;
+                }
+                client
+                    .inputs()
+                    .set_muted(&input, muted)
+                    .await
+                    .with_context(|| format!("set mute state of {input}"))
+            }
+        }
+    }
+    .await;
+    if let Err(e) = res {
+        tracing::warn!("{} failed: {e:#}", message.address);
+    }
+}
+
+/// The feedback message for an OBS event, if it changes something that feedback is sent about.
+fn feedback(event: &Event) -> Option<Message> {
+    let (address, arg) = match event {
+        Event::CurrentProgramSceneChanged { name } => {
+            (String::from("scene"), Arg::Str(name.clone()))
+        }
+        Event::InputVolumeChanged { name, db, .. } => (format!("volume/{name}"), Arg::Float(*db)),
+        Event::InputMuteStateChanged { name, muted } => {
+            (format!("mute/{name}"), Arg::Int(i64::from(*muted)))
+        }
+        Event::StreamStateChanged { active, .. } => {
+            (String::from("streaming"), Arg::Int(i64::from(*active)))
+        }
+        Event::RecordStateChanged { active, .. } => {
+            (String::from("recording"), Arg::Int(i64::from(*active)))
+        }
+        _ => return None,
+    };
+    Some(Message {
+        address: format!("{PREFIX}/{address}"),
+        args: vec![arg],
+    })
+}
+
+/// Sends the current scene and output states, so that a control surface starts out right.
+async fn send_state(client: &Client, socket: &UdpSocket, to: SocketAddr) -> anyhow::Result<()> {
+    let scene = client
+        .scenes()
+        .current_program_scene()
+        .await
+        .context("get current scene")?;
+    let streaming = client
+        .streaming()
+        .status()
+        .await
+        .context("get stream status")?
+        .active;
+    let recording = client
+        .recording()
+        .status()
+        .await
+        .context("get recording status")?
+        .active;
+    for (address, arg) in [
+        ("scene", Arg::Str(scene)),
+        ("streaming", Arg::Int(i64::from(streaming))),
+        ("recording", Arg::Int(i64::from(recording))),
+    ] {
+        let message = Message {
+            address: format!("{PREFIX}/{address}"),
+            args: vec![arg],
+        };
+        send(socket, to, &message).await;
+    }
+    Ok(())
+}
+
+/// Sends feedback; it's only logged if that fails, since the receiver may just not be up yet.
+async fn send(socket: &UdpSocket, to: SocketAddr, message: &Message) {
+    if let Err(e) = socket.send_to(&message.encode(), to).await {
+        tracing::warn!("Failed to send OSC feedback to {to}: {e}");
+    }
+}
+
+/// An OSC message.
+#[derive(Debug, Clone, PartialEq)]
+struct Message {
+    address: String,
+    args: Vec<Arg>,
+}
+
+/// An argument of an OSC message, with the various sizes of numbers folded together.
+#[derive(Debug, Clone, PartialEq)]
+enum Arg {
+    Int(i64),
+    Float(f64),
+    Str(String),
+    Bool(bool),
+}
+
+impl Arg {
+    fn is_truthy(&self) -> bool {
+        match self {
+            Arg::Int(n) => *n != 0,
+            Arg::Float(n) => *n != 0.,
+            Arg::Str(s) => matches!(s.as_str(), "true" | "on" | "1"),
+            Arg::Bool(b) => *b,
+        }
+    }
+}
+
+impl std::fmt::Display for Arg {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Arg::Int(n) => n.fmt(f),
+            Arg::Float(n) => n.fmt(f),
+            Arg::Str(s) => s.fmt(f),
+            Arg::Bool(b) => b.fmt(f),
+        }
+    }
+}
+
+/// Decodes an OSC packet, which is either a single message or a bundle of them.
+fn decode(packet: &[u8]) -> anyhow::Result<Vec<Message>> {
+    let mut reader = Reader(packet);
+    if packet.starts_with(b"#bundle\0") {
+        reader.string()?;
+        // Messages are run as they arrive, whatever time the bundle asks for.
+        reader.take(8)?;
+        let mut messages = Vec::new();
+        while !reader.0.is_empty() {
+            let len = reader.int()?;
+            let len = usize::try_from(len).context("negative bundle element size")?;
+            messages.extend(decode(reader.take(len)?)?);
+        }
+        return Ok(messages);
+    }
+
+    let address = reader.string()?;
+    anyhow::ensure!(address.starts_with('/'), "bad address `{address}`");
+    // Very old senders leave out the type tags when there are no arguments.
+    let tags = if reader.0.is_empty() {
+        String::from(",")
+    } else {
+        reader.string()?
+    };
+    let tags = tags.strip_prefix(',').context("missing type tags")?;
+    let mut args = Vec::new();
+    for tag in tags.chars() {
+        args.push(match tag {
+            'i' => Arg::Int(i64::from(reader.int()?)),
+            'h' => Arg::Int(i64::from_be_bytes(reader.array()?)),
+            'f' => Arg::Float(f64::from(f32::from_be_bytes(reader.array()?))),
+            'd' => Arg::Float(f64::from_be_bytes(reader.array()?)),
+            's' | 'S' => Arg::Str(reader.string()?),
+            'T' => Arg::Bool(true),
+            'F' => Arg::Bool(false),
+            'N' | 'I' => continue,
+            tag => anyhow::bail!("unsupported argument type `{tag}`"),
+        });
+    }
+    Ok(vec![Message { address, args }])
+}
+
+impl Message {
+    fn encode(&self) -> Vec<u8> {
+        let mut tags = String::from(",");
+        let mut args = Vec::new();
+        for arg in &self.args {
+            match arg {
+                Arg::Int(n) => match i32::try_from(*n) {
+                    Ok(n) => {
+                        tags.push('i');
+                        args.extend(n.to_be_bytes());
+                    }
+                    Err(_) => {
+                        tags.push('h');
+                        args.extend(n.to_be_bytes());
+                    }
+                },
+                // Receivers are much more likely to understand 32-bit floats.
+                Arg::Float(n) => {
+                    tags.push('f');
+                    args.extend((*n as f32).to_be_bytes());
+                }
+                Arg::Str(s) => {
+                    tags.push('s');
+                    push_string(&mut args, s);
+                }
+                Arg::Bool(true) => tags.push('T'),
+                Arg::Bool(false) => tags.push('F'),
+            }
+        }
+        let mut packet = Vec::new();
+        push_string(&mut packet, &self.address);
+        push_string(&mut packet, &tags);
+        packet.extend(args);
+        packet
+    }
+}
+
+/// Appends an OSC string: null-terminated, and padded to a multiple of four bytes.
+fn push_string(buf: &mut Vec<u8>, s: &str) {
+    buf.extend(s.as_bytes());
+    let padding = 4 - s.len() % 4;
+    buf.resize(buf.len() + padding, 0);
+}
+
+/// Reads the parts of an OSC packet in order.
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> anyhow::Result<&'a [u8]> {
+        anyhow::ensure!(self.0.len() >= len, "packet ends early");
+        let (taken, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(taken)
+    }
+
+    fn array<const N: usize>(&mut self) -> anyhow::Result<[u8; N]> {
+        Ok(self.take(N)?.try_into().expect("took N bytes"))
+    }
+
+    fn int(&mut self) -> anyhow::Result<i32> {
+        Ok(i32::from_be_bytes(self.array()?))
+    }
+
+    fn string(&mut self) -> anyhow::Result<String> {
+        let len = self
+            .0
+            .iter()
+            .position(|&b| b == 0)
+            .context("unterminated string")?;
+        let padded = (len / 4 + 1) * 4;
+        let bytes = self.take(padded.min(self.0.len()))?;
+        String::from_utf8(bytes[..len].to_vec()).context("string is not UTF-8")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(address: &str, args: Vec<Arg>) -> Message {
+        Message {
+            address: String::from(address),
+            args,
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let sent = message(
+            "/obs/volume/Mic/Aux",
+            vec![
+                Arg::Float(-6.),
+                Arg::Str(String::from("Gaming")),
+                Arg::Int(1),
+                Arg::Bool(true),
+            ],
+        );
+        let packet = sent.encode();
+        assert_eq!(packet.len() % 4, 0);
+        assert_eq!(decode(&packet).unwrap(), vec![sent]);
+    }
+
+    #[test]
+    fn bundles() {
+        let first = message("/obs/scene", vec![Arg::Str(String::from("Gaming"))]);
+        let second = message("/obs/mute/Mic", vec![]);
+        let mut packet = Vec::new();
+        push_string(&mut packet, "#bundle");
+        packet.extend([0, 0, 0, 0, 0, 0, 0, 1]);
+        for message in [&first, &second] {
+            let encoded = message.encode();
+            packet.extend(i32::try_from(encoded.len()).unwrap().to_be_bytes());
+            packet.extend(encoded);
+        }
+        assert_eq!(decode(&packet).unwrap(), vec![first, second]);
+        assert!(decode(&packet[..packet.len() - 2]).is_err());
+    }
+
+    #[test]
+    fn actions() {
+        let action = |address, args| Action::of(&message(address, args)).unwrap();
+        let words = |words: &[&str]| Action::Run(words.iter().map(|w| w.to_string()).collect());
+        assert_eq!(
+            action("/obs/scene", vec![Arg::Str(String::from("Gaming"))]),
+            words(&["set-scene", "Gaming"])
+        );
+        assert_eq!(
+            action("/obs/volume/Mic/Aux", vec![Arg::Float(-6.5)]),
+            words(&["set-volume", "Mic/Aux", "-6.5dB"])
+        );
+        assert_eq!(
+            action("/obs/volume/Music", vec![Arg::Str(String::from("50%"))]),
+            words(&["set-volume", "Music", "50%"])
+        );
+        assert_eq!(
+            action("/obs/mute/Mic", vec![]),
+            words(&["toggle-mute", "Mic"])
+        );
+        assert_eq!(
+            action("/obs/mute/Mic", vec![Arg::Float(0.)]),
+            Action::SetMute {
+                input: String::from("Mic"),
+                muted: false
+            }
+        );
+        assert_eq!(
+            action("/obs/input/set", vec![Arg::Str(String::from("Clock"))]),
+            words(&["input", "set", "Clock"])
+        );
+        assert!(Action::of(&message("/other/scene", vec![])).is_err());
+    }
+}
@@ -0,0 +1,85 @@
+use serde::Serialize;
+use std::{cell::RefCell, fmt::Display, future::Future};
+
+tokio::task_local! {
+    /// Results collected by [`Output::capture`] instead of being printed.
+    static CAPTURED: RefCell<Vec<serde_json::Value>>;
+}
+
+/// Where command results go.
+///
+/// Results are always written to stdout, either as human-readable text or, with `--json`, as one
+/// JSON document per line. Informational chatter is logged (to stderr) through `tracing` instead.
+#[derive(Debug, Clone, Copy)]
+pub struct Output {
+    json: bool,
+}
+
+impl Output {
+    pub fn new(json: bool) -> Self {
+        Self { json }
+    }
+
+    pub fn is_json(&self) -> bool {
+        self.json
+    }
+
+    /// Prints a result as `human` in text mode and as `value` in JSON mode.
+    pub fn result(&self, human: impl Display, value: &impl Serialize) -> anyhow::Result<()> {
+        if self.json {
+            self.json(value)
+        } else {
+            println!("{human}");
+            Ok(())
+        }
+    }
+
+    /// Prints a result that is only of interest to machines, so nothing is printed in text mode.
+    pub fn json(&self, value: &impl Serialize) -> anyhow::Result<()> {
+        if !self.json {
+            return Ok(());
+        }
+        let captured = CAPTURED.try_with(|captured| {
+            serde_json::to_value(value).map(|value| captured.borrow_mut().push(value))
+        });
+        match captured {
+            Ok(pushed) => pushed?,
+            Err(_) => println!("{}", serde_json::to_string(value)?),
+        }
+        Ok(())
+    }
+
+    /// Runs `f`, collecting the JSON results it would print instead of printing them.
+    pub(crate) async fn capture<T>(f: impl Future<Output = T>) -> (T, Vec<serde_json::Value>) {
+        CAPTURED
+            .scope(RefCell::new(Vec::new()), async {
+                let res = f.await;
+                (res, CAPTURED.with(RefCell::take))
+            })
+            .await
+    }
+
+    /// Reports a failed invocation as a JSON document on stdout.
+    pub(crate) fn error(&self, error: &anyhow::Error) {
+        println!("{}", error_json(error));
+    }
+}
+
+/// Folds the results collected by [`Output::capture`] into one value: the result itself if there
+/// is just one, `null` if there are none, and an array of them otherwise.
+pub(crate) fn collapse(results: Vec<serde_json::Value>) -> serde_json::Value {
+    match <[serde_json::Value; 1]>::try_from(results) {
+        Ok([result]) => result,
+        Err(results) if results.is_empty() => serde_json::Value::Null,
+        Err(results) => serde_json::Value::Array(results),
+    }
+}
+
+/// Describes an error, along with the chain of errors that caused it, as JSON.
+pub(crate) fn error_json(error: &anyhow::Error) -> serde_json::Value {
+    let causes: Vec<_> = error.chain().skip(1).map(|e| e.to_string()).collect();
+    serde_json::json!({
+        "error": error.to_string(),
+        "causes": causes,
+    })
+}
@@ -0,0 +1,226 @@
+use anyhow::Context;
+use clap::Subcommand;
+use obws::Client;
+use serde_json::json;
+
+use crate::output::Output;
+
+#[derive(Debug, Clone, Subcommand)]
+pub(crate) enum OutputCommand {
+    /// Lists OBS' outputs, including those added by plugins like NDI or a second RTMP stream,
+    /// and whether they are running.
+    List,
+    /// Starts an output, unless it is running already.
+    Start { name: String },
+    /// Stops an output, unless it isn't running.
+    Stop { name: String },
+    /// Prints whether an output is running, for how long, and how much it sent.
+    Status { name: String },
+}
+
+impl OutputCommand {
+    pub(crate) fn is_mutating(&self) -> bool {
+        match self {
+            OutputCommand::Start { .. } | OutputCommand::Stop { .. } => true,
+            OutputCommand::List | OutputCommand::Status { .. } => false,
+        }
+    }
+}
+
+pub(crate) async fn run(client: &Client, cmd: OutputCommand, out: Output) -> anyhow::Result<()> {
+    let outputs = client.outputs().list().await.context("list outputs")?;
+    let find = |name: &str| {
+        outputs
+            .iter()
+            .find(|output| output.name == name)
+            .ok_or_else(|| {
+                let names: Vec<_> = outputs.iter().map(|output| output.name.clone()).collect();
+                crate::names::unknown("output", name, &names)
+                    .unwrap_or_else(|| anyhow::anyhow!("there is no output named '{name}'"))
+            })
+    };
+    match cmd {
+        OutputCommand::List => {
+            let human = outputs
+                .iter()
+                .map(|output| {
+                    let state = if output.active { "active" } else { "inactive" };
+                    format!("{state:<8}  {}  ({})", output.name, output.kind)
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            out.result(human, &outputs)?;
+        }
+        OutputCommand::Start { name } => {
+            if !find(&name)?.active {
+                client
+                    .outputs()
+                    .start(&name)
+                    .await
+                    .with_context(|| format!("start output {name}"))?;
+            }
+            out.json(&json!({ "output": name, "active": true }))?;
+        }
+        OutputCommand::Stop { name } => {
+            if find(&name)?.active {
+                client
+                    .outputs()
+                    .stop(&name)
+                    .await
+                    .with_context(|| format!("stop output {name}"))?;
+            }
+            out.json(&json!({ "output": name, "active": false }))?;
+        }
+        OutputCommand::Status { name } => {
+            find(&name)?;
+            let status = client
+                .outputs()
+                .status(&name)
+                .await
+                .with_context(|| format!("get status of output {name}"))?;
+            let state = match (status.active, status.reconnecting) {
+                (false, _) => "inactive",
+                (true, true) => "reconnecting",
+                (true, false) => "active",
+            };
+            let mut human = String::from(state);
+            if status.active {
+                let secs = status.duration.whole_seconds().max(0);
+                human.push_str(&format!(
+                    " {}:{:02}:{:02}, {:.1} MB, {}/{} frames skipped",
+                    secs / 3600,
+                    secs / 60 % 60,
+                    secs % 60,
+                    status.bytes as f64 / 1e6,
+                    status.skipped_frames,
+                    status.total_frames
+                ));
+            }
+            out.result(
+                human,
+                &json!({
+                    "output": name,
+                    "state": state,
+                    "duration_secs": status.duration.as_seconds_f64(),
+                    "bytes": status.bytes,
+                    "congestion": status.congestion,
+                    "skipped_frames": status.skipped_frames,
+                    "total_frames": status.total_frames,
+                }),
+            )?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fake OBS with an NDI output that is running and a second stream that isn't.
+    async fn obs() -> (Client, crate::fake_obs::Requests) {
+        crate::fake_obs::connect(|kind, data| {
+            let output = |name: &str, kind: &str, active: bool| {
+                json!({
+                    "outputName": name, "outputKind": kind, "outputActive": active,
+                    "outputWidth": 1920, "outputHeight": 1080,
+                    "outputFlags": {
+                        "OBS_OUTPUT_AUDIO": true, "OBS_OUTPUT_VIDEO": true,
+                        "OBS_OUTPUT_ENCODED": kind != "ndi_output",
+                        "OBS_OUTPUT_MULTI_TRACK": false,
+                        "OBS_OUTPUT_SERVICE": kind != "ndi_output",
+                    },
+                })
+            };
+            Some(match kind {
+                "GetOutputList" => json!({ "outputs": [
+                    output("NDI", "ndi_output", true),
+                    output("Backup", "rtmp_output", false),
+                ]}),
+                "GetOutputStatus" => json!({
+                    "outputActive": data["outputName"] == "NDI", "outputReconnecting": false,
+                    "outputTimecode": "01:02:05.000", "outputDuration": 3_725_000,
+                    "outputCongestion": 0.0, "outputBytes": 2_500_000_000_u64,
+                    "outputSkippedFrames": 12, "outputTotalFrames": 2400,
+                }),
+                "StartOutput" | "StopOutput" => json!(null),
+                _ => return None,
+            })
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn lists_outputs() {
+        let (client, _) = obs().await;
+        let listed = crate::fake_obs::run_line(&client, "output list")
+            .await
+            .unwrap();
+        assert_eq!(listed[0]["outputName"], "NDI");
+        assert_eq!(listed[0]["outputActive"], true);
+        assert_eq!(listed[1]["outputName"], "Backup");
+        assert_eq!(listed[1]["outputKind"], "rtmp_output");
+    }
+
+    #[tokio::test]
+    async fn starts_and_stops_only_what_needs_it() {
+        let (client, requests) = obs().await;
+        for line in [
+            "output start NDI",
+            "output start Backup",
+            "output stop NDI",
+            "output stop Backup",
+        ] {
+            crate::fake_obs::run_line(&client, line).await.unwrap();
+        }
+        let err = crate::fake_obs::run_line(&client, "output start Bakup")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Backup"), "{err}");
+        let changed: Vec<_> = requests
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(kind, _)| kind != "GetOutputList")
+            .cloned()
+            .collect();
+        assert_eq!(
+            changed,
+            [
+                (
+                    String::from("StartOutput"),
+                    json!({ "outputName": "Backup" })
+                ),
+                (String::from("StopOutput"), json!({ "outputName": "NDI" })),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn reports_status() {
+        let (client, _) = obs().await;
+        assert_eq!(
+            crate::fake_obs::run_line(&client, "output status NDI")
+                .await
+                .unwrap(),
+            json!({
+                "output": "NDI",
+                "state": "active",
+                "duration_secs": 3725.0,
+                "bytes": 2_500_000_000_u64,
+                "congestion": 0.0,
+                "skipped_frames": 12,
+                "total_frames": 2400,
+            })
+        );
+        assert_eq!(
+            crate::fake_obs::run_line(&client, "output status Backup")
+                .await
+                .unwrap()["state"],
+            "inactive"
+        );
+        assert!(crate::fake_obs::run_line(&client, "output status Nope")
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,188 @@
+use anyhow::Context;
+use directories::ProjectDirs;
+use std::io::Read;
+use std::process::Stdio;
+
+use crate::config::{Config, Profile};
+
+/// The environment variable that can hold the OBS WebSocket password.
+pub(crate) const ENV_VAR: &str = "OBS_DO_PASSWORD";
+
+/// Name of the file within the obs-do configuration directory that can hold the password.
+pub(crate) const TOKEN_FILE: &str = "websocket-token";
+
+/// The password read from standard input, which can only be read once however many instances
+/// it is used for.
+static STDIN: tokio::sync::OnceCell<String> = tokio::sync::OnceCell::const_new();
+
+/// Looks up the OBS WebSocket password for `profile`, if there is one.
+///
+/// In order of preference, it comes from standard input with `from_stdin`, from the profile's
+/// own `password` or `password-command`, from `OBS_DO_PASSWORD`, from the `password` or
+/// `password-command` at the top level of the config file, from OBS' own configuration with
+/// `--auto-config`, from the OS keyring (see `obs-do auth set`), or from the `websocket-token`
+/// file.
+pub(crate) async fn resolve(
+    proj_dirs: &ProjectDirs,
+    config: &Config,
+    profile: Option<&str>,
+    from_stdin: bool,
+) -> anyhow::Result<Option<String>> {
+    if from_stdin {
+        let password = STDIN
+            .get_or_try_init(|| async {
+                // Just the first line, since the rest may be commands for `rpc` or `repl`.
+                let line = tokio::task::spawn_blocking(|| read_line(unbuffered_stdin()?))
+                    .await
+                    .context("read password from stdin")?
+                    .context("read password from stdin")?;
+                anyhow::Ok(first_line(&line))
+            })
+            .await?;
+        return Ok(Some(password.clone()));
+    }
+
+    if let Some(profile) = config.profile(profile)? {
+        if let Some(password) = from_profile(profile).await? {
+            return Ok(Some(password));
+        }
+    }
+
+    if let Some(password) = std::env::var_os(ENV_VAR).filter(|password| !password.is_empty()) {
+        let password = password
+            .into_string()
+            .map_err(|_| anyhow::anyhow!("{ENV_VAR} is not valid UTF-8"))?;
+        tracing::debug!("using the password from {ENV_VAR}");
+        return Ok(Some(password));
+    }
+
+    if let Some(password) = from_profile(&config.connection).await? {
+        return Ok(Some(password));
+    }
+
+    if let Some(password) = &config.discovered_password {
+        tracing::debug!("using the password from OBS' configuration");
+        return Ok(Some(password.clone()));
+    }
+
+    if let Some(password) = crate::auth::password().await {
+        tracing::debug!("using the password from the keyring");
+        return Ok(Some(password));
+    }
+
+    let file = proj_dirs.config_dir().join(TOKEN_FILE);
+    match tokio::fs::read_to_string(&file).await {
+        Ok(password) => Ok(Some(password.trim().to_string())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            tracing::info!("Attempting to connect to OBS in password-less mode.");
+            Ok(None)
+        }
+        Err(e) => Err(e).with_context(|| {
+            format!(
+                "Failed to read OBS WebSocket password file {}",
+                file.display()
+            )
+        }),
+    }
+}
+
+/// The password that `profile` gives, or the output of its `password-command`.
+async fn from_profile(profile: &Profile) -> anyhow::Result<Option<String>> {
+    if let Some(password) = &profile.password {
+        tracing::debug!("using the password from the config file");
+        return Ok(Some(password.clone()));
+    }
+    let Some(command) = &profile.password_command else {
+        return Ok(None);
+    };
+    tracing::debug!("getting the password from `{command}`");
+    let output = crate::watch::shell(command)
+        .stdin(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .output()
+        .await
+        .with_context(|| format!("spawn `{command}`"))?;
+    anyhow::ensure!(
+        output.status.success(),
+        "password command `{command}` failed: {}",
+        output.status
+    );
+    let password = String::from_utf8(output.stdout)
+        .with_context(|| format!("password command `{command}` printed invalid UTF-8"))?;
+    Ok(Some(first_line(&password)))
+}
+
+/// Stdin without the buffer that [`std::io::stdin`] reads through, so that reading from it takes
+/// no more input than is asked for.
+fn unbuffered_stdin() -> std::io::Result<std::fs::File> {
+    #[cfg(unix)]
+    let handle = std::os::fd::AsFd::as_fd(&std::io::stdin()).try_clone_to_owned()?;
+    #[cfg(windows)]
+    let handle =
+        std::os::windows::io::AsHandle::as_handle(&std::io::stdin()).try_clone_to_owned()?;
+    Ok(std::fs::File::from(handle))
+}
+
+/// Reads up to and including the next newline one byte at a time, leaving everything after it for
+/// whoever reads `input` next.
+pub(crate) fn read_line(mut input: impl Read) -> std::io::Result<String> {
+    let mut line = Vec::new();
+    let mut byte = [0];
+    loop {
+        match input.read(&mut byte) {
+            Ok(0) => break,
+            Ok(_) => {
+                line.push(byte[0]);
+                if byte[0] == b'\n' {
+                    break;
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    String::from_utf8(line).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
+/// The first line of what a password manager printed, which is where `pass` and friends put the
+/// password itself.
+fn first_line(output: &str) -> String {
+    output.lines().next().unwrap_or_default().to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn profiles_have_their_own_passwords() {
+        let config: Config = toml::from_str(
+            r#"
+            password = "top"
+
+            [profiles.studio]
+            password-command = "echo studio"
+
+            [profiles.backup]
+            host = "backup.local"
+            "#,
+        )
+        .unwrap();
+        let dirs = ProjectDirs::from("", "", "obs-do-test").unwrap();
+        let resolve = |profile| resolve(&dirs, &config, profile, false);
+        if std::env::var_os(ENV_VAR).is_none() {
+            assert_eq!(resolve(None).await.unwrap().as_deref(), Some("top"));
+            assert_eq!(
+                resolve(Some("backup")).await.unwrap().as_deref(),
+                Some("top")
+            );
+        }
+        if cfg!(unix) {
+            assert_eq!(
+                resolve(Some("studio")).await.unwrap().as_deref(),
+                Some("studio")
+            );
+        }
+        assert!(resolve(Some("missing")).await.is_err());
+    }
+}
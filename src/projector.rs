@@ -0,0 +1,212 @@
+use anyhow::Context;
+use clap::Subcommand;
+use obws::{
+    requests::ui::{
+        Location, OpenSourceProjector, OpenVideoMixProjector, QtGeometry, QtRect, VideoMixType,
+    },
+    responses::ui::Monitor,
+    Client,
+};
+use serde_json::json;
+
+use crate::output::Output;
+
+#[derive(Debug, Clone, Subcommand)]
+pub(crate) enum ProjectorCommand {
+    /// Opens a projector, like the multiview on a second display.
+    ///
+    /// Without `--monitor` or `--geometry`, the projector opens in a window wherever OBS puts it.
+    Open {
+        /// `multiview`, `preview`, `program`, or the name of a scene or source to project.
+        target: String,
+
+        /// Show the projector full screen on this monitor, as numbered by `projector monitors`.
+        #[arg(long, value_name = "N", conflicts_with = "geometry")]
+        monitor: Option<u32>,
+
+        /// Show the projector in a window of this size and position, like `1280x720+1920+0`.
+        #[arg(long, value_name = "WxH+X+Y")]
+        geometry: Option<String>,
+    },
+    /// Lists the monitors projectors can be shown on.
+    Monitors,
+}
+
+impl ProjectorCommand {
+    pub(crate) fn is_mutating(&self) -> bool {
+        match self {
+            ProjectorCommand::Open { .. } => true,
+            ProjectorCommand::Monitors => false,
+        }
+    }
+}
+
+pub(crate) async fn run(client: &Client, cmd: ProjectorCommand, out: Output) -> anyhow::Result<()> {
+    match cmd {
+        ProjectorCommand::Open {
+            target,
+            monitor,
+            geometry,
+        } => {
+            let geometry = match geometry {
+                Some(geometry) => {
+                    let geometry = parse_geometry(&geometry)?;
+                    let monitors = list_monitors(client).await?;
+                    Some(qt_geometry(geometry, &monitors))
+                }
+                None => None,
+            };
+            let location = match (monitor, geometry) {
+                (Some(monitor), _) => Some(Location::MonitorIndex(
+                    i32::try_from(monitor).context("monitor number is too large")?,
+                )),
+                (None, Some(geometry)) => Some(Location::ProjectorGeometry(geometry)),
+                (None, None) => None,
+            };
+            let mix = match target.as_str() {
+                "multiview" => Some(VideoMixType::Multiview),
+                "preview" => Some(VideoMixType::Preview),
+                "program" => Some(VideoMixType::Program),
+                _ => None,
+            };
+            match mix {
+                Some(r#type) => client
+                    .ui()
+                    .open_video_mix_projector(OpenVideoMixProjector { r#type, location })
+                    .await
+                    .with_context(|| format!("open {target} projector"))?,
+                None => client
+                    .ui()
+                    .open_source_projector(OpenSourceProjector {
+                        source: &target,
+                        location,
+                    })
+                    .await
+                    .with_context(|| format!("open projector of {target}"))?,
+            }
+            out.json(&json!({ "projector": target, "monitor": monitor }))?;
+        }
+        ProjectorCommand::Monitors => {
+            let monitors = list_monitors(client).await?;
+            let human = monitors
+                .iter()
+                .map(|monitor| {
+                    format!(
+                        "{:>2}  {}  {}x{} at {},{}",
+                        monitor.index,
+                        monitor.name,
+                        monitor.size.width,
+                        monitor.size.height,
+                        monitor.position.x,
+                        monitor.position.y
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            out.result(human, &monitors)?;
+        }
+    }
+    Ok(())
+}
+
+async fn list_monitors(client: &Client) -> anyhow::Result<Vec<Monitor>> {
+    client.ui().list_monitors().await.context("list monitors")
+}
+
+/// A window's size and position on the desktop, in pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Geometry {
+    width: i32,
+    height: i32,
+    x: i32,
+    y: i32,
+}
+
+/// Parses a geometry like `1280x720+1920+0`, as X11 programs take them.
+fn parse_geometry(geometry: &str) -> anyhow::Result<Geometry> {
+    let invalid = || anyhow::anyhow!("invalid geometry `{geometry}`, expected e.g. `1280x720+0+0`");
+    let (size, position) = geometry.split_once('+').ok_or_else(invalid)?;
+    let (width, height) = size.split_once('x').ok_or_else(invalid)?;
+    let (x, y) = position.split_once('+').ok_or_else(invalid)?;
+    let number = |n: &str| n.parse::<i32>().map_err(|_| invalid());
+    let geometry = Geometry {
+        width: number(width)?,
+        height: number(height)?,
+        x: number(x)?,
+        y: number(y)?,
+    };
+    anyhow::ensure!(geometry.width > 0 && geometry.height > 0, invalid());
+    Ok(geometry)
+}
+
+/// The window geometry OBS expects, which is what Qt's `QWidget::saveGeometry` produces.
+///
+/// Qt moves windows that don't fit on the screen they were saved on, so the screen is the
+/// monitor the window's top-left corner is on.
+fn qt_geometry(geometry: Geometry, monitors: &[Monitor]) -> QtGeometry {
+    let Geometry {
+        width,
+        height,
+        x,
+        y,
+    } = geometry;
+    let screen = monitors.iter().find(|monitor| {
+        let (left, top) = (i32::from(monitor.position.x), i32::from(monitor.position.y));
+        (left..left + i32::from(monitor.size.width)).contains(&x)
+            && (top..top + i32::from(monitor.size.height)).contains(&y)
+    });
+    QtGeometry {
+        screen_number: screen.map_or(QtGeometry::DEFAULT_SCREEN, |monitor| monitor.index as i32),
+        screen_width: screen.map_or(0, |monitor| i32::from(monitor.size.width)),
+        ..QtGeometry::new(QtRect {
+            left: x,
+            top: y,
+            right: x + width,
+            bottom: y + height,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn geometries() {
+        assert_eq!(
+            parse_geometry("1280x720+1920+0").unwrap(),
+            Geometry {
+                width: 1280,
+                height: 720,
+                x: 1920,
+                y: 0,
+            }
+        );
+        assert!(parse_geometry("1280x720").is_err());
+        assert!(parse_geometry("0x720+0+0").is_err());
+        assert!(parse_geometry("big+0+0").is_err());
+
+        let monitor: Monitor = serde_json::from_value(json!({
+            "monitorName": "Side",
+            "monitorIndex": 1,
+            "monitorWidth": 1280,
+            "monitorHeight": 1024,
+            "monitorPositionX": 1920,
+            "monitorPositionY": 0,
+        }))
+        .unwrap();
+        let on_side = qt_geometry(parse_geometry("100x50+2000+20").unwrap(), &[monitor]);
+        assert_eq!((on_side.screen_number, on_side.screen_width), (1, 1280));
+        assert_eq!(
+            (
+                on_side.rect.left,
+                on_side.rect.top,
+                on_side.rect.right,
+                on_side.rect.bottom
+            ),
+            (2000, 20, 2100, 70)
+        );
+        let elsewhere = qt_geometry(parse_geometry("100x50+0+0").unwrap(), &[]);
+        assert_eq!(elsewhere.screen_number, QtGeometry::DEFAULT_SCREEN);
+    }
+}
@@ -0,0 +1,27 @@
+use anyhow::Context;
+use std::io::{IsTerminal, Write};
+
+/// Fails with a hint at the non-interactive alternative unless stdin is a terminal.
+pub(crate) fn ensure_interactive(alternative: &str) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        std::io::stdin().is_terminal(),
+        "{alternative} when not running interactively"
+    );
+    Ok(())
+}
+
+/// Prints `question` to stderr and returns the line typed in reply, without the line break.
+pub(crate) async fn ask(question: impl Into<String>) -> anyhow::Result<String> {
+    let question = question.into();
+    tokio::task::spawn_blocking(move || -> std::io::Result<String> {
+        let mut stderr = std::io::stderr();
+        write!(stderr, "{question}")?;
+        stderr.flush()?;
+        let mut line = String::new();
+        std::io::stdin().read_line(&mut line)?;
+        Ok(line.trim_end_matches(['\r', '\n']).to_owned())
+    })
+    .await
+    .context("read answer")?
+    .context("read answer")
+}
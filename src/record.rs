@@ -0,0 +1,839 @@
+use anyhow::Context;
+use clap::Subcommand;
+use obws::{events::Event, responses::recording::RecordStatus, Client};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use crate::output::Output;
+
+/// How often the size of a recording is sampled while waiting for OBS to finish writing it.
+const SETTLE_POLL: Duration = Duration::from_millis(500);
+
+/// Why recordings can't be verified when OBS runs elsewhere.
+const REMOTE: &str = "OBS runs on another machine, so its recordings can't be looked at from here";
+
+/// How far the recorded duration may be off from the expected one, at the least.
+const MIN_DURATION_SLACK: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Subcommand)]
+pub(crate) enum RecordCommand {
+    /// Stops the recording, and prints the path of the recorded file.
+    Stop {
+        /// Then check that the file is usable, as with `record verify`, expecting it to be as
+        /// long as OBS says the recording was. This is skipped when OBS runs on another machine.
+        #[arg(long)]
+        verify: bool,
+
+        /// How long OBS may take to finish writing the file before `--verify` fails.
+        #[arg(
+            long,
+            value_name = "DURATION",
+            default_value = "10s",
+            requires = "verify"
+        )]
+        settle: String,
+    },
+    /// Checks that a recorded file is usable: that it exists, isn't empty, and that OBS has
+    /// finished writing it.
+    ///
+    /// If `ffprobe` is installed, also checks that the file can be read and, with `--duration`,
+    /// that it is about as long as expected. Nothing is checked when OBS runs on another machine,
+    /// since its recordings aren't here.
+    Verify {
+        /// The recorded file.
+        path: PathBuf,
+
+        /// How long the recording should be, like `1h30m`.
+        #[arg(long, value_name = "DURATION")]
+        duration: Option<String>,
+
+        /// How long OBS may take to finish writing the file before it counts as broken.
+        #[arg(long, value_name = "DURATION", default_value = "10s")]
+        settle: String,
+    },
+    /// Ends the current recording file and carries on recording into a new one.
+    ///
+    /// OBS only does this with automatic file splitting turned on in its output settings, which
+    /// requires advanced output mode.
+    Split,
+    /// Marks the current moment in the recording with a chapter.
+    ///
+    /// Only Hybrid MP4 recordings have chapters, which OBS supports since version 30.2. The
+    /// chapter is added through OBS' hotkey for it, which leaves it unnamed; naming chapters takes
+    /// a request that the obs-websocket client library doesn't offer yet.
+    Chapter,
+    /// Prints the path of the most recent recording, like for a script that remuxes it.
+    ///
+    /// OBS only tells where a recording went when it is stopped, so this is the last path obs-do
+    /// heard of that way, from `record stop`, `wait` or the daemon. Recordings stopped elsewhere
+    /// are found as the newest in the recording directory, for which OBS has to run on this
+    /// machine.
+    LastPath,
+    /// Prints the directory OBS saves recordings to.
+    GetDirectory,
+    /// Changes the directory OBS saves recordings to, like a folder for today's show.
+    SetDirectory {
+        path: PathBuf,
+
+        /// Create the directory first if it doesn't exist yet. This only works when OBS runs on
+        /// this machine.
+        #[arg(long)]
+        create: bool,
+    },
+    /// Changes how OBS names recordings, like `%CCYY-%MM-%DD %hh-%mm-%ss`.
+    ///
+    /// See the Advanced section of OBS' settings for the placeholders it understands.
+    SetFilenameFormat { format: String },
+}
+
+impl RecordCommand {
+    pub(crate) fn is_mutating(&self) -> bool {
+        match self {
+            RecordCommand::Stop { .. }
+            | RecordCommand::Split
+            | RecordCommand::Chapter
+            | RecordCommand::SetDirectory { .. }
+            | RecordCommand::SetFilenameFormat { .. } => true,
+            RecordCommand::Verify { .. }
+            | RecordCommand::LastPath
+            | RecordCommand::GetDirectory => false,
+        }
+    }
+}
+
+pub(crate) async fn run(client: &Client, cmd: RecordCommand, out: Output) -> anyhow::Result<()> {
+    match cmd {
+        RecordCommand::Stop { verify, settle } => {
+            let status = client
+                .recording()
+                .status()
+                .await
+                .context("get recording status")?;
+            anyhow::ensure!(status.active, "OBS is not recording");
+            let path = client.recording().stop().await.context("stop recording")?;
+            remember(&path, false).await;
+            crate::notify::outcome("Recording saved", &path).await;
+            if verify && !crate::obs_is_local() {
+                tracing::warn!("Not verifying {path}: {REMOTE}.");
+                out.result(
+                    &path,
+                    &json!({ "recording": false, "path": path, "skipped": REMOTE }),
+                )?;
+            } else if verify {
+                let expected = Duration::try_from(status.duration).unwrap_or_default();
+                let settle = crate::parse_duration(&settle)?;
+                self::verify(Path::new(&path), Some(expected), settle, out).await?;
+            } else {
+                out.result(&path, &json!({ "recording": false, "path": path }))?;
+            }
+        }
+        RecordCommand::Verify {
+            path,
+            duration,
+            settle,
+        } => {
+            let expected = duration.as_deref().map(crate::parse_duration).transpose()?;
+            let settle = crate::parse_duration(&settle)?;
+            if crate::obs_is_local() {
+                verify(&path, expected, settle, out).await?;
+            } else {
+                out.result(
+                    format!("{} was not checked: {REMOTE}", path.display()),
+                    &json!({ "path": path, "verified": false, "skipped": REMOTE }),
+                )?;
+            }
+        }
+        RecordCommand::Split => {
+            let splits = crate::mix::is_advanced_output(client).await?
+                && crate::mix::parameter(client, "AdvOut", "RecSplitFile")
+                    .await?
+                    .as_deref()
+                    == Some("true");
+            anyhow::ensure!(
+                splits,
+                "OBS only splits recordings with Settings -> Output -> Recording -> \
+                 Automatic File Splitting turned on, in advanced output mode"
+            );
+            press(client, "OBSBasic.SplitFile", "split recordings").await?;
+            out.json(&json!({ "split": true }))?;
+        }
+        RecordCommand::Chapter => {
+            press(client, "OBSBasic.AddChapterMarker", "add chapters").await?;
+            out.json(&json!({ "chapter": true }))?;
+        }
+        RecordCommand::LastPath => {
+            let directory = client
+                .config()
+                .record_directory()
+                .await
+                .context("get recording directory")?;
+            let path = recording_path(client, &directory, false)
+                .await?
+                .with_context(|| {
+                    format!("found no recordings in {directory}, which has to be on this machine")
+                })?;
+            out.result(path.display(), &json!({ "path": path }))?;
+        }
+        RecordCommand::GetDirectory => {
+            let directory = client
+                .config()
+                .record_directory()
+                .await
+                .context("get recording directory")?;
+            out.result(&directory, &json!({ "directory": directory }))?;
+        }
+        RecordCommand::SetDirectory { path, create } => {
+            // OBS doesn't know the directory obs-do runs in.
+            let path = crate::absolute(&path)?;
+            if create {
+                tokio::fs::create_dir_all(&path)
+                    .await
+                    .with_context(|| format!("create {}", path.display()))?;
+            }
+            let directory = path
+                .to_str()
+                .with_context(|| format!("{} is not valid UTF-8", path.display()))?;
+            // obws has no request for this, but the directory is a profile setting like any other,
+            // which OBS reads whenever a recording starts.
+            let (category, name) = if crate::mix::is_advanced_output(client).await? {
+                ("AdvOut", "RecFilePath")
+            } else {
+                ("SimpleOutput", "FilePath")
+            };
+            crate::mix::set_parameter(client, category, name, directory).await?;
+            out.json(&json!({ "directory": directory }))?;
+        }
+        RecordCommand::SetFilenameFormat { format } => {
+            crate::mix::set_parameter(client, "Output", "FilenameFormatting", &format).await?;
+            out.json(&json!({ "filename_format": format }))?;
+        }
+    }
+    Ok(())
+}
+
+/// Presses one of OBS' recording hotkeys, which is the only way to reach some features over the
+/// WebSocket protocol that obs-do speaks.
+async fn press(client: &Client, hotkey: &str, what: &str) -> anyhow::Result<()> {
+    let status = client
+        .recording()
+        .status()
+        .await
+        .context("get recording status")?;
+    anyhow::ensure!(status.active, "OBS is not recording");
+    // Pressing a hotkey that doesn't exist does nothing, without an error.
+    let hotkeys = client.hotkeys().list().await.context("list hotkeys")?;
+    anyhow::ensure!(
+        hotkeys.iter().any(|name| name == hotkey),
+        "this version of OBS cannot {what}"
+    );
+    client
+        .hotkeys()
+        .trigger_by_name(hotkey)
+        .await
+        .with_context(|| format!("press {hotkey}"))
+}
+
+/// Prints whether OBS is recording, for how long and how much, and where to.
+///
+/// OBS only tells which file it records to in its events, so the path is only known for sure
+/// when the daemon saw the recording start, and is a guess otherwise: the newest recording in the
+/// recording directory, if OBS runs on this machine.
+pub(crate) async fn status(client: &Client, out: Output) -> anyhow::Result<()> {
+    let status = client
+        .recording()
+        .status()
+        .await
+        .context("get recording status")?;
+    let directory = client
+        .config()
+        .record_directory()
+        .await
+        .context("get recording directory")?;
+    let path = if status.active {
+        recording_path(client, &directory, true).await?
+    } else {
+        None
+    };
+
+    let (human, json) = describe(&status, &directory, path.as_deref());
+    out.result(human, &json)
+}
+
+/// Describes the recording status for [`status`], both for people and as JSON.
+fn describe(
+    status: &RecordStatus,
+    directory: &str,
+    path: Option<&Path>,
+) -> (String, serde_json::Value) {
+    let state = match (status.active, status.paused) {
+        (false, _) => "stopped",
+        (true, true) => "paused",
+        (true, false) => "recording",
+    };
+    let secs = status.duration.whole_seconds().max(0);
+    let timecode = timecode(status.timecode);
+    let mut human = String::from(state);
+    if status.active {
+        human.push_str(&format!(
+            " {}:{:02}:{:02} (timecode {timecode}), {:.1} MB",
+            secs / 3600,
+            secs / 60 % 60,
+            secs % 60,
+            status.bytes as f64 / 1e6
+        ));
+        match path {
+            Some(path) => human.push_str(&format!(", {}", path.display())),
+            None => human.push_str(&format!(", in {directory}")),
+        }
+    }
+    let json = json!({
+        "state": state,
+        "active": status.active,
+        "paused": status.paused,
+        "duration_secs": status.duration.as_seconds_f64(),
+        "timecode": timecode,
+        "bytes": status.bytes,
+        "directory": directory,
+        "path": path,
+    });
+    (human, json)
+}
+
+/// Formats a recording's timecode the way OBS does, as `HH:MM:SS.mmm`.
+fn timecode(timecode: time::Duration) -> String {
+    let millis = timecode.whole_milliseconds().max(0);
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        millis % 1000
+    )
+}
+
+/// A recording OBS told about, as kept by [`remember`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Heard {
+    path: PathBuf,
+    /// Whether it was still being recorded to at the time.
+    active: bool,
+}
+
+/// Where the last recording OBS told about is kept.
+fn heard_path() -> anyhow::Result<PathBuf> {
+    Ok(crate::runtime_dir()?.join("last-recording.json"))
+}
+
+/// Keeps the path of a recording OBS told about, for `record last-path` and `record-status`.
+///
+/// Failing to is only logged, since those fall back to looking for the file.
+pub(crate) async fn remember(path: &str, active: bool) {
+    let heard = Heard {
+        path: PathBuf::from(path),
+        active,
+    };
+    let res = async {
+        let file = heard_path()?;
+        if let Some(dir) = file.parent() {
+            tokio::fs::create_dir_all(dir)
+                .await
+                .with_context(|| format!("create {}", dir.display()))?;
+        }
+        tokio::fs::write(&file, serde_json::to_vec(&heard)?)
+            .await
+            .with_context(|| format!("write {}", file.display()))
+    }
+    .await;
+    if let Err(e) = res {
+        tracing::debug!("could not remember the recording path: {e:#}");
+    }
+}
+
+/// Keeps the paths of recordings that OBS reports in its events.
+pub(crate) async fn follow(event: &Event) {
+    if let Event::RecordStateChanged {
+        active,
+        path: Some(path),
+        ..
+    } = event
+    {
+        remember(path, *active).await;
+    }
+}
+
+async fn heard() -> Option<Heard> {
+    let contents = tokio::fs::read(heard_path().ok()?).await.ok()?;
+    serde_json::from_slice(&contents).ok()
+}
+
+/// The file OBS records to if `active`, or last recorded to otherwise, if that can be found.
+async fn recording_path(
+    client: &Client,
+    directory: &str,
+    active: bool,
+) -> anyhow::Result<Option<PathBuf>> {
+    let heard = match heard().await {
+        Some(heard) => {
+            let modified = tokio::fs::metadata(&heard.path)
+                .await
+                .and_then(|metadata| metadata.modified())
+                .ok();
+            Some((heard, modified))
+        }
+        None => None,
+    };
+    // Looking through the directory only helps with a local OBS, but doesn't hurt otherwise.
+    let (extension, replay_prefix) = recording_names(client).await?;
+    let files = files(Path::new(directory)).await;
+    let found = newest_recording(files, &extension, &replay_prefix);
+    Ok(choose(heard, found, active))
+}
+
+/// Picks between the recording OBS last told about and the newest one found in the recording
+/// directory, if any, which is newer when a recording was stopped without obs-do noticing.
+fn choose(
+    heard: Option<(Heard, Option<SystemTime>)>,
+    found: Option<(SystemTime, PathBuf)>,
+    active: bool,
+) -> Option<PathBuf> {
+    // While recording, only a recording that was heard starting can be the current one.
+    let heard = heard.filter(|(heard, _)| heard.active || !active);
+    match (heard, found) {
+        (Some((_, Some(heard_at))), Some((found_at, found))) if found_at > heard_at => Some(found),
+        (Some((heard, _)), _) => Some(heard.path),
+        (None, found) => found.map(|(_, path)| path),
+    }
+}
+
+/// The extension of the files OBS records to, and what the names of replays start with, going
+/// by the current profile.
+async fn recording_names(client: &Client) -> anyhow::Result<(String, String)> {
+    let category = if crate::mix::is_advanced_output(client).await? {
+        "AdvOut"
+    } else {
+        "SimpleOutput"
+    };
+    // OBS 30 renamed the setting when it added formats.
+    let format = match crate::mix::parameter(client, category, "RecFormat2").await? {
+        Some(format) => format,
+        None => crate::mix::parameter(client, category, "RecFormat")
+            .await?
+            .unwrap_or_else(|| String::from("mkv")),
+    };
+    let extension = match format.as_str() {
+        "hybrid_mp4" | "fragmented_mp4" => "mp4",
+        "fragmented_mov" => "mov",
+        "mpegts" => "ts",
+        "hls" => "m3u8",
+        format => format,
+    };
+    // Both output modes name replays this way.
+    let replay_prefix = crate::mix::parameter(client, "SimpleOutput", "RecRBPrefix")
+        .await?
+        .unwrap_or_else(|| String::from("Replay"));
+    Ok((extension.to_owned(), replay_prefix))
+}
+
+/// The files directly in `dir` and when they were last modified, or none if it can't be read
+/// from here.
+async fn files(dir: &Path) -> Vec<(SystemTime, PathBuf)> {
+    let mut files = Vec::new();
+    let Ok(mut entries) = tokio::fs::read_dir(dir).await else {
+        return files;
+    };
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let Ok(metadata) = entry.metadata().await else {
+            continue;
+        };
+        if let (true, Ok(modified)) = (metadata.is_file(), metadata.modified()) {
+            files.push((modified, entry.path()));
+        }
+    }
+    files
+}
+
+/// The most recently modified of `files` with the `extension` of recordings, leaving out replays,
+/// whose names start with `replay_prefix`.
+fn newest_recording(
+    files: Vec<(SystemTime, PathBuf)>,
+    extension: &str,
+    replay_prefix: &str,
+) -> Option<(SystemTime, PathBuf)> {
+    files
+        .into_iter()
+        .filter(|(_, path)| {
+            let is_recording = path
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case(extension));
+            let is_replay = !replay_prefix.is_empty()
+                && path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.starts_with(replay_prefix));
+            is_recording && !is_replay
+        })
+        .max_by_key(|(modified, _)| *modified)
+}
+
+/// Fails loudly if the recording at `path` is unusable, and prints what was found otherwise.
+async fn verify(
+    path: &Path,
+    expected: Option<Duration>,
+    settle: Duration,
+    out: Output,
+) -> anyhow::Result<()> {
+    let bytes = settled_size(path, settle).await?;
+    anyhow::ensure!(bytes > 0, "recording {} is empty", path.display());
+
+    let duration = match probe_duration(path).await? {
+        Some(duration) => duration,
+        None => {
+            tracing::info!(
+                "ffprobe is not installed, so the recording's duration was not checked."
+            );
+            return out.result(
+                format!("{} looks fine ({bytes} bytes)", path.display()),
+                &json!({ "path": path, "bytes": bytes, "verified": true }),
+            );
+        }
+    };
+    if let Some(expected) = expected {
+        let slack = (expected / 20).max(MIN_DURATION_SLACK);
+        let off = duration.max(expected) - duration.min(expected);
+        anyhow::ensure!(
+            off <= slack,
+            "recording {} is {:.1}s long, but should be about {:.1}s",
+            path.display(),
+            duration.as_secs_f64(),
+            expected.as_secs_f64()
+        );
+    }
+    out.result(
+        format!(
+            "{} looks fine ({bytes} bytes, {:.1}s)",
+            path.display(),
+            duration.as_secs_f64()
+        ),
+        &json!({
+            "path": path,
+            "bytes": bytes,
+            "duration_secs": duration.as_secs_f64(),
+            "verified": true,
+        }),
+    )
+}
+
+/// The size of the file at `path` once it stops growing, waiting at most `settle` for that.
+async fn settled_size(path: &Path, settle: Duration) -> anyhow::Result<u64> {
+    let size = || async {
+        tokio::fs::metadata(path)
+            .await
+            .map(|metadata| metadata.len())
+            .with_context(|| format!("recording {} is missing", path.display()))
+    };
+    let deadline = tokio::time::Instant::now() + settle;
+    let mut last = size().await?;
+    loop {
+        tokio::time::sleep(SETTLE_POLL).await;
+        let now = size().await?;
+        if now == last {
+            return Ok(now);
+        }
+        anyhow::ensure!(
+            tokio::time::Instant::now() < deadline,
+            "recording {} is still growing {:.1}s after it was stopped",
+            path.display(),
+            settle.as_secs_f64()
+        );
+        last = now;
+    }
+}
+
+/// How long the media file at `path` is according to `ffprobe`, or `None` if it isn't installed.
+async fn probe_duration(path: &Path) -> anyhow::Result<Option<Duration>> {
+    let output = tokio::process::Command::new("ffprobe")
+        .args(["-v", "error", "-show_entries", "format=duration"])
+        .args(["-of", "default=noprint_wrappers=1:nokey=1"])
+        .arg(path)
+        .output()
+        .await;
+    let output = match output {
+        Ok(output) => output,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e).context("run ffprobe"),
+    };
+    anyhow::ensure!(
+        output.status.success(),
+        "ffprobe could not read recording {}: {}",
+        path.display(),
+        String::from_utf8_lossy(&output.stderr).trim()
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let secs: f64 = stdout
+        .trim()
+        .parse()
+        .with_context(|| format!("ffprobe found no duration in recording {}", path.display()))?;
+    anyhow::ensure!(
+        secs.is_finite() && secs >= 0.0,
+        "ffprobe found no duration in recording {}",
+        path.display()
+    );
+    Ok(Some(Duration::from_secs_f64(secs)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn statuses() {
+        let status = RecordStatus {
+            active: true,
+            paused: false,
+            timecode: time::Duration::milliseconds(3_723_040),
+            duration: time::Duration::seconds(3723),
+            bytes: 1_500_000,
+        };
+        let (human, json) = describe(&status, "/videos", Some(Path::new("/videos/show.mkv")));
+        assert_eq!(
+            human,
+            "recording 1:02:03 (timecode 01:02:03.040), 1.5 MB, /videos/show.mkv"
+        );
+        assert_eq!(json["state"], "recording");
+        assert_eq!(json["timecode"], "01:02:03.040");
+        assert_eq!(json["path"], "/videos/show.mkv");
+
+        let paused = RecordStatus {
+            paused: true,
+            ..status
+        };
+        let (human, json) = describe(&paused, "/videos", None);
+        assert_eq!(
+            human,
+            "paused 1:02:03 (timecode 01:02:03.040), 1.5 MB, in /videos"
+        );
+        assert_eq!(json["path"], serde_json::Value::Null);
+
+        let (human, json) = describe(&RecordStatus::default(), "/videos", None);
+        assert_eq!(human, "stopped");
+        assert_eq!(json["active"], false);
+        assert_eq!(json["timecode"], "00:00:00.000");
+    }
+
+    #[test]
+    fn finds_recordings() {
+        let at = |secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+        let files = vec![
+            (at(1), PathBuf::from("/videos/2026-10-16 19-00-00.mkv")),
+            (at(2), PathBuf::from("/videos/2026-10-16 20-00-00.MKV")),
+            (
+                at(3),
+                PathBuf::from("/videos/Replay 2026-10-16 20-30-00.mkv"),
+            ),
+            (at(4), PathBuf::from("/videos/notes.txt")),
+            (at(5), PathBuf::from("/videos/2026-10-16 18-00-00.mp4")),
+        ];
+        assert_eq!(
+            newest_recording(files.clone(), "mkv", "Replay"),
+            Some((at(2), PathBuf::from("/videos/2026-10-16 20-00-00.MKV")))
+        );
+        assert_eq!(
+            newest_recording(files.clone(), "mp4", "Replay").map(|(at, _)| at),
+            Some(at(5))
+        );
+        assert_eq!(newest_recording(files, "mov", "Replay"), None);
+    }
+
+    #[test]
+    fn prefers_what_obs_told() {
+        let at = |secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+        let heard = |active| Heard {
+            path: PathBuf::from("/videos/heard.mkv"),
+            active,
+        };
+        let found = Some((at(2), PathBuf::from("/videos/found.mkv")));
+        let heard_path = Some(PathBuf::from("/videos/heard.mkv"));
+        let found_path = Some(PathBuf::from("/videos/found.mkv"));
+
+        assert_eq!(
+            choose(Some((heard(false), Some(at(3)))), found.clone(), false),
+            heard_path
+        );
+        // OBS isn't on this machine, so its files can't be looked at.
+        assert_eq!(choose(Some((heard(false), None)), None, false), heard_path);
+        // A recording was stopped without obs-do hearing of it.
+        assert_eq!(
+            choose(Some((heard(false), Some(at(1)))), found.clone(), false),
+            found_path
+        );
+        // The recording that was heard of has ended, so it isn't the one going on now.
+        assert_eq!(
+            choose(Some((heard(false), Some(at(3)))), found.clone(), true),
+            found_path
+        );
+        assert_eq!(
+            choose(Some((heard(true), Some(at(3)))), found.clone(), true),
+            heard_path
+        );
+        assert_eq!(choose(None, found, false), found_path);
+        assert_eq!(choose(None, None, false), None);
+    }
+
+    #[tokio::test]
+    async fn names_recordings_by_profile() {
+        let (client, _) = crate::fake_obs::connect(|_, data| {
+            let value = match data["parameterName"].as_str() {
+                Some("Mode") => json!("Advanced"),
+                Some("RecFormat2") => json!("hybrid_mp4"),
+                _ => json!(null),
+            };
+            Some(json!({ "parameterValue": value, "defaultParameterValue": null }))
+        })
+        .await;
+        assert_eq!(
+            recording_names(&client).await.unwrap(),
+            (String::from("mp4"), String::from("Replay"))
+        );
+    }
+
+    /// A fake OBS that is recording if `recording`, in advanced output mode with file splitting
+    /// if `splits`, and has the hotkeys `hotkeys`.
+    async fn recording_obs(
+        recording: bool,
+        splits: bool,
+        hotkeys: &'static [&'static str],
+    ) -> (Client, crate::fake_obs::Requests) {
+        crate::fake_obs::connect(move |kind, data| {
+            Some(match kind {
+                "GetRecordStatus" => json!({
+                    "outputActive": recording,
+                    "outputPaused": false,
+                    "outputTimecode": "00:01:00.000",
+                    "outputDuration": 60000,
+                    "outputBytes": 1000,
+                }),
+                "GetProfileParameter" => {
+                    let value = match data["parameterName"].as_str() {
+                        Some("Mode") if splits => "Advanced",
+                        Some("Mode") => "Simple",
+                        _ => "true",
+                    };
+                    json!({ "parameterValue": value, "defaultParameterValue": null })
+                }
+                "GetHotkeyList" => json!({ "hotkeys": hotkeys }),
+                _ => json!(null),
+            })
+        })
+        .await
+    }
+
+    fn pressed(requests: &crate::fake_obs::Requests) -> Vec<String> {
+        requests
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(kind, _)| kind == "TriggerHotkeyByName")
+            .map(|(_, data)| data["hotkeyName"].as_str().unwrap().to_owned())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn splits_and_marks_chapters() {
+        let hotkeys = &["OBSBasic.SplitFile", "OBSBasic.AddChapterMarker"];
+        let (client, requests) = recording_obs(true, true, hotkeys).await;
+        let out = Output::new(true);
+        let (res, results) = Output::capture(async {
+            run(&client, RecordCommand::Split, out).await?;
+            run(&client, RecordCommand::Chapter, out).await
+        })
+        .await;
+        res.unwrap();
+        assert_eq!(
+            pressed(&requests),
+            ["OBSBasic.SplitFile", "OBSBasic.AddChapterMarker"]
+        );
+        assert_eq!(
+            results,
+            [json!({ "split": true }), json!({ "chapter": true })]
+        );
+    }
+
+    #[tokio::test]
+    async fn verifies_only_local_recordings() {
+        let (client, _) = recording_obs(false, false, &[]).await;
+        let verify = || RecordCommand::Verify {
+            path: PathBuf::from("/nonexistent/obs-do/recording.mkv"),
+            duration: None,
+            settle: String::from("0s"),
+        };
+        let out = Output::new(true);
+        let e = run(&client, verify(), out).await.unwrap_err();
+        assert!(e.to_string().contains("is missing"), "{e}");
+
+        let (res, results) = Output::capture(
+            crate::OBS_IS_LOCAL.scope(false, async { run(&client, verify(), out).await }),
+        )
+        .await;
+        res.unwrap();
+        assert_eq!(results[0]["verified"], false);
+        assert_eq!(results[0]["skipped"], REMOTE);
+    }
+
+    #[tokio::test]
+    async fn refuses_what_obs_cannot_do() {
+        let out = Output::new(true);
+        let (client, requests) = recording_obs(true, false, &["OBSBasic.SplitFile"]).await;
+        let e = run(&client, RecordCommand::Split, out).await.unwrap_err();
+        assert!(e.to_string().contains("Automatic File Splitting"), "{e}");
+        let e = run(&client, RecordCommand::Chapter, out).await.unwrap_err();
+        assert_eq!(e.to_string(), "this version of OBS cannot add chapters");
+        assert!(pressed(&requests).is_empty());
+
+        let (client, _) = recording_obs(false, true, &["OBSBasic.SplitFile"]).await;
+        let e = run(&client, RecordCommand::Split, out).await.unwrap_err();
+        assert_eq!(e.to_string(), "OBS is not recording");
+    }
+
+    #[tokio::test]
+    async fn sets_directory_for_the_output_mode() {
+        for (mode, category, name) in [
+            ("Simple", "SimpleOutput", "FilePath"),
+            ("Advanced", "AdvOut", "RecFilePath"),
+        ] {
+            let (client, requests) = crate::fake_obs::connect(move |kind, _| {
+                Some(match kind {
+                    "GetProfileParameter" => json!({
+                        "parameterValue": mode,
+                        "defaultParameterValue": "Simple",
+                    }),
+                    _ => json!(null),
+                })
+            })
+            .await;
+            let cmd = RecordCommand::SetDirectory {
+                path: PathBuf::from("shows"),
+                create: false,
+            };
+            let (res, results) = Output::capture(run(&client, cmd, Output::new(true))).await;
+            res.unwrap();
+            let directory = std::env::current_dir().unwrap().join("shows");
+            let directory = directory.to_str().unwrap();
+            assert_eq!(results, [json!({ "directory": directory })]);
+            assert_eq!(
+                requests.lock().unwrap().last().unwrap(),
+                &(
+                    String::from("SetProfileParameter"),
+                    json!({
+                        "parameterCategory": category,
+                        "parameterName": name,
+                        "parameterValue": directory,
+                    })
+                )
+            );
+        }
+    }
+}
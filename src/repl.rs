@@ -0,0 +1,249 @@
+use anyhow::Context;
+use clap::CommandFactory;
+use obws::Client;
+use rustyline::{
+    completion::{Completer, Pair},
+    error::ReadlineError,
+    highlight::Highlighter,
+    hint::Hinter,
+    history::DefaultHistory,
+    validate::Validator,
+    Editor, Helper,
+};
+use std::path::Path;
+
+use crate::{complete::NameKind, output::Output, CHAIN_SEPARATOR};
+
+/// Name of the history file, within the obs-do data directory.
+const HISTORY_NAME: &str = "history";
+
+/// Commands understood by the REPL itself rather than passed on to OBS.
+const BUILTINS: &[&str] = &["exit", "history", "quit"];
+
+type LineEditor = Editor<Names, DefaultHistory>;
+
+/// Reads commands from the terminal and runs them one line at a time until end of input.
+///
+/// Lines are remembered across sessions. `!!` repeats the previous line and `!n` the line
+/// numbered `n` by `history`, and either may be followed by more arguments. Ctrl-R searches the
+/// history, and Tab completes command names as well as the scenes and inputs in OBS.
+pub(crate) async fn run(client: &Client, read_only: bool, out: Output) -> anyhow::Result<()> {
+    let history = crate::project_dirs()?.data_dir().join(HISTORY_NAME);
+    let mut editor = LineEditor::new().context("set up line editor")?;
+    editor.set_helper(Some(Names::default()));
+    match editor.load_history(&history) {
+        Ok(()) => {}
+        Err(ReadlineError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => tracing::warn!("Failed to load history from {}: {e}", history.display()),
+    }
+
+    loop {
+        refresh_names(client, &mut editor).await;
+        let (returned, line) = tokio::task::spawn_blocking(move || {
+            let line = editor.readline("obs-do> ");
+            (editor, line)
+        })
+        .await
+        .context("read command")?;
+        editor = returned;
+        let line = match line {
+            Ok(line) => line,
+            // Ctrl-C abandons the line being typed, as in a shell.
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(e) => return Err(e).context("read command"),
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let line = match expand(&line, editor.history()) {
+            Ok(expanded) if expanded != line => {
+                eprintln!("{expanded}");
+                expanded
+            }
+            Ok(_) => line,
+            Err(e) => {
+                report(&e, out);
+                continue;
+            }
+        };
+        remember(&mut editor, &line, &history);
+
+        let mut words = line.split_whitespace();
+        match words.next() {
+            Some("exit" | "quit") => break,
+            Some("history") => {
+                let query = words.collect::<Vec<_>>().join(" ");
+                for (n, entry) in editor.history().iter().enumerate() {
+                    if entry.contains(&query) {
+                        println!("{:>5}  {entry}", n + 1);
+                    }
+                }
+                continue;
+            }
+            _ => {}
+        }
+        if let Err(e) = execute(client, &line, read_only, out).await {
+            report(&e, out);
+        }
+    }
+    Ok(())
+}
+
+/// Runs the commands on one line, which may be chained with `;` just like on the command line.
+async fn execute(client: &Client, line: &str, read_only: bool, out: Output) -> anyhow::Result<()> {
+    let words = shell_words::split(line).context("split command into arguments")?;
+    let mut cmds = Vec::new();
+    for words in words.split(|word| word == CHAIN_SEPARATOR) {
+        cmds.push(crate::parse_fuzzy(client, words.to_vec()).await?);
+    }
+    if read_only && cmds.iter().any(|cmd| cmd.is_mutating()) {
+        crate::ensure_not_streaming(client).await?;
+    }
+    for cmd in cmds {
+        crate::run(client, cmd, out).await?;
+    }
+    Ok(())
+}
+
+fn report(error: &anyhow::Error, out: Output) {
+    if out.is_json() {
+        out.error(error);
+    } else {
+        eprintln!("Error: {error:#}");
+    }
+}
+
+/// Replaces a leading `!!` or `!n` with the history entry it refers to.
+fn expand(line: &str, history: &DefaultHistory) -> anyhow::Result<String> {
+    let Some(reference) = line.strip_prefix('!') else {
+        return Ok(line.to_owned());
+    };
+    let (entry, rest) = if let Some(rest) = reference.strip_prefix('!') {
+        let entry = history.iter().next_back().context("the history is empty")?;
+        (entry, rest)
+    } else {
+        let digits = reference.find(|c: char| !c.is_ascii_digit());
+        let (n, rest) = reference.split_at(digits.unwrap_or(reference.len()));
+        let n: usize = n
+            .parse()
+            .with_context(|| format!("expected `!!` or `!n` at the start of `{line}`"))?;
+        let entry = n
+            .checked_sub(1)
+            .and_then(|n| history.iter().nth(n))
+            .with_context(|| format!("there is no history entry {n}"))?;
+        (entry, rest)
+    };
+    Ok(format!("{entry}{rest}"))
+}
+
+/// Adds `line` to the history, and to the history file so that it's kept even if we crash.
+fn remember(editor: &mut LineEditor, line: &str, history: &Path) {
+    let saved = editor
+        .add_history_entry(line)
+        .map_err(anyhow::Error::from)
+        .and_then(|_| {
+            if let Some(dir) = history.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            Ok(editor.append_history(history)?)
+        });
+    if let Err(e) = saved {
+        tracing::warn!("Failed to save history to {}: {e:#}", history.display());
+    }
+}
+
+/// Fetches the current scene and input names for completion, since they may have changed since
+/// the last command.
+async fn refresh_names(client: &Client, editor: &mut LineEditor) {
+    let (scenes, inputs) = tokio::join!(
+        crate::complete::names(client, NameKind::Scenes, None),
+        crate::complete::names(client, NameKind::Inputs, None)
+    );
+    let Some(names) = editor.helper_mut() else {
+        return;
+    };
+    names.obs.clear();
+    for names_of_kind in [scenes, inputs] {
+        match names_of_kind {
+            Ok(more) => names.obs.extend(more),
+            Err(e) => tracing::debug!("not completing names: {e:#}"),
+        }
+    }
+}
+
+/// Completes command names at the start of a command, and OBS names after that.
+#[derive(Debug)]
+struct Names {
+    commands: Vec<String>,
+    obs: Vec<String>,
+}
+
+impl Default for Names {
+    fn default() -> Self {
+        let mut commands: Vec<String> = crate::Args::command()
+            .get_subcommands()
+            .filter(|cmd| !cmd.is_hide_set())
+            .map(|cmd| cmd.get_name().to_owned())
+            .chain(BUILTINS.iter().map(|&builtin| builtin.to_owned()))
+            .collect();
+        commands.sort();
+        Names {
+            commands,
+            obs: Vec::new(),
+        }
+    }
+}
+
+impl Completer for Names {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &rustyline::Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        // Find where the word under the cursor starts, and whether it's the first of a command.
+        let mut start = 0;
+        let mut first = true;
+        let mut quote = None;
+        for (i, c) in line[..pos].char_indices() {
+            match (quote, c) {
+                (Some(q), c) if c == q => quote = None,
+                (Some(_), _) => {}
+                (None, '\'' | '"') => quote = Some(c),
+                (None, c) if c.is_whitespace() => {
+                    let word = line[start..i].trim();
+                    if !word.is_empty() {
+                        first = word == CHAIN_SEPARATOR;
+                    }
+                    start = i + c.len_utf8();
+                }
+                (None, _) => {}
+            }
+        }
+        let prefix = line[start..pos].trim_start_matches(['\'', '"']);
+        let candidates = if first { &self.commands } else { &self.obs };
+        let pairs = candidates
+            .iter()
+            .filter(|candidate| candidate.starts_with(prefix))
+            .map(|candidate| Pair {
+                display: candidate.clone(),
+                replacement: shell_words::quote(candidate).into_owned(),
+            })
+            .collect();
+        Ok((start, pairs))
+    }
+}
+
+impl Hinter for Names {
+    type Hint = String;
+}
+
+impl Highlighter for Names {}
+
+impl Validator for Names {}
+
+impl Helper for Names {}
@@ -0,0 +1,39 @@
+use anyhow::Context;
+use clap::Subcommand;
+use obws::Client;
+use serde_json::json;
+
+use crate::output::Output;
+
+#[derive(Debug, Clone, Subcommand)]
+pub(crate) enum ReplayBufferCommand {
+    /// Prints the path of the replay the buffer saved last, like for a script that uploads it.
+    LastPath,
+}
+
+impl ReplayBufferCommand {
+    pub(crate) fn is_mutating(&self) -> bool {
+        match self {
+            ReplayBufferCommand::LastPath => false,
+        }
+    }
+}
+
+pub(crate) async fn run(
+    client: &Client,
+    cmd: ReplayBufferCommand,
+    out: Output,
+) -> anyhow::Result<()> {
+    match cmd {
+        ReplayBufferCommand::LastPath => {
+            // OBS only answers this while the buffer is running.
+            let path = client
+                .replay_buffer()
+                .last_replay()
+                .await
+                .context("get last replay (the replay buffer has to be running)")?;
+            out.result(&path, &json!({ "path": path }))?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,275 @@
+use anyhow::Context;
+use futures_util::{stream::FuturesUnordered, StreamExt};
+use obws::Client;
+use serde_json::json;
+use std::net::SocketAddr;
+use tokio::{
+    io::BufReader,
+    net::{TcpListener, TcpStream},
+};
+
+use crate::{http::Request, output::Output};
+
+#[derive(Debug, Clone, clap::Args)]
+pub(crate) struct ServeArgs {
+    /// The address to serve commands on.
+    ///
+    /// Use `0.0.0.0:8765` to accept commands from other machines, along with `--token`.
+    #[arg(long, value_name = "ADDR", default_value = "127.0.0.1:8765")]
+    listen: String,
+
+    /// Only run commands from requests that carry `Authorization: Bearer <TOKEN>`.
+    #[arg(long, value_name = "TOKEN")]
+    token: Option<String>,
+}
+
+/// Keeps a connection to OBS open and runs the commands posted to it over HTTP, until
+/// interrupted.
+///
+/// `POST /set-scene` runs `set-scene` with the arguments in the body, given either as a JSON array
+/// of strings or as words like on the command line; subcommands go in the path, as in
+/// `POST /input/set-volume`. The answer is `{"ok":true,"result":...}` with what the command
+/// would print with `--json`, or the error as JSON. `GET /status` tells
+/// whether OBS is streaming or recording and which scene is live.
+pub(crate) async fn run(client: &Client, args: &ServeArgs, read_only: bool) -> anyhow::Result<()> {
+    let listener = TcpListener::bind(&args.listen)
+        .await
+        .with_context(|| format!("listen on {}", args.listen))?;
+    let local = listener.local_addr().context("get listening address")?;
+    if args.token.is_none() && !local.ip().is_loopback() {
+        tracing::warn!("Anyone who can reach {local} can control OBS; consider setting --token.");
+    }
+    tracing::info!("Serving commands on http://{local}/.");
+
+    let mut requests = FuturesUnordered::new();
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, peer)) => {
+                    requests.push(serve(stream, peer, client, args, read_only));
+                }
+                Err(e) => tracing::warn!("Failed to accept connection: {e}"),
+            },
+            Some(res) = requests.next() => {
+                if let Err(e) = res {
+                    tracing::warn!("Request failed: {e:#}");
+                }
+            }
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+        }
+    }
+}
+
+async fn serve(
+    stream: TcpStream,
+    peer: SocketAddr,
+    client: &Client,
+    args: &ServeArgs,
+    read_only: bool,
+) -> anyhow::Result<()> {
+    let mut stream = BufReader::new(stream);
+    let (status, body) = match crate::http::read_request(&mut stream).await {
+        Ok(request) => {
+            tracing::debug!("{peer}: {} {}", request.method, request.path);
+            handle(&request, client, args, read_only).await
+        }
+        Err(e) => ("400 Bad Request", crate::output::error_json(&e)),
+    };
+    crate::http::respond(
+        stream.get_mut(),
+        status,
+        "application/json",
+        &body.to_string(),
+    )
+    .await
+}
+
+async fn handle(
+    request: &Request,
+    client: &Client,
+    args: &ServeArgs,
+    read_only: bool,
+) -> (&'static str, serde_json::Value) {
+    if let Some(token) = &args.token {
+        if !authorized(request, token) {
+            return (
+                "401 Unauthorized",
+                json!({ "error": "missing or wrong token" }),
+            );
+        }
+    }
+    match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/status") => match status(client).await {
+            Ok(status) => ("200 OK", status),
+            Err(e) => ("503 Service Unavailable", crate::output::error_json(&e)),
+        },
+        ("POST", _) => {
+            let cmd = async { crate::parse_fuzzy(client, words(request)?).await }.await;
+            let cmd = match cmd {
+                Ok(cmd) => cmd,
+                Err(e) => return ("400 Bad Request", crate::output::error_json(&e)),
+            };
+            if !cmd.is_servable() {
+                let e = anyhow::anyhow!("this command cannot be run over HTTP");
+                return ("400 Bad Request", crate::output::error_json(&e));
+            }
+            if read_only && cmd.is_mutating() {
+                if let Err(e) = crate::ensure_not_streaming(client).await {
+                    return ("403 Forbidden", crate::output::error_json(&e));
+                }
+            }
+            match Output::capture(crate::run(client, cmd, Output::new(true))).await {
+                (Ok(()), results) => (
+                    "200 OK",
+                    json!({ "ok": true, "result": crate::output::collapse(results) }),
+                ),
+                (Err(e), _) => {
+                    tracing::warn!("Command failed: {e:#}");
+                    ("500 Internal Server Error", crate::output::error_json(&e))
+                }
+            }
+        }
+        (_, "/status") => ("405 Method Not Allowed", json!({ "error": "use GET" })),
+        _ => ("405 Method Not Allowed", json!({ "error": "use POST" })),
+    }
+}
+
+/// The words of the command a request asks for: the path, then the arguments in the body.
+fn words(request: &Request) -> anyhow::Result<Vec<String>> {
+    let path = request.path.split('?').next().unwrap_or_default();
+    let mut words = path
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| {
+            percent_decode(segment).with_context(|| format!("invalid path segment `{segment}`"))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    anyhow::ensure!(!words.is_empty(), "no command given in the path");
+
+    words.extend(arguments(&request.body)?);
+    Ok(words)
+}
+
+/// Parses the arguments sent along with a command, given either as a JSON array of strings or
+/// as words like on the command line.
+pub(crate) fn arguments(body: &[u8]) -> anyhow::Result<Vec<String>> {
+    let body = std::str::from_utf8(body).context("arguments are not UTF-8")?;
+    let body = body.trim();
+    if body.starts_with('[') {
+        serde_json::from_str(body).context("arguments are not a JSON array of strings")
+    } else {
+        shell_words::split(body).context("split arguments")
+    }
+}
+
+/// Decodes `%XX` escapes in a path segment.
+fn percent_decode(segment: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(segment.len());
+    let mut rest = segment.as_bytes();
+    while let Some((&byte, after)) = rest.split_first() {
+        if byte == b'%' {
+            let hex = std::str::from_utf8(after.get(..2)?).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+            rest = &after[2..];
+        } else {
+            bytes.push(byte);
+            rest = after;
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+/// Checks the request's bearer token, taking as long to reject a token that is nearly right as
+/// one that is all wrong.
+fn authorized(request: &Request, token: &str) -> bool {
+    let Some(given) = request
+        .header("authorization")
+        .and_then(|value| value.strip_prefix("Bearer "))
+    else {
+        return false;
+    };
+    given.len() == token.len()
+        && given
+            .bytes()
+            .zip(token.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// What OBS is up to, as answered to `GET /status`.
+async fn status(client: &Client) -> anyhow::Result<serde_json::Value> {
+    let stream = client
+        .streaming()
+        .status()
+        .await
+        .context("get stream status")?;
+    let record = client
+        .recording()
+        .status()
+        .await
+        .context("get recording status")?;
+    let scene = client
+        .scenes()
+        .current_program_scene()
+        .await
+        .context("get current scene")?;
+    Ok(json!({
+        "scene": scene,
+        "streaming": {
+            "active": stream.active,
+            "reconnecting": stream.reconnecting,
+            "duration_secs": stream.duration.whole_seconds(),
+        },
+        "recording": {
+            "active": record.active,
+            "paused": record.paused,
+            "duration_secs": record.duration.whole_seconds(),
+        },
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(path: &str, headers: &[(&str, &str)], body: &str) -> Request {
+        Request {
+            method: String::from("POST"),
+            path: String::from(path),
+            headers: headers
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            body: body.as_bytes().to_vec(),
+        }
+    }
+
+    #[test]
+    fn words_from_path_and_body() {
+        let words = |path, body| super::words(&request(path, &[], body)).unwrap();
+        assert_eq!(
+            words("/set-scene", r#"["Be Right Back"]"#),
+            ["set-scene", "Be Right Back"]
+        );
+        assert_eq!(
+            words("/toggle-mute", "'Mic/Aux'"),
+            ["toggle-mute", "Mic/Aux"]
+        );
+        assert_eq!(
+            words("/set-scene/Be%20Right%20Back", ""),
+            ["set-scene", "Be Right Back"]
+        );
+        assert!(super::words(&request("/", &[], "")).is_err());
+        assert!(super::words(&request("/x%2", &[], "")).is_err());
+    }
+
+    #[test]
+    fn bearer_tokens() {
+        let with = |value| request("/", &[("authorization", value)], "");
+        assert!(authorized(&with("Bearer secret"), "secret"));
+        assert!(!authorized(&with("Bearer secreT"), "secret"));
+        assert!(!authorized(&with("Bearer secret2"), "secret"));
+        assert!(!authorized(&with("secret"), "secret"));
+        assert!(!authorized(&request("/", &[], ""), "secret"));
+    }
+}
@@ -0,0 +1,63 @@
+use anyhow::Context;
+use obws::Client;
+use tokio::time::MissedTickBehavior;
+
+#[derive(Debug, Clone, clap::Args)]
+pub(crate) struct RotateArgs {
+    /// How long each scene stays live, like `30s` or `2m`.
+    #[arg(long, value_name = "DURATION", default_value = "30s")]
+    interval: String,
+
+    /// The scenes to go through, in order, separated by commas.
+    ///
+    /// Defaults to every scene, from the top of OBS' scene list down.
+    #[arg(long, value_name = "SCENES", value_delimiter = ',')]
+    scenes: Vec<String>,
+}
+
+/// Switches to the next scene of the rotation every interval, until interrupted.
+///
+/// If the live scene is part of the rotation, it gets a full interval before the rotation
+/// carries on after it; otherwise the rotation starts right away with its first scene.
+pub(crate) async fn run(client: &Client, args: &RotateArgs) -> anyhow::Result<()> {
+    let interval = crate::parse_duration(&args.interval)?;
+    anyhow::ensure!(!interval.is_zero(), "the interval must be longer than zero");
+
+    let (all, current) = crate::cycle::scenes(client).await?;
+    let scenes = if args.scenes.is_empty() {
+        all
+    } else {
+        for scene in &args.scenes {
+            anyhow::ensure!(all.contains(scene), "there is no scene named {scene}");
+        }
+        args.scenes.clone()
+    };
+    anyhow::ensure!(!scenes.is_empty(), "there are no scenes to rotate through");
+
+    let mut ticks = tokio::time::interval(interval);
+    ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let live = current.and_then(|current| scenes.iter().position(|scene| *scene == current));
+    let mut next = match live {
+        Some(i) => {
+            // The first tick is immediate, and the live scene should get its turn first.
+            ticks.tick().await;
+            (i + 1) % scenes.len()
+        }
+        None => 0,
+    };
+
+    loop {
+        tokio::select! {
+            _ = ticks.tick() => {}
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+        }
+        let scene = &scenes[next];
+        client
+            .scenes()
+            .set_current_program_scene(scene)
+            .await
+            .with_context(|| format!("set-scene {scene}"))?;
+        tracing::info!("Switched to {scene}.");
+        next = (next + 1) % scenes.len();
+    }
+}
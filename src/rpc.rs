@@ -0,0 +1,221 @@
+use anyhow::Context;
+use futures_util::{stream::FuturesUnordered, StreamExt};
+use obws::Client;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+
+use crate::output::Output;
+
+/// Error codes defined by JSON-RPC 2.0.
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const INVALID_PARAMS: i64 = -32602;
+/// The code for a command that was understood but failed, from the range left to servers.
+const COMMAND_FAILED: i64 = -32000;
+
+/// A JSON-RPC request, or a notification if it has no `id`.
+#[derive(Debug, Deserialize)]
+struct Request {
+    jsonrpc: String,
+    #[serde(default)]
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Vec<Value>,
+}
+
+/// Why a request couldn't be answered with a result.
+#[derive(Debug)]
+struct Failure {
+    code: i64,
+    error: anyhow::Error,
+}
+
+/// Answers JSON-RPC 2.0 requests read from stdin, one per line, on stdout, until stdin closes.
+///
+/// The `method` is a command, with any subcommand after a space (like `"input set"`), and the
+/// `params` are its arguments as an array. The result is what the command prints with `--json`:
+/// `null` if nothing, the document if one, and an array if several. Requests run concurrently,
+/// so responses may come in a different order than the requests.
+pub(crate) async fn run(client: &Client, read_only: bool) -> anyhow::Result<()> {
+    serve(client, tokio::io::stdin(), tokio::io::stdout(), read_only).await
+}
+
+/// Answers the requests in `input` on `output`, as [`run`] does for stdin and stdout.
+async fn serve(
+    client: &Client,
+    input: impl AsyncRead + Unpin,
+    mut output: impl AsyncWrite + Unpin,
+    read_only: bool,
+) -> anyhow::Result<()> {
+    let mut lines = BufReader::new(input).lines();
+    let mut running = FuturesUnordered::new();
+    let mut open = true;
+    while open || !running.is_empty() {
+        tokio::select! {
+            line = lines.next_line(), if open => {
+                let Some(line) = line.context("read request")? else {
+                    open = false;
+                    continue;
+                };
+                if line.trim().is_empty() {
+                    continue;
+                }
+                running.push(answer(client, line, read_only));
+            }
+            Some(response) = running.next() => {
+                let Some(response) = response else {
+                    continue;
+                };
+                let mut line = serde_json::to_vec(&response).context("serialize response")?;
+                line.push(b'\n');
+                output.write_all(&line).await.context("write response")?;
+                output.flush().await.context("write response")?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Handles one line of input, and returns the response to it unless it was a notification.
+async fn answer(client: &Client, line: String, read_only: bool) -> Option<Value> {
+    let request: Request = match serde_json::from_str::<Value>(&line) {
+        Err(e) => return Some(failure(Value::Null, PARSE_ERROR, e.into())),
+        Ok(value) => {
+            // The id is echoed back even if the rest of the request is wrong.
+            let id = value.get("id").cloned().unwrap_or_default();
+            match serde_json::from_value(value) {
+                Ok(request) => request,
+                Err(e) => return Some(failure(id, INVALID_REQUEST, e.into())),
+            }
+        }
+    };
+    let id = request.id.clone();
+    let (res, results) = Output::capture(execute(client, request, read_only)).await;
+    let id = id?;
+    Some(match res {
+        Ok(()) => {
+            let result = crate::output::collapse(results);
+            json!({ "jsonrpc": "2.0", "id": id, "result": result })
+        }
+        Err(Failure { code, error }) => failure(id, code, error),
+    })
+}
+
+async fn execute(client: &Client, request: Request, read_only: bool) -> Result<(), Failure> {
+    let invalid = |error| Failure {
+        code: INVALID_PARAMS,
+        error,
+    };
+    if request.jsonrpc != "2.0" {
+        return Err(Failure {
+            code: INVALID_REQUEST,
+            error: anyhow::anyhow!("only JSON-RPC 2.0 is supported"),
+        });
+    }
+    let words = words(&request.method, &request.params).map_err(invalid)?;
+    let cmd = crate::parse_fuzzy(client, words).await.map_err(invalid)?;
+    if !cmd.is_servable() {
+        return Err(invalid(anyhow::anyhow!(
+            "this command cannot be run over JSON-RPC"
+        )));
+    }
+    let failed = |error| Failure {
+        code: COMMAND_FAILED,
+        error,
+    };
+    if read_only && cmd.is_mutating() {
+        crate::ensure_not_streaming(client).await.map_err(failed)?;
+    }
+    crate::run(client, cmd, Output::new(true))
+        .await
+        .map_err(failed)
+}
+
+/// The command line that a method and its parameters stand for.
+fn words(method: &str, params: &[Value]) -> anyhow::Result<Vec<String>> {
+    let mut words: Vec<_> = method.split_whitespace().map(String::from).collect();
+    anyhow::ensure!(!words.is_empty(), "no method given");
+    for param in params {
+        words.push(match param {
+            Value::String(s) => s.clone(),
+            Value::Number(n) => n.to_string(),
+            Value::Bool(b) => b.to_string(),
+            _ => anyhow::bail!("parameters must be strings, numbers or booleans"),
+        });
+    }
+    Ok(words)
+}
+
+fn failure(id: Value, code: i64, error: anyhow::Error) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": {
+            "code": code,
+            "message": error.to_string(),
+            "data": crate::output::error_json(&error),
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn methods_and_params() {
+        assert_eq!(
+            words("set-volume", &[json!("Mic/Aux"), json!(-6)]).unwrap(),
+            ["set-volume", "Mic/Aux", "-6"]
+        );
+        assert_eq!(
+            words("input set", &[json!("Clock"), json!("/text")]).unwrap(),
+            ["input", "set", "Clock", "/text"]
+        );
+        assert!(words(" ", &[]).is_err());
+        assert!(words("set-scene", &[json!({ "scene": "Gaming" })]).is_err());
+    }
+
+    #[tokio::test]
+    async fn captures_results() {
+        let out = Output::new(true);
+        let (res, results) = Output::capture(async {
+            out.json(&json!({ "a": 1 }))?;
+            out.result("b", &json!({ "b": 2 }))
+        })
+        .await;
+        res.unwrap();
+        assert_eq!(results, [json!({ "a": 1 }), json!({ "b": 2 })]);
+    }
+
+    #[tokio::test]
+    async fn requests_after_the_password_on_stdin() {
+        let (client, requests) = crate::fake_obs::connect(|_, _| Some(json!(null))).await;
+        let mut input: &[u8] = concat!(
+            "hunter2\n",
+            r#"{"jsonrpc":"2.0","id":1,"method":"set-scene","params":["Gaming"]}"#,
+            "\n",
+            r#"{"jsonrpc":"2.0","id":2,"method":"set-scene","params":["BRB"]}"#,
+            "\n",
+        )
+        .as_bytes();
+        let password = crate::password::read_line(&mut input).unwrap();
+        assert_eq!(password, "hunter2\n");
+        let mut output = Vec::new();
+        serve(&client, input, &mut output, false).await.unwrap();
+        let mut ids: Vec<_> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| {
+                let response: Value = serde_json::from_str(line).unwrap();
+                assert!(response.get("error").is_none(), "{response}");
+                response["id"].clone()
+            })
+            .collect();
+        ids.sort_by_key(|id| id.as_i64());
+        assert_eq!(ids, [json!(1), json!(2)]);
+        assert_eq!(requests.lock().unwrap().len(), 2);
+    }
+}
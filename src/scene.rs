@@ -0,0 +1,165 @@
+use anyhow::Context;
+use clap::{Subcommand, ValueEnum};
+use obws::Client;
+use serde_json::json;
+
+use crate::output::Output;
+
+#[derive(Debug, Clone, Subcommand)]
+pub(crate) enum SceneCommand {
+    /// Creates an empty scene.
+    Create { name: String },
+    /// Removes a scene, along with its sources that aren't used in any other scene.
+    Remove { scene: String },
+    /// Renames a scene.
+    Rename { scene: String, new_name: String },
+    /// Prints the scenes in the order of OBS' scene list, from the top down.
+    ///
+    /// OBS offers no way to move scenes over WebSocket, so `--expect` and `--sort` check the
+    /// order instead, and fail with the order to drag the scenes into if it's off.
+    Order {
+        /// The scenes that should be listed in this order, separated by commas.
+        ///
+        /// Scenes that aren't mentioned can be anywhere.
+        #[arg(
+            long,
+            value_name = "SCENES",
+            value_delimiter = ',',
+            conflicts_with = "sort"
+        )]
+        expect: Vec<String>,
+
+        /// Check that all scenes are sorted this way.
+        #[arg(long, value_enum)]
+        sort: Option<Sort>,
+    },
+}
+
+impl SceneCommand {
+    pub(crate) fn is_mutating(&self) -> bool {
+        match self {
+            SceneCommand::Create { .. }
+            | SceneCommand::Remove { .. }
+            | SceneCommand::Rename { .. } => true,
+            SceneCommand::Order { .. } => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub(crate) enum Sort {
+    /// Alphabetically, ignoring case.
+    Alpha,
+}
+
+pub(crate) async fn run(client: &Client, cmd: SceneCommand, out: Output) -> anyhow::Result<()> {
+    match cmd {
+        SceneCommand::Create { name } => {
+            client
+                .scenes()
+                .create(&name)
+                .await
+                .with_context(|| format!("create scene {name}"))?;
+            out.json(&json!({ "scene": name, "created": true }))?;
+        }
+        SceneCommand::Remove { scene } => {
+            client
+                .scenes()
+                .remove(&scene)
+                .await
+                .with_context(|| format!("remove scene {scene}"))?;
+            out.json(&json!({ "scene": scene, "removed": true }))?;
+        }
+        SceneCommand::Rename { scene, new_name } => {
+            client
+                .scenes()
+                .set_name(&scene, &new_name)
+                .await
+                .with_context(|| format!("rename scene {scene} to {new_name}"))?;
+            out.json(&json!({ "scene": new_name, "previous": scene }))?;
+        }
+        SceneCommand::Order { expect, sort } => {
+            let (scenes, _) = crate::cycle::scenes(client).await?;
+            let wanted = match sort {
+                Some(Sort::Alpha) => {
+                    let mut sorted = scenes.clone();
+                    sorted.sort_by_cached_key(|scene| scene.to_lowercase());
+                    sorted
+                }
+                None => {
+                    for scene in &expect {
+                        anyhow::ensure!(scenes.contains(scene), "there is no scene named {scene}");
+                    }
+                    expect
+                }
+            };
+            let actual: Vec<&String> = scenes
+                .iter()
+                .filter(|scene| wanted.contains(scene))
+                .collect();
+            let in_order = actual.iter().copied().eq(wanted.iter());
+            anyhow::ensure!(
+                in_order,
+                "the scene list is out of order; it should go {}",
+                wanted.join(", ")
+            );
+            out.result(scenes.join("\n"), &scenes)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn creates_removes_and_renames() {
+        let (client, requests) = crate::fake_obs::connect(|_, _| Some(json!(null))).await;
+        let out = Output::new(true);
+        let (res, results) = Output::capture(async {
+            run(
+                &client,
+                SceneCommand::Create {
+                    name: "Guests".into(),
+                },
+                out,
+            )
+            .await?;
+            let rename = SceneCommand::Rename {
+                scene: "Guests".into(),
+                new_name: "Panel".into(),
+            };
+            run(&client, rename, out).await?;
+            run(
+                &client,
+                SceneCommand::Remove {
+                    scene: "Panel".into(),
+                },
+                out,
+            )
+            .await
+        })
+        .await;
+        res.unwrap();
+        assert_eq!(
+            *requests.lock().unwrap(),
+            [
+                ("CreateScene".into(), json!({ "sceneName": "Guests" })),
+                (
+                    "SetSceneName".into(),
+                    json!({ "sceneName": "Guests", "newSceneName": "Panel" })
+                ),
+                ("RemoveScene".into(), json!({ "sceneName": "Panel" })),
+            ]
+        );
+        assert_eq!(
+            results,
+            [
+                json!({ "scene": "Guests", "created": true }),
+                json!({ "scene": "Panel", "previous": "Guests" }),
+                json!({ "scene": "Panel", "removed": true }),
+            ]
+        );
+    }
+}
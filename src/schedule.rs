@@ -0,0 +1,227 @@
+use anyhow::Context;
+use chrono::{DateTime, Local, NaiveTime, TimeZone};
+use clap::CommandFactory;
+use croner::Cron;
+use std::{ffi::OsString, time::Duration};
+
+use crate::{config::Scheduled, Command};
+
+/// The longest to sleep in one go while waiting for a moment, so that time the machine spends
+/// suspended doesn't push the moment back.
+const MAX_NAP: Duration = Duration::from_secs(60);
+
+/// How late a scheduled command may still run, like after the machine wakes up from suspend.
+/// Anything later is skipped, since starting a stream hours after the show was due helps no one.
+const MAX_LATENESS: Duration = Duration::from_secs(5 * 60);
+
+/// If the first command is `at` or `in`, waits until it's time and returns the commands with it
+/// replaced by the one it was waiting to run; otherwise returns them as they are.
+///
+/// `line` holds the words of the first command, following the program name, and is replaced
+/// along with it.
+///
+/// Returns `None` if interrupted while waiting, in which case nothing should run.
+pub(crate) async fn wait(
+    mut cmds: Vec<Command>,
+    line: &mut Vec<OsString>,
+) -> anyhow::Result<Option<Vec<Command>>> {
+    loop {
+        let (when, words) = match &cmds[0] {
+            Command::At { time, command } => (next_time_of_day(&Local::now(), time)?, command),
+            Command::In { delay, command } => {
+                let delay = crate::parse_duration(delay)?;
+                let delay = chrono::Duration::from_std(delay).with_context(|| {
+                    format!("`{}` is too long to wait", shell_words::join(command))
+                })?;
+                (Local::now() + delay, command)
+            }
+            _ => return Ok(Some(cmds)),
+        };
+        // Mistakes in the command should show up now, not hours from now.
+        let cmd = crate::parse_chained(words.clone())?;
+        tracing::info!(
+            "Waiting until {} to run `{}`.",
+            when.format("%Y-%m-%d %H:%M:%S"),
+            shell_words::join(words)
+        );
+        tokio::select! {
+            () = until(when) => {}
+            _ = tokio::signal::ctrl_c() => return Ok(None),
+        }
+        let program = line[0].clone();
+        let words = std::iter::once(program).chain(words.iter().map(OsString::from));
+        *line = crate::alias::resolve(crate::Args::command(), words.collect());
+        cmds[0] = cmd;
+    }
+}
+
+/// Sleeps until the clock on the wall says `when`.
+async fn until(when: DateTime<Local>) {
+    // A plain sleep doesn't count time spent suspended, so the clock is checked now and then.
+    while let Ok(left) = (when - Local::now()).to_std() {
+        if left.is_zero() {
+            break;
+        }
+        tokio::time::sleep(left.min(MAX_NAP)).await;
+    }
+}
+
+/// The next time after `now` that the clock reads `time`, given as `HH:MM` or `HH:MM:SS`.
+fn next_time_of_day<Tz: TimeZone>(now: &DateTime<Tz>, time: &str) -> anyhow::Result<DateTime<Tz>> {
+    let of_day = NaiveTime::parse_from_str(time, "%H:%M:%S")
+        .or_else(|_| NaiveTime::parse_from_str(time, "%H:%M"))
+        .map_err(|_| {
+            anyhow::anyhow!("invalid time `{time}`, expected e.g. `20:00` or `20:00:30`")
+        })?;
+    let mut date = now.date_naive();
+    loop {
+        // A time that a daylight saving change skips doesn't happen that day.
+        let at = now
+            .timezone()
+            .from_local_datetime(&date.and_time(of_day))
+            .earliest();
+        match at {
+            Some(at) if at > *now => return Ok(at),
+            _ => date = date.succ_opt().context("no such date")?,
+        }
+    }
+}
+
+/// The `[[schedule]]` entries from the config file, for the daemon to run as they come due.
+pub(crate) struct Schedule {
+    entries: Vec<(Cron, String)>,
+    /// When the schedule was last looked at; only what comes due after this still has to run.
+    since: DateTime<Local>,
+}
+
+impl Schedule {
+    /// Parses the entries, so that mistakes show up when the daemon starts rather than when an
+    /// entry comes due.
+    pub(crate) fn new(entries: &[Scheduled]) -> anyhow::Result<Self> {
+        let entries = entries
+            .iter()
+            .map(|entry| {
+                let cron = Cron::new(&entry.cron)
+                    .parse()
+                    .with_context(|| format!("invalid cron expression `{}`", entry.cron))?;
+                let words = shell_words::split(&entry.run)
+                    .with_context(|| format!("split `{}` into arguments", entry.run))?;
+                let cmd = crate::parse_chained(words)
+                    .with_context(|| format!("invalid command scheduled for `{}`", entry.cron))?;
+                anyhow::ensure!(
+                    cmd.is_servable(),
+                    "`{}` cannot be run on a schedule",
+                    entry.run
+                );
+                Ok((cron, entry.run.clone()))
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(Schedule {
+            entries,
+            since: Local::now(),
+        })
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Waits until entries come due, and returns their command lines.
+    ///
+    /// Never returns if nothing is scheduled. Cancelling this doesn't lose any entries.
+    pub(crate) async fn next(&mut self) -> Vec<String> {
+        loop {
+            let Some((when, due)) = self.upcoming(&self.since) else {
+                return std::future::pending().await;
+            };
+            let due: Vec<_> = due.into_iter().map(String::from).collect();
+            until(when).await;
+            let now = Local::now();
+            self.since = when.max(now);
+            if (now - when).to_std().is_ok_and(|late| late > MAX_LATENESS) {
+                for line in due {
+                    tracing::warn!(
+                        "Skipped `{line}`, which was due at {}.",
+                        when.format("%Y-%m-%d %H:%M:%S")
+                    );
+                }
+                continue;
+            }
+            return due;
+        }
+    }
+
+    /// When entries come due next after `since`, and which ones.
+    fn upcoming<Tz: TimeZone>(&self, since: &DateTime<Tz>) -> Option<(DateTime<Tz>, Vec<&str>)> {
+        let mut next: Option<(DateTime<Tz>, Vec<&str>)> = None;
+        for (cron, line) in &self.entries {
+            // Expressions that can never match, like `0 0 30 2 *`, just never come due.
+            let Ok(when) = cron.find_next_occurrence(since, false) else {
+                continue;
+            };
+            match &mut next {
+                Some((first, due)) if *first == when => due.push(line),
+                Some((first, _)) if *first < when => {}
+                _ => next = Some((when, vec![line])),
+            }
+        }
+        next
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn times_of_day() {
+        let now = utc("2024-05-03T18:30:00Z");
+        assert_eq!(
+            next_time_of_day(&now, "20:00").unwrap(),
+            utc("2024-05-03T20:00:00Z")
+        );
+        assert_eq!(
+            next_time_of_day(&now, "08:15:30").unwrap(),
+            utc("2024-05-04T08:15:30Z")
+        );
+        assert_eq!(
+            next_time_of_day(&now, "18:30").unwrap(),
+            utc("2024-05-04T18:30:00Z")
+        );
+        assert!(next_time_of_day(&now, "25:00").is_err());
+        assert!(next_time_of_day(&now, "8pm").is_err());
+    }
+
+    #[test]
+    fn schedules() {
+        let scheduled = |cron: &str, run: &str| Scheduled {
+            cron: cron.to_owned(),
+            run: run.to_owned(),
+        };
+        let schedule = Schedule::new(&[
+            scheduled("0 20 * * FRI", "start-stream"),
+            scheduled("0 22 * * FRI", "stop-stream"),
+            scheduled("0 20 * * *", "set-scene 'Starting Soon'"),
+        ])
+        .unwrap();
+        // 2024-05-03 was a Friday.
+        let (when, due) = schedule.upcoming(&utc("2024-05-03T12:00:00Z")).unwrap();
+        assert_eq!(when, utc("2024-05-03T20:00:00Z"));
+        assert_eq!(due, ["start-stream", "set-scene 'Starting Soon'"]);
+        let (when, due) = schedule.upcoming(&when).unwrap();
+        assert_eq!(when, utc("2024-05-03T22:00:00Z"));
+        assert_eq!(due, ["stop-stream"]);
+
+        assert!(Schedule::new(&[scheduled("0 25 * * *", "start-stream")]).is_err());
+        assert!(Schedule::new(&[scheduled("0 20 * * *", "repl")]).is_err());
+    }
+}
@@ -0,0 +1,215 @@
+use anyhow::Context;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use clap::ValueEnum;
+use image::{
+    codecs::{jpeg::JpegEncoder, png::PngEncoder, webp::WebPEncoder},
+    DynamicImage, ImageFormat,
+};
+use obws::{requests::sources::TakeScreenshot, Client};
+use serde_json::json;
+use std::{io::Cursor, path::PathBuf};
+
+use crate::output::Output;
+
+#[derive(Debug, Clone, clap::Args)]
+pub(crate) struct ScreenshotArgs {
+    /// Where to write the image.
+    path: PathBuf,
+
+    /// The scene or source to capture.
+    ///
+    /// Defaults to the current program scene.
+    #[arg(long)]
+    source: Option<String>,
+
+    /// Only keep this region of the source, in source pixels.
+    #[arg(long, value_name = "X,Y,W,H")]
+    crop: Option<String>,
+
+    /// The image format.
+    ///
+    /// Defaults to the one matching the extension of the path, or PNG if it has none.
+    #[arg(long, value_enum)]
+    format: Option<Format>,
+
+    /// Compression quality from 0 (smallest) to 100 (best), for JPEG and WebP.
+    ///
+    /// Cropped WebP images are always saved losslessly.
+    #[arg(long, value_parser = clap::value_parser!(u8).range(0..=100))]
+    quality: Option<u8>,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub(crate) enum Format {
+    Png,
+    #[value(alias = "jpeg")]
+    Jpg,
+    Webp,
+}
+
+impl Format {
+    fn from_path(path: &std::path::Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        Format::from_str(&extension, true).ok()
+    }
+
+    /// The name OBS knows the format by.
+    fn name(self) -> &'static str {
+        match self {
+            Format::Png => "png",
+            Format::Jpg => "jpg",
+            Format::Webp => "webp",
+        }
+    }
+}
+
+/// A region of the captured image.
+#[derive(Debug, Clone, Copy)]
+struct Crop {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+impl Crop {
+    /// Parses `X,Y,W,H`.
+    fn parse(crop: &str) -> anyhow::Result<Self> {
+        let parts: Vec<_> = crop
+            .split(',')
+            .map(|part| part.trim().parse().ok())
+            .collect();
+        let [Some(x), Some(y), Some(width), Some(height)] = parts[..] else {
+            anyhow::bail!("invalid crop {crop}, expected X,Y,W,H");
+        };
+        anyhow::ensure!(
+            width > 0 && height > 0,
+            "crop {crop} must have a non-zero size"
+        );
+        Ok(Crop {
+            x,
+            y,
+            width,
+            height,
+        })
+    }
+}
+
+/// Captures a scene or source and writes it to a file.
+///
+/// OBS can only scale screenshots, not crop them, so a cropped screenshot is captured losslessly
+/// at full size and then cropped and encoded here.
+pub(crate) async fn run(client: &Client, args: &ScreenshotArgs, out: Output) -> anyhow::Result<()> {
+    let crop = args.crop.as_deref().map(Crop::parse).transpose()?;
+    let format = args
+        .format
+        .or_else(|| Format::from_path(&args.path))
+        .unwrap_or(Format::Png);
+    let source = match &args.source {
+        Some(source) => source.clone(),
+        None => client
+            .scenes()
+            .current_program_scene()
+            .await
+            .context("get current program scene")?,
+    };
+
+    let image = match crop {
+        None => capture(client, &source, format, args.quality).await?,
+        Some(crop) => {
+            let full = capture(client, &source, Format::Png, None).await?;
+            let full = image::load_from_memory_with_format(&full, ImageFormat::Png)
+                .context("decode screenshot")?;
+            anyhow::ensure!(
+                crop.x.saturating_add(crop.width) <= full.width()
+                    && crop.y.saturating_add(crop.height) <= full.height(),
+                "crop {} does not fit within {source}, which is {}x{}",
+                args.crop.as_deref().unwrap_or_default(),
+                full.width(),
+                full.height()
+            );
+            let cropped = full.crop_imm(crop.x, crop.y, crop.width, crop.height);
+            encode(&cropped, format, args.quality).context("encode screenshot")?
+        }
+    };
+    tokio::fs::write(&args.path, &image)
+        .await
+        .with_context(|| format!("write {}", args.path.display()))?;
+
+    out.result(
+        format!("Saved screenshot of {source} to {}", args.path.display()),
+        &json!({
+            "source": source,
+            "path": args.path,
+            "format": format.name(),
+            "bytes": image.len(),
+        }),
+    )
+}
+
+/// Asks OBS for a screenshot of `source`, at its own size.
+async fn capture(
+    client: &Client,
+    source: &str,
+    format: Format,
+    quality: Option<u8>,
+) -> anyhow::Result<Vec<u8>> {
+    let data_url = client
+        .sources()
+        .take_screenshot(TakeScreenshot {
+            source,
+            format: format.name(),
+            width: None,
+            height: None,
+            compression_quality: quality.map(i32::from),
+        })
+        .await
+        .with_context(|| format!("take screenshot of {source}"))?;
+    // The image comes back as a `data:image/png;base64,...` URL.
+    let data = data_url
+        .split_once(',')
+        .map_or(data_url.as_str(), |(_, data)| data);
+    BASE64.decode(data).context("decode screenshot data")
+}
+
+fn encode(image: &DynamicImage, format: Format, quality: Option<u8>) -> anyhow::Result<Vec<u8>> {
+    let mut encoded = Cursor::new(Vec::new());
+    match format {
+        Format::Png => image.write_with_encoder(PngEncoder::new(&mut encoded))?,
+        // JPEG has no alpha channel.
+        Format::Jpg => DynamicImage::from(image.to_rgb8()).write_with_encoder(
+            JpegEncoder::new_with_quality(&mut encoded, quality.unwrap_or(90)),
+        )?,
+        // Only lossless WebP can be encoded here, so the quality doesn't apply.
+        Format::Webp => DynamicImage::from(image.to_rgba8())
+            .write_with_encoder(WebPEncoder::new_lossless(&mut encoded))?,
+    }
+    Ok(encoded.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crops() {
+        let crop = Crop::parse("10,20,640,360").unwrap();
+        assert_eq!(
+            (crop.x, crop.y, crop.width, crop.height),
+            (10, 20, 640, 360)
+        );
+        let crop = Crop::parse(" 0, 0 ,1, 1").unwrap();
+        assert_eq!((crop.x, crop.y, crop.width, crop.height), (0, 0, 1, 1));
+        for crop in [
+            "",
+            "10,20,640",
+            "10,20,640,360,1",
+            "-1,0,10,10",
+            "0,0,0,10",
+            "0,0,10,0",
+            "a,b,c,d",
+        ] {
+            assert!(Crop::parse(crop).is_err(), "{crop}");
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use anyhow::Context;
+use futures_util::StreamExt;
+use obws::{
+    events::{Event, OutputState},
+    Client,
+};
+use serde::Serialize;
+use std::time::Duration;
+use tokio::time::{Instant, MissedTickBehavior};
+
+use crate::{
+    output::Output,
+    sink::{Sink, Sinks},
+};
+
+/// How often output statistics are sampled while a session runs, since OBS resets them as soon
+/// as the output stops.
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, clap::Args)]
+pub(crate) struct SessionArgs {
+    /// Also POST each summary as JSON to this URL.
+    #[arg(long, value_name = "URL")]
+    report_webhook: Option<String>,
+
+    /// Also send each summary to `file:<path>`, `udp:<host>:<port>` or `webhook:<url>` (may be
+    /// repeated).
+    #[arg(long, value_name = "SINK")]
+    sink: Vec<Sink>,
+}
+
+/// What is reported about a stream or recording once it ends.
+#[derive(Debug, Serialize)]
+struct Summary {
+    output: &'static str,
+    duration_secs: f64,
+    bytes: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    total_frames: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    skipped_frames: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    disconnects: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path: Option<String>,
+}
+
+impl std::fmt::Display for Summary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let secs = self.duration_secs as u64;
+        let output = match self.output {
+            "stream" => "Stream",
+            _ => "Recording",
+        };
+        write!(
+            f,
+            "{output} ended after {}:{:02}:{:02}, {:.1} MB",
+            secs / 3600,
+            secs / 60 % 60,
+            secs % 60,
+            self.bytes as f64 / 1e6
+        )?;
+        if let (Some(total), Some(skipped)) = (self.total_frames, self.skipped_frames) {
+            write!(f, ", {skipped} of {total} frames dropped")?;
+        }
+        if let Some(disconnects) = self.disconnects {
+            write!(f, ", {disconnects} disconnects")?;
+        }
+        if let Some(path) = &self.path {
+            write!(f, ", saved to {path}")?;
+        }
+        Ok(())
+    }
+}
+
+/// A stream or recording that is currently running.
+struct Running {
+    started: Instant,
+    summary: Summary,
+}
+
+impl Running {
+    fn new(output: &'static str, elapsed: Duration) -> Self {
+        let started = Instant::now()
+            .checked_sub(elapsed)
+            .unwrap_or_else(Instant::now);
+        Running {
+            started,
+            summary: Summary {
+                output,
+                duration_secs: 0.,
+                bytes: 0,
+                total_frames: None,
+                skipped_frames: None,
+                disconnects: (output == "stream").then_some(0),
+                path: None,
+            },
+        }
+    }
+
+    fn finish(mut self) -> Summary {
+        self.summary.duration_secs = self.started.elapsed().as_secs_f64();
+        self.summary
+    }
+}
+
+/// Follows streaming and recording sessions until interrupted, and reports a summary of each
+/// when it ends.
+pub(crate) async fn run(client: &Client, args: &SessionArgs, out: Output) -> anyhow::Result<()> {
+    let events = client.events().context("subscribe to OBS events")?;
+    let mut events = std::pin::pin!(events);
+    let mut sinks = args.sink.clone();
+    sinks.extend(args.report_webhook.clone().map(Sink::Webhook));
+    let mut sinks = Sinks::open(&sinks).await?;
+
+    // Pick up sessions that were already running.
+    let mut stream = None;
+    let mut record = None;
+    sample(client, &mut stream, &mut record).await?;
+
+    let mut samples = tokio::time::interval(SAMPLE_INTERVAL);
+    samples.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    loop {
+        let ended = tokio::select! {
+            event = events.next() => match event {
+                Some(Event::StreamStateChanged { state, .. }) => match state {
+                    OutputState::Started => {
+                        stream = Some(Running::new("stream", Duration::ZERO));
+                        None
+                    }
+                    OutputState::Reconnecting => {
+                        if let Some(disconnects) = stream
+                            .as_mut()
+                            .and_then(|stream| stream.summary.disconnects.as_mut())
+                        {
+                            *disconnects += 1;
+                        }
+                        None
+                    }
+                    OutputState::Stopped => stream.take().map(Running::finish),
+                    _ => None,
+                },
+                Some(Event::RecordStateChanged { state, path, .. }) => match state {
+                    OutputState::Started => {
+                        record = Some(Running::new("record", Duration::ZERO));
+                        None
+                    }
+                    OutputState::Stopped => record.take().map(|record| {
+                        let mut summary = record.finish();
+                        summary.path = path;
+                        summary
+                    }),
+                    _ => None,
+                },
+                Some(_) => None,
+                None => anyhow::bail!("lost connection to OBS"),
+            },
+            _ = samples.tick() => {
+                if let Err(e) = sample(client, &mut stream, &mut record).await {
+                    tracing::warn!("Failed to sample output statistics: {e:#}");
+                }
+                None
+            }
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+        };
+        if let Some(summary) = ended {
+            out.result(&summary, &summary)?;
+            sinks.send(&summary).await?;
+        }
+    }
+}
+
+/// Updates the statistics of running sessions, and starts tracking sessions that are running
+/// but aren't tracked yet.
+async fn sample(
+    client: &Client,
+    stream: &mut Option<Running>,
+    record: &mut Option<Running>,
+) -> anyhow::Result<()> {
+    let (stream_status, record_status) = tokio::try_join!(
+        async {
+            client
+                .streaming()
+                .status()
+                .await
+                .context("get streaming status")
+        },
+        async {
+            client
+                .recording()
+                .status()
+                .await
+                .context("get recording status")
+        },
+    )?;
+    if stream_status.active {
+        let elapsed = Duration::try_from(stream_status.duration).unwrap_or_default();
+        let running = stream.get_or_insert_with(|| Running::new("stream", elapsed));
+        running.summary.bytes = stream_status.bytes;
+        running.summary.total_frames = Some(stream_status.total_frames);
+        running.summary.skipped_frames = Some(stream_status.skipped_frames);
+    }
+    if record_status.active {
+        let elapsed = Duration::try_from(record_status.duration).unwrap_or_default();
+        let running = record.get_or_insert_with(|| Running::new("record", elapsed));
+        running.summary.bytes = record_status.bytes;
+    }
+    Ok(())
+}
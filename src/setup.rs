@@ -0,0 +1,226 @@
+use anyhow::Context;
+use obws::Client;
+use serde_json::json;
+use std::collections::BTreeMap;
+
+use crate::{output::Output, prompt::ask};
+
+/// Walks through the scenes and audio inputs in OBS, asking what they should be called and how
+/// they should sound, and adds the answers to the config file.
+///
+/// The short names go into `[aliases]`. Each scene's audio preset, and the panic button, go into
+/// `[macros]` as `enforce` commands, so that they only change what's off.
+pub(crate) async fn run(client: &Client, out: Output) -> anyhow::Result<()> {
+    crate::prompt::ensure_interactive("write the config file by hand, or run setup in a terminal")?;
+    let (scenes, _) = crate::cycle::scenes(client).await?;
+    let inputs = audio_inputs(client).await?;
+    eprintln!("Scenes: {}", numbered(&scenes));
+    eprintln!("Audio inputs: {}", numbered(&inputs));
+
+    eprintln!("\nPick a short name for each, to use in commands and key bindings.");
+    eprintln!("Press enter to take the suggestion, or type - for none.");
+    let mut aliases = BTreeMap::new();
+    for name in scenes.iter().chain(&inputs) {
+        let suggestion = crate::import::suggest_alias(name, &aliases);
+        loop {
+            let alias = ask(format!("{name} [{suggestion}]: ")).await?;
+            let alias = match alias.trim() {
+                "" => suggestion.clone(),
+                "-" => break,
+                alias => alias.to_owned(),
+            };
+            if let Some(taken) = aliases.get(&alias) {
+                eprintln!("{alias} is already short for {taken}.");
+                continue;
+            }
+            aliases.insert(alias, name.clone());
+            break;
+        }
+    }
+    let alias_of = |name: &str| {
+        aliases
+            .iter()
+            .find(|(_, target)| *target == name)
+            .map_or_else(|| name.to_owned(), |(alias, _)| alias.clone())
+    };
+
+    let mut macros = BTreeMap::new();
+    if !inputs.is_empty() {
+        eprintln!("\nFor each scene, list the audio inputs that should be live while it shows,");
+        eprintln!("by number or name and separated by commas. The others are muted.");
+        eprintln!("Press enter to leave a scene's audio alone.");
+        for scene in &scenes {
+            let Some(live) = pick(&format!("{scene}: "), &inputs, &aliases).await? else {
+                continue;
+            };
+            let mut command = vec![
+                String::from("enforce"),
+                String::from("--scene"),
+                scene.clone(),
+            ];
+            for input in &inputs {
+                let flag = if live.contains(input) {
+                    "--unmute"
+                } else {
+                    "--mute"
+                };
+                command.extend([flag.to_owned(), input.clone()]);
+            }
+            macros.insert(alias_of(scene), vec![shell_words::join(command)]);
+        }
+    }
+
+    eprintln!("\nThe panic button cuts to a safe scene and mutes inputs in one go.");
+    let panic_scene = loop {
+        let answer = ask("Scene to cut to (by number or name, enter for none): ").await?;
+        if answer.trim().is_empty() {
+            break None;
+        }
+        match resolve(answer.trim(), &scenes, &aliases) {
+            Some(scene) => break Some(scene),
+            None => eprintln!("There is no scene {}.", answer.trim()),
+        }
+    };
+    let panic_mutes = if inputs.is_empty() {
+        Vec::new()
+    } else {
+        pick("Inputs to mute (enter for all): ", &inputs, &aliases)
+            .await?
+            .unwrap_or_else(|| inputs.clone())
+    };
+    if panic_scene.is_some() || !panic_mutes.is_empty() {
+        let mut command = vec![String::from("enforce")];
+        if let Some(scene) = panic_scene {
+            command.extend([String::from("--scene"), scene]);
+        }
+        for input in panic_mutes {
+            command.extend([String::from("--mute"), input]);
+        }
+        macros.insert(String::from("panic"), vec![shell_words::join(command)]);
+    }
+
+    let dirs = crate::project_dirs()?;
+    let path = dirs.config_dir().join(crate::config::FILE_NAME);
+    let mut config = match tokio::fs::read_to_string(&path).await {
+        Ok(contents) => toml::from_str::<toml::Table>(&contents)
+            .with_context(|| format!("parse config file {}", path.display()))?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => toml::Table::new(),
+        Err(e) => return Err(e).with_context(|| format!("read config file {}", path.display())),
+    };
+    merge(&mut config, "aliases", &aliases)?;
+    merge(&mut config, "macros", &macros)?;
+    let contents = toml::to_string(&config).context("serialize configuration")?;
+
+    eprintln!("\n{contents}");
+    let answer = ask(format!("Write this to {}? [Y/n] ", path.display())).await?;
+    if answer.trim().eq_ignore_ascii_case("n") {
+        anyhow::bail!("nothing was written");
+    }
+    tokio::fs::create_dir_all(dirs.config_dir())
+        .await
+        .with_context(|| format!("create {}", dirs.config_dir().display()))?;
+    if tokio::fs::try_exists(&path).await.unwrap_or(false) {
+        let backup = path.with_extension("toml.bak");
+        tokio::fs::copy(&path, &backup)
+            .await
+            .with_context(|| format!("back up config file to {}", backup.display()))?;
+        tracing::info!("Kept the previous config file as {}.", backup.display());
+    }
+    tokio::fs::write(&path, contents)
+        .await
+        .with_context(|| format!("write config file {}", path.display()))?;
+
+    out.result(
+        format!(
+            "Wrote {} aliases and {} macros to {}",
+            aliases.len(),
+            macros.len(),
+            path.display()
+        ),
+        &json!({ "path": path, "aliases": aliases, "macros": macros }),
+    )
+}
+
+/// The inputs that have audio, in the order OBS lists them.
+async fn audio_inputs(client: &Client) -> anyhow::Result<Vec<String>> {
+    let inputs = client.inputs().list(None).await.context("list inputs")?;
+    let mut audio = Vec::new();
+    for input in inputs {
+        // OBS refuses to report a volume for inputs without audio, which is how we tell them apart.
+        if client.inputs().volume(&input.name).await.is_ok() {
+            audio.push(input.name);
+        }
+    }
+    Ok(audio)
+}
+
+fn numbered(names: &[String]) -> String {
+    if names.is_empty() {
+        return String::from("(none)");
+    }
+    names
+        .iter()
+        .enumerate()
+        .map(|(i, name)| format!("{}) {name}", i + 1))
+        .collect::<Vec<_>>()
+        .join("  ")
+}
+
+/// Finds what `answer` refers to among `names`: a number from the list, an alias, or a name.
+fn resolve(answer: &str, names: &[String], aliases: &BTreeMap<String, String>) -> Option<String> {
+    if let Ok(n) = answer.parse::<usize>() {
+        return n.checked_sub(1).and_then(|i| names.get(i)).cloned();
+    }
+    let name = aliases.get(answer).map_or(answer, String::as_str);
+    names.iter().find(|candidate| *candidate == name).cloned()
+}
+
+/// Asks for some of `names`, until every one given is known; `None` if nothing was typed.
+async fn pick(
+    question: &str,
+    names: &[String],
+    aliases: &BTreeMap<String, String>,
+) -> anyhow::Result<Option<Vec<String>>> {
+    'ask: loop {
+        let answer = ask(question).await?;
+        if answer.trim().is_empty() {
+            return Ok(None);
+        }
+        let mut picked = Vec::new();
+        for part in answer
+            .split(',')
+            .map(str::trim)
+            .filter(|part| !part.is_empty())
+        {
+            match resolve(part, names, aliases) {
+                Some(name) => picked.push(name),
+                None => {
+                    eprintln!("{part} is not one of them.");
+                    continue 'ask;
+                }
+            }
+        }
+        return Ok(Some(picked));
+    }
+}
+
+/// Adds `entries` to the `table` section of `config`, replacing entries of the same name.
+fn merge<T: serde::Serialize>(
+    config: &mut toml::Table,
+    table: &str,
+    entries: &BTreeMap<String, T>,
+) -> anyhow::Result<()> {
+    if entries.is_empty() {
+        return Ok(());
+    }
+    let section = config
+        .entry(table)
+        .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+        .as_table_mut()
+        .with_context(|| format!("`{table}` in the config file is not a table"))?;
+    for (name, value) in entries {
+        let value = toml::Value::try_from(value).context("serialize configuration")?;
+        section.insert(name.clone(), value);
+    }
+    Ok(())
+}
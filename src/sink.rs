@@ -0,0 +1,154 @@
+use anyhow::Context;
+use serde::Serialize;
+use std::{path::PathBuf, str::FromStr};
+use tokio::{fs::File, io::AsyncWriteExt, net::UdpSocket};
+
+/// Somewhere other than stdout to deliver JSON documents, like events or reports.
+#[derive(Debug, Clone)]
+pub(crate) enum Sink {
+    /// `file:<path>` appends one document per line to a file.
+    File(PathBuf),
+    /// `udp:<host>:<port>` sends one datagram per document.
+    Udp(String),
+    /// `webhook:<url>` POSTs each document to a URL.
+    Webhook(String),
+}
+
+impl FromStr for Sink {
+    type Err = anyhow::Error;
+
+    fn from_str(sink: &str) -> anyhow::Result<Self> {
+        let (kind, target) = sink
+            .split_once(':')
+            .filter(|(_, target)| !target.is_empty())
+            .with_context(|| {
+                format!("invalid sink `{sink}`, expected `file:<path>`, `udp:<host>:<port>` or `webhook:<url>`")
+            })?;
+        Ok(match kind {
+            "file" => Sink::File(PathBuf::from(target)),
+            "udp" => Sink::Udp(target.to_owned()),
+            "webhook" => Sink::Webhook(target.to_owned()),
+            _ => anyhow::bail!("unknown sink type `{kind}`, expected `file`, `udp` or `webhook`"),
+        })
+    }
+}
+
+/// Sinks that are ready to take documents.
+pub(crate) struct Sinks {
+    open: Vec<Open>,
+    http: reqwest::Client,
+}
+
+enum Open {
+    File(File, PathBuf),
+    Udp(UdpSocket, String),
+    Webhook(String),
+}
+
+impl Sinks {
+    /// Opens the files and sockets of `sinks`, so that mistakes show up before anything is sent.
+    pub(crate) async fn open(sinks: &[Sink]) -> anyhow::Result<Self> {
+        let mut open = Vec::with_capacity(sinks.len());
+        for sink in sinks {
+            open.push(match sink {
+                Sink::File(path) => {
+                    let file = tokio::fs::OpenOptions::new()
+                        .create(true)
+                        .append(true)
+                        .open(path)
+                        .await
+                        .with_context(|| format!("open {}", path.display()))?;
+                    Open::File(file, path.clone())
+                }
+                Sink::Udp(target) => {
+                    let addr = tokio::net::lookup_host(target.as_str())
+                        .await
+                        .with_context(|| format!("resolve {target}"))?
+                        .next()
+                        .with_context(|| format!("{target} has no addresses"))?;
+                    let local = if addr.is_ipv4() {
+                        "0.0.0.0:0"
+                    } else {
+                        "[::]:0"
+                    };
+                    let socket = UdpSocket::bind(local).await.context("bind UDP socket")?;
+                    socket
+                        .connect(addr)
+                        .await
+                        .with_context(|| format!("connect UDP socket to {target}"))?;
+                    Open::Udp(socket, target.clone())
+                }
+                Sink::Webhook(url) => Open::Webhook(url.clone()),
+            });
+        }
+        Ok(Sinks {
+            open,
+            http: reqwest::Client::new(),
+        })
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.open.is_empty()
+    }
+
+    /// Delivers `document` to every sink.
+    ///
+    /// Sinks across the network that can't be reached are logged rather than returned, since
+    /// they may well be back for the next document.
+    pub(crate) async fn send(&mut self, document: &impl Serialize) -> anyhow::Result<()> {
+        let mut line = serde_json::to_vec(document).context("serialize document")?;
+        for open in &mut self.open {
+            match open {
+                Open::File(file, path) => {
+                    line.push(b'\n');
+                    let written = file.write_all(&line).await;
+                    line.pop();
+                    written.with_context(|| format!("write to {}", path.display()))?;
+                }
+                Open::Udp(socket, target) => {
+                    if let Err(e) = socket.send(&line).await {
+                        tracing::warn!("Failed to send to {target}: {e}");
+                    }
+                }
+                Open::Webhook(url) => {
+                    let posted = self
+                        .http
+                        .post(url.as_str())
+                        .header(reqwest::header::CONTENT_TYPE, "application/json")
+                        .body(line.clone())
+                        .send()
+                        .await
+                        .and_then(|response| response.error_for_status());
+                    if let Err(e) = posted {
+                        tracing::warn!("Failed to post to {url}: {e}");
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sinks() {
+        assert!(matches!(
+            "file:/tmp/events.jsonl".parse().unwrap(),
+            Sink::File(path) if path == std::path::Path::new("/tmp/events.jsonl")
+        ));
+        assert!(matches!(
+            "udp:127.0.0.1:9000".parse().unwrap(),
+            Sink::Udp(addr) if addr == "127.0.0.1:9000"
+        ));
+        assert!(matches!(
+            "webhook:https://example.com/hook".parse().unwrap(),
+            Sink::Webhook(url) if url == "https://example.com/hook"
+        ));
+        for sink in ["file:", "events.jsonl", "tcp:localhost:1"] {
+            assert!(sink.parse::<Sink>().is_err(), "{sink} parsed");
+        }
+    }
+}
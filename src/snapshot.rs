@@ -0,0 +1,229 @@
+use anyhow::Context;
+use clap::Subcommand;
+use futures_util::future::{join_all, try_join_all};
+use obws::{requests::inputs::Volume, Client};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{collections::BTreeMap, path::PathBuf};
+
+use crate::{audio::Monitoring, output::Output};
+
+#[derive(Debug, Clone, Subcommand)]
+pub(crate) enum AudioSnapshotCommand {
+    /// Saves the volume, mute state, sync offset, balance and monitoring of every audio input.
+    Save { name: String },
+    /// Puts every input saved in a snapshot back the way it was.
+    Restore { name: String },
+    /// Lists the saved snapshots.
+    List,
+}
+
+impl AudioSnapshotCommand {
+    pub(crate) fn is_mutating(&self) -> bool {
+        matches!(self, AudioSnapshotCommand::Restore { .. })
+    }
+}
+
+/// The audio state of one input, as kept in a snapshot.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Channel {
+    pub(crate) volume_mul: f32,
+    pub(crate) muted: bool,
+    pub(crate) sync_offset_ms: i64,
+    pub(crate) balance: f32,
+    pub(crate) monitoring: Monitoring,
+}
+
+pub(crate) async fn run(
+    client: &Client,
+    cmd: AudioSnapshotCommand,
+    out: Output,
+) -> anyhow::Result<()> {
+    match cmd {
+        AudioSnapshotCommand::Save { name } => {
+            let path = snapshot_path(&name)?;
+            let inputs = client.inputs().list(None).await.context("list inputs")?;
+            let channels = join_all(inputs.into_iter().map(|input| async move {
+                // Inputs without audio have no volume; they have nothing to snapshot.
+                let volume = client.inputs().volume(&input.name).await.ok()?;
+                Some(
+                    capture(client, &input.name, volume.mul)
+                        .await
+                        .map(|c| (input.name, c)),
+                )
+            }))
+            .await;
+            let channels = channels
+                .into_iter()
+                .flatten()
+                .collect::<anyhow::Result<BTreeMap<_, _>>>()?;
+
+            if let Some(dir) = path.parent() {
+                tokio::fs::create_dir_all(dir)
+                    .await
+                    .with_context(|| format!("create {}", dir.display()))?;
+            }
+            let contents = serde_json::to_string_pretty(&channels).context("serialize snapshot")?;
+            tokio::fs::write(&path, contents)
+                .await
+                .with_context(|| format!("write {}", path.display()))?;
+            out.result(
+                format!("Saved {} inputs to snapshot {name}", channels.len()),
+                &json!({ "snapshot": name, "inputs": channels.keys().collect::<Vec<_>>() }),
+            )?;
+        }
+        AudioSnapshotCommand::Restore { name } => {
+            let path = snapshot_path(&name)?;
+            let contents = tokio::fs::read_to_string(&path)
+                .await
+                .with_context(|| format!("read snapshot {name} from {}", path.display()))?;
+            let channels: BTreeMap<String, Channel> = serde_json::from_str(&contents)
+                .with_context(|| format!("parse snapshot {}", path.display()))?;
+
+            let present: Vec<String> = client
+                .inputs()
+                .list(None)
+                .await
+                .context("list inputs")?
+                .into_iter()
+                .map(|input| input.name)
+                .collect();
+            let (restore, missing): (Vec<_>, Vec<_>) = channels
+                .iter()
+                .partition(|(input, _)| present.contains(input));
+            for (input, _) in &missing {
+                tracing::warn!("Skipping {input}, which no longer exists.");
+            }
+            try_join_all(
+                restore
+                    .iter()
+                    .map(|(input, channel)| apply(client, input, channel)),
+            )
+            .await?;
+            out.result(
+                format!("Restored {} inputs from snapshot {name}", restore.len()),
+                &json!({
+                    "snapshot": name,
+                    "inputs": restore.iter().map(|(input, _)| input).collect::<Vec<_>>(),
+                    "missing": missing.iter().map(|(input, _)| input).collect::<Vec<_>>(),
+                }),
+            )?;
+        }
+        AudioSnapshotCommand::List => {
+            let dir = snapshot_dir()?;
+            let mut names = Vec::new();
+            match tokio::fs::read_dir(&dir).await {
+                Ok(mut entries) => {
+                    while let Some(entry) = entries
+                        .next_entry()
+                        .await
+                        .with_context(|| format!("list {}", dir.display()))?
+                    {
+                        let path = entry.path();
+                        if path.extension() == Some("json".as_ref()) {
+                            if let Some(name) = path.file_stem() {
+                                names.push(name.to_string_lossy().into_owned());
+                            }
+                        }
+                    }
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e).with_context(|| format!("list {}", dir.display())),
+            }
+            names.sort();
+            out.result(names.join("\n"), &names)?;
+        }
+    }
+
+    Ok(())
+}
+
+pub(crate) async fn capture(
+    client: &Client,
+    input: &str,
+    volume_mul: f32,
+) -> anyhow::Result<Channel> {
+    let inputs = client.inputs();
+    let (muted, sync_offset, balance, monitor_type) = tokio::try_join!(
+        async {
+            inputs
+                .muted(input)
+                .await
+                .with_context(|| format!("get mute state of {input}"))
+        },
+        async {
+            inputs
+                .audio_sync_offset(input)
+                .await
+                .with_context(|| format!("get sync offset of {input}"))
+        },
+        async {
+            inputs
+                .audio_balance(input)
+                .await
+                .with_context(|| format!("get balance of {input}"))
+        },
+        async {
+            inputs
+                .audio_monitor_type(input)
+                .await
+                .with_context(|| format!("get monitoring of {input}"))
+        },
+    )?;
+    Ok(Channel {
+        volume_mul,
+        muted,
+        sync_offset_ms: sync_offset.whole_milliseconds() as i64,
+        balance,
+        monitoring: monitor_type.into(),
+    })
+}
+
+pub(crate) async fn apply(client: &Client, input: &str, channel: &Channel) -> anyhow::Result<()> {
+    let inputs = client.inputs();
+    tokio::try_join!(
+        async {
+            inputs
+                .set_volume(input, Volume::Mul(channel.volume_mul))
+                .await
+                .with_context(|| format!("set volume of {input}"))
+        },
+        async {
+            inputs
+                .set_muted(input, channel.muted)
+                .await
+                .with_context(|| format!("set mute state of {input}"))
+        },
+        async {
+            inputs
+                .set_audio_sync_offset(input, time::Duration::milliseconds(channel.sync_offset_ms))
+                .await
+                .with_context(|| format!("set sync offset of {input}"))
+        },
+        async {
+            inputs
+                .set_audio_balance(input, channel.balance)
+                .await
+                .with_context(|| format!("set balance of {input}"))
+        },
+        async {
+            inputs
+                .set_audio_monitor_type(input, channel.monitoring.into())
+                .await
+                .with_context(|| format!("set monitoring of {input}"))
+        },
+    )?;
+    Ok(())
+}
+
+fn snapshot_dir() -> anyhow::Result<PathBuf> {
+    Ok(crate::project_dirs()?.data_dir().join("audio-snapshots"))
+}
+
+fn snapshot_path(name: &str) -> anyhow::Result<PathBuf> {
+    anyhow::ensure!(
+        !name.is_empty() && !name.contains(['/', '\\']) && !name.starts_with('.'),
+        "invalid snapshot name `{name}`"
+    );
+    Ok(snapshot_dir()?.join(format!("{name}.json")))
+}
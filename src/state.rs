@@ -0,0 +1,1124 @@
+use anyhow::Context;
+use clap::Subcommand;
+use futures_util::future::try_join_all;
+use obws::{
+    common::{Alignment, BoundsType},
+    requests::{
+        filters,
+        inputs::Create,
+        scene_items::{
+            Bounds, CreateSceneItem, Crop, Position, Scale, SceneItemTransform, SetEnabled,
+            SetIndex, SetTransform,
+        },
+    },
+    Client,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use crate::{audio::Monitoring, output::Output, snapshot::Channel};
+
+#[derive(Debug, Clone, Subcommand)]
+pub(crate) enum StateCommand {
+    /// Saves the scenes, their items and transforms, the inputs with their settings, audio and
+    /// filters, and the transition to a JSON file.
+    Export { file: PathBuf },
+    /// Puts OBS back the way it was when a file was exported.
+    ///
+    /// Missing scenes, inputs, items and filters are created, and items and filters that aren't
+    /// in the file are removed. Scenes and inputs that aren't in the file are left alone.
+    Import {
+        file: PathBuf,
+
+        /// Only print what would change.
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Shows what differs between two exported files, like what was fiddled with mid-show.
+    Diff {
+        /// The state to compare from.
+        a: PathBuf,
+
+        /// The state to compare to.
+        #[arg(required_unless_present = "live", conflicts_with = "live")]
+        b: Option<PathBuf>,
+
+        /// Compare the file to OBS as it is now.
+        #[arg(long)]
+        live: bool,
+    },
+}
+
+impl StateCommand {
+    pub(crate) fn is_mutating(&self) -> bool {
+        match self {
+            StateCommand::Import { dry_run, .. } => !dry_run,
+            StateCommand::Export { .. } | StateCommand::Diff { .. } => false,
+        }
+    }
+}
+
+/// Everything about OBS that `state export` saves.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct State {
+    /// From the top of OBS' scene list down.
+    scenes: Vec<Scene>,
+    inputs: BTreeMap<String, Input>,
+    transition: Transition,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Scene {
+    name: String,
+    /// From the bottom up, which is the order OBS numbers them in.
+    items: Vec<Item>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Item {
+    source: String,
+    enabled: bool,
+    transform: Transform,
+}
+
+/// The parts of an item's transform that can be set, leaving out what OBS works out from them.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+struct Transform {
+    x: f32,
+    y: f32,
+    rotation: f32,
+    scale_x: f32,
+    scale_y: f32,
+    alignment: Alignment,
+    bounds_type: BoundsType,
+    bounds_alignment: Alignment,
+    bounds_width: f32,
+    bounds_height: f32,
+    /// Pixels cropped off the left, right, top and bottom.
+    crop: [u32; 4],
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Input {
+    kind: String,
+    settings: serde_json::Value,
+    /// Missing for inputs without audio.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    audio: Option<Channel>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    filters: Vec<Filter>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Filter {
+    name: String,
+    kind: String,
+    enabled: bool,
+    settings: serde_json::Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Transition {
+    name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    duration_ms: Option<i64>,
+}
+
+/// One difference between two states, as the step that turns the first into the second.
+#[derive(Debug, Clone, PartialEq)]
+enum Change {
+    CreateScene {
+        scene: String,
+    },
+    /// A scene that only the first state has; importing leaves it alone.
+    ExtraScene {
+        scene: String,
+    },
+    /// Creates an input, which also adds it to the scene.
+    CreateInput {
+        scene: String,
+        input: String,
+        kind: String,
+        settings: serde_json::Value,
+        enabled: bool,
+    },
+    /// An input that only the second state has, but isn't in any of its scenes, so there's no
+    /// scene to create it in.
+    MissingInput {
+        input: String,
+    },
+    /// An input that only the first state has; importing leaves it alone.
+    ExtraInput {
+        input: String,
+    },
+    SetSettings {
+        input: String,
+        settings: serde_json::Value,
+        /// The settings whose values differ, for showing what changed.
+        changed: Vec<String>,
+    },
+    SetAudio {
+        input: String,
+        from: Option<Channel>,
+        to: Channel,
+    },
+    AddFilter {
+        input: String,
+        filter: Filter,
+    },
+    SetFilter {
+        input: String,
+        filter: Filter,
+    },
+    RemoveFilter {
+        input: String,
+        filter: String,
+    },
+    AddItem {
+        scene: String,
+        source: String,
+        enabled: bool,
+    },
+    /// Removes the `occurrence`th item (counting from 0, from the bottom up) showing `source`.
+    RemoveItem {
+        scene: String,
+        source: String,
+        occurrence: usize,
+    },
+    SetItem {
+        scene: String,
+        source: String,
+        occurrence: usize,
+        from: Option<Item>,
+        to: Item,
+    },
+    /// Stacks the items of a scene in this order, from the bottom up.
+    Reorder {
+        scene: String,
+        sources: Vec<String>,
+    },
+    SetTransition {
+        from: Transition,
+        to: Transition,
+    },
+}
+
+pub(crate) async fn run(client: &Client, cmd: StateCommand, out: Output) -> anyhow::Result<()> {
+    match cmd {
+        StateCommand::Export { file } => {
+            let state = capture(client).await?;
+            write(&file, &state).await?;
+            out.result(
+                format!(
+                    "Saved {} scenes and {} inputs to {}",
+                    state.scenes.len(),
+                    state.inputs.len(),
+                    file.display()
+                ),
+                &json!({
+                    "file": file,
+                    "scenes": state.scenes.len(),
+                    "inputs": state.inputs.len(),
+                }),
+            )?;
+        }
+        StateCommand::Import { file, dry_run } => {
+            let wanted = read(&file).await?;
+            let live = capture(client).await?;
+            let changes = diff(&live, &wanted);
+            if !dry_run {
+                for change in &changes {
+                    apply(client, change).await?;
+                }
+            }
+            out.result(
+                summary(&changes, "Nothing to change"),
+                &json!({ "file": file, "dry_run": dry_run, "changes": descriptions(&changes) }),
+            )?;
+        }
+        StateCommand::Diff { a, b, .. } => {
+            let from = read(&a).await?;
+            // Without a second file, clap makes sure `--live` was given.
+            let to = match b {
+                Some(b) => read(&b).await?,
+                None => capture(client).await?,
+            };
+            show_diff(&from, &to, out)?;
+        }
+    }
+    Ok(())
+}
+
+/// Shows what differs between two exported files, for which OBS doesn't have to be running.
+pub(crate) async fn diff_files(a: &Path, b: &Path, out: Output) -> anyhow::Result<()> {
+    show_diff(&read(a).await?, &read(b).await?, out)
+}
+
+fn show_diff(from: &State, to: &State, out: Output) -> anyhow::Result<()> {
+    let changes = diff(from, to);
+    out.result(
+        summary(&changes, "No differences"),
+        &json!({ "changes": descriptions(&changes) }),
+    )
+}
+
+/// The changes one per line, or `unchanged` if there are none.
+fn summary(changes: &[Change], unchanged: &str) -> String {
+    if changes.is_empty() {
+        unchanged.to_owned()
+    } else {
+        descriptions(changes).join("\n")
+    }
+}
+
+fn descriptions(changes: &[Change]) -> Vec<String> {
+    changes.iter().map(ToString::to_string).collect()
+}
+
+async fn read(path: &Path) -> anyhow::Result<State> {
+    let contents = tokio::fs::read_to_string(path)
+        .await
+        .with_context(|| format!("read {}", path.display()))?;
+    serde_json::from_str(&contents).with_context(|| format!("parse {}", path.display()))
+}
+
+async fn write(path: &Path, state: &State) -> anyhow::Result<()> {
+    let contents = serde_json::to_string_pretty(state).context("serialize state")?;
+    tokio::fs::write(path, contents)
+        .await
+        .with_context(|| format!("write {}", path.display()))
+}
+
+/// Reads the state of OBS.
+async fn capture(client: &Client) -> anyhow::Result<State> {
+    let (names, _) = crate::cycle::scenes(client).await?;
+    let scenes = try_join_all(names.into_iter().map(|name| capture_scene(client, name))).await?;
+    let inputs = client.inputs().list(None).await.context("list inputs")?;
+    let inputs = try_join_all(inputs.into_iter().map(|input| async move {
+        let input_state = capture_input(client, &input.name).await?;
+        anyhow::Ok((input.name, input_state))
+    }))
+    .await?
+    .into_iter()
+    .collect();
+    let transition = client
+        .transitions()
+        .current()
+        .await
+        .context("get current transition")?;
+    Ok(State {
+        scenes,
+        inputs,
+        transition: Transition {
+            name: transition.name,
+            duration_ms: transition
+                .duration
+                .map(|duration| duration.whole_milliseconds() as i64),
+        },
+    })
+}
+
+async fn capture_scene(client: &Client, name: String) -> anyhow::Result<Scene> {
+    let mut listed = client
+        .scene_items()
+        .list(&name)
+        .await
+        .with_context(|| format!("list sources in {name}"))?;
+    listed.sort_by_key(|item| item.index);
+    let items = try_join_all(listed.iter().map(|item| {
+        let name = &name;
+        async move {
+            let (enabled, transform) = tokio::try_join!(
+                async {
+                    client
+                        .scene_items()
+                        .enabled(name, item.id)
+                        .await
+                        .with_context(|| format!("check whether {} is shown", item.source_name))
+                },
+                async {
+                    client
+                        .scene_items()
+                        .transform(name, item.id)
+                        .await
+                        .with_context(|| format!("get transform of {}", item.source_name))
+                },
+            )?;
+            anyhow::Ok(Item {
+                source: item.source_name.clone(),
+                enabled,
+                transform: Transform {
+                    x: transform.position_x,
+                    y: transform.position_y,
+                    rotation: transform.rotation,
+                    scale_x: transform.scale_x,
+                    scale_y: transform.scale_y,
+                    alignment: transform.alignment,
+                    bounds_type: transform.bounds_type,
+                    bounds_alignment: transform.bounds_alignment,
+                    bounds_width: transform.bounds_width,
+                    bounds_height: transform.bounds_height,
+                    crop: [
+                        transform.crop_left,
+                        transform.crop_right,
+                        transform.crop_top,
+                        transform.crop_bottom,
+                    ],
+                },
+            })
+        }
+    }))
+    .await?;
+    Ok(Scene { name, items })
+}
+
+async fn capture_input(client: &Client, input: &str) -> anyhow::Result<Input> {
+    let settings = client
+        .inputs()
+        .settings::<serde_json::Value>(input)
+        .await
+        .with_context(|| format!("get settings of {input}"))?;
+    // Inputs without audio have no volume.
+    let audio = match client.inputs().volume(input).await {
+        Ok(volume) => Some(crate::snapshot::capture(client, input, volume.mul).await?),
+        Err(_) => None,
+    };
+    let mut filters = client
+        .filters()
+        .list(input)
+        .await
+        .with_context(|| format!("list filters of {input}"))?;
+    filters.sort_by_key(|filter| filter.index);
+    Ok(Input {
+        kind: settings.kind,
+        settings: settings.settings,
+        audio,
+        filters: filters
+            .into_iter()
+            .map(|filter| Filter {
+                name: filter.name,
+                kind: filter.kind,
+                enabled: filter.enabled,
+                settings: filter.settings,
+            })
+            .collect(),
+    })
+}
+
+/// The steps that turn state `a` into state `b`, in an order they can be taken in.
+fn diff(a: &State, b: &State) -> Vec<Change> {
+    let mut changes = Vec::new();
+    let a_scenes: BTreeSet<_> = a.scenes.iter().map(|scene| &scene.name).collect();
+    let b_scenes: BTreeSet<_> = b.scenes.iter().map(|scene| &scene.name).collect();
+    for scene in &b.scenes {
+        if !a_scenes.contains(&scene.name) {
+            changes.push(Change::CreateScene {
+                scene: scene.name.clone(),
+            });
+        }
+    }
+    for scene in &a.scenes {
+        if !b_scenes.contains(&scene.name) {
+            changes.push(Change::ExtraScene {
+                scene: scene.name.clone(),
+            });
+        }
+    }
+
+    // Inputs are created in the first scene that shows them.
+    let mut created = BTreeSet::new();
+    let empty = Vec::new();
+    for scene in &b.scenes {
+        let before = a
+            .scenes
+            .iter()
+            .find(|a_scene| a_scene.name == scene.name)
+            .map_or(&empty, |a_scene| &a_scene.items);
+        diff_items(
+            &scene.name,
+            before,
+            &scene.items,
+            |source| {
+                let input = b.inputs.get(source)?;
+                let is_new = !a.inputs.contains_key(source) && created.insert(source.to_owned());
+                is_new.then_some(input)
+            },
+            &mut changes,
+        );
+    }
+
+    for (name, input) in &b.inputs {
+        let Some(before) = a.inputs.get(name) else {
+            if !created.contains(name) {
+                changes.push(Change::MissingInput {
+                    input: name.clone(),
+                });
+            }
+            // A created input starts out with the settings it was created with, but the rest
+            // still has to be set.
+            diff_input(name, None, input, &mut changes);
+            continue;
+        };
+        diff_input(name, Some(before), input, &mut changes);
+    }
+    for name in a.inputs.keys() {
+        if !b.inputs.contains_key(name) {
+            changes.push(Change::ExtraInput {
+                input: name.clone(),
+            });
+        }
+    }
+
+    if a.transition != b.transition {
+        changes.push(Change::SetTransition {
+            from: a.transition.clone(),
+            to: b.transition.clone(),
+        });
+    }
+    changes
+}
+
+/// The steps that turn the items `a` of `scene` into `b`. `create` says which sources are inputs
+/// that don't exist yet and have to be created rather than added.
+fn diff_items<'a>(
+    scene: &str,
+    a: &[Item],
+    b: &'a [Item],
+    mut create: impl FnMut(&str) -> Option<&'a Input>,
+    changes: &mut Vec<Change>,
+) {
+    fn count(items: &[Item]) -> BTreeMap<&str, usize> {
+        let mut counts = BTreeMap::new();
+        for item in items {
+            *counts.entry(item.source.as_str()).or_default() += 1;
+        }
+        counts
+    }
+    let (a_counts, b_counts) = (count(a), count(b));
+
+    // The stack as it will be once items are added and removed: what's kept stays in place, and
+    // new items go on top.
+    let mut stack = Vec::new();
+    let mut seen: HashMap<&str, usize> = HashMap::new();
+    for item in a {
+        let occurrence = seen.entry(&item.source).or_default();
+        if *occurrence < b_counts.get(item.source.as_str()).copied().unwrap_or(0) {
+            stack.push(item.source.clone());
+        }
+        *occurrence += 1;
+    }
+    for (source, &a_count) in &a_counts {
+        let b_count = b_counts.get(source).copied().unwrap_or(0);
+        // From the top down, so that the occurrences below keep their numbers.
+        for occurrence in (b_count..a_count).rev() {
+            changes.push(Change::RemoveItem {
+                scene: scene.to_owned(),
+                source: (*source).to_owned(),
+                occurrence,
+            });
+        }
+    }
+    let mut added: HashMap<&str, usize> = HashMap::new();
+    for item in b {
+        let a_count = a_counts.get(item.source.as_str()).copied().unwrap_or(0);
+        let occurrence = added.entry(&item.source).or_default();
+        *occurrence += 1;
+        if *occurrence <= a_count {
+            continue;
+        }
+        stack.push(item.source.clone());
+        changes.push(match create(&item.source) {
+            Some(input) => Change::CreateInput {
+                scene: scene.to_owned(),
+                input: item.source.clone(),
+                kind: input.kind.clone(),
+                settings: input.settings.clone(),
+                enabled: item.enabled,
+            },
+            None => Change::AddItem {
+                scene: scene.to_owned(),
+                source: item.source.clone(),
+                enabled: item.enabled,
+            },
+        });
+    }
+
+    let order: Vec<String> = b.iter().map(|item| item.source.clone()).collect();
+    if stack != order {
+        changes.push(Change::Reorder {
+            scene: scene.to_owned(),
+            sources: order,
+        });
+    }
+
+    let mut a_seen: HashMap<&str, Vec<&Item>> = HashMap::new();
+    for item in a {
+        a_seen.entry(&item.source).or_default().push(item);
+    }
+    let mut b_seen: HashMap<&str, usize> = HashMap::new();
+    for item in b {
+        let occurrence = b_seen.entry(&item.source).or_default();
+        let before = a_seen
+            .get(item.source.as_str())
+            .and_then(|items| items.get(*occurrence))
+            .copied();
+        // New items only need their transform set; they are added shown or hidden already.
+        let differs = match before {
+            Some(before) => before != item,
+            None => item.transform != Transform::DEFAULT,
+        };
+        if differs {
+            changes.push(Change::SetItem {
+                scene: scene.to_owned(),
+                source: item.source.clone(),
+                occurrence: *occurrence,
+                from: before.cloned(),
+                to: item.clone(),
+            });
+        }
+        *occurrence += 1;
+    }
+}
+
+/// The steps that turn input `a` (or nothing, for a new input) into `b`, apart from creating it.
+fn diff_input(name: &str, a: Option<&Input>, b: &Input, changes: &mut Vec<Change>) {
+    if let Some(a) = a {
+        if a.settings != b.settings {
+            changes.push(Change::SetSettings {
+                input: name.to_owned(),
+                settings: b.settings.clone(),
+                changed: changed_keys(&a.settings, &b.settings),
+            });
+        }
+    }
+    if let Some(audio) = &b.audio {
+        let before = a.and_then(|a| a.audio.clone());
+        if before.as_ref() != Some(audio) {
+            changes.push(Change::SetAudio {
+                input: name.to_owned(),
+                from: before,
+                to: audio.clone(),
+            });
+        }
+    }
+    let a_filters = a.map_or(&[][..], |a| &a.filters);
+    for filter in &b.filters {
+        match a_filters
+            .iter()
+            .find(|a_filter| a_filter.name == filter.name)
+        {
+            None => changes.push(Change::AddFilter {
+                input: name.to_owned(),
+                filter: filter.clone(),
+            }),
+            Some(before) if before != filter => changes.push(Change::SetFilter {
+                input: name.to_owned(),
+                filter: filter.clone(),
+            }),
+            Some(_) => {}
+        }
+    }
+    for filter in a_filters {
+        if !b
+            .filters
+            .iter()
+            .any(|b_filter| b_filter.name == filter.name)
+        {
+            changes.push(Change::RemoveFilter {
+                input: name.to_owned(),
+                filter: filter.name.clone(),
+            });
+        }
+    }
+}
+
+/// The top-level settings that differ between `a` and `b`.
+fn changed_keys(a: &serde_json::Value, b: &serde_json::Value) -> Vec<String> {
+    let empty = serde_json::Map::new();
+    let a = a.as_object().unwrap_or(&empty);
+    let b = b.as_object().unwrap_or(&empty);
+    a.keys()
+        .chain(b.keys())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .filter(|key| a.get(*key) != b.get(*key))
+        .cloned()
+        .collect()
+}
+
+impl Transform {
+    /// Where OBS puts a newly added item.
+    const DEFAULT: Transform = Transform {
+        x: 0.,
+        y: 0.,
+        rotation: 0.,
+        scale_x: 1.,
+        scale_y: 1.,
+        alignment: Alignment::LEFT.union(Alignment::TOP),
+        bounds_type: BoundsType::None,
+        bounds_alignment: Alignment::CENTER,
+        bounds_width: 0.,
+        bounds_height: 0.,
+        crop: [0; 4],
+    };
+}
+
+impl From<Transform> for SceneItemTransform {
+    fn from(transform: Transform) -> Self {
+        let [left, right, top, bottom] = transform.crop;
+        SceneItemTransform {
+            position: Some(Position {
+                x: Some(transform.x),
+                y: Some(transform.y),
+            }),
+            rotation: Some(transform.rotation),
+            scale: Some(Scale {
+                x: Some(transform.scale_x),
+                y: Some(transform.scale_y),
+            }),
+            alignment: Some(transform.alignment),
+            bounds: Some(Bounds {
+                r#type: Some(transform.bounds_type),
+                alignment: Some(transform.bounds_alignment),
+                width: Some(transform.bounds_width),
+                height: Some(transform.bounds_height),
+            }),
+            crop: Some(Crop {
+                left: Some(left),
+                right: Some(right),
+                top: Some(top),
+                bottom: Some(bottom),
+            }),
+        }
+    }
+}
+
+/// Takes one step towards the imported state.
+async fn apply(client: &Client, change: &Change) -> anyhow::Result<()> {
+    match change {
+        Change::CreateScene { scene } => client
+            .scenes()
+            .create(scene)
+            .await
+            .with_context(|| format!("create scene {scene}"))?,
+        Change::ExtraScene { .. } | Change::ExtraInput { .. } => {}
+        Change::MissingInput { input } => {
+            tracing::warn!("Skipping {input}, which isn't in any scene to create it in.");
+        }
+        Change::CreateInput {
+            scene,
+            input,
+            kind,
+            settings,
+            enabled,
+        } => {
+            client
+                .inputs()
+                .create(Create {
+                    scene,
+                    input,
+                    kind,
+                    settings: Some(settings),
+                    enabled: Some(*enabled),
+                })
+                .await
+                .with_context(|| format!("create input {input} in {scene}"))?;
+        }
+        Change::SetSettings {
+            input, settings, ..
+        } => client
+            .inputs()
+            .set_settings(obws::requests::inputs::SetSettings {
+                input,
+                settings,
+                overlay: Some(false),
+            })
+            .await
+            .with_context(|| format!("set settings of {input}"))?,
+        Change::SetAudio { input, to, .. } => crate::snapshot::apply(client, input, to).await?,
+        Change::AddFilter { input, filter } => {
+            client
+                .filters()
+                .create(filters::Create {
+                    source: input,
+                    filter: &filter.name,
+                    kind: &filter.kind,
+                    settings: Some(&filter.settings),
+                })
+                .await
+                .with_context(|| format!("add filter {} to {input}", filter.name))?;
+            set_filter_enabled(client, input, filter).await?;
+        }
+        Change::SetFilter { input, filter } => {
+            client
+                .filters()
+                .set_settings(filters::SetSettings {
+                    source: input,
+                    filter: &filter.name,
+                    settings: &filter.settings,
+                    overlay: Some(false),
+                })
+                .await
+                .with_context(|| format!("set settings of filter {} on {input}", filter.name))?;
+            set_filter_enabled(client, input, filter).await?;
+        }
+        Change::RemoveFilter { input, filter } => client
+            .filters()
+            .remove(input, filter)
+            .await
+            .with_context(|| format!("remove filter {filter} from {input}"))?,
+        Change::AddItem {
+            scene,
+            source,
+            enabled,
+        } => {
+            client
+                .scene_items()
+                .create(CreateSceneItem {
+                    scene,
+                    source,
+                    enabled: Some(*enabled),
+                })
+                .await
+                .with_context(|| format!("add {source} to {scene}"))?;
+        }
+        Change::RemoveItem {
+            scene,
+            source,
+            occurrence,
+        } => {
+            let ids = item_ids(client, scene).await?;
+            let id = find(&ids, source, *occurrence)?;
+            client
+                .scene_items()
+                .remove(scene, id)
+                .await
+                .with_context(|| format!("remove {source} from {scene}"))?;
+        }
+        Change::SetItem {
+            scene,
+            source,
+            occurrence,
+            to,
+            ..
+        } => {
+            let ids = item_ids(client, scene).await?;
+            let item_id = find(&ids, source, *occurrence)?;
+            client
+                .scene_items()
+                .set_transform(SetTransform {
+                    scene,
+                    item_id,
+                    transform: to.transform.into(),
+                })
+                .await
+                .with_context(|| format!("set transform of {source} in {scene}"))?;
+            client
+                .scene_items()
+                .set_enabled(SetEnabled {
+                    scene,
+                    item_id,
+                    enabled: to.enabled,
+                })
+                .await
+                .with_context(|| format!("show or hide {source} in {scene}"))?;
+        }
+        Change::Reorder { scene, sources } => {
+            let ids = item_ids(client, scene).await?;
+            let mut seen: HashMap<&str, usize> = HashMap::new();
+            let mut order = Vec::new();
+            for source in sources {
+                let occurrence = seen.entry(source).or_default();
+                order.push((source, find(&ids, source, *occurrence)?));
+                *occurrence += 1;
+            }
+            // Each item that is put in place pushes the ones above it up, so going from the
+            // bottom up leaves them all where they belong.
+            for (index, (source, item_id)) in order.into_iter().enumerate() {
+                client
+                    .scene_items()
+                    .set_index(SetIndex {
+                        scene,
+                        item_id,
+                        index: index as u32,
+                    })
+                    .await
+                    .with_context(|| format!("move {source} in {scene}"))?;
+            }
+        }
+        Change::SetTransition { to, .. } => {
+            client
+                .transitions()
+                .set_current(&to.name)
+                .await
+                .with_context(|| format!("set transition to {}", to.name))?;
+            if let Some(ms) = to.duration_ms {
+                client
+                    .transitions()
+                    .set_current_duration(time::Duration::milliseconds(ms))
+                    .await
+                    .context("set transition duration")?;
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn set_filter_enabled(client: &Client, input: &str, filter: &Filter) -> anyhow::Result<()> {
+    client
+        .filters()
+        .set_enabled(filters::SetEnabled {
+            source: input,
+            filter: &filter.name,
+            enabled: filter.enabled,
+        })
+        .await
+        .with_context(|| format!("enable or disable filter {} on {input}", filter.name))
+}
+
+/// The sources of the items in `scene` and their IDs, from the bottom up.
+async fn item_ids(client: &Client, scene: &str) -> anyhow::Result<Vec<(String, i64)>> {
+    let mut items = client
+        .scene_items()
+        .list(scene)
+        .await
+        .with_context(|| format!("list sources in {scene}"))?;
+    items.sort_by_key(|item| item.index);
+    Ok(items
+        .into_iter()
+        .map(|item| (item.source_name, item.id))
+        .collect())
+}
+
+/// The ID of the `occurrence`th item showing `source`.
+fn find(ids: &[(String, i64)], source: &str, occurrence: usize) -> anyhow::Result<i64> {
+    ids.iter()
+        .filter(|(name, _)| name == source)
+        .nth(occurrence)
+        .map(|&(_, id)| id)
+        .with_context(|| format!("{source} went missing"))
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::CreateScene { scene } => write!(f, "+ scene {scene}"),
+            Change::ExtraScene { scene } => write!(f, "- scene {scene}"),
+            Change::CreateInput {
+                scene, input, kind, ..
+            } => write!(f, "+ input {input} ({kind}) in {scene}"),
+            Change::MissingInput { input } => write!(f, "+ input {input}, which isn't in a scene"),
+            Change::ExtraInput { input } => write!(f, "- input {input}"),
+            Change::SetSettings { input, changed, .. } => {
+                write!(f, "~ input {input}: settings {}", changed.join(", "))
+            }
+            Change::SetAudio { input, from, to } => {
+                write!(f, "~ input {input}: audio ")?;
+                match from {
+                    Some(from) => write!(f, "{}", Audio(from))?,
+                    None => write!(f, "(new)")?,
+                }
+                write!(f, " -> {}", Audio(to))
+            }
+            Change::AddFilter { input, filter } => {
+                write!(f, "+ filter {} ({}) on {input}", filter.name, filter.kind)
+            }
+            Change::SetFilter { input, filter } => write!(f, "~ filter {} on {input}", filter.name),
+            Change::RemoveFilter { input, filter } => write!(f, "- filter {filter} on {input}"),
+            Change::AddItem { scene, source, .. } => write!(f, "+ {scene} / {source}"),
+            Change::RemoveItem { scene, source, .. } => write!(f, "- {scene} / {source}"),
+            Change::SetItem {
+                scene,
+                source,
+                from,
+                to,
+                ..
+            } => {
+                write!(f, "~ {scene} / {source}:")?;
+                let from = from.as_ref();
+                let shown = |enabled| if enabled { "shown" } else { "hidden" };
+                if from.is_some_and(|from| from.enabled != to.enabled) {
+                    write!(f, " {}", shown(to.enabled))?;
+                }
+                let (a, b) = (
+                    from.map_or(Transform::DEFAULT, |from| from.transform),
+                    to.transform,
+                );
+                if (a.x, a.y) != (b.x, b.y) {
+                    write!(f, " moved {},{} -> {},{}", a.x, a.y, b.x, b.y)?;
+                }
+                if (a.scale_x, a.scale_y) != (b.scale_x, b.scale_y) {
+                    write!(
+                        f,
+                        " scaled {}x{} -> {}x{}",
+                        a.scale_x, a.scale_y, b.scale_x, b.scale_y
+                    )?;
+                }
+                if a.rotation != b.rotation {
+                    write!(f, " rotated {}° -> {}°", a.rotation, b.rotation)?;
+                }
+                if a.crop != b.crop {
+                    write!(f, " cropped {:?} -> {:?}", a.crop, b.crop)?;
+                }
+                if (a.alignment, a.bounds_type, a.bounds_alignment)
+                    != (b.alignment, b.bounds_type, b.bounds_alignment)
+                    || (a.bounds_width, a.bounds_height) != (b.bounds_width, b.bounds_height)
+                {
+                    write!(f, " alignment or bounds changed")?;
+                }
+                Ok(())
+            }
+            Change::Reorder { scene, sources } => {
+                let top_down: Vec<_> = sources.iter().rev().map(String::as_str).collect();
+                write!(f, "~ {scene}: order {}", top_down.join(", "))
+            }
+            Change::SetTransition { from, to } => {
+                write!(
+                    f,
+                    "~ transition {} -> {}",
+                    Transition::show(from),
+                    Transition::show(to)
+                )
+            }
+        }
+    }
+}
+
+/// Shows the audio state of an input briefly.
+struct Audio<'a>(&'a Channel);
+
+impl fmt::Display for Audio<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let channel = self.0;
+        if channel.volume_mul > 0. {
+            write!(f, "{:.1} dB", 20. * channel.volume_mul.log10())?;
+        } else {
+            write!(f, "-inf dB")?;
+        }
+        if channel.muted {
+            write!(f, " muted")?;
+        }
+        if channel.sync_offset_ms != 0 {
+            write!(f, ", {} ms offset", channel.sync_offset_ms)?;
+        }
+        if channel.balance != 0.5 {
+            write!(f, ", balance {}", channel.balance)?;
+        }
+        if channel.monitoring != Monitoring::None {
+            write!(f, ", monitoring {}", channel.monitoring.as_str())?;
+        }
+        Ok(())
+    }
+}
+
+impl Transition {
+    fn show(&self) -> String {
+        match self.duration_ms {
+            Some(ms) => format!("{} ({ms} ms)", self.name),
+            None => self.name.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(source: &str, x: f32) -> Item {
+        Item {
+            source: source.to_owned(),
+            enabled: true,
+            transform: Transform {
+                x,
+                ..Transform::DEFAULT
+            },
+        }
+    }
+
+    fn state(items: Vec<Item>) -> State {
+        State {
+            scenes: vec![Scene {
+                name: String::from("Main"),
+                items,
+            }],
+            inputs: BTreeMap::new(),
+            transition: Transition {
+                name: String::from("Fade"),
+                duration_ms: Some(300),
+            },
+        }
+    }
+
+    #[test]
+    fn diffs_items() {
+        let a = state(vec![item("Cam", 0.), item("Slides", 0.), item("Logo", 0.)]);
+        assert_eq!(diff(&a, &a), []);
+
+        let b = state(vec![
+            item("Slides", 0.),
+            item("Cam", 100.),
+            item("Chat", 0.),
+        ]);
+        let changes: Vec<_> = diff(&a, &b).iter().map(ToString::to_string).collect();
+        assert_eq!(
+            changes,
+            [
+                "- Main / Logo",
+                "+ Main / Chat",
+                "~ Main: order Chat, Cam, Slides",
+                "~ Main / Cam: moved 0,0 -> 100,0",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn diffs_files() {
+        let dir = std::env::temp_dir().join(format!("obs-do-state-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (a, b) = (dir.join("a.json"), dir.join("b.json"));
+        write(&a, &state(vec![item("Cam", 0.)])).await.unwrap();
+        write(&b, &state(vec![item("Cam", 0.), item("Chat", 0.)]))
+            .await
+            .unwrap();
+        let (res, results) = Output::capture(diff_files(&a, &b, Output::new(true))).await;
+        res.unwrap();
+        assert_eq!(results, [json!({ "changes": ["+ Main / Chat"] })]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn describes_audio() {
+        let channel = |volume_mul, muted| Channel {
+            volume_mul,
+            muted,
+            sync_offset_ms: 0,
+            balance: 0.5,
+            monitoring: Monitoring::None,
+        };
+        let change = Change::SetAudio {
+            input: String::from("Mic/Aux"),
+            from: Some(channel(1., false)),
+            to: channel(0., true),
+        };
+        assert_eq!(
+            change.to_string(),
+            "~ input Mic/Aux: audio 0.0 dB -> -inf dB muted"
+        );
+    }
+
+    #[test]
+    fn settings_changes() {
+        assert_eq!(
+            changed_keys(
+                &json!({ "url": "a", "width": 800 }),
+                &json!({ "url": "b", "width": 800, "css": "" })
+            ),
+            ["css", "url"]
+        );
+    }
+}
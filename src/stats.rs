@@ -0,0 +1,325 @@
+use anyhow::Context;
+use obws::{responses::general::Stats, Client};
+use serde::Serialize;
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+use tokio::{
+    io::AsyncWriteExt,
+    time::{Instant, MissedTickBehavior},
+};
+
+use crate::output::Output;
+
+#[derive(Debug, Clone, clap::Args)]
+pub(crate) struct StatsArgs {
+    /// Keep refreshing the numbers at this interval, like `1s`, until interrupted.
+    #[arg(long, value_name = "INTERVAL", conflicts_with = "log")]
+    pub(crate) watch: Option<String>,
+
+    /// Append a timestamped row of stats to this file every `--interval`, until interrupted.
+    ///
+    /// Files ending in `.jsonl` get a JSON object per line, and any other file gets CSV.
+    #[arg(long, value_name = "PATH")]
+    pub(crate) log: Option<PathBuf>,
+
+    /// How often to log stats with `--log`.
+    #[arg(long, value_name = "DURATION", default_value = "5s", requires = "log")]
+    interval: String,
+}
+
+/// How OBS is performing at one point in time.
+#[derive(Debug, Serialize)]
+struct Sample {
+    cpu_percent: f64,
+    memory_mb: f64,
+    free_disk_mb: f64,
+    active_fps: f64,
+    /// How many frames were rendered per second since the previous sample, when watching.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    render_fps: Option<f64>,
+    frame_render_ms: f64,
+    /// Frames that were skipped because rendering took too long.
+    render_skipped_frames: u32,
+    render_total_frames: u32,
+    /// Frames that were skipped because encoding took too long.
+    output_skipped_frames: u32,
+    output_total_frames: u32,
+}
+
+impl Sample {
+    fn new(stats: &Stats, previous: Option<(&Stats, Duration)>) -> Self {
+        let render_fps = previous.and_then(|(previous, elapsed)| {
+            frame_rate(
+                previous.render_total_frames,
+                stats.render_total_frames,
+                elapsed,
+            )
+        });
+        Sample {
+            cpu_percent: stats.cpu_usage,
+            memory_mb: stats.memory_usage,
+            free_disk_mb: stats.available_disk_space,
+            active_fps: stats.active_fps,
+            render_fps,
+            frame_render_ms: stats.average_frame_render_time,
+            render_skipped_frames: stats.render_skipped_frames,
+            render_total_frames: stats.render_total_frames,
+            output_skipped_frames: stats.output_skipped_frames,
+            output_total_frames: stats.output_total_frames,
+        }
+    }
+
+    fn lines(&self) -> Vec<String> {
+        let lag = |skipped: u32, total: u32| {
+            let percent = match total {
+                0 => 0.,
+                total => f64::from(skipped) / f64::from(total) * 100.,
+            };
+            format!("{skipped} of {total} frames ({percent:.1}%)")
+        };
+        let mut fps = format!("{:.2}", self.active_fps);
+        if let Some(render_fps) = self.render_fps {
+            fps.push_str(&format!(" (rendering {render_fps:.2})"));
+        }
+        vec![
+            format!("CPU           {:.1}%", self.cpu_percent),
+            format!("Memory        {:.1} MB", self.memory_mb),
+            format!("Free disk     {:.1} GB", self.free_disk_mb / 1000.),
+            format!("FPS           {fps}"),
+            format!("Render time   {:.2} ms", self.frame_render_ms),
+            format!(
+                "Render lag    {}",
+                lag(self.render_skipped_frames, self.render_total_frames)
+            ),
+            format!(
+                "Encoding lag  {}",
+                lag(self.output_skipped_frames, self.output_total_frames)
+            ),
+        ]
+    }
+}
+
+/// Prints how much OBS is using of the machine and how many frames it failed to render or
+/// encode in time.
+///
+/// With `--watch`, redraws the numbers in place like `top` (or prints a JSON line per refresh
+/// with `--json`) until interrupted.
+pub(crate) async fn run(client: &Client, args: &StatsArgs, out: Output) -> anyhow::Result<()> {
+    if let Some(path) = &args.log {
+        let interval = crate::parse_duration(&args.interval)?;
+        return log(client, path, interval).await;
+    }
+    let Some(interval) = &args.watch else {
+        let stats = fetch(client).await?;
+        let sample = Sample::new(&stats, None);
+        return out.result(sample.lines().join("\n"), &sample);
+    };
+    let interval = crate::parse_duration(interval)?;
+    anyhow::ensure!(
+        !interval.is_zero(),
+        "the --watch interval must be longer than 0s"
+    );
+
+    let mut ticks = tokio::time::interval(interval);
+    ticks.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    let mut stdout = std::io::stdout();
+    let mut previous: Option<(Stats, Instant)> = None;
+    let mut drawn = 0;
+    loop {
+        tokio::select! {
+            _ = ticks.tick() => {}
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+        }
+        let stats = fetch(client).await?;
+        let now = Instant::now();
+        let sample = Sample::new(
+            &stats,
+            previous.as_ref().map(|(stats, at)| (stats, now - *at)),
+        );
+        previous = Some((stats, now));
+        if out.is_json() {
+            out.json(&sample)?;
+            continue;
+        }
+
+        if drawn > 0 {
+            // Go back up to redraw the numbers in place.
+            write!(stdout, "\x1b[{drawn}A")?;
+        }
+        let lines = sample.lines();
+        for line in &lines {
+            writeln!(stdout, "\x1b[2K{line}")?;
+        }
+        stdout.flush()?;
+        drawn = lines.len();
+    }
+}
+
+/// A row of the stats log.
+#[derive(Debug, Serialize)]
+struct Row {
+    timestamp: String,
+    cpu_percent: f64,
+    memory_mb: f64,
+    free_disk_mb: f64,
+    active_fps: f64,
+    render_fps: Option<f64>,
+    frame_render_ms: f64,
+    render_skipped_frames: u32,
+    render_total_frames: u32,
+    output_skipped_frames: u32,
+    output_total_frames: u32,
+}
+
+impl Row {
+    fn new(timestamp: String, sample: Sample) -> Self {
+        Row {
+            timestamp,
+            cpu_percent: sample.cpu_percent,
+            memory_mb: sample.memory_mb,
+            free_disk_mb: sample.free_disk_mb,
+            active_fps: sample.active_fps,
+            render_fps: sample.render_fps,
+            frame_render_ms: sample.frame_render_ms,
+            render_skipped_frames: sample.render_skipped_frames,
+            render_total_frames: sample.render_total_frames,
+            output_skipped_frames: sample.output_skipped_frames,
+            output_total_frames: sample.output_total_frames,
+        }
+    }
+
+    /// The row as a line of `format`, preceded by the CSV header if `header` is set.
+    fn encode(&self, format: LogFormat, header: bool) -> anyhow::Result<Vec<u8>> {
+        match format {
+            LogFormat::Csv => {
+                let mut writer = csv::WriterBuilder::new()
+                    .has_headers(header)
+                    .from_writer(Vec::new());
+                writer.serialize(self).context("serialize stats")?;
+                writer.into_inner().context("serialize stats")
+            }
+            LogFormat::Jsonl => {
+                let mut line = serde_json::to_vec(self).context("serialize stats")?;
+                line.push(b'\n');
+                Ok(line)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LogFormat {
+    Csv,
+    Jsonl,
+}
+
+impl LogFormat {
+    fn of(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("jsonl" | "ndjson") => LogFormat::Jsonl,
+            _ => LogFormat::Csv,
+        }
+    }
+}
+
+/// Appends a row of stats to the file at `path` every `interval`, until interrupted.
+///
+/// Each row is written as soon as it is taken, so that the log survives whatever ends the
+/// process.
+async fn log(client: &Client, path: &Path, interval: Duration) -> anyhow::Result<()> {
+    anyhow::ensure!(!interval.is_zero(), "the --interval must be longer than 0s");
+    let format = LogFormat::of(path);
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await
+        .with_context(|| format!("open {}", path.display()))?;
+    let mut header = file
+        .metadata()
+        .await
+        .with_context(|| format!("read {}", path.display()))?
+        .len()
+        == 0;
+    tracing::info!("Logging stats to {} every {interval:?}.", path.display());
+
+    let mut ticks = tokio::time::interval(interval);
+    ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let mut previous: Option<(Stats, Instant)> = None;
+    loop {
+        tokio::select! {
+            _ = ticks.tick() => {}
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+        }
+        let stats = fetch(client).await?;
+        let now = Instant::now();
+        let sample = Sample::new(
+            &stats,
+            previous.as_ref().map(|(stats, at)| (stats, now - *at)),
+        );
+        previous = Some((stats, now));
+        let timestamp = OffsetDateTime::now_utc()
+            .format(&Rfc3339)
+            .context("format timestamp")?;
+        let row = Row::new(timestamp, sample).encode(format, header)?;
+        file.write_all(&row)
+            .await
+            .with_context(|| format!("write to {}", path.display()))?;
+        header = false;
+    }
+}
+
+/// How many frames per second were counted between two samples `elapsed` apart.
+fn frame_rate(before: u32, after: u32, elapsed: Duration) -> Option<f64> {
+    // The count starts over if OBS restarted in between.
+    let frames = after.checked_sub(before)?;
+    (!elapsed.is_zero()).then(|| f64::from(frames) / elapsed.as_secs_f64())
+}
+
+async fn fetch(client: &Client) -> anyhow::Result<Stats> {
+    client.general().stats().await.context("get OBS stats")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_rates() {
+        assert_eq!(frame_rate(100, 160, Duration::from_secs(2)), Some(30.));
+        assert_eq!(frame_rate(100, 10, Duration::from_secs(2)), None);
+        assert_eq!(frame_rate(100, 100, Duration::ZERO), None);
+    }
+
+    #[test]
+    fn log_rows() {
+        let row = Row {
+            timestamp: String::from("2024-05-01T20:00:00Z"),
+            cpu_percent: 12.5,
+            memory_mb: 800.,
+            free_disk_mb: 100_000.,
+            active_fps: 60.,
+            render_fps: None,
+            frame_render_ms: 1.25,
+            render_skipped_frames: 0,
+            render_total_frames: 600,
+            output_skipped_frames: 3,
+            output_total_frames: 600,
+        };
+        let csv = String::from_utf8(row.encode(LogFormat::Csv, true).unwrap()).unwrap();
+        let mut lines = csv.lines();
+        assert!(lines.next().unwrap().starts_with("timestamp,cpu_percent,"));
+        assert_eq!(
+            lines.next(),
+            Some("2024-05-01T20:00:00Z,12.5,800.0,100000.0,60.0,,1.25,0,600,3,600")
+        );
+        let csv = String::from_utf8(row.encode(LogFormat::Csv, false).unwrap()).unwrap();
+        assert_eq!(csv.lines().count(), 1);
+        assert_eq!(LogFormat::of(Path::new("stats.jsonl")), LogFormat::Jsonl);
+        assert_eq!(LogFormat::of(Path::new("stats.csv")), LogFormat::Csv);
+    }
+}
@@ -0,0 +1,357 @@
+use anyhow::Context;
+use clap::Subcommand;
+use obws::Client;
+use serde::Serialize;
+use serde_json::json;
+use std::time::Duration;
+
+use crate::output::Output;
+
+#[derive(Debug, Clone, Subcommand)]
+pub(crate) enum StreamCommand {
+    /// Prints where OBS streams to, with the stream key hidden.
+    GetService {
+        /// Print the stream key too.
+        #[arg(long)]
+        show_key: bool,
+    },
+    /// Changes where OBS streams to, like when rotating a stream key or switching platforms.
+    ///
+    /// Settings that aren't given are kept, so `--key` alone only replaces the stream key.
+    #[command(group(
+        clap::ArgGroup::new("change")
+            .required(true)
+            .multiple(true)
+            .args(["service", "server", "key", "key_env"])
+    ))]
+    SetService {
+        /// Stream to one of the services OBS knows, like `Twitch` or `YouTube - RTMPS`.
+        #[arg(long, value_name = "NAME")]
+        service: Option<String>,
+
+        /// The ingest server to stream to: with `--service`, one of the service's servers
+        /// (`auto` by default), and otherwise the URL of a custom server.
+        #[arg(long, value_name = "URL")]
+        server: Option<String>,
+
+        /// The stream key; `@path` reads it from a file, and `-` from stdin.
+        #[arg(long, value_name = "KEY", conflicts_with = "key_env")]
+        key: Option<String>,
+
+        /// Take the stream key from this environment variable, keeping it out of the shell
+        /// history.
+        #[arg(long, value_name = "VAR")]
+        key_env: Option<String>,
+    },
+}
+
+impl StreamCommand {
+    pub(crate) fn is_mutating(&self) -> bool {
+        match self {
+            StreamCommand::SetService { .. } => true,
+            StreamCommand::GetService { .. } => false,
+        }
+    }
+}
+
+/// The service type OBS uses for the streaming services it has built in.
+const COMMON_SERVICE: &str = "rtmp_common";
+
+/// The service type OBS uses for streaming to any other server.
+const CUSTOM_SERVICE: &str = "rtmp_custom";
+
+pub(crate) async fn run(client: &Client, cmd: StreamCommand, out: Output) -> anyhow::Result<()> {
+    let current = client
+        .config()
+        .stream_service_settings::<serde_json::Value>()
+        .await
+        .context("get stream service settings")?;
+    match cmd {
+        StreamCommand::GetService { show_key } => {
+            let setting = |name: &str| current.settings[name].as_str().map(str::to_owned);
+            let (service, server) = (setting("service"), setting("server"));
+            let key = setting("key").filter(|key| !key.is_empty());
+            let shown_key = match &key {
+                Some(key) if show_key => key.clone(),
+                Some(_) => String::from("(hidden)"),
+                None => String::from("(none)"),
+            };
+            let human = match &service {
+                Some(service) => format!(
+                    "{service} via {}, key {shown_key}",
+                    server.as_deref().unwrap_or("auto")
+                ),
+                None => format!(
+                    "{}, key {shown_key}",
+                    server.as_deref().unwrap_or("(no server)")
+                ),
+            };
+            out.result(
+                human,
+                &json!({
+                    "type": current.r#type,
+                    "service": service,
+                    "server": server,
+                    "has_key": key.is_some(),
+                    "key": key.filter(|_| show_key),
+                }),
+            )?;
+        }
+        StreamCommand::SetService {
+            service,
+            server,
+            key,
+            key_env,
+        } => {
+            let key = match (key, key_env) {
+                (Some(key), _) => Some(crate::text::read(&key).await?),
+                (None, Some(var)) => Some(
+                    std::env::var(&var).with_context(|| format!("read stream key from ${var}"))?,
+                ),
+                (None, None) => None,
+            };
+            let (r#type, mut settings) = match (&service, &server) {
+                (Some(service), server) => (
+                    COMMON_SERVICE,
+                    json!({ "service": service, "server": server.as_deref().unwrap_or("auto") }),
+                ),
+                (None, Some(server)) => (CUSTOM_SERVICE, json!({ "server": server })),
+                (None, None) => (current.r#type.as_str(), current.settings.clone()),
+            };
+            settings["key"] = match key {
+                Some(key) => json!(key),
+                None => current.settings["key"].clone(),
+            };
+            client
+                .config()
+                .set_stream_service_settings(r#type, &settings)
+                .await
+                .context("set stream service settings")?;
+            let streaming = client
+                .streaming()
+                .status()
+                .await
+                .context("get stream status")?
+                .active;
+            if streaming {
+                tracing::warn!("OBS is streaming; the change applies from the next stream on.");
+            }
+            out.json(&json!({
+                "type": r#type,
+                "service": settings["service"],
+                "server": settings["server"],
+            }))?;
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, clap::Args)]
+pub(crate) struct StatusArgs {
+    /// How long to measure the bitrate over, like `1s`.
+    ///
+    /// `0s` reports the average over the whole stream instead, without waiting.
+    #[arg(long, value_name = "DURATION", default_value = "1s")]
+    sample: String,
+}
+
+/// How the stream is doing right now.
+#[derive(Debug, Serialize)]
+struct Status {
+    /// `live`, `reconnecting` or `offline`.
+    state: &'static str,
+    duration_secs: f64,
+    bytes: u64,
+    /// The share of the output buffer that is backed up, from 0 to 1.
+    congestion: f32,
+    skipped_frames: u32,
+    total_frames: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    kbps: Option<f64>,
+}
+
+impl std::fmt::Display for Status {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.state == "offline" {
+            return f.write_str("offline");
+        }
+        let secs = self.duration_secs as u64;
+        write!(
+            f,
+            "{} {}:{:02}:{:02}",
+            self.state,
+            secs / 3600,
+            secs / 60 % 60,
+            secs % 60
+        )?;
+        if let Some(kbps) = self.kbps {
+            write!(f, ", {kbps:.0} kb/s")?;
+        }
+        let skipped = match self.total_frames {
+            0 => 0.,
+            total => f64::from(self.skipped_frames) / f64::from(total) * 100.,
+        };
+        write!(
+            f,
+            ", {:.1} MB, {:.0}% congestion, {}/{} frames skipped ({skipped:.1}%)",
+            self.bytes as f64 / 1e6,
+            self.congestion * 100.,
+            self.skipped_frames,
+            self.total_frames,
+        )
+    }
+}
+
+/// Prints whether OBS is streaming, for how long, and how well, on a single line that fits in a
+/// status bar.
+pub(crate) async fn status(client: &Client, args: &StatusArgs, out: Output) -> anyhow::Result<()> {
+    let sample = crate::parse_duration(&args.sample)?;
+    let first = client
+        .streaming()
+        .status()
+        .await
+        .context("get stream status")?;
+    let (last, kbps) = if !first.active {
+        (first, None)
+    } else if sample.is_zero() {
+        let kbps = bitrate(first.bytes, first.duration.unsigned_abs());
+        (first, kbps)
+    } else {
+        tokio::time::sleep(sample).await;
+        let last = client
+            .streaming()
+            .status()
+            .await
+            .context("get stream status")?;
+        // The stream may have restarted in between, which resets the byte count.
+        let elapsed = (last.duration - first.duration).unsigned_abs();
+        let kbps = last
+            .bytes
+            .checked_sub(first.bytes)
+            .and_then(|bytes| bitrate(bytes, elapsed));
+        (last, kbps)
+    };
+
+    let status = Status {
+        state: match (last.active, last.reconnecting) {
+            (false, _) => "offline",
+            (true, true) => "reconnecting",
+            (true, false) => "live",
+        },
+        duration_secs: last.duration.as_seconds_f64(),
+        bytes: last.bytes,
+        congestion: last.congestion,
+        skipped_frames: last.skipped_frames,
+        total_frames: last.total_frames,
+        kbps,
+    };
+    out.result(&status, &status)
+}
+
+/// The bitrate of `bytes` sent over `elapsed`, in kilobits per second.
+fn bitrate(bytes: u64, elapsed: Duration) -> Option<f64> {
+    (!elapsed.is_zero()).then(|| bytes as f64 * 8. / 1000. / elapsed.as_secs_f64())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    #[test]
+    fn status_line() {
+        let status = Status {
+            state: "live",
+            duration_secs: 3725.4,
+            bytes: 2_500_000_000,
+            congestion: 0.02,
+            skipped_frames: 12,
+            total_frames: 2400,
+            kbps: bitrate(750_000, Duration::from_secs(1)),
+        };
+        assert_eq!(
+            status.to_string(),
+            "live 1:02:05, 6000 kb/s, 2500.0 MB, 2% congestion, 12/2400 frames skipped (0.5%)"
+        );
+        assert_eq!(bitrate(1000, Duration::ZERO), None);
+    }
+
+    /// A fake OBS streaming to Twitch, and the stream service settings it was sent.
+    async fn twitch() -> (Client, crate::fake_obs::Requests) {
+        crate::fake_obs::connect(|kind, _| {
+            Some(match kind {
+                "GetStreamServiceSettings" => json!({
+                    "streamServiceType": COMMON_SERVICE,
+                    "streamServiceSettings": {
+                        "service": "Twitch", "server": "auto", "key": "live_123",
+                    },
+                }),
+                "GetStreamStatus" => json!({
+                    "outputActive": false, "outputReconnecting": false,
+                    "outputTimecode": "00:00:00.000", "outputDuration": 0,
+                    "outputCongestion": 0.0, "outputBytes": 0,
+                    "outputSkippedFrames": 0, "outputTotalFrames": 0,
+                }),
+                "SetStreamServiceSettings" => json!(null),
+                _ => return None,
+            })
+        })
+        .await
+    }
+
+    fn set(requests: &crate::fake_obs::Requests) -> Vec<Value> {
+        requests
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(kind, _)| kind == "SetStreamServiceSettings")
+            .map(|(_, data)| data.clone())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn gets_the_service_without_the_key() {
+        let (client, _) = twitch().await;
+        assert_eq!(
+            crate::fake_obs::run_line(&client, "stream get-service")
+                .await
+                .unwrap(),
+            json!({ "type": COMMON_SERVICE, "service": "Twitch", "server": "auto",
+                    "has_key": true, "key": null })
+        );
+        assert_eq!(
+            crate::fake_obs::run_line(&client, "stream get-service --show-key")
+                .await
+                .unwrap()["key"],
+            "live_123"
+        );
+    }
+
+    #[tokio::test]
+    async fn sets_only_what_is_given() {
+        let (client, requests) = twitch().await;
+        crate::fake_obs::run_line(&client, "stream set-service --key live_456")
+            .await
+            .unwrap();
+        crate::fake_obs::run_line(
+            &client,
+            "stream set-service --server rtmp://example.com/live",
+        )
+        .await
+        .unwrap();
+        crate::fake_obs::run_line(&client, "stream set-service --service YouTube")
+            .await
+            .unwrap();
+        assert_eq!(
+            set(&requests),
+            [
+                json!({ "streamServiceType": COMMON_SERVICE, "streamServiceSettings": {
+                    "service": "Twitch", "server": "auto", "key": "live_456" } }),
+                json!({ "streamServiceType": CUSTOM_SERVICE, "streamServiceSettings": {
+                    "server": "rtmp://example.com/live", "key": "live_123" } }),
+                json!({ "streamServiceType": COMMON_SERVICE, "streamServiceSettings": {
+                    "service": "YouTube", "server": "auto", "key": "live_123" } }),
+            ]
+        );
+    }
+}
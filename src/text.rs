@@ -0,0 +1,208 @@
+use anyhow::Context;
+use clap::{Subcommand, ValueEnum};
+use futures_util::StreamExt;
+use obws::{events::Event, requests::inputs::SetSettings, Client};
+use serde_json::json;
+use std::path::{Path, PathBuf};
+use tokio::{io::AsyncReadExt, time::MissedTickBehavior};
+
+use crate::output::Output;
+
+#[derive(Debug, Clone, Subcommand)]
+pub(crate) enum TextCommand {
+    /// Shows the values of a CSV column in a text source one after the other, such as the
+    /// names of the speakers in a lower third.
+    ///
+    /// Starts with the first row, and finishes once the last row is shown.
+    Rotate {
+        /// The text source to update.
+        input: String,
+
+        /// The file to take the values from; its first line names the columns.
+        #[arg(long, value_name = "PATH")]
+        csv: PathBuf,
+
+        /// The column to show.
+        #[arg(long, value_name = "NAME")]
+        column: String,
+
+        /// When to move on to the next row.
+        #[arg(long, value_enum, default_value_t = Advance::SceneChange)]
+        advance_on: Advance,
+
+        /// How long each value is shown with `--advance-on interval`, like `5m`.
+        #[arg(long, value_name = "DURATION", default_value = "30s")]
+        interval: String,
+    },
+}
+
+impl TextCommand {
+    pub(crate) fn is_mutating(&self) -> bool {
+        match self {
+            TextCommand::Rotate { .. } => true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub(crate) enum Advance {
+    /// Whenever another scene goes live.
+    SceneChange,
+    /// Every `--interval`.
+    Interval,
+}
+
+pub(crate) async fn run(client: &Client, cmd: TextCommand, out: Output) -> anyhow::Result<()> {
+    match cmd {
+        TextCommand::Rotate {
+            input,
+            csv,
+            column,
+            advance_on,
+            interval,
+        } => {
+            let interval = crate::parse_duration(&interval)?;
+            anyhow::ensure!(!interval.is_zero(), "the interval must be longer than zero");
+            let values = read_column(&csv, &column).await?;
+            anyhow::ensure!(!values.is_empty(), "{} has no rows", csv.display());
+
+            let events = client.events().context("subscribe to OBS events")?;
+            let mut events = std::pin::pin!(events);
+            let mut ticks = tokio::time::interval(interval);
+            ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            // The first tick is immediate, and the first value is shown right away anyway.
+            ticks.tick().await;
+
+            for (i, value) in values.iter().enumerate() {
+                if i > 0 {
+                    let advanced = match advance_on {
+                        Advance::Interval => tokio::select! {
+                            _ = ticks.tick() => true,
+                            _ = tokio::signal::ctrl_c() => false,
+                        },
+                        Advance::SceneChange => loop {
+                            tokio::select! {
+                                event = events.next() => match event {
+                                    Some(Event::CurrentProgramSceneChanged { .. }) => break true,
+                                    Some(_) => {}
+                                    None => anyhow::bail!("lost connection to OBS"),
+                                },
+                                _ = tokio::signal::ctrl_c() => break false,
+                            }
+                        },
+                    };
+                    if !advanced {
+                        return Ok(());
+                    }
+                }
+                set_text(client, &input, value).await?;
+                tracing::info!("Showing row {} of {}: {value}", i + 1, values.len());
+                out.json(&json!({ "input": input, "row": i + 1, "text": value }))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The values of `column` in the CSV file at `path`, from the top down.
+async fn read_column(path: &Path, column: &str) -> anyhow::Result<Vec<String>> {
+    let contents = tokio::fs::read(path)
+        .await
+        .with_context(|| format!("read {}", path.display()))?;
+    let mut reader = csv::Reader::from_reader(&contents[..]);
+    let headers = reader
+        .headers()
+        .with_context(|| format!("read the header line of {}", path.display()))?;
+    let index = headers
+        .iter()
+        .position(|header| header.trim() == column)
+        .with_context(|| format!("{} has no column named {column}", path.display()))?;
+    reader
+        .records()
+        .map(|record| {
+            let record = record.with_context(|| format!("parse {}", path.display()))?;
+            Ok(record.get(index).unwrap_or_default().to_owned())
+        })
+        .collect()
+}
+
+/// Sets the text of a text source to `text`, which may also be `@<path>` or `-`, as with [`read`].
+pub(crate) async fn set(
+    client: &Client,
+    input: &str,
+    text: &str,
+    out: Output,
+) -> anyhow::Result<()> {
+    let text = read(text).await?;
+    set_text(client, input, &text).await?;
+    out.json(&json!({ "input": input, "text": text }))
+}
+
+/// Takes an argument as is, or reads the contents of a file for `@<path>`, or of stdin for `-`.
+/// `@@` at the start stands for a literal `@`.
+pub(crate) async fn read(arg: &str) -> anyhow::Result<String> {
+    Ok(match arg {
+        "-" => {
+            let mut text = String::new();
+            tokio::io::stdin()
+                .read_to_string(&mut text)
+                .await
+                .context("read stdin")?;
+            without_final_newline(text)
+        }
+        _ if arg.starts_with("@@") => arg[1..].to_owned(),
+        _ => match arg.strip_prefix('@') {
+            Some(path) => {
+                let text = tokio::fs::read_to_string(path)
+                    .await
+                    .with_context(|| format!("read {path}"))?;
+                without_final_newline(text)
+            }
+            None => arg.to_owned(),
+        },
+    })
+}
+
+/// Whether [`read`] would read the argument on this machine, from a file or stdin.
+pub(crate) fn is_local(text: &str) -> bool {
+    text == "-" || (text.starts_with('@') && !text.starts_with("@@"))
+}
+
+/// Drops the newline that ends most files and `echo` output, which would otherwise show up as an
+/// empty line under the text.
+fn without_final_newline(mut text: String) -> String {
+    if text.ends_with('\n') {
+        text.pop();
+        if text.ends_with('\r') {
+            text.pop();
+        }
+    }
+    text
+}
+
+pub(crate) async fn set_text(client: &Client, input: &str, text: &str) -> anyhow::Result<()> {
+    client
+        .inputs()
+        .set_settings(SetSettings {
+            input,
+            settings: &json!({ "text": text }),
+            overlay: Some(true),
+        })
+        .await
+        .with_context(|| format!("set text of {input}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn texts() {
+        assert!(is_local("-"));
+        assert!(is_local("@now-playing.txt"));
+        assert!(!is_local("@@handle"));
+        assert!(!is_local("Up next: Q&A"));
+        assert_eq!(without_final_newline(String::from("Song\r\n")), "Song");
+        assert_eq!(without_final_newline(String::from("a\nb\n\n")), "a\nb\n");
+    }
+}
@@ -0,0 +1,149 @@
+use anyhow::Context;
+use futures_util::future::{join_all, try_join_all};
+use obws::{requests::inputs::Volume, Client};
+use serde_json::json;
+use std::{collections::BTreeSet, time::Duration};
+
+use crate::output::Output;
+
+/// Transitions the studio mode preview scene to program.
+///
+/// With `audio_follow`, the audio inputs that are only in the outgoing scene fade out, and those
+/// only in the incoming scene fade in, over the length of the transition. The faded-out inputs
+/// get their volume back afterwards, so that they come in at the same level next time.
+pub(crate) async fn run(client: &Client, audio_follow: bool, out: Output) -> anyhow::Result<()> {
+    let studio_mode = client
+        .ui()
+        .studio_mode_enabled()
+        .await
+        .context("get studio mode state")?;
+    anyhow::ensure!(studio_mode, "studio mode is not enabled");
+
+    let (program, preview, transition) = tokio::try_join!(
+        async {
+            client
+                .scenes()
+                .current_program_scene()
+                .await
+                .context("get current program scene")
+        },
+        async {
+            client
+                .scenes()
+                .current_preview_scene()
+                .await
+                .context("get current preview scene")
+        },
+        async {
+            client
+                .transitions()
+                .current()
+                .await
+                .context("get current transition")
+        },
+    )?;
+    // Cuts have no duration to fade over, and the audio switches along with the video anyway.
+    let duration = transition
+        .duration
+        .and_then(|duration| Duration::try_from(duration).ok());
+    let (true, Some(duration)) = (audio_follow, duration) else {
+        trigger(client).await?;
+        return out.result(
+            format!("Transitioned to {preview}"),
+            &json!({ "scene": preview, "faded_out": [], "faded_in": [] }),
+        );
+    };
+
+    let (outgoing, incoming) = tokio::try_join!(
+        scene_inputs(client, &program),
+        scene_inputs(client, &preview)
+    )?;
+    let (faded_out, faded_in) = tokio::join!(
+        audio_levels(client, outgoing.difference(&incoming)),
+        audio_levels(client, incoming.difference(&outgoing))
+    );
+
+    // The incoming inputs become audible as soon as the transition starts, so silence them first.
+    try_join_all(faded_in.iter().map(|(input, _)| async move {
+        client
+            .inputs()
+            .set_volume(input, Volume::Mul(0.))
+            .await
+            .with_context(|| format!("silence {input}"))
+    }))
+    .await?;
+    trigger(client).await?;
+
+    let levels: Vec<_> = faded_out
+        .iter()
+        .map(|(input, level)| (input.as_str(), *level, 0.))
+        .chain(
+            faded_in
+                .iter()
+                .map(|(input, level)| (input.as_str(), 0., *level)),
+        )
+        .collect();
+    crate::fade::fade_levels(client, &levels, duration).await?;
+    try_join_all(faded_out.iter().map(|(input, level)| async move {
+        client
+            .inputs()
+            .set_volume(input, Volume::Mul(*level))
+            .await
+            .with_context(|| format!("restore volume of {input}"))
+    }))
+    .await?;
+
+    let names = |levels: &[(String, f32)]| -> Vec<String> {
+        levels.iter().map(|(input, _)| input.clone()).collect()
+    };
+    out.result(
+        format!("Transitioned to {preview}"),
+        &json!({
+            "scene": preview,
+            "faded_out": names(&faded_out),
+            "faded_in": names(&faded_in),
+        }),
+    )
+}
+
+async fn trigger(client: &Client) -> anyhow::Result<()> {
+    client
+        .transitions()
+        .trigger()
+        .await
+        .context("trigger studio mode transition")
+}
+
+/// The names of the inputs placed directly in `scene`.
+async fn scene_inputs(client: &Client, scene: &str) -> anyhow::Result<BTreeSet<String>> {
+    let items = client
+        .scene_items()
+        .list(scene)
+        .await
+        .with_context(|| format!("list items of scene {scene}"))?;
+    Ok(items
+        .into_iter()
+        // Only inputs have a kind; nested scenes and groups don't.
+        .filter(|item| item.input_kind.is_some())
+        .map(|item| item.source_name)
+        .collect())
+}
+
+/// The volume multipliers of those of `inputs` that have audio.
+async fn audio_levels(
+    client: &Client,
+    inputs: impl Iterator<Item = &String>,
+) -> Vec<(String, f32)> {
+    let levels = join_all(inputs.map(|input| async move {
+        // OBS refuses to report a volume for inputs without audio, which is how we tell them apart.
+        match client.inputs().volume(input).await {
+            Ok(volume) => Some((input.clone(), volume.mul)),
+            Err(e) => {
+                tracing::debug!("not fading {input}: {e}");
+                None
+            }
+        }
+    }))
+    .await;
+    levels.into_iter().flatten().collect()
+}
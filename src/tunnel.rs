@@ -0,0 +1,67 @@
+use anyhow::Context;
+use std::{process::Stdio, time::Duration};
+use tokio::{
+    net::TcpStream,
+    process::{Child, Command},
+    time::Instant,
+};
+
+use crate::config::Endpoint;
+
+/// How long ssh gets to log in and set up the port forward.
+const OPEN_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// An SSH port forward to an OBS that is only reachable from another machine.
+///
+/// The forward is torn down when this is dropped.
+pub(crate) struct Tunnel {
+    _ssh: Child,
+    /// The local end of the forward.
+    pub(crate) endpoint: Endpoint,
+}
+
+impl Tunnel {
+    /// Forwards a local port to port `obs_port` on the far side of `destination`, which is
+    /// anything ssh accepts, such as `ssh://user@box:2222`.
+    ///
+    /// Logging in uses the user's normal ssh configuration and keys.
+    pub(crate) async fn open(destination: &str, obs_port: u16) -> anyhow::Result<Self> {
+        // Let the OS pick a free port, and hand it over to ssh.
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .and_then(|listener| listener.local_addr())
+            .context("find a free local port")?
+            .port();
+        tracing::debug!("tunneling to OBS at {destination} through local port {port}");
+        let mut ssh = Command::new("ssh")
+            .args(["-N", "-o", "ExitOnForwardFailure=yes", "-L"])
+            .arg(format!("127.0.0.1:{port}:localhost:{obs_port}"))
+            .arg(destination)
+            .stdin(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .context("run ssh")?;
+
+        let deadline = Instant::now() + OPEN_TIMEOUT;
+        loop {
+            if let Some(status) = ssh.try_wait().context("check on ssh")? {
+                anyhow::bail!("ssh to {destination} exited ({status}) before the tunnel was up");
+            }
+            if TcpStream::connect(("127.0.0.1", port)).await.is_ok() {
+                break;
+            }
+            anyhow::ensure!(
+                Instant::now() < deadline,
+                "timed out setting up the ssh tunnel to {destination}"
+            );
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+
+        Ok(Tunnel {
+            _ssh: ssh,
+            endpoint: Endpoint {
+                host: String::from("127.0.0.1"),
+                port,
+            },
+        })
+    }
+}
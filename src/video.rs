@@ -0,0 +1,242 @@
+use anyhow::Context;
+use clap::Subcommand;
+use obws::{requests::config::SetVideoSettings, Client};
+use serde_json::json;
+
+use crate::output::Output;
+
+#[derive(Debug, Clone, Subcommand)]
+pub(crate) enum VideoCommand {
+    /// Prints the canvas and output resolutions and the frame rate.
+    GetSettings,
+    /// Changes the resolutions or the frame rate, like between a setup for recording tutorials
+    /// and one for streaming.
+    ///
+    /// OBS refuses while it is streaming, recording, or running any other output.
+    #[command(group(
+        clap::ArgGroup::new("change")
+            .required(true)
+            .multiple(true)
+            .args(["base", "output", "fps"])
+    ))]
+    SetSettings {
+        /// The size of the canvas scenes are laid out on, like `1920x1080`.
+        #[arg(long, value_name = "WxH")]
+        base: Option<String>,
+
+        /// The size the canvas is scaled to for streaming and recording, like `1280x720`.
+        #[arg(long, value_name = "WxH")]
+        output: Option<String>,
+
+        /// The frame rate, like `60`, `29.97` or `30000/1001`.
+        #[arg(long)]
+        fps: Option<String>,
+    },
+}
+
+impl VideoCommand {
+    pub(crate) fn is_mutating(&self) -> bool {
+        match self {
+            VideoCommand::SetSettings { .. } => true,
+            VideoCommand::GetSettings => false,
+        }
+    }
+}
+
+pub(crate) async fn run(client: &Client, cmd: VideoCommand, out: Output) -> anyhow::Result<()> {
+    match cmd {
+        VideoCommand::GetSettings => {
+            let video = client
+                .config()
+                .video_settings()
+                .await
+                .context("get video settings")?;
+            out.result(
+                format!(
+                    "{}x{} canvas, {}x{} output, {} fps",
+                    video.base_width,
+                    video.base_height,
+                    video.output_width,
+                    video.output_height,
+                    fps(video.fps_numerator, video.fps_denominator)
+                ),
+                &json!({
+                    "base": { "width": video.base_width, "height": video.base_height },
+                    "output": { "width": video.output_width, "height": video.output_height },
+                    "fps_numerator": video.fps_numerator,
+                    "fps_denominator": video.fps_denominator,
+                }),
+            )?;
+        }
+        VideoCommand::SetSettings { base, output, fps } => {
+            let base = base.as_deref().map(parse_size).transpose()?;
+            let output = output.as_deref().map(parse_size).transpose()?;
+            let fps = fps.as_deref().map(parse_fps).transpose()?;
+            client
+                .config()
+                .set_video_settings(SetVideoSettings {
+                    fps_numerator: fps.map(|(numerator, _)| numerator),
+                    fps_denominator: fps.map(|(_, denominator)| denominator),
+                    base_width: base.map(|(width, _)| width),
+                    base_height: base.map(|(_, height)| height),
+                    output_width: output.map(|(width, _)| width),
+                    output_height: output.map(|(_, height)| height),
+                })
+                .await
+                .context("set video settings")?;
+            out.json(&json!({
+                "base": base.map(|(width, height)| json!({ "width": width, "height": height })),
+                "output": output.map(|(width, height)| json!({ "width": width, "height": height })),
+                "fps": fps.map(|(numerator, denominator)| fps_value(numerator, denominator)),
+            }))?;
+        }
+    }
+    Ok(())
+}
+
+/// Parses a resolution like `1920x1080`.
+fn parse_size(size: &str) -> anyhow::Result<(u32, u32)> {
+    let invalid = || anyhow::anyhow!("invalid resolution `{size}`, expected e.g. `1920x1080`");
+    let (width, height) = size.split_once('x').ok_or_else(invalid)?;
+    let width: u32 = width.parse().map_err(|_| invalid())?;
+    let height: u32 = height.parse().map_err(|_| invalid())?;
+    anyhow::ensure!(width > 0 && height > 0, invalid());
+    Ok((width, height))
+}
+
+/// Parses a frame rate into the fraction OBS stores it as.
+///
+/// Rates like `29.97` are taken to mean the NTSC rates they are short for, like `30000/1001`.
+fn parse_fps(fps: &str) -> anyhow::Result<(u32, u32)> {
+    let invalid = || anyhow::anyhow!("invalid frame rate `{fps}`, expected e.g. `60` or `29.97`");
+    let (numerator, denominator) = if let Some((numerator, denominator)) = fps.split_once('/') {
+        (
+            numerator.parse().map_err(|_| invalid())?,
+            denominator.parse().map_err(|_| invalid())?,
+        )
+    } else if let Ok(whole) = fps.parse::<u32>() {
+        (whole, 1)
+    } else {
+        let rate: f64 = fps.parse().map_err(|_| invalid())?;
+        anyhow::ensure!(rate.is_finite() && rate > 0. && rate < 1000., invalid());
+        let ntsc = (rate * 1.001).round();
+        if (ntsc / 1.001 - rate).abs() < 0.01 {
+            (ntsc as u32 * 1000, 1001)
+        } else {
+            let thousandths = (rate * 1000.).round() as u32;
+            let divisor = gcd(thousandths, 1000);
+            (thousandths / divisor, 1000 / divisor)
+        }
+    };
+    anyhow::ensure!(numerator > 0 && denominator > 0, invalid());
+    Ok((numerator, denominator))
+}
+
+fn gcd(a: u32, b: u32) -> u32 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+fn fps_value(numerator: u32, denominator: u32) -> f64 {
+    f64::from(numerator) / f64::from(denominator)
+}
+
+/// A frame rate as people write it, like `60` or `29.97`.
+fn fps(numerator: u32, denominator: u32) -> String {
+    if denominator != 0 && numerator % denominator == 0 {
+        (numerator / denominator).to_string()
+    } else {
+        format!("{:.2}", fps_value(numerator, denominator))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    #[test]
+    fn frame_rates() {
+        assert_eq!(parse_fps("60").unwrap(), (60, 1));
+        assert_eq!(parse_fps("30000/1001").unwrap(), (30000, 1001));
+        assert_eq!(parse_fps("29.97").unwrap(), (30000, 1001));
+        assert_eq!(parse_fps("23.976").unwrap(), (24000, 1001));
+        assert_eq!(parse_fps("12.5").unwrap(), (25, 2));
+        assert!(parse_fps("0").is_err());
+        assert!(parse_fps("fast").is_err());
+        assert_eq!(fps(60, 1), "60");
+        assert_eq!(fps(60000, 1001), "59.94");
+
+        assert_eq!(parse_size("1920x1080").unwrap(), (1920, 1080));
+        assert!(parse_size("1920").is_err());
+    }
+
+    #[tokio::test]
+    async fn gets_settings() {
+        let (client, _) = crate::fake_obs::connect(|kind, _| {
+            (kind == "GetVideoSettings").then(|| {
+                json!({
+                    "fpsNumerator": 30000, "fpsDenominator": 1001,
+                    "baseWidth": 2560, "baseHeight": 1440,
+                    "outputWidth": 1920, "outputHeight": 1080,
+                })
+            })
+        })
+        .await;
+        assert_eq!(
+            crate::fake_obs::run_line(&client, "video get-settings")
+                .await
+                .unwrap(),
+            json!({
+                "base": { "width": 2560, "height": 1440 },
+                "output": { "width": 1920, "height": 1080 },
+                "fps_numerator": 30000,
+                "fps_denominator": 1001,
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn sets_only_what_is_given() {
+        let (client, requests) =
+            crate::fake_obs::connect(|kind, _| (kind == "SetVideoSettings").then_some(Value::Null))
+                .await;
+        assert_eq!(
+            crate::fake_obs::run_line(&client, "video set-settings --output 1280x720 --fps 29.97")
+                .await
+                .unwrap(),
+            json!({
+                "base": null,
+                "output": { "width": 1280, "height": 720 },
+                "fps": 30000. / 1001.,
+            })
+        );
+        crate::fake_obs::run_line(&client, "video set-settings --base 1920x1080")
+            .await
+            .unwrap();
+        assert!(
+            crate::fake_obs::run_line(&client, "video set-settings --base 1080p")
+                .await
+                .is_err()
+        );
+        assert_eq!(
+            *requests.lock().unwrap(),
+            [
+                (
+                    String::from("SetVideoSettings"),
+                    json!({
+                        "fpsNumerator": 30000, "fpsDenominator": 1001,
+                        "outputWidth": 1280, "outputHeight": 720,
+                    })
+                ),
+                (
+                    String::from("SetVideoSettings"),
+                    json!({ "baseWidth": 1920, "baseHeight": 1080 })
+                ),
+            ]
+        );
+    }
+}
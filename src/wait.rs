@@ -0,0 +1,212 @@
+use anyhow::Context;
+use futures_util::StreamExt;
+use obws::requests::EventSubscription;
+use obws::{
+    events::{Event, OutputState},
+    Client,
+};
+use serde_json::json;
+use std::{process::Stdio, str::FromStr, time::Duration};
+use tokio::time::Instant;
+
+use crate::{config::Endpoint, output::Output};
+
+/// How long to wait between attempts to connect to OBS.
+const RETRY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Something to happen in OBS that `wait-for` waits for.
+#[derive(Debug, Clone)]
+pub(crate) enum Condition {
+    StreamStarted,
+    StreamStopped,
+    RecordStarted,
+    RecordStopped,
+    /// `scene=<name>`: the scene goes live.
+    Scene(String),
+    /// `event:<type>`: any event of the given type, like `InputMuteStateChanged`.
+    Event(String),
+}
+
+impl FromStr for Condition {
+    type Err = anyhow::Error;
+
+    fn from_str(condition: &str) -> anyhow::Result<Self> {
+        Ok(match condition {
+            "stream-started" => Condition::StreamStarted,
+            "stream-stopped" => Condition::StreamStopped,
+            "record-started" => Condition::RecordStarted,
+            "record-stopped" => Condition::RecordStopped,
+            _ => {
+                if let Some(scene) = condition.strip_prefix("scene=") {
+                    Condition::Scene(scene.to_owned())
+                } else if let Some(kind) = condition.strip_prefix("event:") {
+                    Condition::Event(kind.to_owned())
+                } else {
+                    anyhow::bail!(
+                        "unknown condition `{condition}`, expected one of stream-started, \
+                         stream-stopped, record-started, record-stopped, scene=<name> or \
+                         event:<type>"
+                    )
+                }
+            }
+        })
+    }
+}
+
+impl std::fmt::Display for Condition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Condition::StreamStarted => f.write_str("stream-started"),
+            Condition::StreamStopped => f.write_str("stream-stopped"),
+            Condition::RecordStarted => f.write_str("record-started"),
+            Condition::RecordStopped => f.write_str("record-stopped"),
+            Condition::Scene(scene) => write!(f, "scene={scene}"),
+            Condition::Event(kind) => write!(f, "event:{kind}"),
+        }
+    }
+}
+
+impl Condition {
+    /// What to print once `event` meets the condition, if it does.
+    ///
+    /// That is the file for a stopped recording, so that scripts can pick it up from there.
+    fn met_by(&self, event: &Event, kind: &str) -> Option<String> {
+        match (self, event) {
+            (Condition::StreamStarted, Event::StreamStateChanged { state, .. })
+                if *state == OutputState::Started =>
+            {
+                Some(String::from("stream started"))
+            }
+            (Condition::StreamStopped, Event::StreamStateChanged { state, .. })
+                if *state == OutputState::Stopped =>
+            {
+                Some(String::from("stream stopped"))
+            }
+            (Condition::RecordStarted, Event::RecordStateChanged { state, .. })
+                if *state == OutputState::Started =>
+            {
+                Some(String::from("recording started"))
+            }
+            (Condition::RecordStopped, Event::RecordStateChanged { state, path, .. })
+                if *state == OutputState::Stopped =>
+            {
+                Some(
+                    path.clone()
+                        .unwrap_or_else(|| String::from("recording stopped")),
+                )
+            }
+            (Condition::Scene(scene), Event::CurrentProgramSceneChanged { name })
+                if name == scene =>
+            {
+                Some(name.clone())
+            }
+            (Condition::Event(wanted), _) if wanted == kind => Some(kind.to_owned()),
+            _ => None,
+        }
+    }
+}
+
+/// Blocks until `condition` occurs, and prints the event that met it.
+///
+/// Only changes count: a stream that is already running doesn't meet `stream-started`.
+pub(crate) async fn run(
+    client: &Client,
+    condition: &Condition,
+    timeout: Option<&str>,
+    out: Output,
+) -> anyhow::Result<()> {
+    let timeout = timeout.map(crate::parse_duration).transpose()?;
+    let events = client.events().context("subscribe to OBS events")?;
+    let mut events = std::pin::pin!(events);
+
+    let wait = async {
+        while let Some(event) = events.next().await {
+            crate::record::follow(&event).await;
+            let json = serde_json::to_value(&event).context("serialize event")?;
+            let kind = json["eventType"].as_str().unwrap_or_default();
+            if let Some(human) = condition.met_by(&event, kind) {
+                return out.result(human, &json);
+            }
+        }
+        anyhow::bail!("lost connection to OBS")
+    };
+    match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, wait)
+            .await
+            .map_err(|_| anyhow::anyhow!("timed out waiting for {condition}"))?,
+        None => wait.await,
+    }
+}
+
+/// Tries to connect to OBS until it succeeds, optionally starting OBS with `launch` first if the
+/// first attempt fails.
+///
+/// The launched OBS is left running.
+pub(crate) async fn for_obs(
+    endpoint: &Endpoint,
+    password: Option<String>,
+    timeout: Option<Duration>,
+    launch: Option<&str>,
+    out: Output,
+) -> anyhow::Result<()> {
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+    let mut launched = false;
+    loop {
+        match crate::connect_with(endpoint, password.clone(), EventSubscription::NONE).await {
+            Ok(_) => return out.json(&json!({ "ready": true })),
+            Err(e) => tracing::debug!("OBS is not reachable yet: {e:#}"),
+        }
+        if let (Some(launch), false) = (launch, launched) {
+            tracing::info!("Starting OBS.");
+            crate::watch::shell(launch)
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn()
+                .with_context(|| format!("spawn `{launch}`"))?;
+            launched = true;
+        }
+        if let Some(deadline) = deadline {
+            anyhow::ensure!(
+                Instant::now() + RETRY_INTERVAL < deadline,
+                "OBS was not reachable at {endpoint} in time"
+            );
+        }
+        tokio::time::sleep(RETRY_INTERVAL).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conditions_round_trip() {
+        for condition in [
+            "stream-started",
+            "stream-stopped",
+            "record-started",
+            "record-stopped",
+            "scene=Starting Soon",
+            "event:InputMuteStateChanged",
+        ] {
+            let parsed: Condition = condition.parse().unwrap();
+            assert_eq!(parsed.to_string(), condition);
+        }
+        assert!("stream".parse::<Condition>().is_err());
+    }
+
+    #[test]
+    fn scene_condition() {
+        let condition: Condition = "scene=BRB".parse().unwrap();
+        let event = Event::CurrentProgramSceneChanged {
+            name: String::from("BRB"),
+        };
+        let kind = "CurrentProgramSceneChanged";
+        assert_eq!(condition.met_by(&event, kind).as_deref(), Some("BRB"));
+        let other = Event::CurrentProgramSceneChanged {
+            name: String::from("Main"),
+        };
+        assert_eq!(condition.met_by(&other, kind), None);
+    }
+}
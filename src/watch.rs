@@ -0,0 +1,80 @@
+use anyhow::Context;
+use futures_util::StreamExt;
+use obws::Client;
+use std::process::Stdio;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+use crate::sink::{Sink, Sinks};
+
+/// Prints every OBS event as a line of JSON until OBS goes away or we're interrupted.
+///
+/// With `exec`, the events are instead written to the stdin of a single, long-running child
+/// process running `exec` through the shell, so that high-frequency events don't spawn a
+/// process each. Events also go to every one of `sinks`, and then only to stdout if there is no
+/// `exec` either.
+pub(crate) async fn run(client: &Client, exec: Option<&str>, sinks: &[Sink]) -> anyhow::Result<()> {
+    let events = client.events().context("subscribe to OBS events")?;
+    let mut events = std::pin::pin!(events);
+    let mut sinks = Sinks::open(sinks).await?;
+
+    let mut child = match exec {
+        Some(exec) => Some(
+            shell(exec)
+                .stdin(Stdio::piped())
+                .spawn()
+                .with_context(|| format!("spawn `{exec}`"))?,
+        ),
+        None => None,
+    };
+    let mut out: Option<Box<dyn AsyncWrite + Unpin>> = match &mut child {
+        Some(child) => Some(Box::new(child.stdin.take().expect("stdin is piped"))),
+        None if sinks.is_empty() => Some(Box::new(tokio::io::stdout())),
+        None => None,
+    };
+
+    loop {
+        let event = tokio::select! {
+            event = events.next() => event,
+            _ = tokio::signal::ctrl_c() => None,
+        };
+        let Some(event) = event else {
+            break;
+        };
+
+        sinks.send(&event).await?;
+        let Some(out) = &mut out else {
+            continue;
+        };
+        let mut line = serde_json::to_vec(&event).context("serialize event")?;
+        line.push(b'\n');
+        if let Err(e) = out.write_all(&line).await {
+            if e.kind() == std::io::ErrorKind::BrokenPipe {
+                // The consumer went away, so there's no one left to watch for.
+                break;
+            }
+            return Err(e).context("write event");
+        }
+        out.flush().await.context("flush event")?;
+    }
+
+    // Closing stdin lets the handler see EOF and finish up.
+    drop(out);
+    if let Some(mut child) = child {
+        let status = child.wait().await.context("wait for --exec handler")?;
+        anyhow::ensure!(status.success(), "--exec handler exited with {status}");
+    }
+    Ok(())
+}
+
+/// Builds a command that runs `cmd` through the platform shell.
+pub(crate) fn shell(cmd: &str) -> tokio::process::Command {
+    if cfg!(windows) {
+        let mut shell = tokio::process::Command::new("cmd");
+        shell.arg("/C").arg(cmd);
+        shell
+    } else {
+        let mut shell = tokio::process::Command::new("sh");
+        shell.arg("-c").arg(cmd);
+        shell
+    }
+}